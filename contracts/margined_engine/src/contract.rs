@@ -12,13 +12,13 @@ use margined_perp::margined_engine::{ExecuteMsg, InstantiateMsg, QueryMsg};
 use crate::error::ContractError;
 use crate::{
     handle::{
-        close_position, deposit_margin, liquidate, open_position, pay_funding, update_config,
-        withdraw_margin,
+        close_position, deposit_margin, liquidate, open_position, pay_funding,
+        settle_expired_position, update_config, withdraw_margin,
     },
     query::{
         query_all_positions, query_config, query_cumulative_premium_fraction,
-        query_free_collateral, query_margin_ratio, query_pauser, query_position,
-        query_position_notional_unrealized_pnl, query_state,
+        query_free_collateral, query_margin_ratio, query_open_interest_notional, query_pauser,
+        query_position, query_position_notional_unrealized_pnl, query_state,
         query_trader_balance_with_funding_payment, query_trader_position_with_funding_payment,
     },
     reply::{
@@ -51,6 +51,7 @@ pub const PARTIAL_LIQUIDATION_REPLY_ID: u64 = 7;
 pub const PAY_FUNDING_REPLY_ID: u64 = 8;
 
 pub const TRANSFER_FAILURE_REPLY_ID: u64 = 9;
+pub const INSURANCE_WITHDRAWAL_FAILURE_REPLY_ID: u64 = 10;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -94,6 +95,7 @@ pub fn instantiate(
         maintenance_margin_ratio: msg.maintenance_margin_ratio,
         partial_liquidation_ratio: Uint128::zero(), // set as zero by default
         liquidation_fee: msg.liquidation_fee,
+        use_mark_price: false,
     };
 
     store_config(deps.storage, &config)?;
@@ -124,6 +126,7 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             maintenance_margin_ratio,
             partial_liquidation_ratio,
             liquidation_fee,
+            use_mark_price,
         } => update_config(
             deps,
             info,
@@ -134,6 +137,7 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             maintenance_margin_ratio,
             partial_liquidation_ratio,
             liquidation_fee,
+            use_mark_price,
         ),
         ExecuteMsg::UpdatePauser { pauser } => update_pauser(deps, info, pauser),
         ExecuteMsg::AddWhitelist { address } => add_whitelist(deps, info, address),
@@ -164,6 +168,9 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             quote_asset_limit,
         } => liquidate(deps, env, info, vamm, trader, quote_asset_limit),
         ExecuteMsg::PayFunding { vamm } => pay_funding(deps, env, info, vamm),
+        ExecuteMsg::SettleExpiredPosition { vamm, trader } => {
+            settle_expired_position(deps, env, info, vamm, trader)
+        }
         ExecuteMsg::DepositMargin { vamm, amount } => deposit_margin(deps, env, info, vamm, amount),
         ExecuteMsg::WithdrawMargin { vamm, amount } => {
             withdraw_margin(deps, env, info, vamm, amount)
@@ -188,6 +195,9 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::CumulativePremiumFraction { vamm } => {
             to_binary(&query_cumulative_premium_fraction(deps, vamm)?)
         }
+        QueryMsg::OpenInterestNotional { vamm } => {
+            to_binary(&query_open_interest_notional(deps, vamm)?)
+        }
         QueryMsg::UnrealizedPnl {
             vamm,
             trader,
@@ -266,6 +276,11 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> StdResult<Response> {
                 "transfer failure - reply (id {:?})",
                 msg.id
             ))),
+            // keep the reason so that a draw refused by the fund is visible
+            INSURANCE_WITHDRAWAL_FAILURE_REPLY_ID => Err(StdError::generic_err(format!(
+                "insurance fund withdrawal failure - reply (id {:?}): {}",
+                msg.id, e
+            ))),
             INCREASE_POSITION_REPLY_ID => Err(StdError::generic_err(format!(
                 "increase position failure - reply (id {:?})",
                 msg.id
@@ -7,23 +7,27 @@ use crate::{
     contract::{
         CLOSE_POSITION_REPLY_ID, DECREASE_POSITION_REPLY_ID, INCREASE_POSITION_REPLY_ID,
         LIQUIDATION_REPLY_ID, PARTIAL_CLOSE_POSITION_REPLY_ID, PARTIAL_LIQUIDATION_REPLY_ID,
-        PAY_FUNDING_REPLY_ID, REVERSE_POSITION_REPLY_ID,
+        REVERSE_POSITION_REPLY_ID,
     },
-    messages::{execute_transfer_from, withdraw},
+    messages::{execute_settle_funding, execute_transfer_from, withdraw},
     querier::{
-        query_is_over_fluctuation_limit, query_vamm_output_amount, query_vamm_over_spread_limit,
+        query_insurance_is_vamm, query_is_over_fluctuation_limit, query_vamm_output_amount,
+        query_vamm_over_spread_limit, query_vamm_state,
     },
     query::{query_free_collateral, query_margin_ratio},
     state::{
-        read_config, read_position, read_state, store_config, store_position, store_sent_funds,
-        store_state, store_tmp_liquidator, store_tmp_swap, Config, SentFunds, State, TmpSwapInfo,
+        read_config, read_position, read_state, remove_position, store_config, store_position,
+        store_sent_funds, store_state, store_tmp_liquidator, store_tmp_swap, Config, SentFunds,
+        State, TmpSwapInfo,
     },
     utils::{
         calc_remain_margin_with_funding_payment, direction_to_side, get_asset,
         get_margin_ratio_calc_option, get_position, get_position_notional_unrealized_pnl,
-        position_to_side, require_additional_margin, require_bad_debt, require_insufficient_margin,
-        require_non_zero_input, require_not_paused, require_not_restriction_mode,
-        require_position_not_zero, require_vamm, side_to_direction,
+        position_to_side, realize_bad_debt, require_additional_margin, require_bad_debt,
+        require_insufficient_margin, require_market_open, require_non_zero_input,
+        require_not_expiring, require_not_paused, require_not_restriction_mode,
+        require_position_not_zero, require_vamm, settle_accrued_funding, side_to_direction,
+        update_open_interest_notional,
     },
 };
 use margined_common::{
@@ -32,7 +36,7 @@ use margined_common::{
     validate::{validate_margin_ratios, validate_ratio},
 };
 use margined_perp::margined_engine::{
-    PnlCalcOption, Position, PositionUnrealizedPnlResponse, Side,
+    PnlCalcOption, Position, PositionUnrealizedPnlResponse, RemainMarginResponse, Side,
 };
 use margined_perp::margined_vamm::{Direction, ExecuteMsg};
 
@@ -47,6 +51,7 @@ pub fn update_config(
     maintenance_margin_ratio: Option<Uint128>,
     partial_liquidation_ratio: Option<Uint128>,
     liquidation_fee: Option<Uint128>,
+    use_mark_price: Option<bool>,
 ) -> StdResult<Response> {
     let mut config = read_config(deps.storage)?;
 
@@ -98,6 +103,11 @@ pub fn update_config(
         config.liquidation_fee = liquidation_fee;
     }
 
+    // update whether margin ratios are calculated using the vamm mark price
+    if let Some(use_mark_price) = use_mark_price {
+        config.use_mark_price = use_mark_price;
+    }
+
     store_config(deps.storage, &config)?;
 
     Ok(Response::default().add_attribute("action", "update_config"))
@@ -124,6 +134,7 @@ pub fn open_position(
 
     require_not_paused(state.pause)?;
     require_vamm(deps.as_ref(), &config.insurance_fund, &vamm)?;
+    require_not_expiring(deps.as_ref(), &env, &vamm)?;
     require_not_restriction_mode(deps.storage, &vamm, &trader, env.block.height)?;
     require_non_zero_input(margin_amount)?;
     require_non_zero_input(leverage)?;
@@ -139,6 +150,9 @@ pub fn open_position(
         .checked_div(leverage)?;
     require_additional_margin(Integer::from(margin_ratio), config.initial_margin_ratio)?;
 
+    // settle any funding accrued since the vamm was last interacted with
+    let funding_msgs = settle_accrued_funding(deps.as_ref(), &vamm)?;
+
     // retrieves existing position or creates a new one
     let position: Position = get_position(env, deps.storage, &vamm, &trader, side.clone());
 
@@ -174,6 +188,13 @@ pub fn open_position(
     } = get_position_notional_unrealized_pnl(deps.as_ref(), &position, PnlCalcOption::SpotPrice)
         .unwrap();
 
+    // outside of trading hours only trades that partially reduce a position are allowed
+    require_market_open(
+        deps.as_ref(),
+        &vamm,
+        !is_increase && open_notional < position_notional,
+    )?;
+
     store_tmp_swap(
         deps.storage,
         &TmpSwapInfo {
@@ -198,13 +219,16 @@ pub fn open_position(
         },
     )?;
 
-    Ok(Response::new().add_submessage(msg).add_attributes(vec![
-        ("action", "open_position"),
-        ("vamm", vamm.as_ref()),
-        ("trader", trader.as_ref()),
-        ("margin_amount", &margin_amount.to_string()),
-        ("leverage", &leverage.to_string()),
-    ]))
+    Ok(Response::new()
+        .add_submessages(funding_msgs)
+        .add_submessage(msg)
+        .add_attributes(vec![
+            ("action", "open_position"),
+            ("vamm", vamm.as_ref()),
+            ("trader", trader.as_ref()),
+            ("margin_amount", &margin_amount.to_string()),
+            ("leverage", &leverage.to_string()),
+        ]))
 }
 
 pub fn close_position(
@@ -228,6 +252,10 @@ pub fn close_position(
     require_not_paused(state.pause)?;
     require_position_not_zero(position.size.value)?;
     require_not_restriction_mode(deps.storage, &vamm, &trader, env.block.height)?;
+    require_market_open(deps.as_ref(), &vamm, true)?;
+
+    // settle any funding accrued since the vamm was last interacted with
+    let funding_msgs = settle_accrued_funding(deps.as_ref(), &vamm)?;
 
     // if it is long position, close a position means short it (which means base dir is AddToAmm) and vice versa
     let base_direction = if position.size > Integer::zero() {
@@ -301,11 +329,14 @@ pub fn close_position(
             internal_close_position(deps, &position, quote_amount_limit, CLOSE_POSITION_REPLY_ID)?
         };
 
-    Ok(Response::new().add_submessage(msg).add_attributes(vec![
-        ("action", "close_position"),
-        ("vamm", vamm.as_ref()),
-        ("trader", trader.as_ref()),
-    ]))
+    Ok(Response::new()
+        .add_submessages(funding_msgs)
+        .add_submessage(msg)
+        .add_attributes(vec![
+            ("action", "close_position"),
+            ("vamm", vamm.as_ref()),
+            ("trader", trader.as_ref()),
+        ]))
 }
 
 pub fn liquidate(
@@ -322,6 +353,8 @@ pub fn liquidate(
     let vamm = deps.api.addr_validate(&vamm)?;
     let trader = deps.api.addr_validate(&trader)?;
 
+    require_market_open(deps.as_ref(), &vamm, true)?;
+
     // store the liquidator
     store_tmp_liquidator(deps.storage, &info.sender)?;
 
@@ -350,6 +383,9 @@ pub fn liquidate(
     // check the position isn't zero
     require_position_not_zero(position.size.value)?;
 
+    // settle any funding accrued since the vamm was last interacted with
+    let funding_msgs = settle_accrued_funding(deps.as_ref(), &vamm)?;
+
     // first see if this is a partial liquidation, else get rekt
     let msg = if margin_ratio.value > config.liquidation_fee
         && !config.partial_liquidation_ratio.is_zero()
@@ -359,11 +395,14 @@ pub fn liquidate(
         internal_close_position(deps, &position, quote_asset_limit, LIQUIDATION_REPLY_ID)?
     };
 
-    Ok(Response::new().add_submessage(msg).add_attributes(vec![
-        ("action", "liquidate"),
-        ("vamm", vamm.as_ref()),
-        ("trader", trader.as_ref()),
-    ]))
+    Ok(Response::new()
+        .add_submessages(funding_msgs)
+        .add_submessage(msg)
+        .add_attributes(vec![
+            ("action", "liquidate"),
+            ("vamm", vamm.as_ref()),
+            ("trader", trader.as_ref()),
+        ]))
 }
 
 /// settles funding in amm specified
@@ -381,22 +420,113 @@ pub fn pay_funding(
     // check its a valid vamm
     require_vamm(deps.as_ref(), &config.insurance_fund, &vamm)?;
 
-    let funding_msg = SubMsg {
-        msg: CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: vamm.to_string(),
-            funds: vec![],
-            msg: to_binary(&ExecuteMsg::SettleFunding {})?,
-        }),
-        gas_limit: None,
-        id: PAY_FUNDING_REPLY_ID,
-        reply_on: ReplyOn::Always,
-    };
+    let funding_msg = execute_settle_funding(&vamm)?;
 
     Ok(Response::new()
         .add_submessage(funding_msg)
         .add_attribute("action", "pay_funding"))
 }
 
+/// settles a position in an expired vamm at the settlement price, can be called by anyone
+pub fn settle_expired_position(
+    mut deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    vamm: String,
+    trader: String,
+) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    // validate address inputs
+    let vamm = deps.api.addr_validate(&vamm)?;
+    let trader = deps.api.addr_validate(&trader)?;
+
+    // the vamm is closed once expired so only check that it is registered
+    if !query_insurance_is_vamm(
+        &deps.as_ref(),
+        config.insurance_fund.to_string(),
+        vamm.to_string(),
+    )?
+    .is_vamm
+    {
+        return Err(StdError::generic_err("vAMM is not registered"));
+    }
+
+    let settlement_price =
+        match query_vamm_state(&deps.as_ref(), vamm.to_string())?.settlement_price {
+            Some(settlement_price) => settlement_price,
+            None => return Err(StdError::generic_err("vAMM has not been settled")),
+        };
+
+    let position = read_position(deps.storage, &vamm, &trader)?;
+    require_position_not_zero(position.size.value)?;
+
+    // the position is closed against the settlement price rather than the reserves
+    let settlement_notional = settlement_price
+        .checked_mul(position.size.value)?
+        .checked_div(config.decimals)?;
+
+    let margin_delta: Integer = if position.size.is_positive() {
+        Integer::new_positive(settlement_notional) - Integer::new_positive(position.notional)
+    } else {
+        Integer::new_positive(position.notional) - Integer::new_positive(settlement_notional)
+    };
+
+    let RemainMarginResponse {
+        funding_payment,
+        margin,
+        bad_debt,
+        latest_premium_fraction: _,
+    } = calc_remain_margin_with_funding_payment(deps.as_ref(), position.clone(), margin_delta)?;
+
+    let mut msgs: Vec<SubMsg> = vec![];
+
+    let pre_paid_shortfall: Uint128 = if !bad_debt.is_zero() {
+        realize_bad_debt(deps.as_ref(), &vamm, bad_debt, &mut msgs, &mut state)
+    } else {
+        Uint128::zero()
+    };
+
+    if !margin.is_zero() {
+        msgs.append(&mut withdraw(
+            deps.as_ref(),
+            env,
+            &mut state,
+            &vamm,
+            &trader,
+            config.eligible_collateral,
+            margin,
+            pre_paid_shortfall,
+        )?);
+    }
+
+    let value =
+        margin_delta + Integer::new_positive(bad_debt) + Integer::new_positive(position.notional);
+
+    update_open_interest_notional(
+        &mut deps,
+        &mut state,
+        vamm.clone(),
+        value.invert_sign(),
+        trader.clone(),
+    )?;
+
+    remove_position(deps.storage, &position);
+
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_submessages(msgs).add_attributes(vec![
+        ("action", "settle_expired_position"),
+        ("vamm", vamm.as_ref()),
+        ("trader", trader.as_ref()),
+        ("settlement_price", &settlement_price.to_string()),
+        ("pnl", &margin_delta.to_string()),
+        ("funding_payment", &funding_payment.to_string()),
+        ("bad_debt", &bad_debt.to_string()),
+    ]))
+}
+
 /// Enables a user to directly deposit margin into their position
 pub fn deposit_margin(
     deps: DepsMut,
@@ -444,7 +574,10 @@ pub fn deposit_margin(
 
     store_position(deps.storage, &position)?;
 
-    Ok(response.add_attributes([
+    // settle any funding accrued since the vamm was last interacted with
+    let funding_msgs = settle_accrued_funding(deps.as_ref(), &vamm)?;
+
+    Ok(response.add_submessages(funding_msgs).add_attributes([
         ("action", "deposit_margin"),
         ("trader", trader.as_ref()),
         ("deposit_amount", &amount.to_string()),
@@ -473,6 +606,9 @@ pub fn withdraw_margin(
     // read the position for the trader from vamm
     let mut position = read_position(deps.storage, &vamm, &trader).unwrap();
 
+    // settle any funding accrued since the vamm was last interacted with
+    let funding_msgs = settle_accrued_funding(deps.as_ref(), &vamm)?;
+
     let remain_margin = calc_remain_margin_with_funding_payment(
         deps.as_ref(),
         position.clone(),
@@ -498,6 +634,7 @@ pub fn withdraw_margin(
         deps.as_ref(),
         env,
         &mut state,
+        &vamm,
         &trader,
         config.eligible_collateral,
         amount,
@@ -508,11 +645,14 @@ pub fn withdraw_margin(
     store_position(deps.storage, &position)?;
     store_state(deps.storage, &state)?;
 
-    Ok(Response::new().add_submessages(msgs).add_attributes(vec![
-        ("action", "withdraw_margin"),
-        ("trader", trader.as_ref()),
-        ("withdrawal_amount", &amount.to_string()),
-    ]))
+    Ok(Response::new()
+        .add_submessages(funding_msgs)
+        .add_submessages(msgs)
+        .add_attributes(vec![
+            ("action", "withdraw_margin"),
+            ("trader", trader.as_ref()),
+            ("withdrawal_amount", &amount.to_string()),
+        ]))
 }
 
 // Increase the position through a swap
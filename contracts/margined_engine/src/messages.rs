@@ -5,15 +5,19 @@ use cosmwasm_std::{
 use cw20::Cw20ExecuteMsg;
 
 use crate::{
-    contract::TRANSFER_FAILURE_REPLY_ID,
+    contract::{
+        INSURANCE_WITHDRAWAL_FAILURE_REPLY_ID, PAY_FUNDING_REPLY_ID, TRANSFER_FAILURE_REPLY_ID,
+    },
     querier::query_vamm_calc_fee,
     state::{read_config, State},
 };
 
 use margined_common::asset::AssetInfo;
 use margined_perp::margined_engine::TransferResponse;
-use margined_perp::margined_insurance_fund::ExecuteMsg as InsuranceFundExecuteMessage;
-use margined_perp::margined_vamm::CalcFeeResponse;
+use margined_perp::margined_insurance_fund::{
+    ContributionKind, DrawKind, ExecuteMsg as InsuranceFundExecuteMessage,
+};
+use margined_perp::margined_vamm::{CalcFeeResponse, Direction, ExecuteMsg as VammExecuteMessage};
 use margined_perp::querier::query_token_balance;
 
 pub fn execute_transfer_from(
@@ -85,8 +89,9 @@ pub fn execute_transfer(
 pub fn execute_transfer_to_insurance_fund(
     deps: Deps,
     env: Env,
+    vamm: &Addr,
     amount: Uint128,
-) -> StdResult<SubMsg> {
+) -> StdResult<Vec<SubMsg>> {
     let config = read_config(deps.storage)?;
 
     let token_balance = query_token_balance(
@@ -101,10 +106,45 @@ pub fn execute_transfer_to_insurance_fund(
         amount
     };
 
-    execute_transfer(deps.storage, &config.insurance_fund, amount_to_send)
+    Ok(vec![
+        execute_transfer(deps.storage, &config.insurance_fund, amount_to_send)?,
+        execute_insurance_fund_contribution(
+            deps.storage,
+            vamm,
+            ContributionKind::Funding,
+            amount_to_send,
+        )?,
+    ])
 }
 
-pub fn execute_insurance_fund_withdrawal(deps: Deps, amount: Uint128) -> StdResult<SubMsg> {
+// records what was paid into the insurance fund against the ledger of the vamm
+pub fn execute_insurance_fund_contribution(
+    storage: &dyn Storage,
+    vamm: &Addr,
+    kind: ContributionKind,
+    amount: Uint128,
+) -> StdResult<SubMsg> {
+    let config = read_config(storage)?;
+
+    let msg = WasmMsg::Execute {
+        contract_addr: config.insurance_fund.to_string(),
+        funds: vec![],
+        msg: to_binary(&InsuranceFundExecuteMessage::RecordContribution {
+            vamm: vamm.to_string(),
+            kind,
+            amount,
+        })?,
+    };
+
+    Ok(SubMsg::new(msg))
+}
+
+pub fn execute_insurance_fund_withdrawal(
+    deps: Deps,
+    vamm: &Addr,
+    kind: DrawKind,
+    amount: Uint128,
+) -> StdResult<SubMsg> {
     let config = read_config(deps.storage)?;
 
     let msg = WasmMsg::Execute {
@@ -113,32 +153,53 @@ pub fn execute_insurance_fund_withdrawal(deps: Deps, amount: Uint128) -> StdResu
         msg: to_binary(&InsuranceFundExecuteMessage::Withdraw {
             token: config.eligible_collateral,
             amount,
+            vamm: vamm.to_string(),
+            kind,
+            defer: false,
         })?,
     };
 
     let transfer_msg = SubMsg {
         msg: CosmosMsg::Wasm(msg),
         gas_limit: None,
-        id: TRANSFER_FAILURE_REPLY_ID,
+        id: INSURANCE_WITHDRAWAL_FAILURE_REPLY_ID,
         reply_on: ReplyOn::Error,
     };
 
     Ok(transfer_msg)
 }
 
+pub fn execute_settle_funding(vamm: &Addr) -> StdResult<SubMsg> {
+    let msg = WasmMsg::Execute {
+        contract_addr: vamm.to_string(),
+        funds: vec![],
+        msg: to_binary(&VammExecuteMessage::SettleFunding {})?,
+    };
+
+    let funding_msg = SubMsg {
+        msg: CosmosMsg::Wasm(msg),
+        gas_limit: None,
+        id: PAY_FUNDING_REPLY_ID,
+        reply_on: ReplyOn::Always,
+    };
+
+    Ok(funding_msg)
+}
+
 // Transfers the toll and spread fees to the the insurance fund and fee pool
 pub fn transfer_fees(
     deps: Deps,
     from: Addr,
     vamm: Addr,
     notional: Uint128,
+    direction: Direction,
 ) -> StdResult<TransferResponse> {
     let config = read_config(deps.storage)?;
 
     let CalcFeeResponse {
         spread_fee,
         toll_fee,
-    } = query_vamm_calc_fee(&deps, vamm.into_string(), notional)?;
+    } = query_vamm_calc_fee(&deps, vamm.to_string(), notional, direction)?;
 
     let mut messages: Vec<SubMsg> = vec![];
 
@@ -146,6 +207,12 @@ pub fn transfer_fees(
         let msg =
             execute_transfer_from(deps.storage, &from, &config.insurance_fund, spread_fee).unwrap();
         messages.push(msg);
+        messages.push(execute_insurance_fund_contribution(
+            deps.storage,
+            &vamm,
+            ContributionKind::Fee,
+            spread_fee,
+        )?);
     };
 
     if !toll_fee.is_zero() {
@@ -160,10 +227,12 @@ pub fn transfer_fees(
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn withdraw(
     deps: Deps,
     env: Env,
     state: &mut State,
+    vamm: &Addr,
     receiver: &Addr,
     eligible_collateral: AssetInfo,
     amount: Uint128,
@@ -176,10 +245,16 @@ pub fn withdraw(
     if token_balance.checked_add(pre_paid_shortfall)? < amount {
         let shortfall = amount.checked_sub(token_balance.checked_add(pre_paid_shortfall)?)?;
 
-        // add any shortfall to bad_debt
+        // add any shortfall to bad_debt, the fund pays bad debt in full even over the
+        // allowance of the window as it is paid out straight away
         state.prepaid_bad_debt = state.prepaid_bad_debt.checked_add(shortfall)?;
 
-        messages.push(execute_insurance_fund_withdrawal(deps, shortfall).unwrap());
+        messages.push(execute_insurance_fund_withdrawal(
+            deps,
+            vamm,
+            DrawKind::BadDebt,
+            shortfall,
+        )?);
     }
 
     messages.push(execute_transfer(deps.storage, receiver, amount).unwrap());
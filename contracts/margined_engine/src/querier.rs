@@ -1,11 +1,20 @@
 // Contains queries for external contracts,
-use cosmwasm_std::{to_binary, Deps, QueryRequest, StdResult, Uint128, WasmQuery};
+use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, Uint128, WasmQuery};
 
+use margined_common::integer::Integer;
 use margined_perp::{
-    margined_insurance_fund::{AllVammResponse, QueryMsg as InsuranceFundQueryMsg, VammResponse},
-    margined_vamm::{CalcFeeResponse, ConfigResponse, Direction, QueryMsg, StateResponse},
+    margined_insurance_fund::{
+        AllVammResponse, QueryMsg as InsuranceFundQueryMsg, VammResponse,
+        WithdrawalAllowanceResponse,
+    },
+    margined_vamm::{
+        CalcFeeResponse, ConfigResponse, Direction, MarketStatusResponse, QueryMsg, StateResponse,
+    },
 };
 
+// the page size used when reading the vamms from the insurance contract
+const VAMM_PAGINATION_LIMIT: u32 = 30u32;
+
 // returns the config of the request vamm
 pub fn query_vamm_config(deps: &Deps, address: String) -> StdResult<ConfigResponse> {
     deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
@@ -56,10 +65,14 @@ pub fn query_vamm_calc_fee(
     deps: &Deps,
     address: String,
     quote_asset_amount: Uint128,
+    direction: Direction,
 ) -> StdResult<CalcFeeResponse> {
     deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: address,
-        msg: to_binary(&QueryMsg::CalcFee { quote_asset_amount })?,
+        msg: to_binary(&QueryMsg::CalcFee {
+            quote_asset_amount,
+            direction,
+        })?,
     }))
 }
 
@@ -71,6 +84,30 @@ pub fn query_vamm_over_spread_limit(deps: &Deps, address: String) -> StdResult<b
     }))
 }
 
+// returns whether the vamm market is open under its trading schedule
+pub fn query_vamm_market_status(deps: &Deps, address: String) -> StdResult<MarketStatusResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: address,
+        msg: to_binary(&QueryMsg::MarketStatus {})?,
+    }))
+}
+
+// returns the premium fraction accrued by the vamm since funding was last settled
+pub fn query_vamm_accrued_premium_fraction(deps: &Deps, address: String) -> StdResult<Integer> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: address,
+        msg: to_binary(&QueryMsg::AccruedPremiumFraction {})?,
+    }))
+}
+
+// returns the mark price of the vamm
+pub fn query_vamm_mark_price(deps: &Deps, address: String) -> StdResult<Uint128> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: address,
+        msg: to_binary(&QueryMsg::MarkPrice {})?,
+    }))
+}
+
 // returns pricefeed price of underlying in vamm
 pub fn query_vamm_underlying_price(deps: &Deps, address: String) -> StdResult<Uint128> {
     deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
@@ -91,18 +128,52 @@ pub fn query_insurance_is_vamm(
     }))
 }
 
+// returns what the insurance contract can still pay out in its current window
+pub fn query_insurance_withdrawal_allowance(
+    deps: &Deps,
+    insurance: String,
+) -> StdResult<WithdrawalAllowanceResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: insurance,
+        msg: to_binary(&InsuranceFundQueryMsg::GetWithdrawalAllowance {})?,
+    }))
+}
+
 // returns all vamm registered in the insurance contract
 pub fn query_insurance_all_vamm(
     deps: &Deps,
     insurance: String,
+    start_after: Option<String>,
     limit: Option<u32>,
 ) -> StdResult<AllVammResponse> {
     deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: insurance,
-        msg: to_binary(&InsuranceFundQueryMsg::GetAllVamm { limit })?,
+        msg: to_binary(&InsuranceFundQueryMsg::GetAllVamm { start_after, limit })?,
     }))
 }
 
+// returns every vamm registered in the insurance contract, one page at a time
+pub fn query_insurance_all_vamms(deps: &Deps, insurance: String) -> StdResult<Vec<Addr>> {
+    let mut vamms: Vec<Addr> = vec![];
+    loop {
+        let start_after = vamms.last().map(|vamm| vamm.to_string());
+        let page = query_insurance_all_vamm(
+            deps,
+            insurance.clone(),
+            start_after,
+            Some(VAMM_PAGINATION_LIMIT),
+        )?
+        .vamm_list;
+
+        let is_last_page = page.len() < VAMM_PAGINATION_LIMIT as usize;
+        vamms.extend(page);
+
+        if is_last_page {
+            return Ok(vamms);
+        }
+    }
+}
+
 // returns bool if swap is over fluctuation limit
 pub fn query_is_over_fluctuation_limit(
     deps: &Deps,
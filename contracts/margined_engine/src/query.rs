@@ -7,7 +7,7 @@ use margined_perp::margined_engine::{
 
 use crate::{
     contract::PAUSER,
-    querier::query_insurance_all_vamm,
+    querier::{query_insurance_all_vamms, query_vamm_accrued_premium_fraction},
     state::{read_config, read_position, read_state, read_vamm_map, Config, State},
     utils::{
         calc_funding_payment, calc_remain_margin_with_funding_payment,
@@ -29,6 +29,7 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         maintenance_margin_ratio: config.maintenance_margin_ratio,
         partial_liquidation_ratio: config.partial_liquidation_ratio,
         liquidation_fee: config.liquidation_fee,
+        use_mark_price: config.use_mark_price,
     })
 }
 
@@ -74,7 +75,7 @@ pub fn query_all_positions(deps: Deps, trader: String) -> StdResult<Vec<Position
 
     let mut response: Vec<Position> = vec![];
 
-    let vamms = query_insurance_all_vamm(&deps, config.insurance_fund.to_string(), None)?.vamm_list;
+    let vamms = query_insurance_all_vamms(&deps, config.insurance_fund.to_string())?;
     for vamm in vamms.iter() {
         let position =
             read_position(deps.storage, vamm, &deps.api.addr_validate(&trader)?).unwrap();
@@ -108,7 +109,8 @@ pub fn query_position_notional_unrealized_pnl(
     Ok(result)
 }
 
-/// Queries cumulative premium fractions
+/// Queries cumulative premium fractions, including any premium fraction that has
+/// accrued in the vamm but is yet to be settled
 pub fn query_cumulative_premium_fraction(deps: Deps, vamm: String) -> StdResult<Integer> {
     // retrieve vamm data
     let vamm_map = read_vamm_map(deps.storage, deps.api.addr_validate(&vamm)?).unwrap();
@@ -118,7 +120,16 @@ pub fn query_cumulative_premium_fraction(deps: Deps, vamm: String) -> StdResult<
         n => vamm_map.cumulative_premium_fractions[n - 1],
     };
 
-    Ok(result)
+    let accrued_premium_fraction = query_vamm_accrued_premium_fraction(&deps, vamm)?;
+
+    Ok(result.checked_add(accrued_premium_fraction)?)
+}
+
+/// Queries the open interest notional of a single vamm
+pub fn query_open_interest_notional(deps: Deps, vamm: String) -> StdResult<Uint128> {
+    let vamm_map = read_vamm_map(deps.storage, deps.api.addr_validate(&vamm)?)?;
+
+    Ok(vamm_map.open_interest_notional)
 }
 
 /// Queries traders balance across all vamms with funding payment
@@ -126,7 +137,7 @@ pub fn query_trader_balance_with_funding_payment(deps: Deps, trader: String) ->
     let config = read_config(deps.storage).unwrap();
 
     let mut margin = Uint128::zero();
-    let vamms = query_insurance_all_vamm(&deps, config.insurance_fund.to_string(), None)?.vamm_list;
+    let vamms = query_insurance_all_vamms(&deps, config.insurance_fund.to_string())?;
     for vamm in vamms.iter() {
         let position =
             query_trader_position_with_funding_payment(deps, vamm.to_string(), trader.clone())?;
@@ -186,23 +197,30 @@ pub fn query_margin_ratio(deps: Deps, vamm: String, trader: String) -> StdResult
         return Ok(Integer::zero());
     }
 
+    // the spot pnl is compared against the twap, or the mark price if enabled
+    let reference_option = if config.use_mark_price {
+        PnlCalcOption::MarkPrice
+    } else {
+        PnlCalcOption::Twap
+    };
+
     let PositionUnrealizedPnlResponse {
         position_notional: spot_notional,
         unrealized_pnl: spot_pnl,
     } = get_position_notional_unrealized_pnl(deps, &position, PnlCalcOption::SpotPrice)?;
     let PositionUnrealizedPnlResponse {
-        position_notional: twap_notional,
-        unrealized_pnl: twap_pnl,
-    } = get_position_notional_unrealized_pnl(deps, &position, PnlCalcOption::Twap)?;
+        position_notional: reference_notional,
+        unrealized_pnl: reference_pnl,
+    } = get_position_notional_unrealized_pnl(deps, &position, reference_option)?;
 
     // calculate and return margin
     let PositionUnrealizedPnlResponse {
         position_notional,
         unrealized_pnl,
-    } = if spot_pnl.abs() > twap_pnl.abs() {
+    } = if spot_pnl.abs() > reference_pnl.abs() {
         PositionUnrealizedPnlResponse {
-            position_notional: twap_notional,
-            unrealized_pnl: twap_pnl,
+            position_notional: reference_notional,
+            unrealized_pnl: reference_pnl,
         }
     } else {
         PositionUnrealizedPnlResponse {
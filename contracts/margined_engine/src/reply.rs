@@ -4,10 +4,10 @@ use crate::{
     contract::INCREASE_POSITION_REPLY_ID,
     handle::internal_increase_position,
     messages::{
-        execute_insurance_fund_withdrawal, execute_transfer, execute_transfer_from,
-        execute_transfer_to_insurance_fund, transfer_fees, withdraw,
+        execute_insurance_fund_contribution, execute_insurance_fund_withdrawal, execute_transfer,
+        execute_transfer_from, execute_transfer_to_insurance_fund, transfer_fees, withdraw,
     },
-    querier::query_vamm_state,
+    querier::{query_insurance_withdrawal_allowance, query_vamm_state},
     query::query_margin_ratio,
     state::{
         append_cumulative_premium_fraction, enter_restriction_mode, read_config, read_sent_funds,
@@ -17,20 +17,21 @@ use crate::{
     },
     utils::{
         calc_remain_margin_with_funding_payment, check_base_asset_holding_cap, clear_position,
-        get_position, realize_bad_debt, require_additional_margin, side_to_direction,
-        update_open_interest_notional,
+        get_position, position_to_side, realize_bad_debt, require_additional_margin,
+        side_to_direction, update_open_interest_notional,
     },
 };
 
 use margined_common::{asset::AssetInfo, integer::Integer};
 use margined_perp::{
     margined_engine::{Position, RemainMarginResponse, Side},
+    margined_insurance_fund::{ContributionKind, DrawKind},
     margined_vamm::Direction,
 };
 
 // Updates position after successful execution of the swap
 pub fn update_position_reply(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     input: Uint128,
     output: Uint128,
@@ -57,7 +58,7 @@ pub fn update_position_reply(
     };
 
     update_open_interest_notional(
-        &deps.as_ref(),
+        &mut deps,
         &mut state,
         swap.vamm.clone(),
         if reply_id == INCREASE_POSITION_REPLY_ID {
@@ -87,7 +88,7 @@ pub fn update_position_reply(
                 .checked_add(Integer::new_positive(swap_margin))?;
 
             margin_delta = Integer::new_positive(swap_margin);
-            new_direction = side_to_direction(swap.side);
+            new_direction = side_to_direction(swap.side.clone());
             new_notional = position.notional.checked_add(swap.open_notional)?;
         }
         // DECREASE_POSITION_REPLY
@@ -154,6 +155,7 @@ pub fn update_position_reply(
                 deps.as_ref(),
                 env,
                 &mut state,
+                &swap.vamm,
                 &swap.trader,
                 config.eligible_collateral.clone(),
                 swap.margin_to_vault.value,
@@ -185,8 +187,14 @@ pub fn update_position_reply(
 
     // create messages to pay for toll and spread fees, check flag is true if this follows a reverse
     if !swap.fees_paid {
-        let mut fees =
-            transfer_fees(deps.as_ref(), swap.trader, swap.vamm, swap.open_notional).unwrap();
+        let mut fees = transfer_fees(
+            deps.as_ref(),
+            swap.trader,
+            swap.vamm,
+            swap.open_notional,
+            side_to_direction(swap.side),
+        )
+        .unwrap();
 
         // add the fee transfer messages
         msgs.append(&mut fees.messages);
@@ -229,7 +237,7 @@ pub fn update_position_reply(
 
 // reverse position after successful execution of the swap
 pub fn reverse_position_reply(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     _input: Uint128,
     output: Uint128,
@@ -248,7 +256,7 @@ pub fn reverse_position_reply(
     );
 
     update_open_interest_notional(
-        &deps.as_ref(),
+        &mut deps,
         &mut state,
         swap.vamm.clone(),
         Integer::new_negative(output),
@@ -274,6 +282,7 @@ pub fn reverse_position_reply(
         swap.trader.clone(),
         swap.vamm.clone(),
         current_open_notional,
+        side_to_direction(swap.side.clone()),
     )
     .unwrap();
 
@@ -342,7 +351,7 @@ pub fn reverse_position_reply(
 
 // Closes position after successful execution of the swap
 pub fn close_position_reply(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     _input: Uint128,
     output: Uint128,
@@ -390,6 +399,7 @@ pub fn close_position_reply(
                 deps.as_ref(),
                 env,
                 &mut state,
+                &swap.vamm,
                 &swap.trader,
                 config.eligible_collateral,
                 withdraw_amount.value,
@@ -408,6 +418,7 @@ pub fn close_position_reply(
             swap.trader.clone(),
             swap.vamm.clone(),
             position.notional,
+            side_to_direction(position_to_side(position.size)),
         )
         .unwrap();
 
@@ -421,7 +432,7 @@ pub fn close_position_reply(
         margin_delta + Integer::new_positive(bad_debt) + Integer::new_positive(position.notional);
 
     update_open_interest_notional(
-        &deps.as_ref(),
+        &mut deps,
         &mut state,
         swap.vamm,
         value.invert_sign(),
@@ -445,7 +456,7 @@ pub fn close_position_reply(
 
 // Partially closes position
 pub fn partial_close_position_reply(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     input: Uint128,
     output: Uint128,
@@ -463,7 +474,7 @@ pub fn partial_close_position_reply(
     );
 
     update_open_interest_notional(
-        &deps.as_ref(),
+        &mut deps,
         &mut state,
         swap.vamm.clone(),
         Integer::new_negative(input),
@@ -502,7 +513,14 @@ pub fn partial_close_position_reply(
     };
 
     // calculate the fees
-    let fees = transfer_fees(deps.as_ref(), swap.trader, swap.vamm, swap.open_notional).unwrap();
+    let fees = transfer_fees(
+        deps.as_ref(),
+        swap.trader,
+        swap.vamm,
+        swap.open_notional,
+        side_to_direction(swap.side),
+    )
+    .unwrap();
 
     // set the new position
     position.size += signed_output;
@@ -587,7 +605,13 @@ pub fn liquidate_reply(
     let mut msgs: Vec<SubMsg> = vec![];
 
     let pre_paid_shortfall: Uint128 = if !remain_margin.bad_debt.is_zero() {
-        realize_bad_debt(deps.as_ref(), remain_margin.bad_debt, &mut msgs, &mut state)
+        realize_bad_debt(
+            deps.as_ref(),
+            &swap.vamm,
+            remain_margin.bad_debt,
+            &mut msgs,
+            &mut state,
+        )
     } else {
         Uint128::zero()
     };
@@ -597,6 +621,12 @@ pub fn liquidate_reply(
         msgs.push(
             execute_transfer(deps.storage, &config.insurance_fund, remain_margin.margin).unwrap(),
         );
+        msgs.push(execute_insurance_fund_contribution(
+            deps.storage,
+            &swap.vamm,
+            ContributionKind::Liquidation,
+            remain_margin.margin,
+        )?);
     }
 
     msgs.append(
@@ -604,6 +634,7 @@ pub fn liquidate_reply(
             deps.as_ref(),
             env.clone(),
             &mut state,
+            &swap.vamm,
             &liquidator,
             config.eligible_collateral,
             liquidation_fee,
@@ -699,6 +730,12 @@ pub fn partial_liquidation_reply(
     if !liquidation_fee.is_zero() {
         messages
             .push(execute_transfer(deps.storage, &config.insurance_fund, liquidation_fee).unwrap());
+        messages.push(execute_insurance_fund_contribution(
+            deps.storage,
+            &swap.vamm,
+            ContributionKind::Liquidation,
+            liquidation_fee,
+        )?);
 
         // calculate token balance that should be remaining once
         // insurance fees have been paid
@@ -707,6 +744,7 @@ pub fn partial_liquidation_reply(
                 deps.as_ref(),
                 env.clone(),
                 &mut state,
+                &swap.vamm,
                 &liquidator,
                 config.eligible_collateral,
                 liquidation_fee,
@@ -744,23 +782,50 @@ pub fn pay_funding_reply(
     let config = read_config(deps.storage)?;
     let vamm = deps.api.addr_validate(&sender)?;
 
-    // update the cumulative premium fraction
-    append_cumulative_premium_fraction(deps.storage, vamm.clone(), premium_fraction)?;
-
     let total_position_size =
         query_vamm_state(&deps.as_ref(), vamm.to_string())?.total_position_size;
 
-    let funding_payment =
+    let mut premium_fraction = premium_fraction;
+    let mut funding_payment =
         total_position_size * premium_fraction / Integer::new_positive(config.decimals);
 
+    // the fund only pays funding up to what is left of its allowance, the premium fraction
+    // is scaled down to match so that positions are only credited with what is paid
+    if funding_payment.is_negative() && !funding_payment.is_zero() {
+        let remaining = query_insurance_withdrawal_allowance(
+            &deps.as_ref(),
+            config.insurance_fund.to_string(),
+        )?
+        .remaining;
+
+        if let Some(remaining) = remaining {
+            if funding_payment.value > remaining {
+                premium_fraction.value = premium_fraction
+                    .value
+                    .multiply_ratio(remaining, funding_payment.value);
+                funding_payment =
+                    total_position_size * premium_fraction / Integer::new_positive(config.decimals);
+            }
+        }
+    }
+
+    // update the cumulative premium fraction
+    append_cumulative_premium_fraction(deps.storage, vamm.clone(), premium_fraction)?;
+
     let mut response: Response = Response::new();
 
     if funding_payment.is_negative() && !funding_payment.is_zero() {
-        let msg = execute_insurance_fund_withdrawal(deps.as_ref(), funding_payment.value)?;
+        let msg = execute_insurance_fund_withdrawal(
+            deps.as_ref(),
+            &vamm,
+            DrawKind::Funding,
+            funding_payment.value,
+        )?;
         response = response.add_submessage(msg);
     } else if funding_payment.is_positive() && !funding_payment.is_zero() {
-        let msg = execute_transfer_to_insurance_fund(deps.as_ref(), env, funding_payment.value)?;
-        response = response.add_submessage(msg);
+        let msgs =
+            execute_transfer_to_insurance_fund(deps.as_ref(), env, &vamm, funding_payment.value)?;
+        response = response.add_submessages(msgs);
     };
 
     Ok(response.add_attributes(vec![
@@ -34,6 +34,7 @@ pub struct Config {
     pub maintenance_margin_ratio: Uint128,
     pub partial_liquidation_ratio: Uint128,
     pub liquidation_fee: Uint128,
+    pub use_mark_price: bool,
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -211,6 +212,8 @@ pub fn read_tmp_liquidator(storage: &dyn Storage) -> StdResult<Addr> {
 pub struct VammMap {
     pub last_restriction_block: u64,
     pub cumulative_premium_fractions: Vec<Integer>,
+    #[serde(default)]
+    pub open_interest_notional: Uint128,
 }
 
 fn vamm_map_bucket(storage: &mut dyn Storage) -> Bucket<VammMap> {
@@ -258,6 +261,26 @@ pub fn append_cumulative_premium_fraction(
     store_vamm_map(storage, vamm, &vamm_map)
 }
 
+/// Tracks the open interest of each vamm so the insurance fund can report its coverage
+pub fn update_vamm_open_interest_notional(
+    storage: &mut dyn Storage,
+    vamm: Addr,
+    amount: Integer,
+) -> StdResult<()> {
+    let mut vamm_map = read_vamm_map(storage, vamm.clone())?;
+
+    let updated_open_interest =
+        amount.checked_add(Integer::new_positive(vamm_map.open_interest_notional))?;
+
+    vamm_map.open_interest_notional = if updated_open_interest.is_negative() {
+        Uint128::zero()
+    } else {
+        updated_open_interest.value
+    };
+
+    store_vamm_map(storage, vamm, &vamm_map)
+}
+
 pub fn enter_restriction_mode(
     storage: &mut dyn Storage,
     vamm: Addr,
@@ -0,0 +1,98 @@
+use cosmwasm_std::Uint128;
+use cw_multi_test::Executor;
+use margined_perp::margined_engine::Side;
+use margined_utils::scenarios::{to_decimals, SimpleScenario};
+
+#[test]
+fn test_only_reducing_trades_allowed_while_circuit_breaker_is_tripped() {
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        bob,
+        engine,
+        vamm,
+        ..
+    } = SimpleScenario::new();
+
+    // halt opening trades after a 5% move within 10 minutes
+    let msg = vamm
+        .set_circuit_breaker(600u64, Uint128::from(50_000_000u128))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+
+    // a large trade trips the circuit breaker
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(60u64),
+            to_decimals(10u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    let res = router.execute(alice.clone(), msg).unwrap();
+    assert!(res
+        .events
+        .iter()
+        .any(|event| event.ty == "wasm-circuit_breaker"));
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+
+    // opening a position is rejected
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(10u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    let err = router.execute(bob.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "Generic error: vAMM circuit breaker is tripped".to_string()
+    );
+
+    // reducing a position is allowed
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Sell,
+            to_decimals(10u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    // once the window has rolled past the move trading resumes
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(600);
+        block.height += 1;
+    });
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(10u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(bob.clone(), msg).unwrap();
+}
@@ -138,6 +138,15 @@ fn test_partially_liquidate_long_position() {
         .balance::<_, _, Empty>(&router, insurance_fund.addr().clone())
         .unwrap();
     assert_eq!(insurance_balance, Uint128::from(5_000_855_695_509u128));
+
+    let ledger = insurance_fund
+        .ledger(vamm.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(
+        ledger.liquidation_contributions,
+        Uint128::from(855_695_509u128)
+    );
+    assert_eq!(ledger.total_draws, Uint128::zero());
 }
 
 #[test]
@@ -672,6 +681,12 @@ fn test_long_position_complete_liquidation() {
         .balance::<_, _, Empty>(&router, insurance_fund.addr().clone())
         .unwrap();
     assert_eq!(insurance_balance, Uint128::from(4_996_288_515_407u128));
+
+    let ledger = insurance_fund
+        .ledger(vamm.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(ledger.bad_debt_draws, Uint128::from(3_711_484_593u128));
+    assert_eq!(ledger.total_contributions, Uint128::zero());
 }
 
 #[test]
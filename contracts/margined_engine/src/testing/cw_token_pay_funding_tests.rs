@@ -2,6 +2,7 @@ use cosmwasm_std::{Empty, Uint128};
 use cw_multi_test::Executor;
 use margined_common::integer::Integer;
 use margined_perp::margined_engine::Side;
+use margined_perp::margined_vamm::FundingMode;
 use margined_utils::scenarios::{to_decimals, SimpleScenario};
 
 pub const NEXT_FUNDING_PERIOD_DELTA: u64 = 86_400u64;
@@ -930,3 +931,183 @@ fn test_will_change_nothing_if_funding_rate_is_zero() {
         .unwrap();
     assert_eq!(insurance_balance, Uint128::from(5_000_000_000_000u128));
 }
+
+#[test]
+fn test_continuous_funding_accrues_and_settles_on_position_interaction() {
+    let SimpleScenario {
+        mut router,
+        alice,
+        bob,
+        owner,
+        insurance_fund,
+        engine,
+        vamm,
+        usdc,
+        pricefeed,
+        ..
+    } = SimpleScenario::new();
+
+    let msg = vamm.set_funding_mode(FundingMode::Continuous).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(300u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Sell,
+            to_decimals(1200u64),
+            to_decimals(1u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(bob.clone(), msg).unwrap();
+
+    let price: Uint128 = Uint128::from(1_590_000_000u128);
+    let timestamp: u64 = 1_000_000_000;
+
+    let msg = pricefeed
+        .append_price("ETH".to_string(), price, timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // a full day passes without anyone paying funding
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(NEXT_FUNDING_PERIOD_DELTA);
+        block.height += 1;
+    });
+
+    // the accrued funding is visible before it is settled
+    let premium_fraction = engine
+        .get_latest_cumulative_premium_fraction(&router, vamm.addr().to_string())
+        .unwrap();
+    assert_eq!(premium_fraction, Integer::new_positive(10_000_000u128));
+
+    let alice_position = engine
+        .get_position_with_funding_payment(&router, vamm.addr().to_string(), alice.to_string())
+        .unwrap();
+    assert_eq!(alice_position.margin, Uint128::from(299_625_000_000u128));
+
+    let bob_position = engine
+        .get_position_with_funding_payment(&router, vamm.addr().to_string(), bob.to_string())
+        .unwrap();
+    assert_eq!(bob_position.margin, Uint128::from(1_201_875_000_000u128));
+
+    // alice interacting with her position settles the accrued funding
+    let msg = engine
+        .deposit_margin(vamm.addr().to_string(), to_decimals(10u64), vec![])
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let accrued = vamm.accrued_premium_fraction(&router).unwrap();
+    assert_eq!(accrued, Integer::zero());
+
+    let premium_fraction = engine
+        .get_latest_cumulative_premium_fraction(&router, vamm.addr().to_string())
+        .unwrap();
+    assert_eq!(premium_fraction, Integer::new_positive(10_000_000u128));
+
+    // funding payment generates 1.5 loss which is withdrawn from the insurance fund
+    let engine_balance = usdc
+        .balance::<_, _, Empty>(&router, engine.addr().clone())
+        .unwrap();
+    assert_eq!(engine_balance, Uint128::from(1_511_500_000_000u128));
+    let insurance_balance = usdc
+        .balance::<_, _, Empty>(&router, insurance_fund.addr())
+        .unwrap();
+    assert_eq!(insurance_balance, Uint128::from(4_998_500_000_000u128));
+}
+
+#[test]
+fn test_continuous_funding_is_settled_before_switching_mode() {
+    let SimpleScenario {
+        mut router,
+        alice,
+        bob,
+        owner,
+        engine,
+        vamm,
+        pricefeed,
+        ..
+    } = SimpleScenario::new();
+
+    let msg = vamm.set_funding_mode(FundingMode::Continuous).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(300u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Sell,
+            to_decimals(1200u64),
+            to_decimals(1u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(bob.clone(), msg).unwrap();
+
+    let msg = pricefeed
+        .append_price(
+            "ETH".to_string(),
+            Uint128::from(1_590_000_000u128),
+            1_000_000_000,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(NEXT_FUNDING_PERIOD_DELTA);
+        block.height += 1;
+    });
+
+    // switching would drop the funding that has accrued over the day
+    let msg = vamm.set_funding_mode(FundingMode::Periodic).unwrap();
+    let err = router.execute(owner.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: funding must be settled before changing the funding mode"
+    );
+
+    let msg_pay_funding = engine.pay_funding(vamm.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg_pay_funding).unwrap();
+
+    // once it is settled the mode can change and the settled funding is kept
+    router.execute(owner.clone(), msg).unwrap();
+    assert_eq!(
+        vamm.config(&router).unwrap().funding_mode,
+        FundingMode::Periodic
+    );
+
+    let premium_fraction = engine
+        .get_latest_cumulative_premium_fraction(&router, vamm.addr().to_string())
+        .unwrap();
+    assert_eq!(premium_fraction, Integer::new_positive(10_000_000u128));
+
+    let alice_position = engine
+        .get_position_with_funding_payment(&router, vamm.addr().to_string(), alice.to_string())
+        .unwrap();
+    assert_eq!(alice_position.margin, Uint128::from(299_625_000_000u128));
+}
@@ -0,0 +1,216 @@
+use cosmwasm_std::Empty;
+use cw_multi_test::Executor;
+use margined_common::integer::Integer;
+use margined_perp::margined_engine::Side;
+use margined_utils::scenarios::{to_decimals, PricePath, PricePoint, SimpleScenario};
+
+const ONE_DAY_IN_SECONDS: u64 = 86_400;
+
+#[test]
+fn test_force_error_open_position_within_expiry_cutoff() {
+    let mut scenario = SimpleScenario::new();
+    let expiry = scenario.router.block_info().time.seconds() + ONE_DAY_IN_SECONDS;
+    let dated_vamm = scenario.add_dated_vamm(expiry);
+
+    let SimpleScenario {
+        mut router,
+        alice,
+        engine,
+        ..
+    } = scenario;
+
+    // move to within the default cutoff of one hour
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(ONE_DAY_IN_SECONDS - 1_800);
+        block.height += 1;
+    });
+
+    let msg = engine
+        .open_position(
+            dated_vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(60u64),
+            to_decimals(10u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    let err = router.execute(alice.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "Generic error: vAMM is too close to expiry".to_string()
+    );
+}
+
+#[test]
+fn test_settle_expired_positions_at_settlement_price() {
+    let mut scenario = SimpleScenario::new();
+    let expiry = scenario.router.block_info().time.seconds() + ONE_DAY_IN_SECONDS;
+    let dated_vamm = scenario.add_dated_vamm(expiry);
+
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        bob,
+        carol,
+        usdc,
+        engine,
+        pricefeed,
+        ..
+    } = scenario;
+
+    let msg = engine
+        .open_position(
+            dated_vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(60u64),
+            to_decimals(5u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let msg = engine
+        .open_position(
+            dated_vamm.addr().to_string(),
+            Side::Sell,
+            to_decimals(60u64),
+            to_decimals(5u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(bob.clone(), msg).unwrap();
+
+    let alice_position = engine
+        .position(&router, dated_vamm.addr().to_string(), alice.to_string())
+        .unwrap();
+    let bob_position = engine
+        .position(&router, dated_vamm.addr().to_string(), bob.to_string())
+        .unwrap();
+
+    // positions cannot be settled until the vamm has been settled
+    let msg = engine
+        .settle_expired_position(dated_vamm.addr().to_string(), alice.to_string())
+        .unwrap();
+    let err = router.execute(carol.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "Generic error: vAMM has not been settled".to_string()
+    );
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(ONE_DAY_IN_SECONDS);
+        block.height += 1;
+    });
+
+    let timestamp = router.block_info().time.seconds();
+    let msg = pricefeed
+        .append_price("ETH".to_string(), to_decimals(11u64), timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // anyone can settle the vamm once it has expired
+    let msg = dated_vamm.settle_expiry().unwrap();
+    router.execute(carol.clone(), msg).unwrap();
+
+    let state = dated_vamm.state(&router).unwrap();
+    assert_eq!(state.settlement_price, Some(to_decimals(11u64)));
+    assert!(!state.open);
+
+    let alice_balance_before = usdc.balance::<_, _, Empty>(&router, alice.clone()).unwrap();
+    let bob_balance_before = usdc.balance::<_, _, Empty>(&router, bob.clone()).unwrap();
+
+    let msg = engine
+        .settle_expired_position(dated_vamm.addr().to_string(), alice.to_string())
+        .unwrap();
+    router.execute(carol.clone(), msg).unwrap();
+
+    let msg = engine
+        .settle_expired_position(dated_vamm.addr().to_string(), bob.to_string())
+        .unwrap();
+    router.execute(carol.clone(), msg).unwrap();
+
+    // margin is returned along with the pnl against the settlement price
+    let alice_pnl =
+        Integer::new_positive(to_decimals(11u64) * alice_position.size.value / to_decimals(1u64))
+            - Integer::new_positive(alice_position.notional);
+    let bob_pnl = Integer::new_positive(bob_position.notional)
+        - Integer::new_positive(to_decimals(11u64) * bob_position.size.value / to_decimals(1u64));
+
+    let alice_balance = usdc.balance::<_, _, Empty>(&router, alice.clone()).unwrap();
+    assert_eq!(
+        Integer::new_positive(alice_balance),
+        Integer::new_positive(alice_balance_before)
+            + Integer::new_positive(alice_position.margin)
+            + alice_pnl
+    );
+    let bob_balance = usdc.balance::<_, _, Empty>(&router, bob.clone()).unwrap();
+    assert_eq!(
+        Integer::new_positive(bob_balance),
+        Integer::new_positive(bob_balance_before)
+            + Integer::new_positive(bob_position.margin)
+            + bob_pnl
+    );
+
+    // positions are removed once settled
+    let msg = engine
+        .settle_expired_position(dated_vamm.addr().to_string(), alice.to_string())
+        .unwrap();
+    let err = router.execute(carol.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "Generic error: Position is zero".to_string()
+    );
+}
+
+#[test]
+fn test_settlement_price_is_twap_ending_at_expiry() {
+    let mut scenario = SimpleScenario::new();
+    let start = scenario.router.block_info().time.seconds();
+    let expiry = start + ONE_DAY_IN_SECONDS;
+    let dated_vamm = scenario.add_dated_vamm(expiry);
+
+    let SimpleScenario {
+        mut router,
+        owner,
+        carol,
+        pricefeed,
+        ..
+    } = scenario;
+
+    // the price holds until expiry and doubles over the following day
+    let msg = pricefeed
+        .set_price_path(PricePath::Linear {
+            points: vec![
+                PricePoint {
+                    timestamp: start,
+                    price: to_decimals(10u64),
+                },
+                PricePoint {
+                    timestamp: expiry,
+                    price: to_decimals(10u64),
+                },
+                PricePoint {
+                    timestamp: expiry + ONE_DAY_IN_SECONDS,
+                    price: to_decimals(20u64),
+                },
+            ],
+        })
+        .unwrap();
+    router.execute(owner, msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(2 * ONE_DAY_IN_SECONDS);
+        block.height += 1;
+    });
+
+    // settling late does not pick up the prices after expiry
+    let msg = dated_vamm.settle_expiry().unwrap();
+    router.execute(carol, msg).unwrap();
+
+    let state = dated_vamm.state(&router).unwrap();
+    assert_eq!(state.settlement_price, Some(to_decimals(10u64)));
+}
@@ -0,0 +1,547 @@
+use cosmwasm_std::{Empty, Uint128};
+use cw_multi_test::Executor;
+use margined_perp::margined_engine::Side;
+use margined_utils::scenarios::{to_decimals, SimpleScenario};
+
+#[test]
+fn test_spread_fee_is_contributed_to_vamm_ledger() {
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        engine,
+        vamm,
+        insurance_fund,
+        ..
+    } = SimpleScenario::new();
+
+    let msg = vamm
+        .set_spread_ratio(Uint128::from(50_000_000u128))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // spread fee is 300 x 2 x 5% = 30
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(300u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let ledger = insurance_fund
+        .ledger(vamm.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(ledger.fee_contributions, to_decimals(30u64));
+    assert_eq!(ledger.total_contributions, to_decimals(30u64));
+    assert_eq!(ledger.total_draws, Uint128::zero());
+    assert_eq!(ledger.budget, None);
+}
+
+#[test]
+fn test_coverage_ratio_against_vamm_open_interest() {
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        engine,
+        vamm,
+        insurance_fund,
+        ..
+    } = SimpleScenario::new();
+
+    // nothing is open so there is no ratio
+    let coverage = insurance_fund
+        .coverage(vamm.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(coverage.available, to_decimals(5_000u64));
+    assert_eq!(coverage.open_interest_notional, Uint128::zero());
+    assert_eq!(coverage.coverage_ratio, None);
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(300u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let open_interest_notional = engine
+        .open_interest_notional(&router, vamm.addr().to_string())
+        .unwrap();
+    assert_eq!(open_interest_notional, to_decimals(600u64));
+
+    // 5000 / 600
+    let coverage = insurance_fund
+        .coverage(vamm.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(coverage.open_interest_notional, to_decimals(600u64));
+    assert_eq!(
+        coverage.coverage_ratio,
+        Some(Uint128::from(8_333_333_333u128))
+    );
+
+    // the budget limits what is available to the vamm, 1200 / 600
+    let msg = insurance_fund
+        .set_budget(vamm.addr().to_string(), Some(to_decimals(1_200u64)))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let coverage = insurance_fund
+        .coverage(vamm.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(coverage.available, to_decimals(1_200u64));
+    assert_eq!(coverage.coverage_ratio, Some(to_decimals(2u64)));
+
+    let msg = engine
+        .close_position(vamm.addr().to_string(), to_decimals(0u64))
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let open_interest_notional = engine
+        .open_interest_notional(&router, vamm.addr().to_string())
+        .unwrap();
+    assert_eq!(open_interest_notional, Uint128::zero());
+}
+
+#[test]
+fn test_check_solvency_shuts_down_undercovered_vamm() {
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        bob,
+        engine,
+        vamm,
+        insurance_fund,
+        ..
+    } = SimpleScenario::new();
+
+    // the check is disabled until a minimum ratio is configured
+    let msg = insurance_fund.check_solvency(None, None).unwrap();
+    let err = router.execute(bob.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Solvency check is disabled"
+    );
+
+    let msg = insurance_fund
+        .update_config(
+            Some(to_decimals(3u64)),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+    let err = router.execute(alice.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Generic error: unauthorized");
+    router.execute(owner.clone(), msg).unwrap();
+
+    let config = insurance_fund.config(&router).unwrap();
+    assert_eq!(config.min_coverage_ratio, to_decimals(3u64));
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(300u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    // 5000 / 600 is above the minimum so nothing happens
+    let msg = insurance_fund.check_solvency(None, None).unwrap();
+    let res = router.execute(bob.clone(), msg).unwrap();
+    assert!(!res.events.iter().any(|e| e.ty == "wasm-insolvent_vamm"));
+    assert!(vamm.state(&router).unwrap().open);
+
+    // 1200 / 600 is below the minimum
+    let msg = insurance_fund
+        .set_budget(vamm.addr().to_string(), Some(to_decimals(1_200u64)))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = insurance_fund.check_solvency(None, None).unwrap();
+    let res = router.execute(bob.clone(), msg).unwrap();
+    let event = res
+        .events
+        .iter()
+        .find(|e| e.ty == "wasm-insolvent_vamm")
+        .unwrap();
+    let attribute = |key: &str| {
+        event
+            .attributes
+            .iter()
+            .find(|a| a.key == key)
+            .unwrap()
+            .value
+            .clone()
+    };
+    assert_eq!(attribute("vamm"), vamm.addr().to_string());
+    assert_eq!(attribute("available"), to_decimals(1_200u64).to_string());
+    assert_eq!(
+        attribute("open_interest_notional"),
+        to_decimals(600u64).to_string()
+    );
+    assert_eq!(attribute("coverage_ratio"), to_decimals(2u64).to_string());
+    assert!(!vamm.state(&router).unwrap().open);
+
+    // closed vamms are skipped
+    let msg = insurance_fund.check_solvency(None, None).unwrap();
+    let res = router.execute(bob.clone(), msg).unwrap();
+    assert!(!res.events.iter().any(|e| e.ty == "wasm-insolvent_vamm"));
+
+    let msg = insurance_fund.reopen_vamm(vamm.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    assert!(vamm.state(&router).unwrap().open);
+}
+
+#[test]
+fn test_check_solvency_splits_balance_between_vamms_a_page_at_a_time() {
+    let mut scenario = SimpleScenario::new();
+    let expiry = scenario.router.block_info().time.seconds() + 86_400 * 30;
+    let dated_vamm = scenario.add_dated_vamm(expiry);
+
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        bob,
+        engine,
+        vamm,
+        insurance_fund,
+        ..
+    } = scenario;
+
+    let msg = insurance_fund
+        .update_config(
+            Some(to_decimals(5u64)),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    for market in [&vamm, &dated_vamm] {
+        let msg = engine
+            .open_position(
+                market.addr().to_string(),
+                Side::Buy,
+                to_decimals(300u64),
+                to_decimals(2u64),
+                to_decimals(0u64),
+                vec![],
+            )
+            .unwrap();
+        router.execute(alice.clone(), msg).unwrap();
+    }
+
+    // the balance is shared, so each vamm is only covered by half of it, 2500 / 600
+    let coverage = insurance_fund
+        .coverage(vamm.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(coverage.available, to_decimals(2_500u64));
+    assert_eq!(
+        coverage.coverage_ratio,
+        Some(Uint128::from(4_166_666_666u128))
+    );
+
+    let insolvent_vamms = |res: &cw_multi_test::AppResponse| {
+        res.events
+            .iter()
+            .filter(|e| e.ty == "wasm-insolvent_vamm")
+            .map(|e| {
+                e.attributes
+                    .iter()
+                    .find(|a| a.key == "vamm")
+                    .unwrap()
+                    .value
+                    .clone()
+            })
+            .collect::<Vec<String>>()
+    };
+    let last_vamm = |res: &cw_multi_test::AppResponse| {
+        res.events
+            .iter()
+            .flat_map(|e| e.attributes.iter())
+            .find(|a| a.key == "last_vamm")
+            .map(|a| a.value.clone())
+    };
+
+    // the first page only checks one vamm
+    let msg = insurance_fund.check_solvency(None, Some(1)).unwrap();
+    let res = router.execute(bob.clone(), msg).unwrap();
+    let first = insolvent_vamms(&res);
+    assert_eq!(first.len(), 1);
+    assert_eq!(last_vamm(&res), Some(first[0].clone()));
+
+    // the shut down vamm no longer shares the balance, so the other one is covered by all of
+    // it when the next page picks up where it stopped, 5000 / 600
+    let other = if vamm.addr() == first[0] {
+        &dated_vamm
+    } else {
+        &vamm
+    };
+    let coverage = insurance_fund
+        .coverage(other.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(coverage.available, to_decimals(5_000u64));
+
+    let msg = insurance_fund
+        .check_solvency(Some(first[0].clone()), Some(1))
+        .unwrap();
+    let res = router.execute(bob.clone(), msg).unwrap();
+    assert!(insolvent_vamms(&res).is_empty());
+    assert_eq!(last_vamm(&res), Some(other.addr().to_string()));
+
+    assert!(other.state(&router).unwrap().open);
+
+    // there is nothing left after the last page
+    let msg = insurance_fund
+        .check_solvency(Some(other.addr().to_string()), Some(1))
+        .unwrap();
+    let res = router.execute(bob.clone(), msg).unwrap();
+    assert!(insolvent_vamms(&res).is_empty());
+    assert_eq!(last_vamm(&res), None);
+}
+
+#[test]
+fn test_balance_is_shared_between_the_active_vamms() {
+    let mut scenario = SimpleScenario::new();
+    let expiry = scenario.router.block_info().time.seconds() + 86_400 * 30;
+    let dated_vamm = scenario.add_dated_vamm(expiry);
+
+    let SimpleScenario {
+        mut router,
+        owner,
+        vamm,
+        insurance_fund,
+        ..
+    } = scenario;
+
+    let available = |router: &cw_multi_test::App| {
+        insurance_fund
+            .coverage(vamm.addr().to_string(), router)
+            .unwrap()
+            .available
+    };
+    assert_eq!(available(&router), to_decimals(2_500u64));
+
+    // a vamm that is shut down no longer shares the balance
+    let msg = insurance_fund
+        .shutdown_vamm(dated_vamm.addr().to_string())
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    assert_eq!(available(&router), to_decimals(5_000u64));
+
+    // until it is reopened
+    let msg = insurance_fund
+        .reopen_vamm(dated_vamm.addr().to_string())
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    assert_eq!(available(&router), to_decimals(2_500u64));
+
+    let msg = insurance_fund
+        .remove_vamm(dated_vamm.addr().to_string())
+        .unwrap();
+    router.execute(owner, msg).unwrap();
+    assert_eq!(available(&router), to_decimals(5_000u64));
+}
+
+#[test]
+fn test_funding_is_capped_by_the_allowance() {
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        engine,
+        vamm,
+        pricefeed,
+        insurance_fund,
+        usdc,
+        ..
+    } = SimpleScenario::new();
+
+    // the engine can draw 100 a day from the insurance fund
+    let msg = insurance_fund
+        .update_config(
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(to_decimals(100u64)),
+            None,
+            None,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(300u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    // the index is above the mark so the fund pays longs 37.5 x 6 = 225
+    let msg = pricefeed
+        .append_price(
+            "ETH".to_string(),
+            Uint128::from(31_600_000_000u128),
+            1_000_000_000,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(86_400);
+        block.height += 1;
+    });
+
+    // the fund only pays the 100 left of its allowance, so the funding is scaled to match
+    let msg = engine.pay_funding(vamm.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    assert!(insurance_fund
+        .pending_withdrawals(None, None, &router)
+        .unwrap()
+        .withdrawals
+        .is_empty());
+
+    // the scaled premium fraction is rounded down, so a little of the allowance is left
+    let paid = Uint128::from(99_999_999_975u128);
+    let allowance = insurance_fund.withdrawal_allowance(&router).unwrap();
+    assert_eq!(allowance.withdrawn, paid);
+
+    let engine_balance = usdc.balance::<_, _, Empty>(&router, engine.addr()).unwrap();
+    assert_eq!(engine_balance, to_decimals(300u64) + paid);
+
+    // alice is only credited with the funding that was paid, so the engine can close her
+    let alice_balance = usdc.balance::<_, _, Empty>(&router, alice.clone()).unwrap();
+    let msg = engine
+        .close_position(vamm.addr().to_string(), to_decimals(0u64))
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+    assert_eq!(
+        usdc.balance::<_, _, Empty>(&router, alice.clone()).unwrap(),
+        alice_balance + to_decimals(300u64) + paid
+    );
+}
+
+#[test]
+fn test_bad_debt_over_the_allowance_is_paid_in_full() {
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        bob,
+        engine,
+        vamm,
+        pricefeed,
+        insurance_fund,
+        ..
+    } = SimpleScenario::new();
+
+    // the engine can draw 1000 a day from the insurance fund
+    let msg = insurance_fund
+        .update_config(
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(to_decimals(1_000u64)),
+            None,
+            None,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(300u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Sell,
+            to_decimals(1200u64),
+            to_decimals(1u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(bob.clone(), msg).unwrap();
+
+    let msg = pricefeed
+        .append_price(
+            "ETH".to_string(),
+            Uint128::from(21_600_000_000u128),
+            1_000_000_000,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(86_400);
+        block.height += 1;
+    });
+
+    let msg = engine.pay_funding(vamm.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // the bad debt of 2580 is over the allowance but is paid in full for the liquidation
+    let msg = engine
+        .liquidate(vamm.addr().to_string(), bob.to_string(), to_decimals(0u64))
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+    assert!(insurance_fund
+        .pending_withdrawals(None, None, &router)
+        .unwrap()
+        .withdrawals
+        .is_empty());
+
+    // it still uses up the allowance of the window
+    let allowance = insurance_fund.withdrawal_allowance(&router).unwrap();
+    assert_eq!(allowance.withdrawn, to_decimals(2_580u64));
+    assert_eq!(allowance.remaining, Some(Uint128::zero()));
+
+    let ledger = insurance_fund
+        .ledger(vamm.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(ledger.bad_debt_draws, to_decimals(2_580u64));
+}
@@ -188,6 +188,83 @@ fn test_get_margin_higher_twap() {
     assert_eq!(margin_ratio, Integer::new_positive(96_890_936u128));
 }
 
+#[test]
+fn test_get_margin_ratio_using_mark_price() {
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        bob,
+        engine,
+        vamm,
+        pricefeed,
+        ..
+    } = SimpleScenario::new();
+
+    let msg = engine.set_use_mark_price(true).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let price: Uint128 = to_decimals(10u64);
+    let timestamp: u64 = 1_000_000_000;
+
+    let msg = pricefeed
+        .append_price("ETH".to_string(), price, timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // moves block forward 1 and 15 secs timestamp
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(25u64),
+            to_decimals(10u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15 * 62);
+        block.height += 62;
+    });
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Sell,
+            to_decimals(15u64),
+            to_decimals(10u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(bob.clone(), msg).unwrap();
+
+    // moves block forward 1 and 15 secs timestamp
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+
+    // the oracle twap matches the oracle so the mark is the amm twap
+    let mark_price = vamm.mark_price(&router).unwrap();
+    assert_eq!(mark_price, Uint128::from(15_482_031_249u128));
+
+    // mark pnl: 20 * 15.482 - 250 = 59.64, spot pnl: 198.36 - 250 = -51.64
+    // the smaller pnl is used, so expect to be (25 - 51.64) / 198.36 = -0.1342975
+    let margin_ratio = engine
+        .get_margin_ratio(&router, vamm.addr().to_string(), alice.to_string())
+        .unwrap();
+    assert_eq!(margin_ratio, Integer::new_negative(134_297_520u128));
+}
+
 #[test]
 fn test_verify_margin_ratio_funding_payment_positive() {
     let SimpleScenario {
@@ -0,0 +1,118 @@
+use cosmwasm_std::Uint128;
+use cw_multi_test::Executor;
+use margined_perp::margined_engine::Side;
+use margined_perp::margined_vamm::{TradingHoliday, TradingSchedule};
+use margined_utils::scenarios::{to_decimals, SimpleScenario};
+
+#[test]
+fn test_only_reducing_trades_allowed_while_market_is_closed() {
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        bob,
+        engine,
+        vamm,
+        ..
+    } = SimpleScenario::new();
+
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(60u64),
+            to_decimals(10u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    // close the market for a day from now
+    let now = router.block_info().time.seconds();
+    let schedule = TradingSchedule {
+        sessions: vec![],
+        holidays: vec![TradingHoliday {
+            start: now,
+            end: now + 86_400u64,
+        }],
+        reduce_only_when_closed: true,
+    };
+    let msg = vamm.set_trading_schedule(schedule.clone()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+
+    // increasing a position is rejected
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(10u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    let err = router.execute(alice.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "Generic error: vAMM market is closed".to_string()
+    );
+
+    // as is opening a new position
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Sell,
+            to_decimals(10u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    let err = router.execute(bob.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "Generic error: vAMM market is closed".to_string()
+    );
+
+    // reducing a position is allowed
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Sell,
+            to_decimals(10u64),
+            to_decimals(2u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    // nothing can be traded once reducing trades are disabled
+    let msg = vamm
+        .set_trading_schedule(TradingSchedule {
+            reduce_only_when_closed: false,
+            ..schedule
+        })
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+
+    let msg = engine
+        .close_position(vamm.addr().to_string(), Uint128::zero())
+        .unwrap();
+    let err = router.execute(alice.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "Generic error: vAMM market is closed".to_string()
+    );
+}
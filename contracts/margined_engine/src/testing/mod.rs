@@ -1,20 +1,25 @@
 #[cfg(test)]
 mod bad_debt_tests;
+mod circuit_breaker_tests;
 mod cw_token_add_remove_margin_tests;
 mod cw_token_liquidation_frontrun_hack_tests;
 mod cw_token_liquidation_tests;
 mod cw_token_pay_funding_tests;
 mod cw_token_position_fee_tests;
+mod expiry_tests;
 mod fee_calculation_tests;
 mod fluctuation_tests;
+mod insurance_coverage_tests;
 mod margin_engine_tests;
 mod margin_ratio_tests;
+mod market_status_tests;
 mod native_token_add_remove_margin_tests;
 mod native_token_liquidation_frontrun_hack_tests;
 mod native_token_liquidation_tests;
 mod native_token_pay_funding_tests;
 mod native_token_position_fee_tests;
 mod open_interest_notional_tests;
+mod parse_response_tests;
 mod pausable_tests;
 mod personal_position_tests;
 mod position_liquidation_tests;
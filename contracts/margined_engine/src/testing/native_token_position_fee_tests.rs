@@ -137,6 +137,88 @@ fn test_ten_percent_fee_open_long_position() {
     assert_eq!(engine_balance, Uint128::from(60_000_000u64));
 }
 
+#[test]
+fn test_skew_adjusted_fee_open_long_position() {
+    let NativeTokenScenario {
+        mut router,
+        owner,
+        alice,
+        bob,
+        engine,
+        vamm,
+        ..
+    } = NativeTokenScenario::new();
+
+    let msg = vamm.set_toll_ratio(Uint128::zero()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // 1% spread, increased by half of the skew as a fraction of the base reserve
+    let msg = vamm.set_spread_ratio(Uint128::from(10_000u128)).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm.set_skew_fee_ratio(Uint128::from(500_000u128)).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // alice opens long position with 60 margin, 10x leverage
+    // (1000 + 600) * (100 + baseAssetDelta) = 100k, baseAssetDelta = -37.5
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            Uint128::from(60_000_000u64),
+            Uint128::from(10_000_000u64),
+            Uint128::from(37_500_000u64),
+            calculate_funds_needed(
+                &router,
+                engine.addr(),
+                alice.clone(),
+                Uint128::from(60_000_000u64),
+                Uint128::from(10_000_000u64),
+                Side::Buy,
+                vamm.addr(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    // transferred = margin + fee = 60 + (600 * 1%) = 66
+    let alice_balance = router.wrap().query_balance(&alice, "uwasm").unwrap().amount;
+    assert_eq!(alice_balance, Uint128::new(4_934_000_000));
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+
+    // bob opens long position with 20 margin, 5x leverage
+    // spread ratio = 1% + 0.5 * 37.5 / 62.5 = 31%
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            Uint128::from(20_000_000u64),
+            Uint128::from(5_000_000u64),
+            Uint128::zero(),
+            calculate_funds_needed(
+                &router,
+                engine.addr(),
+                bob.clone(),
+                Uint128::from(20_000_000u64),
+                Uint128::from(5_000_000u64),
+                Side::Buy,
+                vamm.addr(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    router.execute(bob.clone(), msg).unwrap();
+
+    // transferred = margin + fee = 20 + (100 * 31%) = 51
+    let bob_balance = router.wrap().query_balance(&bob, "uwasm").unwrap().amount;
+    assert_eq!(bob_balance, Uint128::new(4_949_000_000));
+}
+
 #[test]
 fn test_force_error_insufficient_token_long_position() {
     let NativeTokenScenario {
@@ -0,0 +1,132 @@
+use crate::utils::{parse_pay_funding, parse_swap};
+use cosmwasm_std::{to_binary, Addr, Binary, Event, SubMsgResponse, Uint128};
+use margined_common::integer::Integer;
+use margined_perp::margined_vamm::{Direction, FundingResult, SwapResult, SwapType};
+use margined_utils::scenarios::to_decimals;
+
+// wraps the data in a protobuf encoded MsgExecuteContractResponse like the chain does
+fn execute_response_data(data: Binary) -> Binary {
+    let mut encoded = vec![0x0a];
+    let mut len = data.len();
+    while len >= 0x80 {
+        encoded.push((len as u8 & 0x7f) | 0x80);
+        len >>= 7;
+    }
+    encoded.push(len as u8);
+    encoded.extend_from_slice(data.as_slice());
+    encoded.into()
+}
+
+fn swap_event(swap_type: &str) -> Event {
+    Event::new("wasm")
+        .add_attribute("_contract_addr", "contract1")
+        .add_attribute("action", "swap")
+        .add_attribute("type", swap_type)
+        .add_attribute("direction", Direction::AddToAmm.to_string())
+        .add_attribute("quote_asset_amount", to_decimals(100).to_string())
+        .add_attribute("base_asset_amount", to_decimals(10).to_string())
+}
+
+#[test]
+fn test_parse_swap_from_response_data() {
+    let result = SwapResult {
+        swap_type: SwapType::Input,
+        direction: Direction::AddToAmm,
+        quote_asset_amount: to_decimals(100),
+        base_asset_amount: to_decimals(10),
+    };
+    let response = SubMsgResponse {
+        events: vec![],
+        data: Some(execute_response_data(to_binary(&result).unwrap())),
+    };
+    assert_eq!(
+        parse_swap(response).unwrap(),
+        (to_decimals(100), to_decimals(10))
+    );
+
+    // output swaps take in the base asset
+    let result = SwapResult {
+        swap_type: SwapType::Output,
+        ..result
+    };
+    let response = SubMsgResponse {
+        events: vec![],
+        data: Some(execute_response_data(to_binary(&result).unwrap())),
+    };
+    assert_eq!(
+        parse_swap(response).unwrap(),
+        (to_decimals(10), to_decimals(100))
+    );
+}
+
+#[test]
+fn test_parse_swap_from_events_without_response_data() {
+    let response = SubMsgResponse {
+        events: vec![swap_event("input")],
+        data: None,
+    };
+    assert_eq!(
+        parse_swap(response).unwrap(),
+        (to_decimals(100), to_decimals(10))
+    );
+
+    let response = SubMsgResponse {
+        events: vec![swap_event("output")],
+        data: None,
+    };
+    assert_eq!(
+        parse_swap(response).unwrap(),
+        (to_decimals(10), to_decimals(100))
+    );
+}
+
+#[test]
+fn test_parse_pay_funding_from_response_data() {
+    let result = FundingResult {
+        vamm: Addr::unchecked("contract1"),
+        premium_fraction: Integer::new_negative(Uint128::from(25_000_000u128)),
+        underlying_price: to_decimals(10),
+        index_price: Uint128::from(10_025_000_000u128),
+    };
+    let response = SubMsgResponse {
+        events: vec![],
+        data: Some(execute_response_data(to_binary(&result).unwrap())),
+    };
+    assert_eq!(
+        parse_pay_funding(response).unwrap(),
+        (
+            Integer::new_negative(Uint128::from(25_000_000u128)),
+            "contract1".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_parse_pay_funding_from_events_without_response_data() {
+    let event = Event::new("wasm")
+        .add_attribute("_contract_address", "contract1")
+        .add_attribute("action", "settle_funding")
+        .add_attribute("premium_fraction", "-25000000")
+        .add_attribute("underlying_price", to_decimals(10).to_string())
+        .add_attribute("index_price", "10025000000");
+    let response = SubMsgResponse {
+        events: vec![event],
+        data: None,
+    };
+    assert_eq!(
+        parse_pay_funding(response).unwrap(),
+        (
+            Integer::new_negative(Uint128::from(25_000_000u128)),
+            "contract1".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_force_error_parse_swap_malformed_response_data() {
+    let response = SubMsgResponse {
+        events: vec![swap_event("input")],
+        data: Some(Binary::from(vec![0x0a, 0x05, 0x01])),
+    };
+    assert!(parse_swap(response).is_err());
+}
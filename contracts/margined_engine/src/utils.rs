@@ -1,8 +1,10 @@
 use cosmwasm_std::{
-    Addr, Deps, DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult, Storage, SubMsg,
-    SubMsgResponse, Uint128,
+    from_binary, Addr, Deps, DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult,
+    Storage, SubMsg, SubMsgResponse, Uint128,
 };
 
+use cw_utils::parse_execute_response_data;
+use serde::de::DeserializeOwned;
 use std::str::FromStr;
 
 use margined_common::{
@@ -12,17 +14,22 @@ use margined_common::{
 use margined_perp::margined_engine::{
     PnlCalcOption, Position, PositionUnrealizedPnlResponse, RemainMarginResponse, Side,
 };
-use margined_perp::margined_vamm::Direction;
+use margined_perp::margined_insurance_fund::DrawKind;
+use margined_perp::margined_vamm::{Direction, FundingResult, SwapResult, SwapType};
 
 use crate::{
     contract::{PAUSER, WHITELIST},
-    messages::execute_insurance_fund_withdrawal,
+    messages::{execute_insurance_fund_withdrawal, execute_settle_funding},
     querier::{
-        query_insurance_is_vamm, query_vamm_config, query_vamm_output_amount,
+        query_insurance_is_vamm, query_vamm_accrued_premium_fraction, query_vamm_config,
+        query_vamm_mark_price, query_vamm_market_status, query_vamm_output_amount,
         query_vamm_output_twap, query_vamm_state, query_vamm_underlying_price,
     },
     query::query_cumulative_premium_fraction,
-    state::{read_config, read_position, read_state, read_vamm_map, store_state, State},
+    state::{
+        read_config, read_position, read_state, read_vamm_map, store_state,
+        update_vamm_open_interest_notional, State,
+    },
 };
 
 // reads position from storage but also handles the case where there is no
@@ -71,6 +78,7 @@ pub fn get_asset(info: MessageInfo, eligible_collateral: AssetInfo) -> Asset {
 
 pub fn realize_bad_debt(
     deps: Deps,
+    vamm: &Addr,
     bad_debt: Uint128,
     messages: &mut Vec<SubMsg>,
     state: &mut State,
@@ -79,10 +87,14 @@ pub fn realize_bad_debt(
         // no need to move extra tokens because vault already prepay bad debt, only need to update the numbers
         state.prepaid_bad_debt = state.prepaid_bad_debt.checked_sub(bad_debt).unwrap();
     } else {
-        // in order to realize all the bad debt vault need extra tokens from insuranceFund
+        // in order to realize all the bad debt vault need extra tokens from insuranceFund,
+        // which pays bad debt in full so that positions can always be liquidated
         let bad_debt_delta = bad_debt.checked_sub(state.prepaid_bad_debt).unwrap();
 
-        messages.push(execute_insurance_fund_withdrawal(deps, bad_debt_delta).unwrap());
+        messages.push(
+            execute_insurance_fund_withdrawal(deps, vamm, DrawKind::BadDebt, bad_debt_delta)
+                .unwrap(),
+        );
 
         state.prepaid_bad_debt = Uint128::zero();
 
@@ -92,15 +104,27 @@ pub fn realize_bad_debt(
     Uint128::zero()
 }
 
+// vamms with continuous funding accrue a premium every block, this settles whatever has
+// accrued so that it is paid before the position is updated
+pub fn settle_accrued_funding(deps: Deps, vamm: &Addr) -> StdResult<Vec<SubMsg>> {
+    let accrued_premium_fraction = query_vamm_accrued_premium_fraction(&deps, vamm.to_string())?;
+
+    if accrued_premium_fraction.is_zero() {
+        return Ok(vec![]);
+    }
+
+    Ok(vec![execute_settle_funding(vamm)?])
+}
+
 // this blocks trades if open interest is too high, required during the bootstrapping of the project
 pub fn update_open_interest_notional(
-    deps: &Deps,
+    deps: &mut DepsMut,
     state: &mut State,
     vamm: Addr,
     amount: Integer,
     trader: Addr,
 ) -> StdResult<Response> {
-    let cap = query_vamm_config(deps, vamm.to_string())?.open_interest_notional_cap;
+    let cap = query_vamm_config(&deps.as_ref(), vamm.to_string())?.open_interest_notional_cap;
 
     let mut updated_open_interest =
         amount.checked_add(Integer::new_positive(state.open_interest_notional))?;
@@ -113,13 +137,15 @@ pub fn update_open_interest_notional(
     if (!cap.is_zero()
         && amount.is_positive()
         && updated_open_interest > Integer::new_positive(cap))
-        && !WHITELIST.query_hook(deps.to_owned(), trader.to_string())?
+        && !WHITELIST.query_hook(deps.as_ref(), trader.to_string())?
     {
         return Err(StdError::generic_err("open interest exceeds cap"));
     }
 
     state.open_interest_notional = updated_open_interest.value;
 
+    update_vamm_open_interest_notional(deps.storage, vamm, amount)?;
+
     Ok(Response::new())
 }
 
@@ -212,6 +238,14 @@ pub fn get_position_notional_unrealized_pnl(
                     .checked_mul(position.size.value)?
                     .checked_div(config.decimals)?;
             }
+            PnlCalcOption::MarkPrice => {
+                let config = read_config(deps.storage)?;
+                let mark_price: Uint128 = query_vamm_mark_price(&deps, position.vamm.to_string())?;
+
+                output_notional = mark_price
+                    .checked_mul(position.size.value)?
+                    .checked_div(config.decimals)?;
+            }
         }
 
         // we are short if the size of the position is less than 0
@@ -347,6 +381,37 @@ pub fn require_vamm(deps: Deps, insurance: &Addr, vamm: &Addr) -> StdResult<Resp
     Ok(Response::new())
 }
 
+// check that a dated vamm is not within its cutoff period before expiry
+pub fn require_not_expiring(deps: Deps, env: &Env, vamm: &Addr) -> StdResult<Response> {
+    let vamm_config = query_vamm_config(&deps, vamm.to_string())?;
+
+    if let Some(expiry) = vamm_config.expiry {
+        let now = env.block.time.seconds();
+        if now.saturating_add(vamm_config.expiry_cutoff_period) >= expiry {
+            return Err(StdError::generic_err("vAMM is too close to expiry"));
+        }
+    }
+
+    Ok(Response::new())
+}
+
+// check that the vamm market is open, or that the trade only reduces the position if the
+// market allows reducing trades while closed
+pub fn require_market_open(deps: Deps, vamm: &Addr, is_reduce: bool) -> StdResult<Response> {
+    let market_status = query_vamm_market_status(&deps, vamm.to_string())?;
+
+    let is_allowed = market_status.is_open || (market_status.is_reduce_only && is_reduce);
+    if !is_allowed {
+        if market_status.is_circuit_breaker_tripped {
+            return Err(StdError::generic_err("vAMM circuit breaker is tripped"));
+        }
+
+        return Err(StdError::generic_err("vAMM market is closed"));
+    }
+
+    Ok(Response::new())
+}
+
 // Check no bad debt
 pub fn require_bad_debt(bad_debt: Uint128) -> StdResult<Response> {
     if !bad_debt.is_zero() {
@@ -423,7 +488,14 @@ pub fn require_non_zero_input(input: Uint128) -> StdResult<Response> {
 }
 
 pub fn parse_swap(response: SubMsgResponse) -> StdResult<(Uint128, Uint128)> {
-    // Find swap inputs and output events
+    if let Some(result) = parse_response_data::<SwapResult>(&response)? {
+        return Ok(match result.swap_type {
+            SwapType::Input => (result.quote_asset_amount, result.base_asset_amount),
+            SwapType::Output => (result.base_asset_amount, result.quote_asset_amount),
+        });
+    }
+
+    // vAMMs that don't return a swap result, find swap inputs and output events
     let wasm = response.events.iter().find(|&e| e.ty == "wasm");
 
     let wasm = wasm.unwrap();
@@ -456,7 +528,11 @@ pub fn parse_swap(response: SubMsgResponse) -> StdResult<(Uint128, Uint128)> {
 }
 
 pub fn parse_pay_funding(response: SubMsgResponse) -> StdResult<(Integer, String)> {
-    // Find swap inputs and output events
+    if let Some(result) = parse_response_data::<FundingResult>(&response)? {
+        return Ok((result.premium_fraction, result.vamm.to_string()));
+    }
+
+    // vAMMs that don't return a funding result, find the premium fraction in the events
     let wasm = response.events.iter().find(|&e| e.ty == "wasm");
     let wasm = wasm.unwrap();
 
@@ -468,6 +544,23 @@ pub fn parse_pay_funding(response: SubMsgResponse) -> StdResult<(Integer, String
     Ok((premium, sender))
 }
 
+// Decodes the result a vAMM returns in the data of its response, the data of an executed
+// submessage is wrapped in the protobuf encoded MsgExecuteContractResponse
+fn parse_response_data<T: DeserializeOwned>(response: &SubMsgResponse) -> StdResult<Option<T>> {
+    let data = match &response.data {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+
+    let execute_response = parse_execute_response_data(data.as_slice())
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    execute_response
+        .data
+        .map(|data| from_binary(&data))
+        .transpose()
+}
+
 // Reads contract address from an event takes into account that there are
 // inconistencies between cosmwasm versions and multitest etc...
 fn read_contract_address(event: &Event) -> StdResult<String> {
@@ -0,0 +1,95 @@
+#[cfg(not(feature = "library"))]
+use crate::error::ContractError;
+use crate::{
+    handle::{create_market, instantiate_vamm_reply, update_config, update_owner},
+    query::{query_config, query_market, query_markets, query_owner},
+    state::{store_config, Config},
+};
+
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError,
+    StdResult, SubMsgResult,
+};
+use cw2::set_contract_version;
+use cw_controllers::Admin;
+use margined_perp::margined_factory::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+/// Contract name that is used for migration.
+const CONTRACT_NAME: &str = "crates.io:margined-factory";
+/// Contract version that is used for migration.
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Owner admin
+pub const OWNER: Admin = Admin::new("owner");
+
+pub const INSTANTIATE_VAMM_REPLY_ID: u64 = 1u64;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let config = Config {
+        vamm_code_id: msg.vamm_code_id,
+        engine: deps.api.addr_validate(&msg.engine)?,
+        insurance_fund: deps.api.addr_validate(&msg.insurance_fund)?,
+        pricefeed: deps.api.addr_validate(&msg.pricefeed)?,
+    };
+
+    store_config(deps.storage, &config)?;
+
+    OWNER.set(deps, Some(info.sender))?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            vamm_code_id,
+            engine,
+            insurance_fund,
+            pricefeed,
+        } => update_config(deps, info, vamm_code_id, engine, insurance_fund, pricefeed),
+        ExecuteMsg::UpdateOwner { owner } => update_owner(deps, info, owner),
+        ExecuteMsg::CreateMarket { symbol, params } => create_market(deps, info, symbol, params),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::GetOwner {} => to_binary(&query_owner(deps)?),
+        QueryMsg::Market { symbol } => to_binary(&query_market(deps, symbol)?),
+        QueryMsg::Markets { start_after, limit } => {
+            to_binary(&query_markets(deps, start_after, limit)?)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> StdResult<Response> {
+    match msg.result {
+        SubMsgResult::Ok(response) => match msg.id {
+            INSTANTIATE_VAMM_REPLY_ID => instantiate_vamm_reply(deps, env, response),
+            _ => Err(StdError::generic_err(format!(
+                "reply (id {:?}) invalid",
+                msg.id
+            ))),
+        },
+        SubMsgResult::Err(e) => Err(StdError::generic_err(format!(
+            "reply (id {:?}) error {:?}",
+            msg.id, e
+        ))),
+    }
+}
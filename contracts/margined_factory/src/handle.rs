@@ -0,0 +1,202 @@
+use cosmwasm_std::{
+    DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult, SubMsgResponse,
+};
+use margined_common::validate::{
+    validate_assets, validate_decimal_places, validate_non_fraction, validate_ratio,
+};
+use margined_perp::margined_factory::MarketParams;
+use margined_perp::margined_vamm::InstantiateMsg as VammInstantiateMsg;
+
+use crate::{
+    contract::OWNER,
+    messages::{
+        execute_add_vamm, execute_instantiate_vamm, execute_vamm_open, execute_vamm_update_owner,
+    },
+    querier::query_pricefeed_price,
+    state::{read_config, store_config, Config, Market, PendingMarket, MARKETS, PENDING_MARKET},
+};
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    vamm_code_id: Option<u64>,
+    engine: Option<String>,
+    insurance_fund: Option<String>,
+    pricefeed: Option<String>,
+) -> StdResult<Response> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // check permission
+    if !OWNER.is_admin(deps.as_ref(), &info.sender)? {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    // change code id of the vamm contract
+    if let Some(vamm_code_id) = vamm_code_id {
+        config.vamm_code_id = vamm_code_id;
+    }
+
+    // change the margin engine that new vamms are wired to
+    if let Some(engine) = engine {
+        config.engine = deps.api.addr_validate(engine.as_str())?;
+    }
+
+    // change the insurance fund that new vamms are registered with
+    if let Some(insurance_fund) = insurance_fund {
+        config.insurance_fund = deps.api.addr_validate(insurance_fund.as_str())?;
+    }
+
+    // change the pricefeed of new vamms
+    if let Some(pricefeed) = pricefeed {
+        config.pricefeed = deps.api.addr_validate(pricefeed.as_str())?;
+    }
+
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::default().add_attribute("action", "update_config"))
+}
+
+pub fn update_owner(deps: DepsMut, info: MessageInfo, owner: String) -> StdResult<Response> {
+    // validate the address
+    let valid_owner = deps.api.addr_validate(&owner)?;
+
+    OWNER
+        .execute_update_admin(deps, info, Some(valid_owner))
+        .map_err(|error| StdError::generic_err(format!("{}", error)))
+}
+
+// Instantiates the vamm of a new market, the market is registered in the reply
+pub fn create_market(
+    deps: DepsMut,
+    info: MessageInfo,
+    symbol: String,
+    params: MarketParams,
+) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+
+    // check permission
+    if !OWNER.is_admin(deps.as_ref(), &info.sender)? {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    if symbol.is_empty() {
+        return Err(StdError::generic_err("symbol cannot be empty"));
+    }
+
+    if MARKETS.has(deps.storage, &symbol) {
+        return Err(StdError::generic_err("market already exists"));
+    }
+
+    // validate the params here rather than leaving it to the vamm instantiation
+    let decimals = validate_decimal_places(params.decimals)?;
+
+    validate_ratio(params.toll_ratio, decimals)?;
+    validate_ratio(params.spread_ratio, decimals)?;
+    validate_ratio(params.fluctuation_limit_ratio, decimals)?;
+
+    validate_assets(params.base_asset.clone())?;
+    validate_assets(params.quote_asset.clone())?;
+
+    validate_non_fraction(params.base_asset_reserve, decimals)?;
+    validate_non_fraction(params.quote_asset_reserve, decimals)?;
+
+    // the vamm queries the pricefeed with its base asset, so it must already be priced
+    let oracle_key = params.base_asset.clone();
+    let price = query_pricefeed_price(&deps.as_ref(), &config.pricefeed, oracle_key.clone())
+        .map_err(|_| StdError::generic_err("pricefeed has no price for the oracle key"))?;
+    if price.is_zero() {
+        return Err(StdError::generic_err(
+            "pricefeed has no price for the oracle key",
+        ));
+    }
+
+    PENDING_MARKET.save(
+        deps.storage,
+        &PendingMarket {
+            symbol: symbol.clone(),
+            oracle_key,
+        },
+    )?;
+
+    let owner = OWNER.get(deps.as_ref())?.unwrap();
+
+    let msg = VammInstantiateMsg {
+        decimals: params.decimals,
+        pricefeed: config.pricefeed.to_string(),
+        margin_engine: Some(config.engine.to_string()),
+        insurance_fund: Some(config.insurance_fund.to_string()),
+        quote_asset: params.quote_asset,
+        base_asset: params.base_asset,
+        quote_asset_reserve: params.quote_asset_reserve,
+        base_asset_reserve: params.base_asset_reserve,
+        funding_period: params.funding_period,
+        toll_ratio: params.toll_ratio,
+        spread_ratio: params.spread_ratio,
+        fluctuation_limit_ratio: params.fluctuation_limit_ratio,
+        expiry: params.expiry,
+        curve: params.curve,
+    };
+
+    Ok(Response::new()
+        .add_submessage(execute_instantiate_vamm(
+            config.vamm_code_id,
+            owner,
+            format!("margined-vamm-{}", symbol),
+            msg,
+        )?)
+        .add_attributes(vec![("action", "create_market"), ("symbol", &symbol)]))
+}
+
+// Registers the new vamm with the insurance fund, opens it and hands it over to the owner
+pub fn instantiate_vamm_reply(
+    deps: DepsMut,
+    env: Env,
+    response: SubMsgResponse,
+) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+
+    let instantiate = response
+        .events
+        .iter()
+        .find(|&e| e.ty == "instantiate")
+        .ok_or_else(|| StdError::generic_err("No instantiate event found"))?;
+    let vamm = deps
+        .api
+        .addr_validate(&read_contract_address(instantiate)?)?;
+
+    let pending = PENDING_MARKET.load(deps.storage)?;
+    PENDING_MARKET.remove(deps.storage);
+
+    let market = Market {
+        symbol: pending.symbol,
+        vamm: vamm.clone(),
+        oracle_key: pending.oracle_key,
+        launch_time: env.block.time.seconds(),
+    };
+    MARKETS.save(deps.storage, &market.symbol, &market)?;
+
+    let owner = OWNER.get(deps.as_ref())?.unwrap();
+
+    Ok(Response::new()
+        .add_submessage(execute_add_vamm(config.insurance_fund, vamm.clone())?)
+        .add_submessage(execute_vamm_open(vamm.clone())?)
+        .add_submessage(execute_vamm_update_owner(vamm.clone(), owner)?)
+        .add_attributes(vec![
+            ("action", "register_market"),
+            ("symbol", &market.symbol),
+            ("vamm", vamm.as_ref()),
+            ("launch_time", &market.launch_time.to_string()),
+        ]))
+}
+
+// Reads contract address from an event takes into account that there are
+// inconistencies between cosmwasm versions and multitest etc...
+fn read_contract_address(event: &Event) -> StdResult<String> {
+    let result = event
+        .attributes
+        .iter()
+        .find(|&attr| attr.key == "_contract_addr" || attr.key == "_contract_address")
+        .ok_or_else(|| StdError::generic_err("No contract address found"))?;
+
+    Ok(result.value.to_string())
+}
@@ -0,0 +1,10 @@
+pub mod contract;
+mod error;
+mod handle;
+mod messages;
+mod querier;
+mod query;
+mod state;
+
+#[cfg(test)]
+mod testing;
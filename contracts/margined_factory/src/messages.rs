@@ -0,0 +1,68 @@
+use cosmwasm_std::{to_binary, Addr, Binary, CosmosMsg, ReplyOn, StdResult, SubMsg, WasmMsg};
+
+use margined_perp::margined_insurance_fund::ExecuteMsg as InsuranceFundExecuteMessage;
+use margined_perp::margined_vamm::{
+    ExecuteMsg as VammExecuteMessage, InstantiateMsg as VammInstantiateMessage,
+};
+
+use crate::contract::INSTANTIATE_VAMM_REPLY_ID;
+
+pub fn execute_instantiate_vamm(
+    code_id: u64,
+    admin: Addr,
+    label: String,
+    msg: VammInstantiateMessage,
+) -> StdResult<SubMsg> {
+    let msg = WasmMsg::Instantiate {
+        admin: Some(admin.to_string()),
+        code_id,
+        msg: to_binary(&msg)?,
+        funds: vec![],
+        label,
+    };
+
+    Ok(SubMsg::reply_on_success(
+        CosmosMsg::Wasm(msg),
+        INSTANTIATE_VAMM_REPLY_ID,
+    ))
+}
+
+pub fn execute_add_vamm(insurance_fund: Addr, vamm: Addr) -> StdResult<SubMsg> {
+    execute_wasm(
+        insurance_fund,
+        to_binary(&InsuranceFundExecuteMessage::AddVamm {
+            vamm: vamm.to_string(),
+        })?,
+    )
+}
+
+pub fn execute_vamm_open(vamm: Addr) -> StdResult<SubMsg> {
+    execute_wasm(
+        vamm,
+        to_binary(&VammExecuteMessage::SetOpen { open: true })?,
+    )
+}
+
+pub fn execute_vamm_update_owner(vamm: Addr, owner: Addr) -> StdResult<SubMsg> {
+    execute_wasm(
+        vamm,
+        to_binary(&VammExecuteMessage::UpdateOwner {
+            owner: owner.to_string(),
+        })?,
+    )
+}
+
+fn execute_wasm(contract: Addr, msg: Binary) -> StdResult<SubMsg> {
+    let msg = WasmMsg::Execute {
+        contract_addr: contract.to_string(),
+        funds: vec![],
+        msg,
+    };
+
+    Ok(SubMsg {
+        msg: CosmosMsg::Wasm(msg),
+        gas_limit: None,
+        id: 0u64,
+        reply_on: ReplyOn::Never,
+    })
+}
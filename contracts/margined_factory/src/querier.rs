@@ -0,0 +1,12 @@
+// Contains queries for external contracts,
+use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, Uint128, WasmQuery};
+
+use margined_perp::margined_pricefeed::QueryMsg;
+
+// returns the latest price of the key provided by an oracle
+pub fn query_pricefeed_price(deps: &Deps, pricefeed: &Addr, key: String) -> StdResult<Uint128> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pricefeed.to_string(),
+        msg: to_binary(&QueryMsg::GetPrice { key })?,
+    }))
+}
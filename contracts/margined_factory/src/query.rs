@@ -0,0 +1,73 @@
+use cosmwasm_std::{Deps, Order, StdError, StdResult};
+use cw_storage_plus::Bound;
+use margined_perp::margined_factory::{
+    ConfigResponse, MarketResponse, MarketsResponse, OwnerResponse,
+};
+
+use crate::{
+    contract::OWNER,
+    state::{read_config, Config, Market, MARKETS},
+};
+
+const DEFAULT_PAGINATION_LIMIT: u32 = 10u32;
+const MAX_PAGINATION_LIMIT: u32 = 30u32;
+
+/// Queries contract config
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    Ok(ConfigResponse {
+        vamm_code_id: config.vamm_code_id,
+        engine: config.engine,
+        insurance_fund: config.insurance_fund,
+        pricefeed: config.pricefeed,
+    })
+}
+
+/// Queries contract owner from the admin
+pub fn query_owner(deps: Deps) -> StdResult<OwnerResponse> {
+    if let Some(owner) = OWNER.get(deps)? {
+        Ok(OwnerResponse { owner })
+    } else {
+        Err(StdError::generic_err("No owner set"))
+    }
+}
+
+/// Queries the market with the given symbol
+pub fn query_market(deps: Deps, symbol: String) -> StdResult<MarketResponse> {
+    let market = MARKETS
+        .may_load(deps.storage, &symbol)?
+        .ok_or_else(|| StdError::generic_err("market does not exist"))?;
+
+    Ok(market_response(market))
+}
+
+/// Queries the markets ordered by symbol
+pub fn query_markets(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MarketsResponse> {
+    // set the limit for pagination
+    let limit = limit
+        .unwrap_or(DEFAULT_PAGINATION_LIMIT)
+        .min(MAX_PAGINATION_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let markets = MARKETS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, market)| market_response(market)))
+        .collect::<StdResult<Vec<MarketResponse>>>()?;
+
+    Ok(MarketsResponse { markets })
+}
+
+fn market_response(market: Market) -> MarketResponse {
+    MarketResponse {
+        symbol: market.symbol,
+        vamm: market.vamm,
+        oracle_key: market.oracle_key,
+        launch_time: market.launch_time,
+    }
+}
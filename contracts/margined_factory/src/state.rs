@@ -0,0 +1,41 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read};
+use cw_storage_plus::{Item, Map};
+
+pub static KEY_CONFIG: &[u8] = b"config";
+pub const MARKETS: Map<&str, Market> = Map::new("markets");
+pub const PENDING_MARKET: Item<PendingMarket> = Item::new("pending-market");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Config {
+    pub vamm_code_id: u64,
+    pub engine: Addr,
+    pub insurance_fund: Addr,
+    pub pricefeed: Addr,
+}
+
+pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+    singleton(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Market {
+    pub symbol: String,
+    pub vamm: Addr,
+    pub oracle_key: String,
+    pub launch_time: u64,
+}
+
+/// The market whose vAMM is being instantiated, it is registered once the reply arrives
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingMarket {
+    pub symbol: String,
+    pub oracle_key: String,
+}
@@ -0,0 +1,282 @@
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, Addr, StdError, Uint128};
+use cw_multi_test::Executor;
+use margined_perp::margined_engine::Side;
+use margined_perp::margined_factory::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MarketParams, QueryMsg,
+};
+use margined_perp::margined_vamm::{OwnerResponse, QueryMsg as VammQueryMsg};
+use margined_utils::contracts::helpers::VammController;
+use margined_utils::scenarios::{to_decimals, SimpleScenario};
+
+fn market_params(base_asset: &str) -> MarketParams {
+    MarketParams {
+        decimals: 9u8,
+        quote_asset: "USD".to_string(),
+        base_asset: base_asset.to_string(),
+        quote_asset_reserve: to_decimals(1_000),
+        base_asset_reserve: to_decimals(100),
+        funding_period: 86_400u64,
+        toll_ratio: Uint128::zero(),
+        spread_ratio: Uint128::zero(),
+        fluctuation_limit_ratio: Uint128::zero(),
+        expiry: None,
+        curve: None,
+    }
+}
+
+#[test]
+fn test_instantiation() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        vamm_code_id: 1u64,
+        engine: "engine".to_string(),
+        insurance_fund: "insurance_fund".to_string(),
+        pricefeed: "pricefeed".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            vamm_code_id: 1u64,
+            engine: Addr::unchecked("engine"),
+            insurance_fund: Addr::unchecked("insurance_fund"),
+            pricefeed: Addr::unchecked("pricefeed"),
+        }
+    );
+
+    // only the owner can change the config
+    let info = mock_info("addr0001", &[]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::UpdateConfig {
+            vamm_code_id: Some(2u64),
+            engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("unauthorized"));
+}
+
+#[test]
+fn test_create_market() {
+    let mut scenario = SimpleScenario::new();
+    let factory = scenario.add_factory();
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        engine,
+        insurance_fund,
+        ..
+    } = scenario;
+
+    let msg = factory
+        .create_market("BTC-PERP".to_string(), market_params("BTC"))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let market = factory.market(&router, "BTC-PERP".to_string()).unwrap();
+    assert_eq!(market.symbol, "BTC-PERP".to_string());
+    assert_eq!(market.oracle_key, "BTC".to_string());
+    assert_eq!(market.launch_time, router.block_info().time.seconds());
+
+    // the vamm is wired up, registered, open and owned by the owner of the factory
+    let vamm = VammController(market.vamm);
+
+    let config = vamm.config(&router).unwrap();
+    assert_eq!(config.margin_engine, engine.addr());
+    assert_eq!(config.insurance_fund, insurance_fund.addr());
+    assert_eq!(config.base_asset, "BTC".to_string());
+
+    assert!(vamm.state(&router).unwrap().open);
+    assert!(
+        insurance_fund
+            .is_vamm(vamm.addr().to_string(), &router)
+            .unwrap()
+            .is_vamm
+    );
+
+    let res: OwnerResponse = router
+        .wrap()
+        .query_wasm_smart(vamm.addr(), &VammQueryMsg::GetOwner {})
+        .unwrap();
+    assert_eq!(res.owner, owner);
+
+    // and can be traded straight away
+    let msg = engine
+        .open_position(
+            vamm.addr().to_string(),
+            Side::Buy,
+            to_decimals(60u64),
+            to_decimals(10u64),
+            to_decimals(0u64),
+            vec![],
+        )
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let position = engine
+        .position(&router, vamm.addr().to_string(), alice.to_string())
+        .unwrap();
+    assert_eq!(
+        position.size.value,
+        to_decimals(375u64) / Uint128::from(10u128)
+    );
+}
+
+#[test]
+fn test_query_markets() {
+    let mut scenario = SimpleScenario::new();
+    let factory = scenario.add_factory();
+    let SimpleScenario {
+        mut router, owner, ..
+    } = scenario;
+
+    let msg = factory
+        .create_market("BTC-PERP".to_string(), market_params("BTC"))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = factory
+        .create_market("ATOM-PERP".to_string(), market_params("ATOM"))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let markets = factory.markets(&router, None, None).unwrap().markets;
+    assert_eq!(
+        markets
+            .iter()
+            .map(|market| market.symbol.clone())
+            .collect::<Vec<String>>(),
+        vec!["ATOM-PERP".to_string(), "BTC-PERP".to_string()]
+    );
+
+    let markets = factory
+        .markets(&router, Some("ATOM-PERP".to_string()), None)
+        .unwrap()
+        .markets;
+    assert_eq!(markets.len(), 1);
+    assert_eq!(markets[0].oracle_key, "BTC".to_string());
+
+    let markets = factory.markets(&router, None, Some(1u32)).unwrap().markets;
+    assert_eq!(markets.len(), 1);
+    assert_eq!(markets[0].symbol, "ATOM-PERP".to_string());
+}
+
+#[test]
+fn test_force_error_create_market() {
+    let mut scenario = SimpleScenario::new();
+    let factory = scenario.add_factory();
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        ..
+    } = scenario;
+
+    // only the owner can create markets
+    let msg = factory
+        .create_market("BTC-PERP".to_string(), market_params("BTC"))
+        .unwrap();
+    let err = router.execute(alice.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "unauthorized".to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    // params are validated before the vamm is instantiated
+    let mut params = market_params("BTC");
+    params.toll_ratio = to_decimals(2u64);
+    let msg = factory
+        .create_market("BTC-PERP".to_string(), params)
+        .unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Invalid ratio".to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    let msg = factory
+        .create_market("".to_string(), market_params("BTC"))
+        .unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "symbol cannot be empty".to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    // symbols are unique
+    let msg = factory
+        .create_market("BTC-PERP".to_string(), market_params("BTC"))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = factory
+        .create_market("BTC-PERP".to_string(), market_params("BTC"))
+        .unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "market already exists".to_string()
+        },
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn test_insurance_fund_stays_with_its_owner() {
+    let mut scenario = SimpleScenario::new();
+    let factory = scenario.add_factory();
+    let SimpleScenario {
+        mut router,
+        owner,
+        alice,
+        insurance_fund,
+        ..
+    } = scenario;
+
+    let config = insurance_fund.config(&router).unwrap();
+    assert_eq!(config.factory, Some(factory.addr()));
+
+    let msg = factory
+        .create_market("BTC-PERP".to_string(), market_params("BTC"))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    let market = factory.market(&router, "BTC-PERP".to_string()).unwrap();
+
+    // the factory can only add vamms, so only the owner can add any other vamm
+    let msg = insurance_fund.add_vamm(market.vamm.to_string()).unwrap();
+    let err = router.execute(alice.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "unauthorized".to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    // the owner of the insurance fund still manages its vamms
+    let msg = insurance_fund.remove_vamm(market.vamm.to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    assert!(
+        !insurance_fund
+            .is_vamm(market.vamm.to_string(), &router)
+            .unwrap()
+            .is_vamm
+    );
+}
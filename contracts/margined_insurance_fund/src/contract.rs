@@ -1,15 +1,20 @@
 #[cfg(not(feature = "library"))]
 use crate::error::ContractError;
 use crate::{
-    handle::{add_vamm, remove_vamm, shutdown_all_vamm, update_owner, withdraw},
+    handle::{
+        add_vamm, approve_withdrawal, check_solvency, record_contribution, reject_withdrawal,
+        remove_vamm, reopen_vamm, request_unstake, set_budget, shutdown_all_vamm, shutdown_vamm,
+        stake, unstake, update_config, update_owner, withdraw,
+    },
     query::{
-        query_all_vamm, query_config, query_is_vamm, query_owner, query_status_all_vamm,
-        query_vamm_status,
+        query_all_vamm, query_config, query_coverage, query_is_vamm, query_ledger, query_owner,
+        query_pending_withdrawals, query_staker, query_staking_pool, query_status_all_vamm,
+        query_vamm_status, query_withdrawal_allowance,
     },
     state::{store_config, Config},
 };
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
 };
 use cw2::set_contract_version;
 use cw_controllers::Admin;
@@ -19,6 +24,12 @@ use margined_perp::margined_insurance_fund::{ExecuteMsg, InstantiateMsg, QueryMs
 const CONTRACT_NAME: &str = "crates.io:margined-insurance-fund";
 /// Contract version that is used for migration.
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Default time stakers have to wait to unstake, one week in seconds.
+const DEFAULT_UNSTAKE_COOLDOWN: u64 = 604_800;
+/// Default time stakers have to unstake after the cooldown, two days in seconds.
+const DEFAULT_UNSTAKE_WINDOW: u64 = 172_800;
+/// Default window that engine withdrawals are capped over, one day in seconds.
+const DEFAULT_WITHDRAWAL_WINDOW: u64 = 86_400;
 /// Owner admin
 pub const OWNER: Admin = Admin::new("owner");
 
@@ -33,6 +44,14 @@ pub fn instantiate(
 
     let config = Config {
         engine: deps.api.addr_validate(&msg.engine)?,
+        min_coverage_ratio: Uint128::zero(),
+        staking_fee_share: Uint128::zero(),
+        unstake_cooldown: DEFAULT_UNSTAKE_COOLDOWN,
+        unstake_window: DEFAULT_UNSTAKE_WINDOW,
+        withdrawal_window: DEFAULT_WITHDRAWAL_WINDOW,
+        max_withdrawal_amount: Uint128::zero(),
+        max_withdrawal_ratio: Uint128::zero(),
+        factory: None,
     };
 
     store_config(deps.storage, &config)?;
@@ -48,19 +67,74 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
         ExecuteMsg::UpdateOwner { owner } => update_owner(deps, info, owner),
         ExecuteMsg::AddVamm { vamm } => add_vamm(deps, info, vamm),
         ExecuteMsg::RemoveVamm { vamm } => remove_vamm(deps, info, vamm),
-        ExecuteMsg::Withdraw { token, amount } => withdraw(deps, info, token, amount),
-        ExecuteMsg::ShutdownVamms {} => shutdown_all_vamm(deps, env, info),
+        ExecuteMsg::Withdraw {
+            token,
+            amount,
+            vamm,
+            kind,
+            defer,
+        } => withdraw(deps, env, info, token, amount, vamm, kind, defer),
+        ExecuteMsg::ApproveWithdrawal { id } => approve_withdrawal(deps, env, info, id),
+        ExecuteMsg::RejectWithdrawal { id } => reject_withdrawal(deps, info, id),
+        ExecuteMsg::RecordContribution { vamm, kind, amount } => {
+            record_contribution(deps, info, vamm, kind, amount)
+        }
+        ExecuteMsg::SetBudget { vamm, budget } => set_budget(deps, info, vamm, budget),
+        ExecuteMsg::UpdateConfig {
+            min_coverage_ratio,
+            staking_fee_share,
+            unstake_cooldown,
+            unstake_window,
+            withdrawal_window,
+            max_withdrawal_amount,
+            max_withdrawal_ratio,
+            factory,
+        } => update_config(
+            deps,
+            info,
+            min_coverage_ratio,
+            staking_fee_share,
+            unstake_cooldown,
+            unstake_window,
+            withdrawal_window,
+            max_withdrawal_amount,
+            max_withdrawal_ratio,
+            factory,
+        ),
+        ExecuteMsg::ShutdownVamms { start_after, limit } => {
+            shutdown_all_vamm(deps, env, info, start_after, limit)
+        }
+        ExecuteMsg::ShutdownVamm { vamm } => shutdown_vamm(deps, env, info, vamm),
+        ExecuteMsg::ReopenVamm { vamm } => reopen_vamm(deps, info, vamm),
+        ExecuteMsg::CheckSolvency { start_after, limit } => {
+            check_solvency(deps, env, start_after, limit)
+        }
+        ExecuteMsg::Stake { amount } => stake(deps, env, info, amount),
+        ExecuteMsg::RequestUnstake { shares } => request_unstake(deps, env, info, shares),
+        ExecuteMsg::Unstake {} => unstake(deps, env, info),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::GetOwner {} => to_binary(&query_owner(deps)?),
         QueryMsg::IsVamm { vamm } => to_binary(&query_is_vamm(deps, vamm)?),
-        QueryMsg::GetAllVamm { limit } => to_binary(&query_all_vamm(deps, limit)?),
+        QueryMsg::GetAllVamm { start_after, limit } => {
+            to_binary(&query_all_vamm(deps, start_after, limit)?)
+        }
         QueryMsg::GetVammStatus { vamm } => to_binary(&query_vamm_status(deps, vamm)?),
-        QueryMsg::GetAllVammStatus { limit } => to_binary(&query_status_all_vamm(deps, limit)?),
+        QueryMsg::GetLedger { vamm } => to_binary(&query_ledger(deps, vamm)?),
+        QueryMsg::GetCoverage { vamm } => to_binary(&query_coverage(deps, env, vamm)?),
+        QueryMsg::GetAllVammStatus { start_after, limit } => {
+            to_binary(&query_status_all_vamm(deps, start_after, limit)?)
+        }
+        QueryMsg::GetStakingPool {} => to_binary(&query_staking_pool(deps)?),
+        QueryMsg::GetStaker { staker } => to_binary(&query_staker(deps, staker)?),
+        QueryMsg::GetWithdrawalAllowance {} => to_binary(&query_withdrawal_allowance(deps, env)?),
+        QueryMsg::GetPendingWithdrawals { start_after, limit } => {
+            to_binary(&query_pending_withdrawals(deps, start_after, limit)?)
+        }
     }
 }
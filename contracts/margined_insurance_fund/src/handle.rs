@@ -1,15 +1,34 @@
 use cosmwasm_std::{
-    to_binary, BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, ReplyOn, Response, StdError,
-    StdResult, SubMsg, Uint128, WasmMsg,
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, DepsMut, Env, Event, MessageInfo, Order, ReplyOn,
+    Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
-use margined_common::asset::AssetInfo;
+use cw_storage_plus::Bound;
+use margined_common::{
+    asset::{Asset, AssetInfo},
+    validate::validate_ratio,
+};
+use margined_perp::margined_insurance_fund::{ContributionKind, DrawKind, PendingWithdrawal};
+use margined_perp::querier::query_token_balance;
 
 use crate::{
     contract::OWNER,
-    messages::execute_vamm_shutdown,
-    querier::{query_engine_decimals, query_vamm_decimals},
-    state::{read_config, read_vammlist, remove_vamm as remove_amm, save_vamm, Config, VAMM_LIMIT},
+    messages::{
+        execute_transfer, execute_transfer_from, execute_vamm_reopen, execute_vamm_shutdown,
+    },
+    querier::{
+        query_engine_collateral, query_engine_decimals, query_vamm_decimals, query_vamm_open,
+    },
+    query::{
+        calc_balance_share, calc_coverage, calc_withdrawal_window, DEFAULT_PAGINATION_LIMIT,
+        MAX_PAGINATION_LIMIT,
+    },
+    state::{
+        is_vamm, read_config, read_staker, read_staking_pool, read_vammlist,
+        record_contribution as store_contribution, record_draw, remove_pending_withdrawal,
+        remove_vamm as remove_amm, save_pending_withdrawal, save_vamm, set_vamm_active,
+        store_config, Config, VammInfo, STAKERS, STAKING_POOL, VAMMS, WITHDRAWAL_WINDOW,
+    },
 };
 
 pub fn update_owner(deps: DepsMut, info: MessageInfo, owner: String) -> StdResult<Response> {
@@ -21,14 +40,85 @@ pub fn update_owner(deps: DepsMut, info: MessageInfo, owner: String) -> StdResul
         .map_err(|error| StdError::generic_err(format!("{}", error)))
 }
 
-pub fn add_vamm(deps: DepsMut, info: MessageInfo, vamm: String) -> StdResult<Response> {
-    let config: Config = read_config(deps.storage)?;
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    min_coverage_ratio: Option<Uint128>,
+    staking_fee_share: Option<Uint128>,
+    unstake_cooldown: Option<u64>,
+    unstake_window: Option<u64>,
+    withdrawal_window: Option<u64>,
+    max_withdrawal_amount: Option<Uint128>,
+    max_withdrawal_ratio: Option<Uint128>,
+    factory: Option<String>,
+) -> StdResult<Response> {
+    let mut config: Config = read_config(deps.storage)?;
 
     // check permission
     if !OWNER.is_admin(deps.as_ref(), &info.sender)? {
         return Err(StdError::generic_err("unauthorized"));
     }
 
+    // change the ratio below which markets are shut down, zero disables the check
+    if let Some(min_coverage_ratio) = min_coverage_ratio {
+        config.min_coverage_ratio = min_coverage_ratio;
+    }
+
+    // change the share of trading fees that is paid to stakers
+    if let Some(staking_fee_share) = staking_fee_share {
+        let decimals = query_engine_decimals(&deps.as_ref(), config.engine.to_string())?;
+        validate_ratio(staking_fee_share, decimals)?;
+        config.staking_fee_share = staking_fee_share;
+    }
+
+    // change how long stakers have to wait to unstake
+    if let Some(unstake_cooldown) = unstake_cooldown {
+        config.unstake_cooldown = unstake_cooldown;
+    }
+
+    // change how long stakers have to unstake after the cooldown, zero never lapses
+    if let Some(unstake_window) = unstake_window {
+        config.unstake_window = unstake_window;
+    }
+
+    // change the length of the window that engine withdrawals are capped over
+    if let Some(withdrawal_window) = withdrawal_window {
+        config.withdrawal_window = withdrawal_window;
+    }
+
+    // change the most the engine can withdraw in a window, zero removes the cap
+    if let Some(max_withdrawal_amount) = max_withdrawal_amount {
+        config.max_withdrawal_amount = max_withdrawal_amount;
+    }
+
+    // change the share of the balance the engine can withdraw in a window, zero removes the cap
+    if let Some(max_withdrawal_ratio) = max_withdrawal_ratio {
+        let decimals = query_engine_decimals(&deps.as_ref(), config.engine.to_string())?;
+        validate_ratio(max_withdrawal_ratio, decimals)?;
+        config.max_withdrawal_ratio = max_withdrawal_ratio;
+    }
+
+    // change the factory that is allowed to add vamms
+    if let Some(factory) = factory {
+        config.factory = Some(deps.api.addr_validate(&factory)?);
+    }
+
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::default().add_attribute("action", "update_config"))
+}
+
+pub fn add_vamm(deps: DepsMut, info: MessageInfo, vamm: String) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+
+    // check permission, the factory can add the vamms of the markets it creates
+    if !OWNER.is_admin(deps.as_ref(), &info.sender)?
+        && config.factory.as_ref() != Some(&info.sender)
+    {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
     // validate address
     let vamm_valid = deps.api.addr_validate(&vamm)?;
 
@@ -44,7 +134,15 @@ pub fn add_vamm(deps: DepsMut, info: MessageInfo, vamm: String) -> StdResult<Res
     }
 
     // add the amm
-    save_vamm(deps, vamm_valid)?;
+    save_vamm(
+        deps,
+        vamm_valid,
+        VammInfo {
+            decimals: vamm_decimals,
+            budget: None,
+            active: true,
+        },
+    )?;
 
     Ok(Response::default())
 }
@@ -64,30 +162,402 @@ pub fn remove_vamm(deps: DepsMut, info: MessageInfo, vamm: String) -> StdResult<
     Ok(Response::default())
 }
 
-pub fn shutdown_all_vamm(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+pub fn set_budget(
+    deps: DepsMut,
+    info: MessageInfo,
+    vamm: String,
+    budget: Option<Uint128>,
+) -> StdResult<Response> {
+    // check permission
+    if !OWNER.is_admin(deps.as_ref(), &info.sender)? {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    // validate address
+    let vamm_valid = deps.api.addr_validate(&vamm)?;
+
+    let mut vamm_info = VAMMS
+        .may_load(deps.storage, &vamm_valid)?
+        .ok_or_else(|| StdError::generic_err("This vAMM has not been added"))?;
+
+    vamm_info.budget = budget;
+
+    VAMMS.save(deps.storage, &vamm_valid, &vamm_info)?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "set_budget"),
+        ("vamm", vamm_valid.as_str()),
+        (
+            "budget",
+            &budget.map_or("none".to_string(), |budget| budget.to_string()),
+        ),
+    ]))
+}
+
+pub fn record_contribution(
+    deps: DepsMut,
+    info: MessageInfo,
+    vamm: String,
+    kind: ContributionKind,
+    amount: Uint128,
+) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+
+    // check permission
+    if info.sender != config.engine {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    // validate address
+    let vamm_valid = deps.api.addr_validate(&vamm)?;
+
+    if !is_vamm(deps.storage, vamm_valid.clone()) {
+        return Err(StdError::generic_err("This vAMM has not been added"));
+    }
+
+    // stakers earn their share of the trading fees
+    let mut staking_reward = Uint128::zero();
+    if kind == ContributionKind::Fee && !config.staking_fee_share.is_zero() {
+        let mut pool = read_staking_pool(deps.storage)?;
+
+        if !pool.total_shares.is_zero() {
+            let decimals = query_engine_decimals(&deps.as_ref(), config.engine.to_string())?;
+            staking_reward = amount.multiply_ratio(config.staking_fee_share, decimals);
+
+            pool.total_staked = pool.total_staked.checked_add(staking_reward)?;
+            STAKING_POOL.save(deps.storage, &pool)?;
+        }
+    }
+
+    store_contribution(deps.storage, &vamm_valid, kind, amount)?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "insurance_contribution"),
+        ("vamm", vamm_valid.as_str()),
+        ("amount", &amount.to_string()),
+        ("staking_reward", &staking_reward.to_string()),
+    ]))
+}
+
+pub fn stake(deps: DepsMut, env: Env, info: MessageInfo, amount: Uint128) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+
+    if amount.is_zero() {
+        return Err(StdError::generic_err("Input must be non-zero"));
+    }
+
+    // stakers deposit the collateral of the margin engine
+    let collateral = query_engine_collateral(&deps.as_ref(), config.engine.to_string())?;
+
+    let mut response = Response::default();
+    match collateral.clone() {
+        AssetInfo::NativeToken { .. } => {
+            let token = Asset {
+                info: collateral,
+                amount,
+            };
+
+            token.assert_sent_native_token_balance(&info)?;
+        }
+        AssetInfo::Token { .. } => {
+            response = response.add_submessage(execute_transfer_from(
+                collateral,
+                &info.sender,
+                &env.contract.address,
+                amount,
+            )?);
+        }
+    };
+
+    let mut pool = read_staking_pool(deps.storage)?;
+    pool.reset_if_wiped_out();
+
+    let shares = pool.shares_for(amount);
+    if shares.is_zero() {
+        return Err(StdError::generic_err("Stake is too small to mint shares"));
+    }
+
+    pool.total_shares = pool.total_shares.checked_add(shares)?;
+    pool.total_staked = pool.total_staked.checked_add(amount)?;
+    STAKING_POOL.save(deps.storage, &pool)?;
+
+    let mut staker = read_staker(deps.storage, &info.sender)?;
+    staker.shares = staker.shares.checked_add(shares)?;
+    STAKERS.save(deps.storage, &info.sender, &staker)?;
+
+    Ok(response.add_attributes(vec![
+        ("action", "stake"),
+        ("staker", info.sender.as_str()),
+        ("amount", &amount.to_string()),
+        ("shares", &shares.to_string()),
+    ]))
+}
+
+/// Starts the cooldown on the shares, they keep absorbing losses until they are
+/// unstaked so that stakers cannot exit ahead of known bad debt
+pub fn request_unstake(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    shares: Uint128,
+) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+
+    if shares.is_zero() {
+        return Err(StdError::generic_err("Input must be non-zero"));
+    }
+
+    let mut staker = read_staker(deps.storage, &info.sender)?;
+    if shares > staker.shares {
+        return Err(StdError::generic_err("Insufficient shares"));
+    }
+
+    // a new request replaces the previous one and restarts the cooldown
+    let release_time = env.block.time.seconds() + config.unstake_cooldown;
+    staker.unstaking_shares = shares;
+    staker.unstake_release_time = Some(release_time);
+    STAKERS.save(deps.storage, &info.sender, &staker)?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "request_unstake"),
+        ("staker", info.sender.as_str()),
+        ("shares", &shares.to_string()),
+        ("release_time", &release_time.to_string()),
+    ]))
+}
+
+pub fn unstake(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+
+    let mut staker = read_staker(deps.storage, &info.sender)?;
+    let release_time = staker
+        .unstake_release_time
+        .ok_or_else(|| StdError::generic_err("No unstake has been requested"))?;
+
+    if env.block.time.seconds() < release_time {
+        return Err(StdError::generic_err("Unstake cooldown has not passed"));
+    }
+
+    // requests lapse after the window so they cannot be held as a free option to exit
+    if config.unstake_window != 0
+        && env.block.time.seconds() >= release_time + config.unstake_window
+    {
+        return Err(StdError::generic_err("Unstake request has expired"));
+    }
+
+    // burn the shares for what is left of their value
+    let shares = staker.unstaking_shares;
+    let mut pool = read_staking_pool(deps.storage)?;
+    let amount = pool.value_of(shares);
+
+    pool.total_shares = pool.total_shares.checked_sub(shares)?;
+    pool.total_staked = pool.total_staked.checked_sub(amount)?;
+    pool.reset_if_wiped_out();
+    STAKING_POOL.save(deps.storage, &pool)?;
+
+    staker.shares = staker.shares.checked_sub(shares)?;
+    staker.unstaking_shares = Uint128::zero();
+    staker.unstake_release_time = None;
+    if staker.shares.is_zero() {
+        STAKERS.remove(deps.storage, &info.sender);
+    } else {
+        STAKERS.save(deps.storage, &info.sender, &staker)?;
+    }
+
+    let mut response = Response::default();
+    if !amount.is_zero() {
+        let collateral = query_engine_collateral(&deps.as_ref(), config.engine.to_string())?;
+        response = response.add_submessage(execute_transfer(collateral, &info.sender, amount)?);
+    }
+
+    Ok(response.add_attributes(vec![
+        ("action", "unstake"),
+        ("staker", info.sender.as_str()),
+        ("shares", &shares.to_string()),
+        ("amount", &amount.to_string()),
+    ]))
+}
+
+/// Shuts down the vAMMs a page at a time, the `last_vamm` attribute is where the next page
+/// starts after
+pub fn shutdown_all_vamm(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Response> {
     // check permission
     if !OWNER.is_admin(deps.as_ref(), &info.sender)? && info.sender != env.contract.address {
         return Err(StdError::generic_err("unauthorized"));
     }
 
+    // set the limit for pagination
+    let limit = limit
+        .unwrap_or(DEFAULT_PAGINATION_LIMIT)
+        .min(MAX_PAGINATION_LIMIT) as usize;
+    let start_after = start_after
+        .map(|vamm| deps.api.addr_validate(&vamm))
+        .transpose()?;
+
     // initialise the submsgs vec
     let mut msgs = vec![];
 
-    // construct all the shutdown messages
-    let keys = read_vammlist(deps.as_ref(), VAMM_LIMIT)?;
+    // construct the shutdown messages of this page
+    let keys = read_vammlist(deps.as_ref(), start_after, Some(limit))?;
 
     for vamm in keys.iter() {
+        set_vamm_active(deps.storage, vamm, false)?;
         msgs.push(execute_vamm_shutdown(vamm.clone())?);
     }
 
-    Ok(Response::default().add_submessages(msgs))
+    let mut response = Response::default()
+        .add_submessages(msgs)
+        .add_attribute("action", "shutdown_vamms");
+
+    if let Some(last_vamm) = keys.last() {
+        response = response.add_attribute("last_vamm", last_vamm.as_str());
+    }
+
+    Ok(response)
+}
+
+pub fn shutdown_vamm(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vamm: String,
+) -> StdResult<Response> {
+    // check permission
+    if !OWNER.is_admin(deps.as_ref(), &info.sender)? && info.sender != env.contract.address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    // validate address
+    let vamm_valid = deps.api.addr_validate(&vamm)?;
+
+    if !is_vamm(deps.storage, vamm_valid.clone()) {
+        return Err(StdError::generic_err("This vAMM has not been added"));
+    }
+
+    if !query_vamm_open(&deps.as_ref(), vamm.clone())? {
+        return Err(StdError::generic_err("This vAMM is already shut down"));
+    }
+
+    set_vamm_active(deps.storage, &vamm_valid, false)?;
+
+    Ok(Response::default()
+        .add_submessage(execute_vamm_shutdown(vamm_valid)?)
+        .add_attributes(vec![("action", "shutdown_vamm"), ("vamm", &vamm)]))
+}
+
+pub fn reopen_vamm(deps: DepsMut, info: MessageInfo, vamm: String) -> StdResult<Response> {
+    // check permission
+    if !OWNER.is_admin(deps.as_ref(), &info.sender)? {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    // validate address
+    let vamm_valid = deps.api.addr_validate(&vamm)?;
+
+    if !is_vamm(deps.storage, vamm_valid.clone()) {
+        return Err(StdError::generic_err("This vAMM has not been added"));
+    }
+
+    if query_vamm_open(&deps.as_ref(), vamm.clone())? {
+        return Err(StdError::generic_err("This vAMM is already open"));
+    }
+
+    set_vamm_active(deps.storage, &vamm_valid, true)?;
+
+    Ok(Response::default()
+        .add_submessage(execute_vamm_reopen(vamm_valid)?)
+        .add_attributes(vec![("action", "reopen_vamm"), ("vamm", &vamm)]))
+}
+
+/// Anyone can check the coverage of the open vAMMs a page at a time, any that are
+/// below the minimum coverage ratio are shut down
+pub fn check_solvency(
+    deps: DepsMut,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+
+    if config.min_coverage_ratio.is_zero() {
+        return Err(StdError::generic_err("Solvency check is disabled"));
+    }
+
+    // set the limit for pagination
+    let limit = limit
+        .unwrap_or(DEFAULT_PAGINATION_LIMIT)
+        .min(MAX_PAGINATION_LIMIT) as usize;
+    let start_after = start_after
+        .map(|vamm| deps.api.addr_validate(&vamm))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let vamms = VAMMS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<(Addr, VammInfo)>>>()?;
+
+    // the balance is loaded once and split between all of the vAMMs
+    let share = calc_balance_share(deps.as_ref(), &env, &config)?;
+
+    let mut response = Response::default().add_attribute("action", "check_solvency");
+
+    if let Some((last_vamm, _)) = vamms.last() {
+        response = response.add_attribute("last_vamm", last_vamm.as_str());
+    }
+
+    for (vamm, vamm_info) in vamms {
+        // vamms can also be opened and closed by their owner, so whether they are active is
+        // brought in line with the vamm as it is checked
+        let is_open = query_vamm_open(&deps.as_ref(), vamm.to_string())?;
+        set_vamm_active(deps.storage, &vamm, is_open)?;
+
+        if !is_open {
+            continue;
+        }
+
+        let coverage = calc_coverage(deps.as_ref(), &config, share, vamm.clone(), &vamm_info)?;
+
+        let coverage_ratio = match coverage.coverage_ratio {
+            Some(coverage_ratio) if coverage_ratio < config.min_coverage_ratio => coverage_ratio,
+            _ => continue,
+        };
+
+        set_vamm_active(deps.storage, &vamm, false)?;
+
+        response = response
+            .add_submessage(execute_vamm_shutdown(vamm.clone())?)
+            .add_event(
+                Event::new("insolvent_vamm")
+                    .add_attribute("vamm", vamm.as_str())
+                    .add_attribute("available", coverage.available.to_string())
+                    .add_attribute(
+                        "open_interest_notional",
+                        coverage.open_interest_notional.to_string(),
+                    )
+                    .add_attribute("coverage_ratio", coverage_ratio.to_string()),
+            );
+    }
+
+    Ok(response)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn withdraw(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     token: AssetInfo,
     amount: Uint128,
+    vamm: String,
+    kind: DrawKind,
+    defer: bool,
 ) -> StdResult<Response> {
     let config: Config = read_config(deps.storage)?;
 
@@ -96,6 +566,145 @@ pub fn withdraw(
         return Err(StdError::generic_err("unauthorized"));
     }
 
+    // validate address
+    let vamm_valid = deps.api.addr_validate(&vamm)?;
+
+    if !is_vamm(deps.storage, vamm_valid.clone()) {
+        return Err(StdError::generic_err("This vAMM has not been added"));
+    }
+
+    // anything over the allowance of the window fails, unless the engine can wait for
+    // the owner to approve it. Bad debt is paid in full so that positions can always be
+    // liquidated, it still uses up the allowance of the window
+    let (mut window, remaining) =
+        calc_withdrawal_window(deps.as_ref(), &env, &config, token.clone())?;
+
+    if let Some(remaining) = remaining {
+        if amount > remaining && kind != DrawKind::BadDebt {
+            if !defer {
+                return Err(StdError::generic_err(
+                    "Withdrawal exceeds the allowance of the window",
+                ));
+            }
+
+            let withdrawal = save_pending_withdrawal(
+                deps.storage,
+                PendingWithdrawal {
+                    id: 0u64,
+                    token,
+                    amount,
+                    vamm: vamm_valid.clone(),
+                    kind,
+                    requested_at: env.block.time.seconds(),
+                },
+            )?;
+
+            return Ok(Response::default()
+                .add_attribute("action", "insurance_withdraw_queued")
+                .add_event(
+                    Event::new("withdrawal_queued")
+                        .add_attribute("id", withdrawal.id.to_string())
+                        .add_attribute("vamm", vamm_valid.as_str())
+                        .add_attribute("amount", amount.to_string())
+                        .add_attribute("remaining_allowance", remaining.to_string()),
+                ));
+        }
+    }
+
+    window.withdrawn = window.withdrawn.checked_add(amount)?;
+    WITHDRAWAL_WINDOW.save(deps.storage, &window)?;
+
+    let response = Response::default().add_attribute("action", "insurance_withdraw");
+
+    pay_withdrawal(
+        deps, env, &config, response, token, amount, vamm_valid, kind,
+    )
+}
+
+pub fn approve_withdrawal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+
+    // check permission
+    if !OWNER.is_admin(deps.as_ref(), &info.sender)? {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let withdrawal = remove_pending_withdrawal(deps.storage, id)?;
+
+    let response = Response::default().add_attributes(vec![
+        ("action", "approve_withdrawal"),
+        ("id", &id.to_string()),
+    ]);
+
+    pay_withdrawal(
+        deps,
+        env,
+        &config,
+        response,
+        withdrawal.token,
+        withdrawal.amount,
+        withdrawal.vamm,
+        withdrawal.kind,
+    )
+}
+
+pub fn reject_withdrawal(deps: DepsMut, info: MessageInfo, id: u64) -> StdResult<Response> {
+    // check permission
+    if !OWNER.is_admin(deps.as_ref(), &info.sender)? {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let withdrawal = remove_pending_withdrawal(deps.storage, id)?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "reject_withdrawal"),
+        ("id", &id.to_string()),
+        ("vamm", withdrawal.vamm.as_str()),
+        ("amount", &withdrawal.amount.to_string()),
+    ]))
+}
+
+// records the draw against the vamm and its stakers and sends the tokens to the engine
+#[allow(clippy::too_many_arguments)]
+fn pay_withdrawal(
+    deps: DepsMut,
+    env: Env,
+    config: &Config,
+    response: Response,
+    token: AssetInfo,
+    amount: Uint128,
+    vamm_valid: Addr,
+    kind: DrawKind,
+) -> StdResult<Response> {
+    // the draw is always paid, if it uses up the budget of the vamm it is shut down
+    let ledger = record_draw(deps.storage, &vamm_valid, kind.clone(), amount)?;
+
+    let budget = VAMMS
+        .may_load(deps.storage, &vamm_valid)?
+        .and_then(|vamm_info| vamm_info.budget);
+    let is_budget_exhausted = ledger.remaining_budget(budget)? == Some(Uint128::zero());
+
+    // stakers absorb their pro-rata part of every draw so that what is staked never
+    // exceeds the balance of the fund
+    let mut staking_loss = Uint128::zero();
+    let mut pool = read_staking_pool(deps.storage)?;
+    let balance = query_token_balance(deps.as_ref(), token.clone(), env.contract.address.clone())?;
+
+    if !pool.total_staked.is_zero() && !balance.is_zero() {
+        staking_loss = amount
+            .multiply_ratio(pool.total_staked, balance)
+            .min(pool.total_staked);
+
+        pool.total_staked = pool.total_staked.checked_sub(staking_loss)?;
+        pool.reset_if_wiped_out();
+        STAKING_POOL.save(deps.storage, &pool)?;
+    }
+
     // send tokens if native or cw20
     let msg: CosmosMsg = match token {
         AssetInfo::NativeToken { denom } => CosmosMsg::Bank(BankMsg::Send {
@@ -119,10 +728,19 @@ pub fn withdraw(
         reply_on: ReplyOn::Never,
     };
 
-    Ok(Response::default()
-        .add_submessage(transfer_msg)
-        .add_attributes(vec![
-            ("action", "insurance_withdraw"),
-            ("amount", &amount.to_string()),
-        ]))
+    let mut response = response.add_submessage(transfer_msg).add_attributes(vec![
+        ("vamm", vamm_valid.as_str()),
+        ("amount", &amount.to_string()),
+        ("staking_loss", &staking_loss.to_string()),
+    ]);
+
+    if is_budget_exhausted && query_vamm_open(&deps.as_ref(), vamm_valid.to_string())? {
+        set_vamm_active(deps.storage, &vamm_valid, false)?;
+
+        response = response
+            .add_submessage(execute_vamm_shutdown(vamm_valid)?)
+            .add_attribute("budget_exhausted", "true");
+    }
+
+    Ok(response)
 }
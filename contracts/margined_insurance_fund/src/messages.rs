@@ -1,5 +1,9 @@
-use cosmwasm_std::{to_binary, Addr, CosmosMsg, ReplyOn, StdResult, SubMsg, WasmMsg};
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, ReplyOn, StdResult, SubMsg, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
 
+use margined_common::asset::AssetInfo;
 use margined_perp::margined_vamm::ExecuteMsg as VammExecuteMessage;
 
 pub fn execute_vamm_shutdown(vamm: Addr) -> StdResult<SubMsg> {
@@ -18,3 +22,64 @@ pub fn execute_vamm_shutdown(vamm: Addr) -> StdResult<SubMsg> {
 
     Ok(status_msg)
 }
+
+pub fn execute_vamm_reopen(vamm: Addr) -> StdResult<SubMsg> {
+    let msg = WasmMsg::Execute {
+        contract_addr: vamm.to_string(),
+        funds: vec![],
+        msg: to_binary(&VammExecuteMessage::SetOpen { open: true })?,
+    };
+
+    let status_msg = SubMsg {
+        msg: CosmosMsg::Wasm(msg),
+        gas_limit: None,
+        id: 0u64,
+        reply_on: ReplyOn::Never,
+    };
+
+    Ok(status_msg)
+}
+
+pub fn execute_transfer_from(
+    token: AssetInfo,
+    owner: &Addr,
+    receiver: &Addr,
+    amount: Uint128,
+) -> StdResult<SubMsg> {
+    let msg: CosmosMsg = match token {
+        AssetInfo::NativeToken { denom } => CosmosMsg::Bank(BankMsg::Send {
+            to_address: receiver.to_string(),
+            amount: vec![Coin { denom, amount }],
+        }),
+        AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: owner.to_string(),
+                recipient: receiver.to_string(),
+                amount,
+            })?,
+        }),
+    };
+
+    Ok(SubMsg::new(msg))
+}
+
+pub fn execute_transfer(token: AssetInfo, receiver: &Addr, amount: Uint128) -> StdResult<SubMsg> {
+    let msg: CosmosMsg = match token {
+        AssetInfo::NativeToken { denom } => CosmosMsg::Bank(BankMsg::Send {
+            to_address: receiver.to_string(),
+            amount: vec![Coin { denom, amount }],
+        }),
+        AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: receiver.to_string(),
+                amount,
+            })?,
+        }),
+    };
+
+    Ok(SubMsg::new(msg))
+}
@@ -1,5 +1,6 @@
 use cosmwasm_std::{to_binary, Deps, QueryRequest, StdResult, Uint128, WasmQuery};
 
+use margined_common::asset::AssetInfo;
 use margined_perp::margined_engine::{ConfigResponse, QueryMsg as EngineQueryMsg};
 use margined_perp::margined_vamm::{
     ConfigResponse as VammConfigResponse, QueryMsg as VammQueryMsg, StateResponse,
@@ -40,3 +41,28 @@ pub fn query_engine_decimals(deps: &Deps, contract: String) -> StdResult<Uint128
         .decimals;
     Ok(result)
 }
+
+// this function queries the engine with given address and returns the open interest notional of the vamm
+pub fn query_engine_open_interest_notional(
+    deps: &Deps,
+    contract: String,
+    vamm: String,
+) -> StdResult<Uint128> {
+    deps.querier
+        .query::<Uint128>(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: contract,
+            msg: to_binary(&EngineQueryMsg::OpenInterestNotional { vamm })?,
+        }))
+}
+
+// this function queries the engine with given address and returns the collateral it uses
+pub fn query_engine_collateral(deps: &Deps, contract: String) -> StdResult<AssetInfo> {
+    let result = deps
+        .querier
+        .query::<ConfigResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: contract,
+            msg: to_binary(&EngineQueryMsg::Config {})?,
+        }))?
+        .eligible_collateral;
+    Ok(result)
+}
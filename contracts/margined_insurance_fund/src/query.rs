@@ -1,17 +1,28 @@
-use cosmwasm_std::{Addr, Deps, StdError, StdResult};
+use cosmwasm_std::{Addr, Deps, Env, Order, StdError, StdResult, Uint128};
+use cw_storage_plus::Bound;
+use margined_common::asset::AssetInfo;
 use margined_perp::margined_insurance_fund::{
-    AllVammResponse, AllVammStatusResponse, ConfigResponse, OwnerResponse, VammResponse,
-    VammStatusResponse,
+    AllVammResponse, AllVammStatusResponse, ConfigResponse, CoverageResponse, LedgerResponse,
+    OwnerResponse, PendingWithdrawalsResponse, StakerResponse, StakingPoolResponse, VammResponse,
+    VammStatusResponse, WithdrawalAllowanceResponse,
 };
+use margined_perp::querier::query_token_balance;
 
 use crate::{
     contract::OWNER,
-    querier::query_vamm_open,
-    state::{is_vamm, read_config, read_vammlist, Config, VAMM_LIMIT},
+    querier::{
+        query_engine_collateral, query_engine_decimals, query_engine_open_interest_notional,
+        query_vamm_open,
+    },
+    state::{
+        is_vamm, read_active_vamm_count, read_config, read_ledger, read_staker, read_staking_pool,
+        read_vammlist, Config, VammInfo, WithdrawalWindow, PENDING_WITHDRAWALS, VAMMS,
+        WITHDRAWAL_WINDOW,
+    },
 };
 
-const DEFAULT_PAGINATION_LIMIT: u32 = 10u32;
-const MAX_PAGINATION_LIMIT: u32 = VAMM_LIMIT as u32;
+pub const DEFAULT_PAGINATION_LIMIT: u32 = 10u32;
+pub const MAX_PAGINATION_LIMIT: u32 = 30u32;
 
 /// Queries contract owner from the admin
 pub fn query_owner(deps: Deps) -> StdResult<OwnerResponse> {
@@ -28,6 +39,14 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 
     Ok(ConfigResponse {
         engine: config.engine,
+        min_coverage_ratio: config.min_coverage_ratio,
+        staking_fee_share: config.staking_fee_share,
+        unstake_cooldown: config.unstake_cooldown,
+        unstake_window: config.unstake_window,
+        withdrawal_window: config.withdrawal_window,
+        max_withdrawal_amount: config.max_withdrawal_amount,
+        max_withdrawal_ratio: config.max_withdrawal_ratio,
+        factory: config.factory,
     })
 }
 
@@ -43,13 +62,20 @@ pub fn query_is_vamm(deps: Deps, vamm: String) -> StdResult<VammResponse> {
 }
 
 /// Queries the list of vAMMs currently stored (not necessarily on)
-pub fn query_all_vamm(deps: Deps, limit: Option<u32>) -> StdResult<AllVammResponse> {
+pub fn query_all_vamm(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllVammResponse> {
     // set the limit for pagination
     let limit = limit
         .unwrap_or(DEFAULT_PAGINATION_LIMIT)
         .min(MAX_PAGINATION_LIMIT) as usize;
+    let start_after = start_after
+        .map(|vamm| deps.api.addr_validate(&vamm))
+        .transpose()?;
 
-    let list = read_vammlist(deps, limit)?;
+    let list = read_vammlist(deps, start_after, Some(limit))?;
     Ok(AllVammResponse { vamm_list: list })
 }
 
@@ -66,17 +92,121 @@ pub fn query_vamm_status(deps: Deps, vamm: String) -> StdResult<VammStatusRespon
     })
 }
 
+/// Queries the insurance contributed and drawn by the vAMM with given address
+pub fn query_ledger(deps: Deps, vamm: String) -> StdResult<LedgerResponse> {
+    // validate address
+    let vamm_valid = deps.api.addr_validate(&vamm)?;
+
+    let ledger = read_ledger(deps.storage, &vamm_valid)?;
+    let budget = VAMMS
+        .may_load(deps.storage, &vamm_valid)?
+        .and_then(|vamm_info| vamm_info.budget);
+
+    Ok(LedgerResponse {
+        vamm: vamm_valid,
+        liquidation_contributions: ledger.liquidation_contributions,
+        fee_contributions: ledger.fee_contributions,
+        funding_contributions: ledger.funding_contributions,
+        bad_debt_draws: ledger.bad_debt_draws,
+        funding_draws: ledger.funding_draws,
+        repeg_draws: ledger.repeg_draws,
+        total_contributions: ledger.total_contributions()?,
+        total_draws: ledger.total_draws()?,
+        budget,
+        remaining_budget: ledger.remaining_budget(budget)?,
+    })
+}
+
+/// Queries how much of the open interest of the vAMM with given address the insurance
+/// fund can cover, limited by the remaining budget of the vAMM if it has one
+pub fn query_coverage(deps: Deps, env: Env, vamm: String) -> StdResult<CoverageResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    // validate address
+    let vamm_valid = deps.api.addr_validate(&vamm)?;
+
+    let vamm_info = VAMMS
+        .may_load(deps.storage, &vamm_valid)?
+        .ok_or_else(|| StdError::generic_err("This vAMM has not been added"))?;
+
+    let share = calc_balance_share(deps, &env, &config)?;
+
+    calc_coverage(deps, &config, share, vamm_valid, &vamm_info)
+}
+
+/// Splits the balance of the fund evenly between the active vAMMs, so the same funds are
+/// not counted as cover for every market at once
+pub fn calc_balance_share(deps: Deps, env: &Env, config: &Config) -> StdResult<Uint128> {
+    let collateral = query_engine_collateral(&deps, config.engine.to_string())?;
+    let balance = query_token_balance(deps, collateral, env.contract.address.clone())?;
+
+    let vamm_count = read_active_vamm_count(deps.storage)?;
+
+    if vamm_count == 0 {
+        return Ok(Uint128::zero());
+    }
+
+    Ok(balance.checked_div(Uint128::from(vamm_count as u128))?)
+}
+
+/// Calculates the coverage of a vAMM from its share of the fund balance, shared by the
+/// query and the solvency check
+pub fn calc_coverage(
+    deps: Deps,
+    config: &Config,
+    share: Uint128,
+    vamm_valid: Addr,
+    vamm_info: &VammInfo,
+) -> StdResult<CoverageResponse> {
+    let remaining_budget =
+        read_ledger(deps.storage, &vamm_valid)?.remaining_budget(vamm_info.budget)?;
+    let available = match remaining_budget {
+        Some(remaining_budget) => share.min(remaining_budget),
+        None => share,
+    };
+
+    let open_interest_notional = query_engine_open_interest_notional(
+        &deps,
+        config.engine.to_string(),
+        vamm_valid.to_string(),
+    )?;
+
+    let coverage_ratio = if open_interest_notional.is_zero() {
+        None
+    } else {
+        Some(
+            available
+                .checked_mul(vamm_info.decimals)?
+                .checked_div(open_interest_notional)?,
+        )
+    };
+
+    Ok(CoverageResponse {
+        vamm: vamm_valid,
+        available,
+        open_interest_notional,
+        coverage_ratio,
+    })
+}
+
 /// Queries the status of multiple vAMMs, returning the vAMM address and whether it is on/off
-pub fn query_status_all_vamm(deps: Deps, limit: Option<u32>) -> StdResult<AllVammStatusResponse> {
+pub fn query_status_all_vamm(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllVammStatusResponse> {
     // set the limit for pagination
     let limit = limit
         .unwrap_or(DEFAULT_PAGINATION_LIMIT)
         .min(MAX_PAGINATION_LIMIT) as usize;
+    let start_after = start_after
+        .map(|vamm| deps.api.addr_validate(&vamm))
+        .transpose()?;
 
     let mut status_list: Vec<(Addr, bool)> = vec![];
 
     // iterate through the vamm list and query the status one by one
-    for vamm in read_vammlist(deps, limit)?.iter() {
+    for vamm in read_vammlist(deps, start_after, Some(limit))?.iter() {
         let vamm_bool = query_vamm_open(&deps, vamm.to_string())?;
         status_list.push((vamm.clone(), vamm_bool));
     }
@@ -85,3 +215,112 @@ pub fn query_status_all_vamm(deps: Deps, limit: Option<u32>) -> StdResult<AllVam
         vamm_list_status: status_list,
     })
 }
+
+/// Queries the shares issued to stakers and what they are worth in total
+pub fn query_staking_pool(deps: Deps) -> StdResult<StakingPoolResponse> {
+    let pool = read_staking_pool(deps.storage)?;
+
+    Ok(StakingPoolResponse {
+        total_shares: pool.total_shares,
+        total_staked: pool.total_staked,
+    })
+}
+
+/// Queries the shares of a staker, what they are worth and any pending unstake
+pub fn query_staker(deps: Deps, staker: String) -> StdResult<StakerResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    // validate address
+    let staker_valid = deps.api.addr_validate(&staker)?;
+
+    let pool = read_staking_pool(deps.storage)?;
+    let staker = read_staker(deps.storage, &staker_valid)?;
+
+    Ok(StakerResponse {
+        staker: staker_valid,
+        shares: staker.shares,
+        value: pool.value_of(staker.shares),
+        unstaking_shares: staker.unstaking_shares,
+        unstake_release_time: staker.unstake_release_time,
+        unstake_expiry_time: staker
+            .unstake_release_time
+            .filter(|_| config.unstake_window != 0)
+            .map(|release_time| release_time + config.unstake_window),
+    })
+}
+
+/// Queries what the margin engine can still withdraw in the current window, there
+/// is no limit if neither cap is set
+pub fn query_withdrawal_allowance(deps: Deps, env: Env) -> StdResult<WithdrawalAllowanceResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    let collateral = query_engine_collateral(&deps, config.engine.to_string())?;
+    let (window, remaining) = calc_withdrawal_window(deps, &env, &config, collateral)?;
+
+    Ok(WithdrawalAllowanceResponse {
+        window_start: window.start,
+        window_end: window.start + config.withdrawal_window,
+        withdrawn: window.withdrawn,
+        remaining,
+    })
+}
+
+/// Calculates the current withdrawal window and what is left of the allowance, shared by
+/// the query and withdrawals, a window that has ended is replaced by a new one
+pub fn calc_withdrawal_window(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    token: AssetInfo,
+) -> StdResult<(WithdrawalWindow, Option<Uint128>)> {
+    let now = env.block.time.seconds();
+
+    let window = match WITHDRAWAL_WINDOW.may_load(deps.storage)? {
+        Some(window) if now < window.start + config.withdrawal_window => window,
+        _ => WithdrawalWindow {
+            start: now,
+            start_balance: query_token_balance(deps, token, env.contract.address.clone())?,
+            withdrawn: Uint128::zero(),
+        },
+    };
+
+    // either cap can be disabled by setting it to zero, the lower one applies
+    let mut cap = None;
+    if !config.max_withdrawal_amount.is_zero() {
+        cap = Some(config.max_withdrawal_amount);
+    }
+
+    if !config.max_withdrawal_ratio.is_zero() {
+        let decimals = query_engine_decimals(&deps, config.engine.to_string())?;
+        let ratio_cap = window
+            .start_balance
+            .multiply_ratio(config.max_withdrawal_ratio, decimals);
+
+        cap = Some(cap.map_or(ratio_cap, |cap: Uint128| cap.min(ratio_cap)));
+    }
+
+    let remaining = cap.map(|cap| cap.saturating_sub(window.withdrawn));
+
+    Ok((window, remaining))
+}
+
+/// Queries the withdrawals waiting for the owner to approve them
+pub fn query_pending_withdrawals(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PendingWithdrawalsResponse> {
+    // set the limit for pagination
+    let limit = limit
+        .unwrap_or(DEFAULT_PAGINATION_LIMIT)
+        .min(MAX_PAGINATION_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let withdrawals = PENDING_WITHDRAWALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, withdrawal)| withdrawal))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PendingWithdrawalsResponse { withdrawals })
+}
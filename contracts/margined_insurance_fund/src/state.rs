@@ -1,94 +1,331 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Deps, DepsMut, StdError, StdResult, Storage};
+use cosmwasm_std::{Addr, Deps, DepsMut, Order, StdError, StdResult, Storage, Uint128};
 use cosmwasm_storage::{singleton, singleton_read};
-use cw_storage_plus::Item;
+use cw_storage_plus::{Bound, Item, Map};
+use margined_perp::margined_insurance_fund::{ContributionKind, DrawKind, PendingWithdrawal};
 
 pub static KEY_CONFIG: &[u8] = b"config";
-pub const VAMM_LIST: Item<Vec<Addr>> = Item::new("vamm-list");
-pub const VAMM_LIMIT: usize = 3usize;
+pub const VAMMS: Map<&Addr, VammInfo> = Map::new("vamms");
+pub const LEDGERS: Map<&Addr, Ledger> = Map::new("ledgers");
+pub const STAKING_POOL: Item<StakingPool> = Item::new("staking_pool");
+pub const STAKERS: Map<&Addr, Staker> = Map::new("stakers");
+pub const WITHDRAWAL_WINDOW: Item<WithdrawalWindow> = Item::new("withdrawal_window");
+pub const PENDING_WITHDRAWALS: Map<u64, PendingWithdrawal> = Map::new("pending_withdrawals");
+pub const PENDING_WITHDRAWAL_COUNT: Item<u64> = Item::new("pending_withdrawal_count");
+pub const ACTIVE_VAMM_COUNT: Item<u32> = Item::new("active_vamm_count");
 
-// function checks if an addr is already added and adds it if not
-// We also check that we have not reached the limit of vAMMs here
-pub fn save_vamm(deps: DepsMut, input: Addr) -> StdResult<()> {
-    // check if there is a vector
-    let mut vamm_list = match VAMM_LIST.may_load(deps.storage)? {
-        None => vec![],
-        Some(value) => value,
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VammInfo {
+    pub decimals: Uint128,
+    #[serde(default)]
+    pub budget: Option<Uint128>,
+    /// whether the vamm shares the balance of the fund, from when it is added until it is
+    /// shut down and again once it is reopened
+    #[serde(default)]
+    pub active: bool,
+}
+
+/// The insurance paid in and drawn by a single vamm, contributions and draws are
+/// reported by the margin engine so they are kept even if the vamm is removed
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct Ledger {
+    pub liquidation_contributions: Uint128,
+    pub fee_contributions: Uint128,
+    pub funding_contributions: Uint128,
+    pub bad_debt_draws: Uint128,
+    pub funding_draws: Uint128,
+    pub repeg_draws: Uint128,
+}
+
+impl Ledger {
+    pub fn total_contributions(&self) -> StdResult<Uint128> {
+        Ok(self
+            .liquidation_contributions
+            .checked_add(self.fee_contributions)?
+            .checked_add(self.funding_contributions)?)
+    }
+
+    pub fn total_draws(&self) -> StdResult<Uint128> {
+        Ok(self
+            .bad_debt_draws
+            .checked_add(self.funding_draws)?
+            .checked_add(self.repeg_draws)?)
+    }
+
+    /// whatever is left of the budget, if the vamm has one
+    pub fn remaining_budget(&self, budget: Option<Uint128>) -> StdResult<Option<Uint128>> {
+        let draws = self.total_draws()?;
+        Ok(budget.map(|budget| budget.saturating_sub(draws)))
+    }
+}
+
+pub fn read_ledger(storage: &dyn Storage, vamm: &Addr) -> StdResult<Ledger> {
+    Ok(LEDGERS.may_load(storage, vamm)?.unwrap_or_default())
+}
+
+pub fn record_contribution(
+    storage: &mut dyn Storage,
+    vamm: &Addr,
+    kind: ContributionKind,
+    amount: Uint128,
+) -> StdResult<Ledger> {
+    let mut ledger = read_ledger(storage, vamm)?;
+
+    let total = match kind {
+        ContributionKind::Liquidation => &mut ledger.liquidation_contributions,
+        ContributionKind::Fee => &mut ledger.fee_contributions,
+        ContributionKind::Funding => &mut ledger.funding_contributions,
+    };
+    *total = total.checked_add(amount)?;
+
+    LEDGERS.save(storage, vamm, &ledger)?;
+
+    Ok(ledger)
+}
+
+pub fn record_draw(
+    storage: &mut dyn Storage,
+    vamm: &Addr,
+    kind: DrawKind,
+    amount: Uint128,
+) -> StdResult<Ledger> {
+    let mut ledger = read_ledger(storage, vamm)?;
+
+    let total = match kind {
+        DrawKind::BadDebt => &mut ledger.bad_debt_draws,
+        DrawKind::Funding => &mut ledger.funding_draws,
+        DrawKind::Repeg => &mut ledger.repeg_draws,
     };
+    *total = total.checked_add(amount)?;
+
+    LEDGERS.save(storage, vamm, &ledger)?;
+
+    Ok(ledger)
+}
+
+/// The part of the fund that belongs to stakers, shares are tracked internally and
+/// are worth a pro-rata part of the total staked
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct StakingPool {
+    pub total_shares: Uint128,
+    pub total_staked: Uint128,
+    #[serde(default)]
+    pub epoch: u64,
+}
+
+impl StakingPool {
+    /// the shares minted for staking the amount, one share per token while the pool is empty
+    pub fn shares_for(&self, amount: Uint128) -> Uint128 {
+        if self.total_shares.is_zero() || self.total_staked.is_zero() {
+            return amount;
+        }
+
+        amount.multiply_ratio(self.total_shares, self.total_staked)
+    }
+
+    /// once losses have taken everything that was staked the shares are worthless, so
+    /// they are written off by starting a new epoch and the pool can be staked again
+    pub fn reset_if_wiped_out(&mut self) {
+        if self.total_staked.is_zero() && !self.total_shares.is_zero() {
+            self.total_shares = Uint128::zero();
+            self.epoch += 1;
+        }
+    }
+
+    /// what the shares can be redeemed for
+    pub fn value_of(&self, shares: Uint128) -> Uint128 {
+        if self.total_shares.is_zero() {
+            return Uint128::zero();
+        }
+
+        shares.multiply_ratio(self.total_staked, self.total_shares)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct Staker {
+    pub shares: Uint128,
+    pub unstaking_shares: Uint128,
+    pub unstake_release_time: Option<u64>,
+    #[serde(default)]
+    pub epoch: u64,
+}
+
+pub fn read_staking_pool(storage: &dyn Storage) -> StdResult<StakingPool> {
+    Ok(STAKING_POOL.may_load(storage)?.unwrap_or_default())
+}
 
+// shares from an earlier epoch of the pool were written off when it was wiped out
+pub fn read_staker(storage: &dyn Storage, staker: &Addr) -> StdResult<Staker> {
+    let pool = read_staking_pool(storage)?;
+
+    match STAKERS.may_load(storage, staker)? {
+        Some(staker) if staker.epoch == pool.epoch => Ok(staker),
+        _ => Ok(Staker {
+            epoch: pool.epoch,
+            ..Staker::default()
+        }),
+    }
+}
+
+/// What the margin engine has withdrawn in the current window, the ratio cap is taken
+/// of the balance when the window started so it cannot shrink as the fund is drawn
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WithdrawalWindow {
+    pub start: u64,
+    pub start_balance: Uint128,
+    pub withdrawn: Uint128,
+}
+
+// queues a withdrawal under the next id
+pub fn save_pending_withdrawal(
+    storage: &mut dyn Storage,
+    mut withdrawal: PendingWithdrawal,
+) -> StdResult<PendingWithdrawal> {
+    let id = PENDING_WITHDRAWAL_COUNT
+        .may_load(storage)?
+        .unwrap_or_default()
+        + 1;
+    PENDING_WITHDRAWAL_COUNT.save(storage, &id)?;
+
+    withdrawal.id = id;
+    PENDING_WITHDRAWALS.save(storage, id, &withdrawal)?;
+
+    Ok(withdrawal)
+}
+
+// takes the withdrawal off the queue
+pub fn remove_pending_withdrawal(
+    storage: &mut dyn Storage,
+    id: u64,
+) -> StdResult<PendingWithdrawal> {
+    let withdrawal = PENDING_WITHDRAWALS
+        .may_load(storage, id)?
+        .ok_or_else(|| StdError::generic_err("Withdrawal not found"))?;
+
+    PENDING_WITHDRAWALS.remove(storage, id);
+
+    Ok(withdrawal)
+}
+
+// function checks if an addr is already added and adds it if not
+pub fn save_vamm(deps: DepsMut, input: Addr, info: VammInfo) -> StdResult<()> {
     // check if we already added the vamm
-    if vamm_list.contains(&input) {
+    if VAMMS.has(deps.storage, &input) {
         return Err(StdError::GenericErr {
             msg: "This vAMM is already added".to_string(),
         });
     };
 
-    // check if we have reached the capacity
-    if vamm_list.len() >= VAMM_LIMIT {
-        return Err(StdError::GenericErr {
-            msg: "The vAMM capacity is already reached".to_string(),
-        });
+    if info.active {
+        let count = read_active_vamm_count(deps.storage)?;
+        store_active_vamm_count(deps.storage, count + 1)?;
+    }
+
+    VAMMS.save(deps.storage, &input, &info)
+}
+
+pub fn read_active_vamm_count(storage: &dyn Storage) -> StdResult<u32> {
+    Ok(ACTIVE_VAMM_COUNT.may_load(storage)?.unwrap_or_default())
+}
+
+fn store_active_vamm_count(storage: &mut dyn Storage, count: u32) -> StdResult<()> {
+    ACTIVE_VAMM_COUNT.save(storage, &count)
+}
+
+// marks whether a vamm is active, keeping the count of active vamms in step
+pub fn set_vamm_active(storage: &mut dyn Storage, vamm: &Addr, active: bool) -> StdResult<()> {
+    let mut info = VAMMS.load(storage, vamm)?;
+    if info.active == active {
+        return Ok(());
+    }
+
+    let count = read_active_vamm_count(storage)?;
+    let count = if active {
+        count + 1
+    } else {
+        count.saturating_sub(1)
     };
+    store_active_vamm_count(storage, count)?;
 
-    // add the vamm to the vector
-    vamm_list.push(input);
-    VAMM_LIST.save(deps.storage, &vamm_list)
+    info.active = active;
+    VAMMS.save(storage, vamm, &info)
 }
 
-// this function reads Addrs stored in the VAMM_LIST.
-// note that this function ONLY takes the first VAMM_LIMIT terms
-pub fn read_vammlist(deps: Deps, limit: usize) -> StdResult<Vec<Addr>> {
-    match VAMM_LIST.may_load(deps.storage)? {
-        None => Err(StdError::GenericErr {
+// this function reads Addrs stored in VAMMS in address order, starting after
+// the given address and taking at most limit terms
+pub fn read_vammlist(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<usize>,
+) -> StdResult<Vec<Addr>> {
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let vamms = VAMMS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect::<StdResult<Vec<Addr>>>()?;
+
+    if vamms.is_empty() && start_after.is_none() {
+        return Err(StdError::GenericErr {
             msg: "No vAMMs are stored".to_string(),
-        }),
-        Some(value) => {
-            let take = limit.min(value.len());
-            Ok(value[..take].to_vec())
-        }
+        });
     }
+
+    Ok(vamms)
 }
 
 // this function checks whether the vamm is stored already
 pub fn is_vamm(storage: &dyn Storage, input: Addr) -> bool {
-    match VAMM_LIST.may_load(storage).unwrap() {
-        None => false,
-        Some(value) => value.contains(&input),
-    }
+    VAMMS.has(storage, &input)
 }
 
 // this function deletes the entry under the given key
 pub fn remove_vamm(deps: DepsMut, input: Addr) -> StdResult<()> {
     // check if there are any vamms stored
-    let mut vamm_list = match VAMM_LIST.may_load(deps.storage)? {
-        None => {
-            return Err(StdError::GenericErr {
-                msg: "No vAMMs are stored".to_string(),
-            })
-        }
-        Some(value) => value,
-    };
+    if VAMMS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_none()
+    {
+        return Err(StdError::GenericErr {
+            msg: "No vAMMs are stored".to_string(),
+        });
+    }
 
     // check if the vamm is added
-    if !vamm_list.contains(&input) {
+    if !VAMMS.has(deps.storage, &input) {
         return Err(StdError::GenericErr {
             msg: "This vAMM has not been added".to_string(),
         });
     }
 
-    // change vamm_list
-    let index = vamm_list.clone().iter().position(|x| x.eq(&input)).unwrap();
-    vamm_list.swap_remove(index);
+    set_vamm_active(deps.storage, &input, false)?;
+    VAMMS.remove(deps.storage, &input);
 
-    // saves the updated vamm_list
-    VAMM_LIST.save(deps.storage, &vamm_list)
+    Ok(())
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
     pub engine: Addr,
+    #[serde(default)]
+    pub min_coverage_ratio: Uint128,
+    #[serde(default)]
+    pub staking_fee_share: Uint128,
+    #[serde(default)]
+    pub unstake_cooldown: u64,
+    #[serde(default)]
+    pub unstake_window: u64,
+    #[serde(default)]
+    pub withdrawal_window: u64,
+    #[serde(default)]
+    pub max_withdrawal_amount: Uint128,
+    #[serde(default)]
+    pub max_withdrawal_ratio: Uint128,
+    #[serde(default)]
+    pub factory: Option<Addr>,
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -0,0 +1,229 @@
+use cosmwasm_std::{coins, StdError, Uint128};
+use cw_multi_test::Executor;
+use margined_common::asset::AssetInfo;
+use margined_perp::margined_insurance_fund::{ContributionKind, DrawKind, ExecuteMsg};
+use margined_utils::scenarios::ShutdownScenario;
+
+#[test]
+fn test_ledger_records_contributions_and_draws() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        vamm1,
+        vamm2,
+        ..
+    } = ShutdownScenario::new();
+
+    let engine = insurance_fund.config(&router).unwrap().engine;
+    let msg = insurance_fund.add_vamm(vamm1.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &insurance_fund.addr(), coins(1_000u128, "uwasm"))
+            .unwrap()
+    });
+
+    let msg = ExecuteMsg::RecordContribution {
+        vamm: vamm1.addr().to_string(),
+        kind: ContributionKind::Fee,
+        amount: Uint128::from(100u128),
+    };
+
+    // only the engine can record contributions
+    let err = router
+        .execute_contract(owner.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "unauthorized".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    // only vamms that have been added can contribute or draw
+    let msg = ExecuteMsg::RecordContribution {
+        vamm: vamm2.addr().to_string(),
+        kind: ContributionKind::Fee,
+        amount: Uint128::from(100u128),
+    };
+    let err = router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "This vAMM has not been added".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(10u128),
+        vamm: vamm2.addr().to_string(),
+        kind: DrawKind::BadDebt,
+        defer: false,
+    };
+    let err = router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "This vAMM has not been added".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    let msg = ExecuteMsg::RecordContribution {
+        vamm: vamm1.addr().to_string(),
+        kind: ContributionKind::Liquidation,
+        amount: Uint128::from(50u128),
+    };
+    router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(30u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::BadDebt,
+        defer: false,
+    };
+    router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    let ledger = insurance_fund
+        .ledger(vamm1.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(ledger.fee_contributions, Uint128::from(100u128));
+    assert_eq!(ledger.liquidation_contributions, Uint128::from(50u128));
+    assert_eq!(ledger.total_contributions, Uint128::from(150u128));
+    assert_eq!(ledger.bad_debt_draws, Uint128::from(30u128));
+    assert_eq!(ledger.total_draws, Uint128::from(30u128));
+    assert_eq!(ledger.remaining_budget, None);
+
+    let balance = router
+        .wrap()
+        .query_balance(engine.to_string(), "uwasm")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::from(30u128));
+}
+
+#[test]
+fn test_exhausted_budget_shuts_down_vamm() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        vamm1,
+        vamm2,
+        ..
+    } = ShutdownScenario::new();
+
+    let engine = insurance_fund.config(&router).unwrap().engine;
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &insurance_fund.addr(), coins(1_000u128, "uwasm"))
+            .unwrap()
+    });
+
+    // the vamm has to be added before it can have a budget
+    let msg = insurance_fund
+        .set_budget(vamm1.addr().to_string(), Some(Uint128::from(100u128)))
+        .unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "This vAMM has not been added".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    for vamm in [&vamm1, &vamm2] {
+        let msg = insurance_fund.add_vamm(vamm.addr().to_string()).unwrap();
+        router.execute(owner.clone(), msg).unwrap();
+    }
+
+    let msg = insurance_fund
+        .set_budget(vamm1.addr().to_string(), Some(Uint128::from(100u128)))
+        .unwrap();
+    let err = router.execute(engine.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "unauthorized".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+    router.execute(owner.clone(), msg).unwrap();
+
+    let withdraw = |vamm: String, amount: u128| ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(amount),
+        vamm,
+        kind: DrawKind::BadDebt,
+        defer: false,
+    };
+
+    router
+        .execute_contract(
+            engine.clone(),
+            insurance_fund.addr(),
+            &withdraw(vamm1.addr().to_string(), 60u128),
+            &[],
+        )
+        .unwrap();
+    assert!(vamm1.state(&router).unwrap().open);
+
+    let ledger = insurance_fund
+        .ledger(vamm1.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(ledger.remaining_budget, Some(Uint128::from(40u128)));
+
+    // the draw that uses up the budget is still paid
+    router
+        .execute_contract(
+            engine.clone(),
+            insurance_fund.addr(),
+            &withdraw(vamm1.addr().to_string(), 50u128),
+            &[],
+        )
+        .unwrap();
+    assert!(!vamm1.state(&router).unwrap().open);
+
+    let ledger = insurance_fund
+        .ledger(vamm1.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(ledger.total_draws, Uint128::from(110u128));
+    assert_eq!(ledger.remaining_budget, Some(Uint128::zero()));
+
+    // other markets are not affected
+    router
+        .execute_contract(
+            engine.clone(),
+            insurance_fund.addr(),
+            &withdraw(vamm2.addr().to_string(), 200u128),
+            &[],
+        )
+        .unwrap();
+    assert!(vamm2.state(&router).unwrap().open);
+
+    let balance = router
+        .wrap()
+        .query_balance(engine.to_string(), "uwasm")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::from(310u128));
+}
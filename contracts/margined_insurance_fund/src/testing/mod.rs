@@ -1 +1,4 @@
+mod ledger_tests;
+mod staking_tests;
 mod tests;
+mod withdrawal_limit_tests;
@@ -0,0 +1,410 @@
+use cosmwasm_std::{coins, Addr, StdError, Uint128};
+use cw_multi_test::Executor;
+use margined_common::asset::AssetInfo;
+use margined_perp::margined_insurance_fund::{ContributionKind, DrawKind, ExecuteMsg};
+use margined_utils::scenarios::ShutdownScenario;
+
+#[test]
+fn test_stake_and_unstake_after_cooldown() {
+    let ShutdownScenario {
+        mut router,
+        insurance_fund,
+        ..
+    } = ShutdownScenario::new();
+
+    let staker = Addr::unchecked("staker");
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &staker, coins(1_000u128, "uwasm"))
+            .unwrap()
+    });
+
+    // the funds sent have to match the amount
+    let msg = insurance_fund
+        .stake(Uint128::from(100u128), coins(50u128, "uwasm"))
+        .unwrap();
+    let err = router.execute(staker.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Native token balance mismatch between the argument and the transferred"
+                .to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    let msg = insurance_fund
+        .stake(Uint128::from(100u128), coins(100u128, "uwasm"))
+        .unwrap();
+    router.execute(staker.clone(), msg).unwrap();
+
+    let res = insurance_fund.staker(staker.to_string(), &router).unwrap();
+    assert_eq!(res.shares, Uint128::from(100u128));
+    assert_eq!(res.value, Uint128::from(100u128));
+    assert_eq!(res.unstake_release_time, None);
+
+    // shares cannot be unstaked without a request
+    let msg = insurance_fund.unstake().unwrap();
+    let err = router.execute(staker.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "No unstake has been requested".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    let msg = insurance_fund
+        .request_unstake(Uint128::from(101u128))
+        .unwrap();
+    let err = router.execute(staker.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Insufficient shares".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    let msg = insurance_fund
+        .request_unstake(Uint128::from(40u128))
+        .unwrap();
+    router.execute(staker.clone(), msg).unwrap();
+
+    let cooldown = insurance_fund.config(&router).unwrap().unstake_cooldown;
+    let res = insurance_fund.staker(staker.to_string(), &router).unwrap();
+    assert_eq!(res.unstaking_shares, Uint128::from(40u128));
+    assert_eq!(
+        res.unstake_release_time,
+        Some(router.block_info().time.seconds() + cooldown)
+    );
+
+    let msg = insurance_fund.unstake().unwrap();
+    let err = router.execute(staker.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Unstake cooldown has not passed".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    router.update_block(|block| block.time = block.time.plus_seconds(cooldown));
+    router.execute(staker.clone(), msg).unwrap();
+
+    let res = insurance_fund.staker(staker.to_string(), &router).unwrap();
+    assert_eq!(res.shares, Uint128::from(60u128));
+    assert_eq!(res.unstaking_shares, Uint128::zero());
+    assert_eq!(res.unstake_release_time, None);
+
+    let pool = insurance_fund.staking_pool(&router).unwrap();
+    assert_eq!(pool.total_shares, Uint128::from(60u128));
+    assert_eq!(pool.total_staked, Uint128::from(60u128));
+
+    let balance = router
+        .wrap()
+        .query_balance(staker.to_string(), "uwasm")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::from(940u128));
+}
+
+#[test]
+fn test_stakers_earn_fees_and_absorb_bad_debt() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        vamm1,
+        ..
+    } = ShutdownScenario::new();
+
+    let engine = insurance_fund.config(&router).unwrap().engine;
+    let msg = insurance_fund.add_vamm(vamm1.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &insurance_fund.addr(), coins(800u128, "uwasm"))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &alice, coins(100u128, "uwasm"))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &bob, coins(100u128, "uwasm"))
+            .unwrap();
+    });
+
+    // the fee share is a ratio
+    let msg = insurance_fund
+        .update_config(
+            None,
+            Some(Uint128::from(2_000_000u128)),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Invalid ratio".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    // stakers get half of the trading fees
+    let msg = insurance_fund
+        .update_config(
+            None,
+            Some(Uint128::from(500_000u128)),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = insurance_fund
+        .stake(Uint128::from(100u128), coins(100u128, "uwasm"))
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::RecordContribution {
+        vamm: vamm1.addr().to_string(),
+        kind: ContributionKind::Fee,
+        amount: Uint128::from(100u128),
+    };
+    router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &insurance_fund.addr(), coins(1_000u128, "uwasm"))
+            .unwrap()
+    });
+
+    // alice earned 50 so bob gets two shares for every three tokens
+    let res = insurance_fund.staker(alice.to_string(), &router).unwrap();
+    assert_eq!(res.value, Uint128::from(150u128));
+
+    let msg = insurance_fund
+        .stake(Uint128::from(75u128), coins(75u128, "uwasm"))
+        .unwrap();
+    router.execute(bob.clone(), msg).unwrap();
+
+    let res = insurance_fund.staker(bob.to_string(), &router).unwrap();
+    assert_eq!(res.shares, Uint128::from(50u128));
+    assert_eq!(res.value, Uint128::from(75u128));
+
+    // liquidations are not shared with stakers
+    let msg = ExecuteMsg::RecordContribution {
+        vamm: vamm1.addr().to_string(),
+        kind: ContributionKind::Liquidation,
+        amount: Uint128::from(100u128),
+    };
+    router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    let pool = insurance_fund.staking_pool(&router).unwrap();
+    assert_eq!(pool.total_shares, Uint128::from(150u128));
+    assert_eq!(pool.total_staked, Uint128::from(225u128));
+
+    // the balance is 1075 of which 225 is staked, so stakers pay 225 / 1075 of the bad debt
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(430u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::BadDebt,
+        defer: false,
+    };
+    router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    let pool = insurance_fund.staking_pool(&router).unwrap();
+    assert_eq!(pool.total_staked, Uint128::from(135u128));
+
+    let res = insurance_fund.staker(alice.to_string(), &router).unwrap();
+    assert_eq!(res.value, Uint128::from(90u128));
+
+    let res = insurance_fund.staker(bob.to_string(), &router).unwrap();
+    assert_eq!(res.value, Uint128::from(45u128));
+
+    // every draw is shared, the balance is 645 so stakers pay 135 / 645 of the funding
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(100u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::Funding,
+        defer: false,
+    };
+    router
+        .execute_contract(engine, insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    let pool = insurance_fund.staking_pool(&router).unwrap();
+    assert_eq!(pool.total_staked, Uint128::from(115u128));
+}
+
+#[test]
+fn test_unstake_request_lapses_after_window() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        ..
+    } = ShutdownScenario::new();
+
+    let staker = Addr::unchecked("staker");
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &staker, coins(100u128, "uwasm"))
+            .unwrap()
+    });
+
+    let msg = insurance_fund
+        .update_config(
+            None,
+            None,
+            Some(100u64),
+            Some(50u64),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = insurance_fund
+        .stake(Uint128::from(100u128), coins(100u128, "uwasm"))
+        .unwrap();
+    router.execute(staker.clone(), msg).unwrap();
+
+    let msg = insurance_fund
+        .request_unstake(Uint128::from(100u128))
+        .unwrap();
+    router.execute(staker.clone(), msg).unwrap();
+
+    let now = router.block_info().time.seconds();
+    let res = insurance_fund.staker(staker.to_string(), &router).unwrap();
+    assert_eq!(res.unstake_release_time, Some(now + 100));
+    assert_eq!(res.unstake_expiry_time, Some(now + 150));
+
+    // the request cannot be used once the window has passed
+    router.update_block(|block| block.time = block.time.plus_seconds(150));
+    let msg = insurance_fund.unstake().unwrap();
+    let err = router.execute(staker.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Unstake request has expired".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    // a new request has to wait for the cooldown again
+    let msg = insurance_fund
+        .request_unstake(Uint128::from(100u128))
+        .unwrap();
+    router.execute(staker.clone(), msg).unwrap();
+
+    router.update_block(|block| block.time = block.time.plus_seconds(149));
+    let msg = insurance_fund.unstake().unwrap();
+    router.execute(staker.clone(), msg).unwrap();
+
+    let balance = router
+        .wrap()
+        .query_balance(staker.to_string(), "uwasm")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::from(100u128));
+}
+
+#[test]
+fn test_pool_can_be_staked_again_after_wipe_out() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        vamm1,
+        ..
+    } = ShutdownScenario::new();
+
+    let engine = insurance_fund.config(&router).unwrap().engine;
+    let msg = insurance_fund.add_vamm(vamm1.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &alice, coins(100u128, "uwasm"))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &bob, coins(50u128, "uwasm"))
+            .unwrap();
+    });
+
+    let msg = insurance_fund
+        .stake(Uint128::from(100u128), coins(100u128, "uwasm"))
+        .unwrap();
+    router.execute(alice.clone(), msg).unwrap();
+
+    // the stake is all the fund has so a draw of all of it wipes out the pool
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(100u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::Repeg,
+        defer: false,
+    };
+    router
+        .execute_contract(engine, insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    let pool = insurance_fund.staking_pool(&router).unwrap();
+    assert_eq!(pool.total_shares, Uint128::zero());
+    assert_eq!(pool.total_staked, Uint128::zero());
+
+    let res = insurance_fund.staker(alice.to_string(), &router).unwrap();
+    assert_eq!(res.shares, Uint128::zero());
+    assert_eq!(res.value, Uint128::zero());
+
+    // new stakers mint one share per token again
+    let msg = insurance_fund
+        .stake(Uint128::from(50u128), coins(50u128, "uwasm"))
+        .unwrap();
+    router.execute(bob.clone(), msg).unwrap();
+
+    let res = insurance_fund.staker(bob.to_string(), &router).unwrap();
+    assert_eq!(res.shares, Uint128::from(50u128));
+    assert_eq!(res.value, Uint128::from(50u128));
+
+    // the written off shares cannot be unstaked
+    let msg = insurance_fund
+        .request_unstake(Uint128::from(100u128))
+        .unwrap();
+    let err = router.execute(alice.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Insufficient shares".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+}
@@ -1,6 +1,6 @@
 use crate::contract::{execute, instantiate, query};
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_binary, Addr, StdError};
+use cosmwasm_std::{from_binary, Addr, StdError, Uint128};
 use cw_multi_test::Executor;
 use margined_perp::margined_insurance_fund::{
     ConfigResponse, ExecuteMsg, InstantiateMsg, OwnerResponse, QueryMsg,
@@ -24,6 +24,14 @@ fn test_instantiation() {
         config,
         ConfigResponse {
             engine: Addr::unchecked(ENGINE.to_string()),
+            min_coverage_ratio: Uint128::zero(),
+            staking_fee_share: Uint128::zero(),
+            unstake_cooldown: 604_800,
+            unstake_window: 172_800,
+            withdrawal_window: 86_400,
+            max_withdrawal_amount: Uint128::zero(),
+            max_withdrawal_ratio: Uint128::zero(),
+            factory: None,
         }
     );
 }
@@ -88,7 +96,7 @@ fn test_query_all_vamm() {
     } = ShutdownScenario::new();
 
     // check to see that there are no vAMMs
-    let res = insurance_fund.all_vamms(None, &router).unwrap_err();
+    let res = insurance_fund.all_vamms(None, None, &router).unwrap_err();
 
     assert_eq!(
         res.to_string(),
@@ -104,7 +112,7 @@ fn test_query_all_vamm() {
     router.execute(owner, msg).unwrap();
 
     // check for the added vAMMs
-    let res = insurance_fund.all_vamms(None, &router).unwrap();
+    let res = insurance_fund.all_vamms(None, None, &router).unwrap();
     let list = res.vamm_list;
 
     assert_eq!(list, vec![vamm1.addr(), vamm2.addr()]);
@@ -314,11 +322,11 @@ fn test_off_vamm_off_again() {
     router.execute(owner.clone(), msg).unwrap();
 
     //turn vamm off
-    let msg = insurance_fund.shutdown_vamms().unwrap();
+    let msg = insurance_fund.shutdown_vamms(None, None).unwrap();
     router.execute(owner.clone(), msg).unwrap();
 
     //turn vamm off again (note the unauthorized error comes from state.open == open)
-    let msg = insurance_fund.shutdown_vamms().unwrap();
+    let msg = insurance_fund.shutdown_vamms(None, None).unwrap();
     let err = router.execute(owner, msg).unwrap_err();
     assert_eq!(
         StdError::GenericErr {
@@ -353,7 +361,7 @@ fn test_vamm_shutdown() {
     router.execute(owner.clone(), msg).unwrap();
 
     // query all vamms' status
-    let res = insurance_fund.all_vamm_status(None, &router).unwrap();
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
     let vamms_status = res.vamm_list_status;
 
     assert_eq!(
@@ -366,11 +374,11 @@ fn test_vamm_shutdown() {
     );
 
     // shutdown all vamms
-    let msg = insurance_fund.shutdown_vamms().unwrap();
+    let msg = insurance_fund.shutdown_vamms(None, None).unwrap();
     router.execute(owner, msg).unwrap();
 
     // query all vamms' status
-    let res = insurance_fund.all_vamm_status(None, &router).unwrap();
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
     let vamms_status = res.vamm_list_status;
 
     assert_eq!(
@@ -383,6 +391,154 @@ fn test_vamm_shutdown() {
     );
 }
 
+#[test]
+fn test_vamm_shutdown_a_page_at_a_time() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        vamm1,
+        vamm2,
+        vamm3,
+        ..
+    } = ShutdownScenario::new();
+
+    for vamm in [&vamm1, &vamm2, &vamm3] {
+        let msg = insurance_fund.add_vamm(vamm.addr().to_string()).unwrap();
+        router.execute(owner.clone(), msg).unwrap();
+    }
+
+    // shutdown the first page of vamms
+    let msg = insurance_fund.shutdown_vamms(None, Some(2u32)).unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+    let last_vamm = res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "last_vamm")
+        .unwrap()
+        .value
+        .clone();
+    assert_eq!(last_vamm, vamm2.addr().to_string());
+
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
+    assert_eq!(
+        res.vamm_list_status,
+        vec![
+            (vamm1.addr(), false),
+            (vamm2.addr(), false),
+            (vamm3.addr(), true)
+        ]
+    );
+
+    // the next page carries on after the last vamm
+    let msg = insurance_fund
+        .shutdown_vamms(Some(last_vamm), Some(2u32))
+        .unwrap();
+    router.execute(owner, msg).unwrap();
+
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
+    assert_eq!(
+        res.vamm_list_status,
+        vec![
+            (vamm1.addr(), false),
+            (vamm2.addr(), false),
+            (vamm3.addr(), false)
+        ]
+    );
+}
+
+#[test]
+fn test_single_vamm_shutdown_and_reopen() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        vamm1,
+        vamm2,
+        vamm3,
+        ..
+    } = ShutdownScenario::new();
+
+    // the vamm has to be added first
+    let msg = insurance_fund
+        .shutdown_vamm(vamm1.addr().to_string())
+        .unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "This vAMM has not been added".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    for vamm in [&vamm1, &vamm2, &vamm3] {
+        let msg = insurance_fund.add_vamm(vamm.addr().to_string()).unwrap();
+        router.execute(owner.clone(), msg).unwrap();
+    }
+
+    // only the owner can shutdown a vamm
+    let msg = insurance_fund
+        .shutdown_vamm(vamm2.addr().to_string())
+        .unwrap();
+    let err = router
+        .execute(Addr::unchecked("not_the_owner"), msg.clone())
+        .unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "unauthorized".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+    router.execute(owner.clone(), msg.clone()).unwrap();
+
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
+    assert_eq!(
+        res.vamm_list_status,
+        vec![
+            (vamm1.addr(), true),
+            (vamm2.addr(), false),
+            (vamm3.addr(), true)
+        ]
+    );
+
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "This vAMM is already shut down".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    // only the owner can reopen a vamm
+    let msg = insurance_fund
+        .reopen_vamm(vamm2.addr().to_string())
+        .unwrap();
+    let err = router
+        .execute(Addr::unchecked("not_the_owner"), msg.clone())
+        .unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "unauthorized".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+    router.execute(owner.clone(), msg.clone()).unwrap();
+
+    let res = insurance_fund
+        .vamm_status(vamm2.addr().to_string(), &router)
+        .unwrap();
+    assert!(res.vamm_status);
+
+    let err = router.execute(owner, msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "This vAMM is already open".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+}
+
 #[test]
 fn test_vamm_shutdown_from_insurance() {
     let ShutdownScenario {
@@ -408,7 +564,7 @@ fn test_vamm_shutdown_from_insurance() {
     router.execute(owner.clone(), msg).unwrap();
 
     // query all vamms' status
-    let res = insurance_fund.all_vamm_status(None, &router).unwrap();
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
     let vamms_status = res.vamm_list_status;
 
     assert_eq!(
@@ -421,11 +577,11 @@ fn test_vamm_shutdown_from_insurance() {
     );
 
     // shutdown all vamms
-    let msg = insurance_fund.shutdown_vamms().unwrap();
+    let msg = insurance_fund.shutdown_vamms(None, None).unwrap();
     router.execute(insurance_fund.addr(), msg).unwrap();
 
     // query all vamms' status
-    let res = insurance_fund.all_vamm_status(None, &router).unwrap();
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
     let vamms_status = res.vamm_list_status;
 
     assert_eq!(
@@ -461,7 +617,7 @@ fn test_query_vamm_status() {
     assert_eq!(status, true);
 
     // shutdown vamm
-    let msg = insurance_fund.shutdown_vamms().unwrap();
+    let msg = insurance_fund.shutdown_vamms(None, None).unwrap();
     router.execute(owner.clone(), msg).unwrap();
 
     // query vamm status
@@ -485,7 +641,9 @@ fn test_all_vamm_status() {
     } = ShutdownScenario::new();
 
     // query all vamms' status (there aren't any yet)
-    let res = insurance_fund.all_vamm_status(None, &router).unwrap_err();
+    let res = insurance_fund
+        .all_vamm_status(None, None, &router)
+        .unwrap_err();
 
     assert_eq!(
         res.to_string(),
@@ -501,7 +659,7 @@ fn test_all_vamm_status() {
     router.execute(owner.clone(), msg).unwrap();
 
     // query all vamms' status
-    let res = insurance_fund.all_vamm_status(None, &router).unwrap();
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
     let vamms_status = res.vamm_list_status;
 
     assert_eq!(
@@ -510,11 +668,11 @@ fn test_all_vamm_status() {
     );
 
     // switch first vamm off
-    let msg = insurance_fund.shutdown_vamms().unwrap();
+    let msg = insurance_fund.shutdown_vamms(None, None).unwrap();
     router.execute(owner.clone(), msg).unwrap();
 
     // query all vamms' status
-    let res = insurance_fund.all_vamm_status(None, &router).unwrap();
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
     let vamms_status = res.vamm_list_status;
 
     assert_eq!(
@@ -525,8 +683,6 @@ fn test_all_vamm_status() {
 
 #[test]
 fn test_pagination() {
-    // note that this test is superfluous because DEFAULT_PAGINATION_LIMIT > MAX_PAGINATION_LIMIT (this tests default pagi limit)
-
     let ShutdownScenario {
         mut router,
         owner,
@@ -545,15 +701,15 @@ fn test_pagination() {
     router.execute(owner.clone(), msg).unwrap();
 
     //query only the first vamm (because we gave it limit of 1)
-    let res = insurance_fund.all_vamm_status(Some(1u32), &router).unwrap();
+    let res = insurance_fund
+        .all_vamm_status(None, Some(1u32), &router)
+        .unwrap();
     let vamms_status = res.vamm_list_status;
 
     assert_eq!(vamms_status, vec![(vamm1.addr(), true)]);
 }
 #[test]
 fn test_pagination_limit() {
-    // for the purpose of this test, VAMM_LIMIT is set to 3 (so four exceeds it!)
-
     let ShutdownScenario {
         mut router,
         owner,
@@ -577,7 +733,7 @@ fn test_pagination_limit() {
     }
 
     // query all vamms status
-    let res = insurance_fund.all_vamm_status(None, &router).unwrap();
+    let res = insurance_fund.all_vamm_status(None, None, &router).unwrap();
     let vamms_status = res.vamm_list_status;
 
     assert_eq!(
@@ -590,7 +746,9 @@ fn test_pagination_limit() {
     );
 
     //query only the first two vamms
-    let res = insurance_fund.all_vamm_status(Some(2u32), &router).unwrap();
+    let res = insurance_fund
+        .all_vamm_status(None, Some(2u32), &router)
+        .unwrap();
     let vamms_status = res.vamm_list_status;
 
     assert_eq!(
@@ -600,9 +758,7 @@ fn test_pagination_limit() {
 }
 
 #[test]
-fn test_vamm_capacity() {
-    // for the purpose of this test, VAMM_LIMIT is set to 3 (so four exceeds it!)
-
+fn test_no_vamm_capacity() {
     let ShutdownScenario {
         mut router,
         owner,
@@ -614,10 +770,6 @@ fn test_vamm_capacity() {
         ..
     } = ShutdownScenario::new();
 
-    ///////////////////////////////////////////////////////
-    // Test exceeding VAMM_LIMIT by adding a single vAMM //
-    ///////////////////////////////////////////////////////
-
     let vamms: Vec<String> = vec![
         vamm1.addr().to_string(),
         vamm2.addr().to_string(),
@@ -625,61 +777,44 @@ fn test_vamm_capacity() {
         vamm4.addr().to_string(),
     ];
 
-    //add three vamms
-    for n in 1..4 {
-        let msg = insurance_fund.add_vamm(vamms[n - 1].clone()).unwrap();
+    // add four vamms, more than the old limit of three
+    for vamm in vamms.iter() {
+        let msg = insurance_fund.add_vamm(vamm.clone()).unwrap();
         router.execute(owner.clone(), msg).unwrap();
     }
 
-    //try to add a fourth vamm
-    let msg = insurance_fund.add_vamm(vamm4.addr().to_string()).unwrap();
-    let err = router.execute(owner, msg).unwrap_err();
-    assert_eq!(
-        StdError::GenericErr {
-            msg: "The vAMM capacity is already reached".to_string(),
-        },
-        err.downcast().unwrap()
-    );
-
-    ////////////////////////////////////////////
-    // Test exceeding VAMM_LIMIT via for loop //
-    ////////////////////////////////////////////
-
-    let ShutdownScenario {
-        mut router,
-        owner,
-        insurance_fund,
-        vamm1,
-        vamm2,
-        vamm3,
-        vamm4,
-        ..
-    } = ShutdownScenario::new();
+    let res = insurance_fund.all_vamms(None, None, &router).unwrap();
+    assert_eq!(res.vamm_list.len(), 4usize);
 
-    let vamms: Vec<String> = vec![
-        vamm1.addr().to_string(),
-        vamm2.addr().to_string(),
-        vamm3.addr().to_string(),
-        vamm4.addr().to_string(),
-    ];
-
-    // add four vamms
-    for n in 1..5 {
-        let msg = insurance_fund.add_vamm(vamms[n - 1].clone()).unwrap();
+    // page through the vamms two at a time
+    let res = insurance_fund
+        .all_vamm_status(None, Some(2u32), &router)
+        .unwrap();
+    let first_page = res.vamm_list_status;
+    assert_eq!(first_page.len(), 2usize);
 
-        if n == 4 {
-            let err = router.execute(owner.clone(), msg).unwrap_err();
+    let start_after = first_page.last().unwrap().0.to_string();
+    let res = insurance_fund
+        .all_vamm_status(Some(start_after), Some(2u32), &router)
+        .unwrap();
+    let second_page = res.vamm_list_status;
+    assert_eq!(second_page.len(), 2usize);
 
-            assert_eq!(
-                StdError::GenericErr {
-                    msg: "The vAMM capacity is already reached".to_string(),
-                },
-                err.downcast().unwrap()
-            );
-            break;
-        }
-        router.execute(owner.clone(), msg).unwrap();
-    }
+    let start_after = second_page.last().unwrap().0.to_string();
+    let res = insurance_fund
+        .all_vamms(Some(start_after), Some(2u32), &router)
+        .unwrap();
+    assert!(res.vamm_list.is_empty());
+
+    let mut paged: Vec<String> = first_page
+        .iter()
+        .chain(second_page.iter())
+        .map(|(vamm, _)| vamm.to_string())
+        .collect();
+    paged.sort();
+    let mut expected = vamms;
+    expected.sort();
+    assert_eq!(paged, expected);
 }
 
 #[test]
@@ -726,7 +861,10 @@ fn test_not_owner() {
 
     //try to shutdown all vamms
     let info = mock_info("not_the_owner", &[]);
-    let msg = ExecuteMsg::ShutdownVamms {};
+    let msg = ExecuteMsg::ShutdownVamms {
+        start_after: None,
+        limit: None,
+    };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
 
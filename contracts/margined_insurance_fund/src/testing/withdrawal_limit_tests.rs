@@ -0,0 +1,365 @@
+use cosmwasm_std::{coins, Addr, StdError, Uint128};
+use cw_multi_test::Executor;
+use margined_common::asset::AssetInfo;
+use margined_perp::margined_insurance_fund::{DrawKind, ExecuteMsg, PendingWithdrawal};
+use margined_utils::scenarios::ShutdownScenario;
+
+#[test]
+fn test_withdrawals_over_allowance_are_queued() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        vamm1,
+        ..
+    } = ShutdownScenario::new();
+
+    let engine = insurance_fund.config(&router).unwrap().engine;
+    let msg = insurance_fund.add_vamm(vamm1.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &insurance_fund.addr(), coins(1_000u128, "uwasm"))
+            .unwrap()
+    });
+
+    // there is no limit until a cap is set
+    let res = insurance_fund.withdrawal_allowance(&router).unwrap();
+    assert_eq!(res.remaining, None);
+
+    // the lower of 300 and 20% of the balance applies
+    let msg = insurance_fund
+        .update_config(
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(Uint128::from(300u128)),
+            Some(Uint128::from(200_000u128)),
+            None,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let res = insurance_fund.withdrawal_allowance(&router).unwrap();
+    let window_start = router.block_info().time.seconds();
+    assert_eq!(res.window_start, window_start);
+    assert_eq!(res.window_end, window_start + 86_400);
+    assert_eq!(res.withdrawn, Uint128::zero());
+    assert_eq!(res.remaining, Some(Uint128::from(200u128)));
+
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(150u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::Funding,
+        defer: true,
+    };
+    router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    let res = insurance_fund.withdrawal_allowance(&router).unwrap();
+    assert_eq!(res.withdrawn, Uint128::from(150u128));
+    assert_eq!(res.remaining, Some(Uint128::from(50u128)));
+
+    // the next draw is over what is left so it fails unless it can wait for the owner
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(100u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::Funding,
+        defer: false,
+    };
+    let err = router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Withdrawal exceeds the allowance of the window".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(100u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::Funding,
+        defer: true,
+    };
+    let res = router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+    let event = res
+        .events
+        .iter()
+        .find(|e| e.ty == "wasm-withdrawal_queued")
+        .unwrap();
+    assert!(event
+        .attributes
+        .iter()
+        .any(|a| a.key == "remaining_allowance" && a.value == "50"));
+
+    let balance = router
+        .wrap()
+        .query_balance(engine.to_string(), "uwasm")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::from(150u128));
+
+    let ledger = insurance_fund
+        .ledger(vamm1.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(ledger.funding_draws, Uint128::from(150u128));
+
+    let res = insurance_fund
+        .pending_withdrawals(None, None, &router)
+        .unwrap();
+    assert_eq!(
+        res.withdrawals,
+        vec![PendingWithdrawal {
+            id: 1u64,
+            token: AssetInfo::NativeToken {
+                denom: "uwasm".to_string(),
+            },
+            amount: Uint128::from(100u128),
+            vamm: vamm1.addr(),
+            kind: DrawKind::Funding,
+            requested_at: window_start,
+        }]
+    );
+
+    // only the owner can approve
+    let msg = insurance_fund.approve_withdrawal(1u64).unwrap();
+    let err = router
+        .execute(Addr::unchecked("not_the_owner"), msg.clone())
+        .unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "unauthorized".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+    router.execute(owner.clone(), msg.clone()).unwrap();
+
+    let balance = router
+        .wrap()
+        .query_balance(engine.to_string(), "uwasm")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::from(250u128));
+
+    let ledger = insurance_fund
+        .ledger(vamm1.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(ledger.funding_draws, Uint128::from(250u128));
+
+    let res = insurance_fund
+        .pending_withdrawals(None, None, &router)
+        .unwrap();
+    assert!(res.withdrawals.is_empty());
+
+    let err = router.execute(owner, msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Withdrawal not found".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    // approved withdrawals do not use the allowance
+    let res = insurance_fund.withdrawal_allowance(&router).unwrap();
+    assert_eq!(res.withdrawn, Uint128::from(150u128));
+
+    // the next window starts from the current balance of 750
+    router.update_block(|block| block.time = block.time.plus_seconds(86_400));
+
+    let res = insurance_fund.withdrawal_allowance(&router).unwrap();
+    assert_eq!(res.window_start, window_start + 86_400);
+    assert_eq!(res.withdrawn, Uint128::zero());
+    assert_eq!(res.remaining, Some(Uint128::from(150u128)));
+}
+
+#[test]
+fn test_reject_queued_withdrawal() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        vamm1,
+        ..
+    } = ShutdownScenario::new();
+
+    let engine = insurance_fund.config(&router).unwrap().engine;
+    let msg = insurance_fund.add_vamm(vamm1.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &insurance_fund.addr(), coins(1_000u128, "uwasm"))
+            .unwrap()
+    });
+
+    // the ratio cannot be more than the whole balance
+    let msg = insurance_fund
+        .update_config(
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(Uint128::from(2_000_000u128)),
+            None,
+        )
+        .unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Invalid ratio".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    let msg = insurance_fund
+        .update_config(
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(Uint128::from(100u128)),
+            None,
+            None,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(500u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::Funding,
+        defer: true,
+    };
+    router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    // only the owner can reject
+    let msg = insurance_fund.reject_withdrawal(1u64).unwrap();
+    let err = router
+        .execute(Addr::unchecked("not_the_owner"), msg.clone())
+        .unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "unauthorized".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+    router.execute(owner, msg).unwrap();
+
+    let res = insurance_fund
+        .pending_withdrawals(None, None, &router)
+        .unwrap();
+    assert!(res.withdrawals.is_empty());
+
+    let balance = router
+        .wrap()
+        .query_balance(insurance_fund.addr().to_string(), "uwasm")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::from(1_000u128));
+
+    let ledger = insurance_fund
+        .ledger(vamm1.addr().to_string(), &router)
+        .unwrap();
+    assert_eq!(ledger.funding_draws, Uint128::zero());
+}
+
+#[test]
+fn test_bad_debt_is_paid_over_the_allowance() {
+    let ShutdownScenario {
+        mut router,
+        owner,
+        insurance_fund,
+        vamm1,
+        ..
+    } = ShutdownScenario::new();
+
+    let engine = insurance_fund.config(&router).unwrap().engine;
+    let msg = insurance_fund.add_vamm(vamm1.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+    router.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &insurance_fund.addr(), coins(1_000u128, "uwasm"))
+            .unwrap()
+    });
+
+    let msg = insurance_fund
+        .update_config(
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(Uint128::from(100u128)),
+            None,
+            None,
+        )
+        .unwrap();
+    router.execute(owner, msg).unwrap();
+
+    // positions have to be closed, so bad debt is paid in full without waiting
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(500u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::BadDebt,
+        defer: false,
+    };
+    router
+        .execute_contract(engine.clone(), insurance_fund.addr(), &msg, &[])
+        .unwrap();
+
+    let balance = router
+        .wrap()
+        .query_balance(engine.to_string(), "uwasm")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::from(500u128));
+
+    // but it still uses up the allowance for the other draws
+    let res = insurance_fund.withdrawal_allowance(&router).unwrap();
+    assert_eq!(res.withdrawn, Uint128::from(500u128));
+    assert_eq!(res.remaining, Some(Uint128::zero()));
+
+    let msg = ExecuteMsg::Withdraw {
+        token: AssetInfo::NativeToken {
+            denom: "uwasm".to_string(),
+        },
+        amount: Uint128::from(1u128),
+        vamm: vamm1.addr().to_string(),
+        kind: DrawKind::Funding,
+        defer: false,
+    };
+    let err = router
+        .execute_contract(engine, insurance_fund.addr(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Withdrawal exceeds the allowance of the window".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+}
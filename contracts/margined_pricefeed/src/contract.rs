@@ -1,18 +1,29 @@
 use crate::error::ContractError;
 use crate::{
-    handle::{append_multiple_price, append_price, update_owner},
+    handle::{
+        add_signer, add_subscriber, append_multiple_price, append_price,
+        migrate_price_history_batch, register_feed, remove_derived_feed, remove_signer,
+        remove_subscriber, set_averaging, set_derived_feed, set_reporters, set_validation,
+        submit_price, submit_signed_prices, subscribe, unsubscribe, update_config, update_feed,
+        update_owner, DEFAULT_MIGRATION_LIMIT,
+    },
     query::{
-        query_config, query_get_previous_price, query_get_price, query_get_twap_price, query_owner,
+        query_averaging, query_config, query_derived_feed, query_feed_info, query_get_ema_price,
+        query_get_median_price, query_get_previous_price, query_get_price, query_get_twap_price,
+        query_get_twap_price_at, query_latest_round_data, query_list_feeds, query_owner,
+        query_price_history, query_reporters, query_round_data, query_signer, query_subscriptions,
+        query_validation,
     },
-    state::{store_config, Config},
+    state::{migrate_price_history, read_config, register_legacy_feeds, store_config, Config},
 };
 use cw2::set_contract_version;
-use cw_controllers::Admin;
+use cw_controllers::{Admin, Hooks};
 
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError,
+    StdResult, SubMsgResult,
 };
-use margined_perp::margined_pricefeed::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use margined_perp::margined_pricefeed::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 /// Contract name that is used for migration.
 const CONTRACT_NAME: &str = "crates.io:margined-pricefeed";
@@ -20,6 +31,13 @@ const CONTRACT_NAME: &str = "crates.io:margined-pricefeed";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Owner admin
 pub const OWNER: Admin = Admin::new("owner");
+/// Hooks controller for the contracts that can subscribe to price updates
+pub const SUBSCRIBERS: Hooks = Hooks::new("subscribers");
+
+pub const PRICE_UPDATED_REPLY_ID: u64 = 1;
+
+const DEFAULT_SUBSCRIBER_GAS_LIMIT: u64 = 500_000;
+const DEFAULT_MAX_SUBSCRIPTIONS: u32 = 10;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -30,7 +48,10 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    let config = Config {};
+    let config = Config {
+        subscriber_gas_limit: DEFAULT_SUBSCRIBER_GAS_LIMIT,
+        max_subscriptions: DEFAULT_MAX_SUBSCRIPTIONS,
+    };
 
     store_config(deps.storage, &config)?;
 
@@ -42,7 +63,7 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
@@ -51,13 +72,85 @@ pub fn execute(
             key,
             price,
             timestamp,
-        } => append_price(deps, info, key, price, timestamp),
+        } => append_price(deps, env, info, key, price, timestamp),
         ExecuteMsg::AppendMultiplePrice {
             key,
             prices,
             timestamps,
-        } => append_multiple_price(deps, info, key, prices, timestamps),
+        } => append_multiple_price(deps, env, info, key, prices, timestamps),
         ExecuteMsg::UpdateOwner { owner } => update_owner(deps, info, owner),
+        ExecuteMsg::UpdateConfig {
+            subscriber_gas_limit,
+            max_subscriptions,
+        } => update_config(deps, info, subscriber_gas_limit, max_subscriptions),
+        ExecuteMsg::RegisterFeed {
+            key,
+            decimals,
+            description,
+            heartbeat,
+            reporters,
+            admin,
+        } => register_feed(
+            deps,
+            info,
+            key,
+            decimals,
+            description,
+            heartbeat,
+            reporters,
+            admin,
+        ),
+        ExecuteMsg::UpdateFeed {
+            key,
+            description,
+            heartbeat,
+            reporters,
+            admin,
+        } => update_feed(deps, info, key, description, heartbeat, reporters, admin),
+        ExecuteMsg::SetReporters {
+            key,
+            reporters,
+            quorum,
+            max_deviation_bps,
+            max_round_age,
+        } => set_reporters(
+            deps,
+            info,
+            key,
+            reporters,
+            quorum,
+            max_deviation_bps,
+            max_round_age,
+        ),
+        ExecuteMsg::SubmitPrice {
+            key,
+            price,
+            timestamp,
+        } => submit_price(deps, env, info, key, price, timestamp),
+        ExecuteMsg::AddSigner {
+            pubkey,
+            algorithm,
+            reporter,
+        } => add_signer(deps, info, pubkey, algorithm, reporter),
+        ExecuteMsg::RemoveSigner { pubkey } => remove_signer(deps, info, pubkey),
+        ExecuteMsg::SubmitSignedPrices { payloads } => submit_signed_prices(deps, env, payloads),
+        ExecuteMsg::SetValidation {
+            key,
+            heartbeat,
+            confirmation_threshold_bps,
+        } => set_validation(deps, info, key, heartbeat, confirmation_threshold_bps),
+        ExecuteMsg::SetAveraging {
+            key,
+            max_gap,
+            ema_half_life,
+        } => set_averaging(deps, info, key, max_gap, ema_half_life),
+        ExecuteMsg::SetDerivedFeed { key, feed } => set_derived_feed(deps, info, key, feed),
+        ExecuteMsg::RemoveDerivedFeed { key } => remove_derived_feed(deps, info, key),
+        ExecuteMsg::AddSubscriber { address } => add_subscriber(deps, info, address),
+        ExecuteMsg::RemoveSubscriber { address } => remove_subscriber(deps, info, address),
+        ExecuteMsg::Subscribe { key, deviation_bps } => subscribe(deps, info, key, deviation_bps),
+        ExecuteMsg::Unsubscribe { key } => unsubscribe(deps, info, key),
+        ExecuteMsg::MigratePriceHistory { limit } => migrate_price_history_batch(deps, limit),
     }
 }
 
@@ -66,7 +159,7 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::GetOwner {} => to_binary(&query_owner(deps)?),
-        QueryMsg::GetPrice { key } => to_binary(&query_get_price(deps, key)?),
+        QueryMsg::GetPrice { key } => to_binary(&query_get_price(deps, env, key)?),
         QueryMsg::GetPreviousPrice {
             key,
             num_round_back,
@@ -74,5 +167,95 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::GetTwapPrice { key, interval } => {
             to_binary(&query_get_twap_price(deps, env, key, interval)?)
         }
+        QueryMsg::GetTwapPriceAt {
+            key,
+            interval,
+            timestamp,
+        } => to_binary(&query_get_twap_price_at(
+            deps, env, key, interval, timestamp,
+        )?),
+        QueryMsg::GetEmaPrice { key } => to_binary(&query_get_ema_price(deps, env, key)?),
+        QueryMsg::GetMedianPrice { key, interval } => {
+            to_binary(&query_get_median_price(deps, env, key, interval)?)
+        }
+        QueryMsg::GetPriceData { key } => to_binary(&query_get_price(deps, env, key)?),
+        QueryMsg::GetPreviousPriceData {
+            key,
+            num_round_back,
+        } => to_binary(&query_get_previous_price(deps, key, num_round_back)?),
+        QueryMsg::GetReporters { key } => to_binary(&query_reporters(deps, key)?),
+        QueryMsg::GetRoundData { key, round_id } => {
+            to_binary(&query_round_data(deps, key, round_id)?)
+        }
+        QueryMsg::GetLatestRoundData { key } => to_binary(&query_latest_round_data(deps, key)?),
+        QueryMsg::GetSigner { pubkey } => to_binary(&query_signer(deps, pubkey)?),
+        QueryMsg::PriceHistory {
+            key,
+            start_after,
+            limit,
+        } => to_binary(&query_price_history(deps, key, start_after, limit)?),
+        QueryMsg::GetValidation { key } => to_binary(&query_validation(deps, key)?),
+        QueryMsg::GetAveraging { key } => to_binary(&query_averaging(deps, key)?),
+        QueryMsg::GetDerivedFeed { key } => to_binary(&query_derived_feed(deps, key)?),
+        QueryMsg::FeedInfo { key } => to_binary(&query_feed_info(deps, key)?),
+        QueryMsg::GetSubscribers {} => to_binary(&SUBSCRIBERS.query_hooks(deps)?),
+        QueryMsg::GetSubscriptions {
+            key,
+            start_after,
+            limit,
+        } => to_binary(&query_subscriptions(deps, key, start_after, limit)?),
+        QueryMsg::ListFeeds { start_after, limit } => {
+            to_binary(&query_list_feeds(deps, start_after, limit)?)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> StdResult<Response> {
+    match msg.id {
+        // the callback of the subscriber is reverted but the price update goes through
+        PRICE_UPDATED_REPLY_ID => {
+            let error = match msg.result {
+                SubMsgResult::Err(error) => error,
+                SubMsgResult::Ok(_) => String::default(),
+            };
+
+            Ok(Response::default()
+                .add_attributes(vec![("action", "price_updated_failed"), ("error", &error)]))
+        }
+        _ => Err(StdError::generic_err(format!(
+            "reply (id {:?}) invalid",
+            msg.id
+        ))),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // configs stored before the subscriber limits were introduced take the defaults
+    let mut config = read_config(deps.storage)?;
+    if config.subscriber_gas_limit == 0 {
+        config.subscriber_gas_limit = DEFAULT_SUBSCRIBER_GAS_LIMIT;
     }
+    if config.max_subscriptions == 0 {
+        config.max_subscriptions = DEFAULT_MAX_SUBSCRIPTIONS;
+    }
+    store_config(deps.storage, &config)?;
+
+    // keys priced before feeds were introduced are registered to the owner
+    let owner = OWNER.get(deps.as_ref())?.unwrap();
+    let registered = register_legacy_feeds(deps.storage, &owner, msg.decimals)?;
+
+    // large histories are left to be moved in batches by migrate price history
+    let (migrated, has_remaining) =
+        migrate_price_history(deps.storage, DEFAULT_MIGRATION_LIMIT as u64)?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "migrate"),
+        ("registered_feeds", &registered.to_string()),
+        ("migrated_rounds", &migrated.to_string()),
+        ("has_remaining", &has_remaining.to_string()),
+    ]))
 }
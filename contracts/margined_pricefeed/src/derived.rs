@@ -0,0 +1,233 @@
+use cosmwasm_std::{Env, StdError, StdResult, Storage, Timestamp, Uint128};
+use margined_perp::margined_pricefeed::{Derivation, DerivedFeed};
+
+use crate::state::{
+    find_round_at, read_averaging, read_latest_round_id, read_price_data, read_validation,
+    PriceData,
+};
+
+/// The most derived rounds a time weighted price steps through within its interval
+const MAX_DERIVED_TWAP_ROUNDS: u32 = 100u32;
+
+/// A point in the history of a derived feed, i.e. the round each underlying key was at.
+/// The derived round id is the sum of the underlying round ids, so it increases by one with
+/// every update of an underlying key
+pub struct DerivedCursor {
+    keys: Vec<String>,
+    rounds: Vec<PriceData>,
+}
+
+impl DerivedCursor {
+    /// Returns the cursor at the latest round of every underlying key
+    pub fn latest(storage: &dyn Storage, feed: &DerivedFeed) -> StdResult<Self> {
+        let keys = underlying_keys(&feed.derivation);
+
+        let mut rounds = vec![];
+        for key in keys.iter() {
+            let round_id = read_latest_round_id(storage, key)?;
+            if round_id == 0 {
+                return Err(StdError::generic_err(format!(
+                    "Unable to retrieve price data for key: {}",
+                    key
+                )));
+            }
+
+            rounds.push(read_price_data(storage, key, round_id)?);
+        }
+
+        Ok(DerivedCursor { keys, rounds })
+    }
+
+    /// Returns the cursor at the latest round of every underlying key published at or before
+    /// the timestamp
+    pub fn at(storage: &dyn Storage, feed: &DerivedFeed, timestamp: u64) -> StdResult<Self> {
+        let cursor = DerivedCursor::latest(storage, feed)?;
+
+        let mut rounds = vec![];
+        for (key, latest) in cursor.keys.iter().zip(cursor.rounds.iter()) {
+            let round_id = find_round_at(storage, key, timestamp, latest.round_id.u128())?
+                .ok_or_else(|| StdError::generic_err("Insufficient history"))?;
+
+            rounds.push(read_price_data(storage, key, round_id)?);
+        }
+
+        Ok(DerivedCursor {
+            keys: cursor.keys,
+            rounds,
+        })
+    }
+
+    pub fn round_id(&self) -> Uint128 {
+        self.rounds.iter().map(|round| round.round_id).sum()
+    }
+
+    /// The derived price takes effect once the last of its underlying prices is published
+    pub fn timestamp(&self) -> Timestamp {
+        self.rounds
+            .iter()
+            .map(|round| round.timestamp)
+            .max()
+            .unwrap()
+    }
+
+    pub fn price(&self, feed: &DerivedFeed) -> StdResult<Uint128> {
+        let unit = feed.unit;
+        let prices: Vec<Uint128> = self.rounds.iter().map(|round| round.price).collect();
+
+        let price = match feed.derivation {
+            Derivation::Ratio { .. } => prices[0].checked_multiply_ratio(unit, prices[1]),
+            Derivation::Product { .. } => prices[0].checked_multiply_ratio(prices[1], unit),
+            Derivation::Inverse { .. } => unit.checked_multiply_ratio(unit, prices[0]),
+        }
+        .map_err(|_| StdError::generic_err("Unable to derive price"))?;
+
+        match feed.scale {
+            Some(scale) => price
+                .checked_multiply_ratio(scale, unit)
+                .map_err(|_| StdError::generic_err("Unable to derive price")),
+            None => Ok(price),
+        }
+    }
+
+    pub fn price_data(&self, feed: &DerivedFeed) -> StdResult<PriceData> {
+        Ok(PriceData {
+            round_id: self.round_id(),
+            price: self.price(feed)?,
+            timestamp: self.timestamp(),
+        })
+    }
+
+    /// Steps back to the previous derived round by undoing the latest underlying update,
+    /// returns false if that would go past the first round of an underlying key
+    pub fn step_back(&mut self, storage: &dyn Storage) -> StdResult<bool> {
+        let index = self
+            .rounds
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, round)| (round.timestamp, std::cmp::Reverse(*index)))
+            .map(|(index, _)| index)
+            .unwrap();
+
+        let round_id = self.rounds[index].round_id.u128();
+        if round_id <= 1 {
+            return Ok(false);
+        }
+
+        self.rounds[index] = read_price_data(storage, &self.keys[index], round_id - 1)?;
+
+        Ok(true)
+    }
+
+    /// A derived price is stale if any of its underlying prices is, or if it is older than
+    /// the heartbeat of the derived key itself
+    pub fn is_stale(&self, storage: &dyn Storage, env: &Env, key: &str) -> StdResult<bool> {
+        if is_stale(storage, env, key, self.timestamp())? {
+            return Ok(true);
+        }
+
+        for (key, round) in self.keys.iter().zip(self.rounds.iter()) {
+            if is_stale(storage, env, key, round.timestamp)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+fn is_stale(storage: &dyn Storage, env: &Env, key: &str, timestamp: Timestamp) -> StdResult<bool> {
+    let validation = read_validation(storage, key)?;
+    let age = env.block.time.seconds().saturating_sub(timestamp.seconds());
+
+    Ok(validation.heartbeat != 0 && age > validation.heartbeat)
+}
+
+pub fn underlying_keys(derivation: &Derivation) -> Vec<String> {
+    match derivation {
+        Derivation::Ratio {
+            numerator,
+            denominator,
+        } => vec![numerator.clone(), denominator.clone()],
+        Derivation::Product { first, second } => vec![first.clone(), second.clone()],
+        Derivation::Inverse { key } => vec![key.clone()],
+    }
+}
+
+/// Returns the derived price a number of rounds back
+pub fn derived_previous_price(
+    storage: &dyn Storage,
+    feed: &DerivedFeed,
+    num_round_back: Uint128,
+) -> StdResult<PriceData> {
+    let mut cursor = DerivedCursor::latest(storage, feed)?;
+
+    for _ in 0..num_round_back.u128() {
+        if !cursor.step_back(storage)? {
+            return Err(StdError::generic_err("Not enough history"));
+        }
+    }
+
+    cursor.price_data(feed)
+}
+
+/// Returns the time weighted derived price over the interval, weighting each derived round
+/// from when the last of its underlying prices was published for at most the max gap of the
+/// derived key. Errors if the interval spans more than MAX_DERIVED_TWAP_ROUNDS derived rounds
+pub fn derived_twap_price(
+    storage: &dyn Storage,
+    key: &str,
+    end_timestamp: u64,
+    feed: &DerivedFeed,
+    interval: u64,
+) -> StdResult<Uint128> {
+    let base_timestamp = end_timestamp.saturating_sub(interval);
+
+    // a derived round is worked out from its underlying rounds, so the current averaging of
+    // the derived key applies to all of them
+    let max_gap = read_averaging(storage, key)?.max_gap;
+    let weight = |elapsed: u64| match max_gap {
+        0 => elapsed,
+        max_gap => elapsed.min(max_gap),
+    };
+
+    // the window ends at the end timestamp so only the rounds published by then count
+    let mut cursor = DerivedCursor::at(storage, feed, end_timestamp)?;
+    let latest_price = cursor.price(feed)?;
+    let latest_timestamp = cursor.timestamp().seconds();
+
+    let mut weighted_price = Uint128::zero();
+    let mut cumulative_time = Uint128::zero();
+    let mut timestamp = end_timestamp;
+
+    for _ in 0..MAX_DERIVED_TWAP_ROUNDS {
+        let round_timestamp = cursor.timestamp().seconds();
+        let start = round_timestamp.max(base_timestamp);
+
+        let delta_timestamp =
+            Uint128::from(weight(timestamp - round_timestamp) - weight(start - round_timestamp));
+        weighted_price =
+            weighted_price.checked_add(cursor.price(feed)?.checked_mul(delta_timestamp)?)?;
+        cumulative_time = cumulative_time.checked_add(delta_timestamp)?;
+
+        if round_timestamp <= base_timestamp || !cursor.step_back(storage)? {
+            if !cumulative_time.is_zero() {
+                return Ok(weighted_price.checked_div(cumulative_time)?);
+            }
+
+            // the only price was published at the end of the window
+            if latest_timestamp == end_timestamp {
+                return Ok(latest_price);
+            }
+
+            return Err(StdError::generic_err(
+                "No prices within the max gap during the interval",
+            ));
+        }
+
+        timestamp = round_timestamp;
+    }
+
+    Err(StdError::generic_err(
+        "Too many derived rounds during the interval",
+    ))
+}
@@ -1,5 +1,5 @@
 use cosmwasm_std::StdError;
-use cw_controllers::AdminError;
+use cw_controllers::{AdminError, HookError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -10,6 +10,9 @@ pub enum ContractError {
     #[error("{0}")]
     Admin(#[from] AdminError),
 
+    #[error("{0}")]
+    Hook(#[from] HookError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
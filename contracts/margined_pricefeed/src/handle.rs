@@ -1,6 +1,27 @@
-use cosmwasm_std::{DepsMut, MessageInfo, Response, StdError, Uint128};
+use cosmwasm_std::{
+    attr, to_binary, Addr, Attribute, Binary, Deps, DepsMut, Env, Event, MessageInfo, Order,
+    Response, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+};
+use margined_perp::margined_pricefeed::{
+    DerivedFeed, PriceHookMsg, SignatureAlgorithm, SignedPrice,
+};
 
-use crate::{contract::OWNER, error::ContractError, state::store_price_data};
+use crate::{
+    contract::{OWNER, PRICE_UPDATED_REPLY_ID, SUBSCRIBERS},
+    derived::underlying_keys,
+    error::ContractError,
+    state::{
+        migrate_price_history, read_config, read_feed, read_latest_round_id, read_price_data,
+        read_validation, store_config, store_price_data, Averaging, Feed, PendingRound, PriceData,
+        ReporterConfig, RoundMetadata, Signer, Submission, Subscription, UnconfirmedPrice,
+        Validation, AVERAGINGS, DERIVED_FEEDS, FEEDS, PENDING_ROUNDS, REPORTERS, ROUNDS, SIGNERS,
+        SIGNER_TIMESTAMPS, SUBSCRIPTIONS, UNCONFIRMED_PRICES, VALIDATIONS,
+    },
+    utils::{calc_median, filter_outliers, is_over_threshold, price_payload_hash, BPS_DENOMINATOR},
+};
+
+pub const DEFAULT_MIGRATION_LIMIT: u32 = 100u32;
+const MAX_MIGRATION_LIMIT: u32 = 500u32;
 
 pub fn update_owner(
     deps: DepsMut,
@@ -13,36 +34,83 @@ pub fn update_owner(
     Ok(OWNER.execute_update_admin(deps, info, Some(valid_owner))?)
 }
 
-/// this is a mock function that enables storage of data
-/// by the contract owner will be replaced by integration
-/// with on-chain price oracles in the future.
+/// Sets the gas each subscriber is called back with and how many subscriptions a key can have
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    subscriber_gas_limit: Option<u64>,
+    max_subscriptions: Option<u32>,
+) -> Result<Response, ContractError> {
+    // check permission
+    OWNER.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let mut config = read_config(deps.storage)?;
+
+    // change the gas limit of subscriber callbacks
+    if let Some(subscriber_gas_limit) = subscriber_gas_limit {
+        if subscriber_gas_limit == 0 {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Invalid subscriber gas limit",
+            )));
+        }
+        config.subscriber_gas_limit = subscriber_gas_limit;
+    }
+
+    // change the most subscriptions a key can have, existing subscriptions are kept
+    if let Some(max_subscriptions) = max_subscriptions {
+        config.max_subscriptions = max_subscriptions;
+    }
+
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::default().add_attribute("action", "update_config"))
+}
+
+/// Lets the owner or the admin of a feed push a price directly, keys that need to be
+/// relied on in production should be given reporters and updated through `submit_price`
 pub fn append_price(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     key: String,
     price: Uint128,
     timestamp: u64,
 ) -> Result<Response, ContractError> {
     // check permission
-    OWNER.assert_admin(deps.as_ref(), &info.sender)?;
+    assert_feed_admin(deps.as_ref(), &key, &info.sender)?;
 
-    store_price_data(deps.storage, key, price, timestamp)?;
+    let price_data = store_owner_round(
+        deps.storage,
+        &env,
+        &info.sender,
+        key.clone(),
+        price,
+        timestamp,
+    )?;
 
-    Ok(Response::default().add_attribute("action", "append_price"))
+    let response = Response::default().add_attribute("action", "append_price");
+
+    match price_data {
+        Some(price_data) => {
+            let messages = notify_subscribers(deps, &key, &price_data)?;
+            Ok(response.add_submessages(messages))
+        }
+        None => Ok(response.add_attribute("requires_confirmation", "true")),
+    }
 }
 
-/// this is a mock function that enables storage of data
-/// by the contract owner will be replaced by integration
-/// with on-chain price oracles in the future.
+/// Pushes a batch of prices for a key in order, with the same permissions and validation
+/// as `append_price`
 pub fn append_multiple_price(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     key: String,
     prices: Vec<Uint128>,
     timestamps: Vec<u64>,
 ) -> Result<Response, ContractError> {
     // check permission
-    OWNER.assert_admin(deps.as_ref(), &info.sender)?;
+    assert_feed_admin(deps.as_ref(), &key, &info.sender)?;
 
     // This throws if the prices and timestamps are not the same length
     if prices.len() != timestamps.len() {
@@ -51,9 +119,910 @@ pub fn append_multiple_price(
         )));
     }
 
+    let mut messages: Vec<SubMsg> = vec![];
     for index in 0..prices.len() {
-        store_price_data(deps.storage, key.clone(), prices[index], timestamps[index])?;
+        let price_data = store_owner_round(
+            deps.storage,
+            &env,
+            &info.sender,
+            key.clone(),
+            prices[index],
+            timestamps[index],
+        )?;
+
+        if let Some(price_data) = price_data {
+            messages.extend(notify_subscribers(deps.branch(), &key, &price_data)?);
+        }
+    }
+
+    Ok(Response::default().add_submessages(messages))
+}
+
+/// Registers a feed, prices are only accepted for registered keys
+#[allow(clippy::too_many_arguments)]
+pub fn register_feed(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+    decimals: u8,
+    description: String,
+    heartbeat: u64,
+    reporters: Vec<String>,
+    admin: Option<String>,
+) -> Result<Response, ContractError> {
+    // check permission
+    OWNER.assert_admin(deps.as_ref(), &info.sender)?;
+
+    if FEEDS.has(deps.storage, &key) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Feed already registered",
+        )));
+    }
+
+    let admin = match admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    let reporters = validate_reporters(deps.as_ref(), reporters)?;
+
+    FEEDS.save(
+        deps.storage,
+        &key,
+        &Feed {
+            decimals,
+            description,
+            reporters,
+            admin: admin.clone(),
+        },
+    )?;
+
+    let mut validation = read_validation(deps.storage, &key)?;
+    validation.heartbeat = heartbeat;
+    VALIDATIONS.save(deps.storage, &key, &validation)?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "register_feed"),
+        ("key", &key),
+        ("decimals", &decimals.to_string()),
+        ("admin", admin.as_str()),
+    ]))
+}
+
+/// Updates the metadata of a feed, only callable by the owner or the admin of the feed
+pub fn update_feed(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+    description: Option<String>,
+    heartbeat: Option<u64>,
+    reporters: Option<Vec<String>>,
+    admin: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut feed = read_feed(deps.storage, &key)?;
+
+    // check permission
+    assert_feed_admin(deps.as_ref(), &key, &info.sender)?;
+
+    // change description
+    if let Some(description) = description {
+        feed.description = description;
+    }
+
+    // change heartbeat
+    if let Some(heartbeat) = heartbeat {
+        let mut validation = read_validation(deps.storage, &key)?;
+        validation.heartbeat = heartbeat;
+        VALIDATIONS.save(deps.storage, &key, &validation)?;
+    }
+
+    // change the allowed reporters, the reporters of the key are cleared if any is no longer allowed
+    if let Some(reporters) = reporters {
+        feed.reporters = validate_reporters(deps.as_ref(), reporters)?;
+
+        if let Some(config) = REPORTERS.may_load(deps.storage, &key)? {
+            if config.reporters.iter().any(|r| !feed.reporters.contains(r)) {
+                REPORTERS.remove(deps.storage, &key);
+                PENDING_ROUNDS.remove(deps.storage, &key);
+            }
+        }
+    }
+
+    // change admin
+    if let Some(admin) = admin {
+        feed.admin = deps.api.addr_validate(&admin)?;
+    }
+
+    FEEDS.save(deps.storage, &key, &feed)?;
+
+    Ok(Response::default().add_attributes(vec![("action", "update_feed"), ("key", &key)]))
+}
+
+/// Sets the reporters of a key, a round is answered by the median of the submissions once
+/// quorum of them are within the max deviation of each other
+#[allow(clippy::too_many_arguments)]
+pub fn set_reporters(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+    reporters: Vec<String>,
+    quorum: u32,
+    max_deviation_bps: u64,
+    max_round_age: u64,
+) -> Result<Response, ContractError> {
+    // check permission
+    assert_feed_admin(deps.as_ref(), &key, &info.sender)?;
+
+    let feed = read_feed(deps.storage, &key)?;
+
+    let valid_reporters = validate_reporters(deps.as_ref(), reporters)?;
+    if valid_reporters.iter().any(|r| !feed.reporters.contains(r)) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Reporter not allowed for feed",
+        )));
+    }
+
+    if quorum == 0 || quorum as usize > valid_reporters.len() {
+        return Err(ContractError::Std(StdError::generic_err("Invalid quorum")));
+    }
+
+    if max_deviation_bps > BPS_DENOMINATOR {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Invalid max deviation",
+        )));
+    }
+
+    if max_round_age == 0 {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Invalid max round age",
+        )));
+    }
+
+    REPORTERS.save(
+        deps.storage,
+        &key,
+        &ReporterConfig {
+            reporters: valid_reporters,
+            quorum,
+            max_deviation_bps,
+            max_round_age,
+        },
+    )?;
+
+    // submissions made under the previous reporters no longer count
+    PENDING_ROUNDS.remove(deps.storage, &key);
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "set_reporters"),
+        ("key", &key),
+        ("quorum", &quorum.to_string()),
+    ]))
+}
+
+/// Sets how the prices of a key are validated, a price is stale once it is older than the
+/// heartbeat and a move above the confirmation threshold needs a second price to confirm it,
+/// zero disables either
+pub fn set_validation(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+    heartbeat: u64,
+    confirmation_threshold_bps: u64,
+) -> Result<Response, ContractError> {
+    // check permission
+    assert_feed_admin(deps.as_ref(), &key, &info.sender)?;
+
+    VALIDATIONS.save(
+        deps.storage,
+        &key,
+        &Validation {
+            heartbeat,
+            confirmation_threshold_bps,
+        },
+    )?;
+
+    // a held price may not be over the new threshold
+    UNCONFIRMED_PRICES.remove(deps.storage, &key);
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "set_validation"),
+        ("key", &key),
+        ("heartbeat", &heartbeat.to_string()),
+        (
+            "confirmation_threshold_bps",
+            &confirmation_threshold_bps.to_string(),
+        ),
+    ]))
+}
+
+/// Sets how the averages of a key are taken, which applies to the rounds stored afterwards
+pub fn set_averaging(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+    max_gap: u64,
+    ema_half_life: u64,
+) -> Result<Response, ContractError> {
+    // check permission
+    assert_feed_admin(deps.as_ref(), &key, &info.sender)?;
+
+    read_feed(deps.storage, &key)?;
+
+    AVERAGINGS.save(
+        deps.storage,
+        &key,
+        &Averaging {
+            max_gap,
+            ema_half_life,
+        },
+    )?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "set_averaging"),
+        ("key", &key),
+        ("max_gap", &max_gap.to_string()),
+        ("ema_half_life", &ema_half_life.to_string()),
+    ]))
+}
+
+/// Defines a key whose price is derived from the prices of other keys, which can't be
+/// derived themselves
+pub fn set_derived_feed(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+    feed: DerivedFeed,
+) -> Result<Response, ContractError> {
+    // check permission
+    assert_feed_admin(deps.as_ref(), &key, &info.sender)?;
+
+    if feed.unit.is_zero() || feed.scale == Some(Uint128::zero()) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Unit and scale can't be zero",
+        )));
+    }
+
+    for underlying in underlying_keys(&feed.derivation) {
+        if underlying == key {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Derived feed can't depend on itself",
+            )));
+        }
+
+        if DERIVED_FEEDS.has(deps.storage, &underlying) {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Derived feeds can't depend on derived feeds",
+            )));
+        }
+    }
+
+    read_feed(deps.storage, &key)?;
+
+    if read_latest_round_id(deps.storage, &key)? != 0 {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Key already has prices",
+        )));
+    }
+
+    // a feed that others are derived from can't become derived itself
+    let is_underlying = DERIVED_FEEDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, derived)| underlying_keys(&derived.derivation)))
+        .collect::<StdResult<Vec<Vec<String>>>>()?
+        .iter()
+        .any(|keys| keys.contains(&key));
+    if is_underlying {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Derived feeds can't depend on derived feeds",
+        )));
+    }
+
+    DERIVED_FEEDS.save(deps.storage, &key, &feed)?;
+
+    Ok(Response::default().add_attributes(vec![("action", "set_derived_feed"), ("key", &key)]))
+}
+
+/// Removes a derived feed, after which prices can be stored for its key
+pub fn remove_derived_feed(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+) -> Result<Response, ContractError> {
+    // check permission
+    assert_feed_admin(deps.as_ref(), &key, &info.sender)?;
+
+    if !DERIVED_FEEDS.has(deps.storage, &key) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Derived feed not found",
+        )));
+    }
+
+    DERIVED_FEEDS.remove(deps.storage, &key);
+
+    Ok(Response::default().add_attributes(vec![("action", "remove_derived_feed"), ("key", &key)]))
+}
+
+/// Registers a contract that can subscribe to price updates, only callable by the owner
+pub fn add_subscriber(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    // validate the address
+    let valid_addr = deps.api.addr_validate(&address)?;
+
+    Ok(SUBSCRIBERS.execute_add_hook(&OWNER, deps, info, valid_addr)?)
+}
+
+/// Removes a subscriber, which is no longer called back for the keys it subscribed to
+pub fn remove_subscriber(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    // validate the address
+    let valid_addr = deps.api.addr_validate(&address)?;
+
+    Ok(SUBSCRIBERS.execute_remove_hook(&OWNER, deps, info, valid_addr)?)
+}
+
+/// Subscribes the sender to the rounds of a key, a non-zero deviation only calls back once
+/// the price moves by more than it from the last price the sender was sent
+pub fn subscribe(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+    deviation_bps: u64,
+) -> Result<Response, ContractError> {
+    if !SUBSCRIBERS.query_hook(deps.as_ref(), info.sender.to_string())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    read_feed(deps.storage, &key)?;
+
+    if DERIVED_FEEDS.has(deps.storage, &key) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Can't subscribe to a derived feed",
+        )));
     }
 
-    Ok(Response::default())
+    // every subscription is called back with each price update, so they are capped per key
+    let config = read_config(deps.storage)?;
+    if !SUBSCRIPTIONS.has(deps.storage, (&key, &info.sender))
+        && SUBSCRIPTIONS
+            .prefix(&key)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count()
+            >= config.max_subscriptions as usize
+    {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Too many subscriptions for key",
+        )));
+    }
+
+    SUBSCRIPTIONS.save(
+        deps.storage,
+        (&key, &info.sender),
+        &Subscription {
+            deviation_bps,
+            last_price: None,
+        },
+    )?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "subscribe"),
+        ("key", &key),
+        ("subscriber", info.sender.as_str()),
+        ("deviation_bps", &deviation_bps.to_string()),
+    ]))
+}
+
+/// Unsubscribes the sender from a key
+pub fn unsubscribe(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+) -> Result<Response, ContractError> {
+    if !SUBSCRIPTIONS.has(deps.storage, (&key, &info.sender)) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Subscription not found",
+        )));
+    }
+
+    SUBSCRIPTIONS.remove(deps.storage, (&key, &info.sender));
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "unsubscribe"),
+        ("key", &key),
+        ("subscriber", info.sender.as_str()),
+    ]))
+}
+
+/// Submits a reporters price to the current round of a key
+pub fn submit_price(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    key: String,
+    price: Uint128,
+    timestamp: u64,
+) -> Result<Response, ContractError> {
+    let (attributes, price_data) = record_submission(
+        deps.storage,
+        &env,
+        key.clone(),
+        info.sender,
+        price,
+        timestamp,
+    )?;
+
+    let messages = match price_data {
+        Some(price_data) => notify_subscribers(deps, &key, &price_data)?,
+        None => vec![],
+    };
+
+    Ok(Response::default()
+        .add_attribute("action", "submit_price")
+        .add_attributes(attributes)
+        .add_submessages(messages))
+}
+
+/// Registers the public key of an off-chain signer whose prices are submitted on behalf
+/// of a reporter
+pub fn add_signer(
+    deps: DepsMut,
+    info: MessageInfo,
+    pubkey: Binary,
+    algorithm: SignatureAlgorithm,
+    reporter: String,
+) -> Result<Response, ContractError> {
+    // check permission
+    OWNER.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let reporter = deps.api.addr_validate(&reporter)?;
+
+    if SIGNERS.has(deps.storage, pubkey.as_slice()) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Signer already exists",
+        )));
+    }
+
+    SIGNERS.save(
+        deps.storage,
+        pubkey.as_slice(),
+        &Signer {
+            algorithm,
+            reporter: reporter.clone(),
+        },
+    )?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "add_signer"),
+        ("pubkey", &pubkey.to_base64()),
+        ("reporter", reporter.as_str()),
+    ]))
+}
+
+pub fn remove_signer(
+    deps: DepsMut,
+    info: MessageInfo,
+    pubkey: Binary,
+) -> Result<Response, ContractError> {
+    // check permission
+    OWNER.assert_admin(deps.as_ref(), &info.sender)?;
+
+    if !SIGNERS.has(deps.storage, pubkey.as_slice()) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Signer not found",
+        )));
+    }
+
+    SIGNERS.remove(deps.storage, pubkey.as_slice());
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "remove_signer"),
+        ("pubkey", &pubkey.to_base64()),
+    ]))
+}
+
+/// Submits prices signed by registered signers, anyone can relay them
+pub fn submit_signed_prices(
+    mut deps: DepsMut,
+    env: Env,
+    payloads: Vec<SignedPrice>,
+) -> Result<Response, ContractError> {
+    if payloads.is_empty() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "No signed prices given",
+        )));
+    }
+
+    let mut response = Response::default().add_attribute("action", "submit_signed_prices");
+
+    for payload in payloads {
+        let signer = SIGNERS
+            .may_load(deps.storage, payload.pubkey.as_slice())?
+            .ok_or_else(|| StdError::generic_err("Signer not found"))?;
+
+        let message_hash =
+            price_payload_hash(&env, &payload.key, payload.price, payload.timestamp)?;
+        let is_valid = match signer.algorithm {
+            SignatureAlgorithm::Secp256k1 => {
+                deps.api
+                    .secp256k1_verify(&message_hash, &payload.signature, &payload.pubkey)
+            }
+            SignatureAlgorithm::Ed25519 => {
+                deps.api
+                    .ed25519_verify(&message_hash, &payload.signature, &payload.pubkey)
+            }
+        }
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+        if !is_valid {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Invalid signature",
+            )));
+        }
+
+        // a signed price can only be used once, as each signer's timestamps must increase
+        let last_timestamp =
+            SIGNER_TIMESTAMPS.may_load(deps.storage, (payload.pubkey.as_slice(), &payload.key))?;
+        if let Some(last_timestamp) = last_timestamp {
+            if payload.timestamp <= last_timestamp {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "Signed price is not newer than the last price of the signer",
+                )));
+            }
+        }
+        SIGNER_TIMESTAMPS.save(
+            deps.storage,
+            (payload.pubkey.as_slice(), &payload.key),
+            &payload.timestamp,
+        )?;
+
+        let (attributes, price_data) = record_submission(
+            deps.storage,
+            &env,
+            payload.key.clone(),
+            signer.reporter,
+            payload.price,
+            payload.timestamp,
+        )?;
+
+        if let Some(price_data) = price_data {
+            let messages = notify_subscribers(deps.branch(), &payload.key, &price_data)?;
+            response = response.add_submessages(messages);
+        }
+
+        response = response.add_event(Event::new("submit_price").add_attributes(attributes));
+    }
+
+    Ok(response)
+}
+
+// adds the price of a reporter to the current round of a key, answering the round
+// once quorum is reached
+fn record_submission(
+    storage: &mut dyn Storage,
+    env: &Env,
+    key: String,
+    reporter: Addr,
+    price: Uint128,
+    timestamp: u64,
+) -> Result<(Vec<Attribute>, Option<PriceData>), ContractError> {
+    let config = REPORTERS
+        .may_load(storage, &key)?
+        .ok_or_else(|| StdError::generic_err("No reporters set for key"))?;
+
+    if !config.reporters.contains(&reporter) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut attributes = vec![attr("key", &key), attr("reporter", reporter.as_str())];
+
+    // a round that has not reached quorum within the max age is discarded and a new round
+    // is started, reporters configured before the max age was introduced keep their rounds
+    let mut round = match PENDING_ROUNDS.may_load(storage, &key)? {
+        Some(round)
+            if config.max_round_age != 0
+                && env.block.time.seconds()
+                    >= round
+                        .started_at
+                        .seconds()
+                        .saturating_add(config.max_round_age) =>
+        {
+            attributes.push(attr("expired", "true"));
+            None
+        }
+        round => round,
+    }
+    .unwrap_or(PendingRound {
+        started_at: env.block.time,
+        submissions: vec![],
+    });
+
+    if round
+        .submissions
+        .iter()
+        .any(|submission| submission.reporter == reporter)
+    {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Reporter has already submitted for this round",
+        )));
+    }
+
+    validate_price(storage, env, &key, price, timestamp)?;
+
+    round.submissions.push(Submission {
+        reporter: reporter.clone(),
+        price,
+        timestamp,
+    });
+
+    let round_id = Uint128::from(read_latest_round_id(storage, &key)? + 1);
+
+    attributes.push(attr("round_id", round_id.to_string()));
+
+    let quorum = config.quorum as usize;
+    if round.submissions.len() < quorum {
+        PENDING_ROUNDS.save(storage, &key, &round)?;
+        return Ok((attributes, None));
+    }
+
+    let (accepted, outliers) = filter_outliers(&round.submissions, config.max_deviation_bps)?;
+
+    if accepted.len() < quorum {
+        // once every reporter has submitted without agreeing the round can never be answered
+        if round.submissions.len() == config.reporters.len() {
+            PENDING_ROUNDS.remove(storage, &key);
+            attributes.push(attr("discarded", "true"));
+            return Ok((attributes, None));
+        }
+
+        PENDING_ROUNDS.save(storage, &key, &round)?;
+        return Ok((attributes, None));
+    }
+
+    let price = calc_median(accepted.iter().map(|s| s.price).collect())?;
+    let timestamp = accepted.iter().map(|s| s.timestamp).max().unwrap();
+
+    let price_data = store_round(
+        storage,
+        env,
+        &key,
+        price,
+        timestamp,
+        RoundMetadata {
+            reporters: accepted.into_iter().map(|s| s.reporter).collect(),
+            started_at: round.started_at,
+            updated_at: env.block.time,
+        },
+    )?;
+    PENDING_ROUNDS.remove(storage, &key);
+
+    let outliers: Vec<String> = outliers.into_iter().map(|s| s.reporter.into()).collect();
+
+    attributes.push(attr("price", price.to_string()));
+    attributes.push(attr("outliers", outliers.join(",")));
+
+    if price_data.is_none() {
+        attributes.push(attr("requires_confirmation", "true"));
+    }
+
+    Ok((attributes, price_data))
+}
+
+// calls back the subscribers of a key with a new round, a failing subscriber is caught in
+// the reply so that it can't block price updates
+fn notify_subscribers(
+    deps: DepsMut,
+    key: &str,
+    price_data: &PriceData,
+) -> Result<Vec<SubMsg>, ContractError> {
+    let config = read_config(deps.storage)?;
+    let subscribers = SUBSCRIBERS.query_hooks(deps.as_ref())?.hooks;
+
+    let subscriptions = SUBSCRIPTIONS
+        .prefix(key)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(Addr, Subscription)>>>()?;
+
+    let mut messages: Vec<SubMsg> = vec![];
+    for (subscriber, mut subscription) in subscriptions {
+        if !subscribers.contains(&subscriber.to_string()) {
+            continue;
+        }
+
+        let is_crossed = match subscription.last_price {
+            Some(last_price) if subscription.deviation_bps != 0 => {
+                is_over_threshold(last_price, price_data.price, subscription.deviation_bps)
+            }
+            _ => true,
+        };
+        if !is_crossed {
+            continue;
+        }
+
+        subscription.last_price = Some(price_data.price);
+        SUBSCRIPTIONS.save(deps.storage, (key, &subscriber), &subscription)?;
+
+        let msg = WasmMsg::Execute {
+            contract_addr: subscriber.to_string(),
+            msg: to_binary(&PriceHookMsg::PriceUpdated {
+                key: key.to_string(),
+                round_id: price_data.round_id,
+                price: price_data.price,
+                timestamp: price_data.timestamp,
+            })?,
+            funds: vec![],
+        };
+        messages.push(
+            SubMsg::reply_on_error(msg, PRICE_UPDATED_REPLY_ID)
+                .with_gas_limit(config.subscriber_gas_limit),
+        );
+    }
+
+    Ok(messages)
+}
+
+// the admin of a registered feed manages it alongside the owner
+fn assert_feed_admin(deps: Deps, key: &str, sender: &Addr) -> Result<(), ContractError> {
+    if let Some(feed) = FEEDS.may_load(deps.storage, key)? {
+        if feed.admin == *sender {
+            return Ok(());
+        }
+    }
+
+    Ok(OWNER.assert_admin(deps, sender)?)
+}
+
+fn validate_reporters(deps: Deps, reporters: Vec<String>) -> Result<Vec<Addr>, ContractError> {
+    let mut valid_reporters: Vec<Addr> = vec![];
+    for reporter in reporters {
+        let reporter = deps.api.addr_validate(&reporter)?;
+
+        if valid_reporters.contains(&reporter) {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Duplicate reporter",
+            )));
+        }
+
+        valid_reporters.push(reporter);
+    }
+
+    Ok(valid_reporters)
+}
+
+// stores a price pushed by the owner as a round it reported alone
+fn store_owner_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    key: String,
+    price: Uint128,
+    timestamp: u64,
+) -> Result<Option<PriceData>, ContractError> {
+    validate_price(storage, env, &key, price, timestamp)?;
+
+    store_round(
+        storage,
+        env,
+        &key,
+        price,
+        timestamp,
+        RoundMetadata {
+            reporters: vec![owner.clone()],
+            started_at: env.block.time,
+            updated_at: env.block.time,
+        },
+    )
+}
+
+// stores the answer of a round, unless it moves the price by more than the confirmation
+// threshold in which case it is held until a second price confirms the move
+fn store_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    key: &str,
+    price: Uint128,
+    timestamp: u64,
+    metadata: RoundMetadata,
+) -> Result<Option<PriceData>, ContractError> {
+    let validation = read_validation(storage, key)?;
+
+    if validation.confirmation_threshold_bps != 0 {
+        let latest_round_id = read_latest_round_id(storage, key)?;
+
+        if latest_round_id != 0 {
+            let latest_price = read_price_data(storage, key, latest_round_id)?.price;
+            let threshold = validation.confirmation_threshold_bps;
+
+            // a held price is confirmed by a second price close to it
+            let is_confirmed = match UNCONFIRMED_PRICES.may_load(storage, key)? {
+                Some(unconfirmed) => !is_over_threshold(unconfirmed.price, price, threshold),
+                None => false,
+            };
+
+            if is_over_threshold(latest_price, price, threshold) && !is_confirmed {
+                UNCONFIRMED_PRICES.save(
+                    storage,
+                    key,
+                    &UnconfirmedPrice {
+                        price,
+                        timestamp,
+                        flagged_at: env.block.time,
+                    },
+                )?;
+
+                return Ok(None);
+            }
+        }
+    }
+
+    UNCONFIRMED_PRICES.remove(storage, key);
+
+    let price_data = store_price_data(storage, key.to_string(), price, timestamp)?;
+    ROUNDS.save(storage, (key, price_data.round_id.u128()), &metadata)?;
+
+    Ok(Some(price_data))
+}
+
+// prices must be positive and timestamped after the latest round without being in the future
+fn validate_price(
+    storage: &dyn Storage,
+    env: &Env,
+    key: &str,
+    price: Uint128,
+    timestamp: u64,
+) -> Result<(), ContractError> {
+    if DERIVED_FEEDS.has(storage, key) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Prices can't be stored for a derived feed",
+        )));
+    }
+
+    read_feed(storage, key)?;
+
+    if price.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Price can't be zero",
+        )));
+    }
+
+    if timestamp > env.block.time.seconds() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Timestamp is in the future",
+        )));
+    }
+
+    let latest_round_id = read_latest_round_id(storage, key)?;
+    if latest_round_id != 0 {
+        let latest_price = read_price_data(storage, key, latest_round_id)?;
+
+        if timestamp <= latest_price.timestamp.seconds() {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Timestamp must be after the latest round",
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves the next batch of a legacy price history into rounds, anyone can call it until
+/// nothing is left
+pub fn migrate_price_history_batch(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit
+        .unwrap_or(DEFAULT_MIGRATION_LIMIT)
+        .min(MAX_MIGRATION_LIMIT) as u64;
+
+    let (migrated, has_remaining) = migrate_price_history(deps.storage, limit)?;
+
+    Ok(Response::default().add_attributes(vec![
+        ("action", "migrate_price_history"),
+        ("migrated_rounds", &migrated.to_string()),
+        ("has_remaining", &has_remaining.to_string()),
+    ]))
 }
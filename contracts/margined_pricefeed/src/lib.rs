@@ -1,8 +1,10 @@
 pub mod contract;
+mod derived;
 mod error;
 mod handle;
 mod query;
 mod state;
+mod utils;
 
 #[cfg(test)]
 mod testing;
@@ -1,14 +1,35 @@
-use cosmwasm_std::{Deps, Env, StdError, StdResult, Uint128};
-use margined_perp::margined_pricefeed::{ConfigResponse, OwnerResponse};
+use cosmwasm_std::{Binary, Deps, Env, Order, StdError, StdResult, Uint128};
+use cw_storage_plus::Bound;
+use margined_perp::margined_pricefeed::{
+    AveragingResponse, ConfigResponse, DerivedFeed, FeedInfoResponse, FeedsResponse, OwnerResponse,
+    PriceDataResponse, PriceHistoryResponse, PriceResponse, ReportersResponse, RoundDataResponse,
+    SignerResponse, SubscriptionResponse, SubscriptionsResponse, ValidationResponse,
+};
 
 use crate::{
     contract::OWNER,
-    state::{read_price_data, PriceData},
+    derived::{derived_previous_price, derived_twap_price, DerivedCursor},
+    state::{
+        find_round_at, read_averaging, read_config, read_cumulative_at, read_feed,
+        read_latest_price_data, read_latest_round_id, read_price_data, read_validation, Feed,
+        PriceData, DERIVED_FEEDS, FEEDS, PENDING_ROUNDS, PRICES, REPORTERS, ROUNDS, SIGNERS,
+        SUBSCRIPTIONS, UNCONFIRMED_PRICES,
+    },
+    utils::calc_median,
 };
 
+const DEFAULT_PAGINATION_LIMIT: u32 = 10u32;
+const MAX_PAGINATION_LIMIT: u32 = 30u32;
+const MAX_MEDIAN_ROUNDS: u128 = 100u128;
+
 /// Queries contract Config
-pub fn query_config(_deps: Deps) -> StdResult<ConfigResponse> {
-    Ok(ConfigResponse {})
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = read_config(deps.storage)?;
+
+    Ok(ConfigResponse {
+        subscriber_gas_limit: config.subscriber_gas_limit,
+        max_subscriptions: config.max_subscriptions,
+    })
 }
 
 /// Queries contract owner from the admin
@@ -21,13 +42,45 @@ pub fn query_owner(deps: Deps) -> StdResult<OwnerResponse> {
 }
 
 /// Queries latest price for pair stored with key
-pub fn query_get_price(deps: Deps, key: String) -> StdResult<PriceData> {
-    let prices_response = read_price_data(deps.storage, key);
+pub fn query_get_price(deps: Deps, env: Env, key: String) -> StdResult<PriceResponse> {
+    if let Some(feed) = DERIVED_FEEDS.may_load(deps.storage, &key)? {
+        let cursor = DerivedCursor::latest(deps.storage, &feed)?;
+
+        return Ok(PriceResponse {
+            round_id: cursor.round_id(),
+            price: cursor.price(&feed)?,
+            timestamp: cursor.timestamp(),
+            is_stale: cursor.is_stale(deps.storage, &env, &key)?,
+        });
+    }
+
+    let price_data = read_latest_price_data(deps.storage, &key)?;
+    let validation = read_validation(deps.storage, &key)?;
 
-    let prices = prices_response.unwrap();
-    let price = prices.last().unwrap();
+    let age = env
+        .block
+        .time
+        .seconds()
+        .saturating_sub(price_data.timestamp.seconds());
 
-    Ok(price.clone())
+    Ok(PriceResponse {
+        round_id: price_data.round_id,
+        price: price_data.price,
+        timestamp: price_data.timestamp,
+        is_stale: validation.heartbeat != 0 && age > validation.heartbeat,
+    })
+}
+
+/// Queries how the prices of a key are validated
+pub fn query_validation(deps: Deps, key: String) -> StdResult<ValidationResponse> {
+    let validation = read_validation(deps.storage, &key)?;
+    let unconfirmed_price = UNCONFIRMED_PRICES.may_load(deps.storage, &key)?;
+
+    Ok(ValidationResponse {
+        heartbeat: validation.heartbeat,
+        confirmation_threshold_bps: validation.confirmation_threshold_bps,
+        unconfirmed_price: unconfirmed_price.map(|unconfirmed| unconfirmed.price),
+    })
 }
 
 /// Queries previous price for pair stored with key
@@ -36,99 +89,340 @@ pub fn query_get_previous_price(
     key: String,
     num_round_back: Uint128,
 ) -> StdResult<PriceData> {
-    let prices_response = read_price_data(deps.storage, key);
+    if let Some(feed) = DERIVED_FEEDS.may_load(deps.storage, &key)? {
+        return derived_previous_price(deps.storage, &feed, num_round_back);
+    }
 
-    let prices = prices_response.unwrap();
-    let latest_price = prices.last().unwrap();
+    let latest_round_id = read_latest_round_id(deps.storage, &key)?;
 
-    if num_round_back > latest_price.round_id {
+    if num_round_back.u128() >= latest_round_id {
         return Err(StdError::generic_err("Not enough history"));
     }
 
-    let mut previous_prices = prices.clone();
+    read_price_data(deps.storage, &key, latest_round_id - num_round_back.u128())
+}
 
-    // obvs not the most efficient way to do this but this is
-    // just a placeholder while we build the twap logic and
-    // do the integration with the rest of the project.
-    let mut i = 0;
-    while i < num_round_back.u128() {
-        previous_prices.pop();
-        i += 1;
-    }
+/// Queries the prices of a key in order of their rounds
+pub fn query_price_history(
+    deps: Deps,
+    key: String,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+) -> StdResult<PriceHistoryResponse> {
+    // set the limit for pagination
+    let limit = limit
+        .unwrap_or(DEFAULT_PAGINATION_LIMIT)
+        .min(MAX_PAGINATION_LIMIT) as usize;
+    let start = start_after.map(|round_id| Bound::exclusive(round_id.u128()));
 
-    let previous_price = previous_prices.last().unwrap();
+    let prices = PRICES
+        .prefix(&key)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(_, price_data)| PriceDataResponse {
+                round_id: price_data.round_id,
+                price: price_data.price,
+                timestamp: price_data.timestamp,
+            })
+        })
+        .collect::<StdResult<Vec<PriceDataResponse>>>()?;
 
-    Ok(previous_price.clone())
+    Ok(PriceHistoryResponse { prices })
 }
 
-/// Queries contract Config
+/// Queries the time weighted average price over the interval, a price only counts for the
+/// max gap after it is published and the average is over the history if that is shorter
 pub fn query_get_twap_price(
     deps: Deps,
     env: Env,
     key: String,
     interval: u64,
+) -> StdResult<Uint128> {
+    let now = env.block.time.seconds();
+
+    query_get_twap_price_at(deps, env, key, interval, now)
+}
+
+/// Queries the time weighted average price over the interval ending at the timestamp, so
+/// that the average does not depend on when it is queried
+pub fn query_get_twap_price_at(
+    deps: Deps,
+    env: Env,
+    key: String,
+    interval: u64,
+    timestamp: u64,
 ) -> StdResult<Uint128> {
     if interval == 0 {
         return Err(StdError::generic_err("Interval can't be zero"));
     }
 
-    let base_timestamp = env.block.time.seconds().checked_sub(interval).unwrap();
-    let prices_response = read_price_data(deps.storage, key);
+    if timestamp > env.block.time.seconds() {
+        return Err(StdError::generic_err("Timestamp is in the future"));
+    }
+
+    if let Some(feed) = DERIVED_FEEDS.may_load(deps.storage, &key)? {
+        return derived_twap_price(deps.storage, &key, timestamp, &feed, interval);
+    }
+
+    // the window ends at the timestamp so only the rounds published by then count
+    let latest_round_id = read_latest_round_id(deps.storage, &key)?;
+    let latest_round_id = match find_round_at(deps.storage, &key, timestamp, latest_round_id)? {
+        Some(round_id) => round_id,
+        None => return Err(StdError::generic_err("Insufficient history")),
+    };
+
+    let now = timestamp;
+    let (start_round_id, start) = read_window_start(deps, &key, now, interval, latest_round_id)?;
+
+    let start_cumulative = read_cumulative_at(deps.storage, &key, start_round_id, start)?;
+    let end_cumulative = read_cumulative_at(deps.storage, &key, latest_round_id, now)?;
+
+    let cumulative_time =
+        Uint128::from(end_cumulative.time).checked_sub(Uint128::from(start_cumulative.time))?;
+    if cumulative_time.is_zero() {
+        // the only price was published in this block
+        if start == now {
+            return Ok(read_price_data(deps.storage, &key, latest_round_id)?.price);
+        }
+
+        return Err(StdError::generic_err(
+            "No prices within the max gap during the interval",
+        ));
+    }
+
+    let cumulative_price = end_cumulative.price.checked_sub(start_cumulative.price)?;
 
-    // get the current data
-    let mut prices = prices_response.unwrap();
-    let mut latest_round = prices.last().unwrap();
-    let mut timestamp = latest_round.timestamp.seconds();
+    Ok(cumulative_price.checked_div(cumulative_time)?)
+}
 
-    if latest_round.round_id == Uint128::zero() {
+/// Queries the exponential moving average price with the half-life of the key
+pub fn query_get_ema_price(deps: Deps, env: Env, key: String) -> StdResult<Uint128> {
+    let latest_round_id = read_latest_round_id(deps.storage, &key)?;
+    if latest_round_id == 0 {
         return Err(StdError::generic_err("Insufficient history"));
     }
 
-    // if latest updated timestamp is earlier than target timestamp, return the latest price.
-    if latest_round.timestamp.seconds() < base_timestamp
-        || latest_round.round_id == Uint128::from(1u128)
-    {
-        return Ok(latest_round.price);
+    let cumulative = read_cumulative_at(
+        deps.storage,
+        &key,
+        latest_round_id,
+        env.block.time.seconds(),
+    )?;
+
+    Ok(cumulative.ema)
+}
+
+/// Queries the median of the prices during the interval, taken over at most the latest
+/// MAX_MEDIAN_ROUNDS rounds
+pub fn query_get_median_price(
+    deps: Deps,
+    env: Env,
+    key: String,
+    interval: u64,
+) -> StdResult<Uint128> {
+    if interval == 0 {
+        return Err(StdError::generic_err("Interval can't be zero"));
     }
 
-    let mut cumulative_time =
-        Uint128::from(env.block.time.seconds().checked_sub(timestamp).unwrap());
+    let latest_round_id = read_latest_round_id(deps.storage, &key)?;
+    if latest_round_id == 0 {
+        return Err(StdError::generic_err("Insufficient history"));
+    }
+
+    let now = env.block.time.seconds();
+    let (start_round_id, _) = read_window_start(deps, &key, now, interval, latest_round_id)?;
+    let start_round_id = start_round_id.max(latest_round_id.saturating_sub(MAX_MEDIAN_ROUNDS) + 1);
 
-    let mut weighted_price = latest_round.price.checked_mul(cumulative_time)?;
+    let prices = (start_round_id..=latest_round_id)
+        .map(|round_id| read_price_data(deps.storage, &key, round_id).map(|data| data.price))
+        .collect::<StdResult<Vec<Uint128>>>()?;
 
-    loop {
-        if latest_round.round_id == Uint128::from(1u128) {
-            let twap = weighted_price.checked_div(cumulative_time).unwrap();
-            return Ok(twap);
+    calc_median(prices)
+}
+
+// returns the round in effect at the start of the interval and when the interval starts,
+// which is no earlier than the first round
+fn read_window_start(
+    deps: Deps,
+    key: &str,
+    now: u64,
+    interval: u64,
+    latest_round_id: u128,
+) -> StdResult<(u128, u64)> {
+    let start = now.saturating_sub(interval);
+
+    match find_round_at(deps.storage, key, start, latest_round_id)? {
+        Some(round_id) => Ok((round_id, start)),
+        None => {
+            let first_round = read_price_data(deps.storage, key, 1)?;
+            Ok((1, first_round.timestamp.seconds().min(now)))
         }
+    }
+}
+
+/// Queries how the averages of a key are taken
+pub fn query_averaging(deps: Deps, key: String) -> StdResult<AveragingResponse> {
+    let averaging = read_averaging(deps.storage, &key)?;
 
-        prices.pop();
-        latest_round = prices.last().unwrap();
+    Ok(AveragingResponse {
+        max_gap: averaging.max_gap,
+        ema_half_life: averaging.ema_half_life,
+    })
+}
 
-        if latest_round.timestamp.seconds() <= base_timestamp {
-            let delta_timestamp = Uint128::from(timestamp.checked_sub(base_timestamp).unwrap());
+/// Queries the metadata of a registered feed
+pub fn query_feed_info(deps: Deps, key: String) -> StdResult<FeedInfoResponse> {
+    let feed = read_feed(deps.storage, &key)?;
 
-            weighted_price = weighted_price
-                .checked_add(latest_round.price.checked_mul(delta_timestamp).unwrap())
-                .unwrap();
+    read_feed_info(deps, key, feed)
+}
 
-            break;
-        }
+/// Queries the registered feeds in order of their keys
+pub fn query_list_feeds(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FeedsResponse> {
+    // set the limit for pagination
+    let limit = limit
+        .unwrap_or(DEFAULT_PAGINATION_LIMIT)
+        .min(MAX_PAGINATION_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let feeds = FEEDS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.and_then(|(key, feed)| read_feed_info(deps, key, feed)))
+        .collect::<StdResult<Vec<FeedInfoResponse>>>()?;
+
+    Ok(FeedsResponse { feeds })
+}
+
+fn read_feed_info(deps: Deps, key: String, feed: Feed) -> StdResult<FeedInfoResponse> {
+    let validation = read_validation(deps.storage, &key)?;
+
+    Ok(FeedInfoResponse {
+        key,
+        decimals: feed.decimals,
+        description: feed.description,
+        heartbeat: validation.heartbeat,
+        reporters: feed.reporters,
+        admin: feed.admin,
+    })
+}
+
+/// Queries the definition of a derived feed
+pub fn query_derived_feed(deps: Deps, key: String) -> StdResult<DerivedFeed> {
+    DERIVED_FEEDS
+        .may_load(deps.storage, &key)?
+        .ok_or_else(|| StdError::generic_err("Derived feed not found"))
+}
+
+/// Queries the subscriptions to a key in order of the subscribers
+pub fn query_subscriptions(
+    deps: Deps,
+    key: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SubscriptionsResponse> {
+    // set the limit for pagination
+    let limit = limit
+        .unwrap_or(DEFAULT_PAGINATION_LIMIT)
+        .min(MAX_PAGINATION_LIMIT) as usize;
+    let start_after = start_after
+        .map(|subscriber| deps.api.addr_validate(&subscriber))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let subscriptions = SUBSCRIPTIONS
+        .prefix(&key)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(subscriber, subscription)| SubscriptionResponse {
+                subscriber,
+                deviation_bps: subscription.deviation_bps,
+                last_price: subscription.last_price,
+            })
+        })
+        .collect::<StdResult<Vec<SubscriptionResponse>>>()?;
+
+    Ok(SubscriptionsResponse { subscriptions })
+}
+
+/// Queries the reporters of a key
+pub fn query_reporters(deps: Deps, key: String) -> StdResult<ReportersResponse> {
+    let config = REPORTERS
+        .may_load(deps.storage, &key)?
+        .ok_or_else(|| StdError::generic_err("No reporters set for key"))?;
+
+    Ok(ReportersResponse {
+        reporters: config.reporters,
+        quorum: config.quorum,
+        max_deviation_bps: config.max_deviation_bps,
+        max_round_age: config.max_round_age,
+    })
+}
+
+/// Queries the answer of a round and how it was reached
+pub fn query_round_data(
+    deps: Deps,
+    key: String,
+    round_id: Uint128,
+) -> StdResult<RoundDataResponse> {
+    let price_data = read_price_data(deps.storage, &key, round_id.u128())?;
+
+    read_round_data(deps, &key, &price_data)
+}
+
+/// Queries the latest round of a key, which is answered by the previous round while it is
+/// still collecting submissions
+pub fn query_latest_round_data(deps: Deps, key: String) -> StdResult<RoundDataResponse> {
+    let latest_round_id = read_latest_round_id(deps.storage, &key)?;
+    let latest_price = read_price_data(deps.storage, &key, latest_round_id)?;
+
+    let round_data = read_round_data(deps, &key, &latest_price)?;
 
-        let delta_timestamp = Uint128::from(
-            timestamp
-                .checked_sub(latest_round.timestamp.seconds())
-                .unwrap(),
-        );
-        weighted_price = weighted_price
-            .checked_add(latest_round.price.checked_mul(delta_timestamp).unwrap())
-            .unwrap();
-
-        cumulative_time = cumulative_time.checked_add(delta_timestamp).unwrap();
-        timestamp = latest_round.timestamp.seconds();
+    match PENDING_ROUNDS.may_load(deps.storage, &key)? {
+        Some(pending) => Ok(RoundDataResponse {
+            round_id: latest_price.round_id + Uint128::from(1u128),
+            started_at: pending.started_at,
+            ..round_data
+        }),
+        None => Ok(round_data),
     }
+}
+
+fn read_round_data(deps: Deps, key: &str, price_data: &PriceData) -> StdResult<RoundDataResponse> {
+    let metadata = ROUNDS.may_load(deps.storage, (key, price_data.round_id.u128()))?;
+
+    // rounds stored before reporters were introduced have no metadata
+    let (reporters, started_at, updated_at) = match metadata {
+        Some(metadata) => (metadata.reporters, metadata.started_at, metadata.updated_at),
+        None => (vec![], price_data.timestamp, price_data.timestamp),
+    };
+
+    Ok(RoundDataResponse {
+        round_id: price_data.round_id,
+        price: price_data.price,
+        timestamp: price_data.timestamp,
+        answered_in_round: price_data.round_id,
+        reporters,
+        started_at,
+        updated_at,
+    })
+}
 
-    let twap = weighted_price.checked_div(Uint128::from(interval)).unwrap();
+/// Queries a signer by its public key
+pub fn query_signer(deps: Deps, pubkey: Binary) -> StdResult<SignerResponse> {
+    let signer = SIGNERS
+        .may_load(deps.storage, pubkey.as_slice())?
+        .ok_or_else(|| StdError::generic_err("Signer not found"))?;
 
-    Ok(twap)
+    Ok(SignerResponse {
+        pubkey,
+        algorithm: signer.algorithm,
+        reporter: signer.reporter,
+    })
 }
@@ -1,21 +1,50 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{StdResult, Storage, Timestamp, Uint128};
-use cosmwasm_storage::singleton;
+use cosmwasm_std::{Addr, Order, StdError, StdResult, Storage, Timestamp, Uint128};
+use cosmwasm_storage::{singleton, singleton_read};
 use cw_storage_plus::Map;
+use margined_perp::margined_pricefeed::{DerivedFeed, SignatureAlgorithm};
+
+use crate::utils::calc_ema;
 
 pub static KEY_CONFIG: &[u8] = b"config";
 
-pub const PRICES: Map<String, Vec<PriceData>> = Map::new("prices");
+/// The prices stored as a single history per key, only read when migrating
+pub const LEGACY_PRICES: Map<String, Vec<PriceData>> = Map::new("prices");
+pub const PRICES: Map<(&str, u128), PriceData> = Map::new("price_rounds");
+pub const LATEST_ROUNDS: Map<&str, u128> = Map::new("latest_rounds");
+pub const REPORTERS: Map<&str, ReporterConfig> = Map::new("reporters");
+pub const PENDING_ROUNDS: Map<&str, PendingRound> = Map::new("pending_rounds");
+pub const ROUNDS: Map<(&str, u128), RoundMetadata> = Map::new("rounds");
+pub const SIGNERS: Map<&[u8], Signer> = Map::new("signers");
+pub const SIGNER_TIMESTAMPS: Map<(&[u8], &str), u64> = Map::new("signer_timestamps");
+pub const VALIDATIONS: Map<&str, Validation> = Map::new("validations");
+pub const UNCONFIRMED_PRICES: Map<&str, UnconfirmedPrice> = Map::new("unconfirmed_prices");
+pub const DERIVED_FEEDS: Map<&str, DerivedFeed> = Map::new("derived_feeds");
+pub const FEEDS: Map<&str, Feed> = Map::new("feeds");
+pub const AVERAGINGS: Map<&str, Averaging> = Map::new("averagings");
+pub const CUMULATIVES: Map<(&str, u128), Cumulative> = Map::new("cumulatives");
+pub const SUBSCRIPTIONS: Map<(&str, &Addr), Subscription> = Map::new("subscriptions");
 
+/// Subscribers are called back with at most `subscriber_gas_limit` gas each and a key can
+/// have at most `max_subscriptions` subscriptions, so price updates are bounded
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct Config {}
+pub struct Config {
+    #[serde(default)]
+    pub subscriber_gas_limit: u64,
+    #[serde(default)]
+    pub max_subscriptions: u32,
+}
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
     singleton(storage, KEY_CONFIG).save(config)
 }
 
+pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
 #[derive(Serialize, Default, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct PriceData {
     pub round_id: Uint128,
@@ -23,35 +52,336 @@ pub struct PriceData {
     pub timestamp: Timestamp,
 }
 
+/// A registered key, its admin manages the prices and reporters of the key alongside the owner
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Feed {
+    pub decimals: u8,
+    pub description: String,
+    pub reporters: Vec<Addr>,
+    pub admin: Addr,
+}
+
+pub fn read_feed(storage: &dyn Storage, key: &str) -> StdResult<Feed> {
+    FEEDS
+        .may_load(storage, key)?
+        .ok_or_else(|| StdError::generic_err("Feed not registered"))
+}
+
+/// The reporters that can submit prices for a key and how many of them must agree
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReporterConfig {
+    pub reporters: Vec<Addr>,
+    pub quorum: u32,
+    pub max_deviation_bps: u64,
+    #[serde(default)]
+    pub max_round_age: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Submission {
+    pub reporter: Addr,
+    pub price: Uint128,
+    pub timestamp: u64,
+}
+
+/// The submissions of a round that has not reached quorum yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingRound {
+    pub started_at: Timestamp,
+    pub submissions: Vec<Submission>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RoundMetadata {
+    pub reporters: Vec<Addr>,
+    pub started_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/// An off-chain signer, keyed by its public key, that submits prices for a reporter
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Signer {
+    pub algorithm: SignatureAlgorithm,
+    pub reporter: Addr,
+}
+
+/// How the prices of a key are validated, zero disables either check
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct Validation {
+    pub heartbeat: u64,
+    pub confirmation_threshold_bps: u64,
+}
+
+/// A price that moved by more than the confirmation threshold and is held until confirmed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct UnconfirmedPrice {
+    pub price: Uint128,
+    pub timestamp: u64,
+    pub flagged_at: Timestamp,
+}
+
+/// How the averages of a key are taken, a price only counts towards the twap for `max_gap`
+/// seconds after it is published, zero disables the max gap and a zero half-life makes the EMA
+/// track the price. Each round keeps the averaging it was stored with, so changes apply to the
+/// rounds stored afterwards
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct Averaging {
+    pub max_gap: u64,
+    pub ema_half_life: u64,
+}
+
+/// The running values of a key at the start of a round along with the averaging the round
+/// is taken with, so that averages over any window take a bounded number of reads
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct Cumulative {
+    pub price: Uint128,
+    pub time: u64,
+    pub ema: Uint128,
+    #[serde(default)]
+    pub averaging: Averaging,
+}
+
+impl Cumulative {
+    /// Returns the running values once the price of the round has held until the timestamp
+    pub fn advance(&self, round: &PriceData, timestamp: u64) -> StdResult<Cumulative> {
+        let averaging = &self.averaging;
+        let elapsed = timestamp.saturating_sub(round.timestamp.seconds());
+        let weight = match averaging.max_gap {
+            0 => elapsed,
+            max_gap => elapsed.min(max_gap),
+        };
+
+        Ok(Cumulative {
+            price: self
+                .price
+                .checked_add(round.price.checked_mul(Uint128::from(weight))?)?,
+            time: self.time + weight,
+            ema: calc_ema(self.ema, round.price, elapsed, averaging.ema_half_life)?,
+            averaging: averaging.clone(),
+        })
+    }
+}
+
+/// A subscriber is called back with every round of the key, or only once the price moves
+/// by more than the deviation from the last price it was sent
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Subscription {
+    pub deviation_bps: u64,
+    pub last_price: Option<Uint128>,
+}
+
+pub fn read_averaging(storage: &dyn Storage, key: &str) -> StdResult<Averaging> {
+    Ok(AVERAGINGS.may_load(storage, key)?.unwrap_or_default())
+}
+
+/// Returns the running values of a key at a timestamp during a round
+pub fn read_cumulative_at(
+    storage: &dyn Storage,
+    key: &str,
+    round_id: u128,
+    timestamp: u64,
+) -> StdResult<Cumulative> {
+    let round = read_price_data(storage, key, round_id)?;
+    let cumulative = CUMULATIVES
+        .may_load(storage, (key, round_id))?
+        .ok_or_else(|| StdError::generic_err("No cumulative values for round"))?;
+
+    cumulative.advance(&round, timestamp)
+}
+
+/// Returns the latest round of a key published at or before the timestamp
+pub fn find_round_at(
+    storage: &dyn Storage,
+    key: &str,
+    timestamp: u64,
+    latest_round_id: u128,
+) -> StdResult<Option<u128>> {
+    let (mut low, mut high) = (1u128, latest_round_id);
+    let mut found = None;
+
+    while low <= high {
+        let middle = low + (high - low) / 2;
+
+        if read_price_data(storage, key, middle)?.timestamp.seconds() <= timestamp {
+            found = Some(middle);
+            low = middle + 1;
+        } else {
+            high = middle - 1;
+        }
+    }
+
+    Ok(found)
+}
+
+pub fn read_validation(storage: &dyn Storage, key: &str) -> StdResult<Validation> {
+    Ok(VALIDATIONS.may_load(storage, key)?.unwrap_or_default())
+}
+
 pub fn store_price_data(
     storage: &mut dyn Storage,
     key: String,
     price: Uint128,
     timestamp: u64,
-) -> StdResult<()> {
-    // load the existing data
-    let mut prices = read_price_data(storage, key.clone()).unwrap();
+) -> StdResult<PriceData> {
+    let round_id = read_latest_round_id(storage, &key)? + 1;
 
     let price_data: PriceData = PriceData {
-        round_id: Uint128::from(prices.len() as u64),
+        round_id: Uint128::from(round_id),
         price,
         timestamp: Timestamp::from_seconds(timestamp),
     };
 
-    prices.push(price_data);
+    let cumulative = next_cumulative(storage, &key, round_id, &price_data)?;
 
-    PRICES.save(storage, key, &prices)
+    PRICES.save(storage, (&key, round_id), &price_data)?;
+    CUMULATIVES.save(storage, (&key, round_id), &cumulative)?;
+    LATEST_ROUNDS.save(storage, &key, &round_id)?;
+
+    Ok(price_data)
 }
 
-pub fn read_price_data(storage: &dyn Storage, key: String) -> StdResult<Vec<PriceData>> {
-    let prices = PRICES.may_load(storage, key)?;
-    let mut result = Vec::new();
+// the running values at the start of a round carry on from the previous round, which is
+// advanced with its own averaging, and the round is taken with the current averaging
+fn next_cumulative(
+    storage: &dyn Storage,
+    key: &str,
+    round_id: u128,
+    price_data: &PriceData,
+) -> StdResult<Cumulative> {
+    let averaging = read_averaging(storage, key)?;
 
-    if let Some(prices) = prices {
-        result = prices;
-    } else {
-        result.push(PriceData::default());
+    if round_id == 1 {
+        return Ok(Cumulative {
+            ema: price_data.price,
+            averaging,
+            ..Cumulative::default()
+        });
     }
 
-    Ok(result)
+    Ok(Cumulative {
+        averaging,
+        ..read_cumulative_at(storage, key, round_id - 1, price_data.timestamp.seconds())?
+    })
+}
+
+/// Returns the latest round of a key, which is zero if it has no prices. A key whose history
+/// is still being migrated can't be read or written, as its latest round is the cursor of the
+/// migration rather than the latest price
+pub fn read_latest_round_id(storage: &dyn Storage, key: &str) -> StdResult<u128> {
+    if LEGACY_PRICES.has(storage, key.to_string()) {
+        return Err(StdError::generic_err(format!(
+            "Price history of key is still being migrated: {}",
+            key
+        )));
+    }
+
+    Ok(LATEST_ROUNDS.may_load(storage, key)?.unwrap_or_default())
+}
+
+pub fn read_price_data(storage: &dyn Storage, key: &str, round_id: u128) -> StdResult<PriceData> {
+    PRICES
+        .may_load(storage, (key, round_id))?
+        .ok_or_else(|| StdError::generic_err("No data for round"))
+}
+
+pub fn read_latest_price_data(storage: &dyn Storage, key: &str) -> StdResult<PriceData> {
+    let round_id = read_latest_round_id(storage, key)?;
+
+    if round_id == 0 {
+        return Err(StdError::generic_err(format!(
+            "Unable to retrieve price data for key: {}",
+            key
+        )));
+    }
+
+    read_price_data(storage, key, round_id)
+}
+
+/// Registers every key that has prices but no feed, which were stored before feeds were
+/// introduced, so that they keep accepting prices
+pub fn register_legacy_feeds(
+    storage: &mut dyn Storage,
+    admin: &Addr,
+    decimals: u8,
+) -> StdResult<u64> {
+    let mut keys = LEGACY_PRICES
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<String>>>()?;
+    keys.extend(
+        LATEST_ROUNDS
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<String>>>()?,
+    );
+    keys.sort();
+    keys.dedup();
+
+    let mut registered = 0u64;
+    for key in keys {
+        if FEEDS.has(storage, &key) {
+            continue;
+        }
+
+        FEEDS.save(
+            storage,
+            &key,
+            &Feed {
+                decimals,
+                description: key.clone(),
+                reporters: vec![],
+                admin: admin.clone(),
+            },
+        )?;
+        registered += 1;
+    }
+
+    Ok(registered)
+}
+
+/// Moves up to `limit` of the prices stored as a single history per key into their rounds,
+/// dropping the empty round zero that was stored with every history. The latest round of a
+/// key is the cursor that the next batch carries on from and a history is removed once all
+/// of it has been moved, returns the number of rounds moved and whether any are left
+pub fn migrate_price_history(storage: &mut dyn Storage, limit: u64) -> StdResult<(u64, bool)> {
+    let mut migrated = 0u64;
+    while migrated < limit {
+        let key = match LEGACY_PRICES
+            .keys(storage, None, None, Order::Ascending)
+            .next()
+        {
+            Some(key) => key?,
+            None => return Ok((migrated, false)),
+        };
+
+        let mut latest_round_id = LATEST_ROUNDS.may_load(storage, &key)?.unwrap_or_default();
+        let remaining = LEGACY_PRICES
+            .load(storage, key.clone())?
+            .into_iter()
+            .filter(|p| p.round_id.u128() > latest_round_id)
+            .collect::<Vec<PriceData>>();
+
+        let batch = remaining.len().min((limit - migrated) as usize);
+        for price_data in remaining.iter().take(batch) {
+            latest_round_id = price_data.round_id.u128();
+
+            let cumulative = next_cumulative(storage, &key, latest_round_id, price_data)?;
+            PRICES.save(storage, (&key, latest_round_id), price_data)?;
+            CUMULATIVES.save(storage, (&key, latest_round_id), &cumulative)?;
+            migrated += 1;
+        }
+
+        if latest_round_id != 0 {
+            LATEST_ROUNDS.save(storage, &key, &latest_round_id)?;
+        }
+
+        if batch == remaining.len() {
+            LEGACY_PRICES.remove(storage, key);
+        }
+    }
+
+    let has_remaining = LEGACY_PRICES
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+
+    Ok((migrated, has_remaining))
 }
@@ -0,0 +1,174 @@
+use super::register_feed;
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{from_binary, Env, MemoryStorage, OwnedDeps, StdResult, Uint128};
+use margined_perp::margined_pricefeed::{AveragingResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+
+fn setup(max_gap: u64, ema_half_life: u64) -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
+
+    let msg = ExecuteMsg::SetAveraging {
+        key: "ETHUSD".to_string(),
+        max_gap,
+        ema_half_life,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps
+}
+
+fn append_prices(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    prices: &[(u128, u64)],
+) {
+    let now = mock_env().block.time.seconds();
+
+    let msg = ExecuteMsg::AppendMultiplePrice {
+        key: "ETHUSD".to_string(),
+        prices: prices
+            .iter()
+            .map(|(price, _)| Uint128::from(*price))
+            .collect(),
+        timestamps: prices.iter().map(|(_, age)| now - age).collect(),
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+fn query_average(
+    deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    env: Env,
+    msg: QueryMsg,
+) -> StdResult<Uint128> {
+    query(deps.as_ref(), env, msg).map(|res| from_binary(&res).unwrap())
+}
+
+fn twap(interval: u64) -> QueryMsg {
+    QueryMsg::GetTwapPrice {
+        key: "ETHUSD".to_string(),
+        interval,
+    }
+}
+
+#[test]
+fn test_twap_with_max_gap() {
+    let mut deps = setup(0, 0);
+    append_prices(&mut deps, &[(100, 1_000), (200, 400)]);
+    assert_eq!(
+        query_average(&deps, mock_env(), twap(1_000)).unwrap(),
+        Uint128::from(140u128)
+    );
+
+    // each price only counts for the first 100 seconds after it is published
+    let mut deps = setup(100, 0);
+    append_prices(&mut deps, &[(100, 1_000), (200, 400)]);
+    assert_eq!(
+        query_average(&deps, mock_env(), twap(1_000)).unwrap(),
+        Uint128::from(150u128)
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GetAveraging {
+            key: "ETHUSD".to_string(),
+        },
+    )
+    .unwrap();
+    let averaging: AveragingResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        averaging,
+        AveragingResponse {
+            max_gap: 100,
+            ema_half_life: 0,
+        }
+    );
+
+    let err = query_average(&deps, mock_env(), twap(200)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: No prices within the max gap during the interval"
+    );
+}
+
+#[test]
+fn test_averaging_changes_apply_to_later_rounds() {
+    let mut deps = setup(0, 0);
+    append_prices(&mut deps, &[(100, 1_000), (200, 400)]);
+
+    let msg = ExecuteMsg::SetAveraging {
+        key: "ETHUSD".to_string(),
+        max_gap: 100,
+        ema_half_life: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the rounds already stored keep counting for as long as they held
+    assert_eq!(
+        query_average(&deps, mock_env(), twap(1_000)).unwrap(),
+        Uint128::from(140u128)
+    );
+
+    // the new round only counts for the first 100 of its 200 seconds
+    append_prices(&mut deps, &[(300, 200)]);
+    assert_eq!(
+        query_average(&deps, mock_env(), twap(1_000)).unwrap(),
+        Uint128::from(144u128)
+    );
+}
+
+#[test]
+fn test_ema_price() {
+    let mut deps = setup(0, 100);
+    append_prices(&mut deps, &[(100, 300), (200, 200)]);
+
+    let ema = QueryMsg::GetEmaPrice {
+        key: "ETHUSD".to_string(),
+    };
+
+    // two half-lives after the move a quarter of the distance remains
+    assert_eq!(
+        query_average(&deps, mock_env(), ema.clone()).unwrap(),
+        Uint128::from(175u128)
+    );
+
+    // the decay within a half-life is linear
+    let mut env = mock_env();
+    env.block.time = env.block.time.minus_seconds(150);
+    assert_eq!(
+        query_average(&deps, env, ema).unwrap(),
+        Uint128::from(125u128)
+    );
+}
+
+#[test]
+fn test_median_price() {
+    let mut deps = setup(0, 0);
+    append_prices(&mut deps, &[(100, 400), (300, 300), (200, 200), (500, 100)]);
+
+    let median = |interval: u64| QueryMsg::GetMedianPrice {
+        key: "ETHUSD".to_string(),
+        interval,
+    };
+
+    // the price in effect at the start of the interval counts
+    assert_eq!(
+        query_average(&deps, mock_env(), median(250)).unwrap(),
+        Uint128::from(300u128)
+    );
+    assert_eq!(
+        query_average(&deps, mock_env(), median(1_000)).unwrap(),
+        Uint128::from(250u128)
+    );
+
+    let err = query_average(&deps, mock_env(), median(0)).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Interval can't be zero");
+}
@@ -0,0 +1,347 @@
+use super::register_feed;
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{from_binary, Env, MemoryStorage, OwnedDeps, Response, Timestamp, Uint128};
+use margined_perp::margined_pricefeed::{
+    Derivation, DerivedFeed, ExecuteMsg, InstantiateMsg, PriceResponse, QueryMsg,
+};
+
+use crate::{error::ContractError, state::PriceData};
+
+const UNIT: u128 = 1_000_000u128;
+
+fn setup(
+    derivation: Derivation,
+    scale: Option<u128>,
+) -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    for key in ["ETHUSD", "BTCUSD", "SOLUSD", "DERIVED", "OTHER"] {
+        register_feed(deps.as_mut(), key, &[]);
+    }
+
+    set_derived_feed(&mut deps, "DERIVED", derivation, scale).unwrap();
+
+    deps
+}
+
+fn set_derived_feed(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    key: &str,
+    derivation: Derivation,
+    scale: Option<u128>,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::SetDerivedFeed {
+        key: key.to_string(),
+        feed: DerivedFeed {
+            derivation,
+            unit: Uint128::from(UNIT),
+            scale: scale.map(Uint128::from),
+        },
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg)
+}
+
+fn append_price(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    key: &str,
+    price: u128,
+    timestamp: u64,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::AppendPrice {
+        key: key.to_string(),
+        price: Uint128::from(price),
+        timestamp,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg)
+}
+
+fn query_price(deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>, env: Env) -> PriceResponse {
+    let msg = QueryMsg::GetPrice {
+        key: "DERIVED".to_string(),
+    };
+    let res = query(deps.as_ref(), env, msg).unwrap();
+    from_binary(&res).unwrap()
+}
+
+fn ratio() -> Derivation {
+    Derivation::Ratio {
+        numerator: "ETHUSD".to_string(),
+        denominator: "BTCUSD".to_string(),
+    }
+}
+
+#[test]
+fn test_derived_prices() {
+    let now = mock_env().block.time.seconds();
+
+    let mut deps = setup(ratio(), None);
+    append_price(&mut deps, "ETHUSD", 2_000 * UNIT, now - 20).unwrap();
+    append_price(&mut deps, "BTCUSD", 40_000 * UNIT, now - 10).unwrap();
+
+    // the derived price takes the timestamp of the latest underlying price
+    let res = query_price(&deps, mock_env());
+    assert_eq!(
+        res,
+        PriceResponse {
+            round_id: Uint128::from(2u128),
+            price: Uint128::from(50_000u128),
+            timestamp: Timestamp::from_seconds(now - 10),
+            is_stale: false,
+        }
+    );
+
+    let product = Derivation::Product {
+        first: "ETHUSD".to_string(),
+        second: "BTCUSD".to_string(),
+    };
+    let mut deps = setup(product, None);
+    append_price(&mut deps, "ETHUSD", 2 * UNIT, now - 20).unwrap();
+    append_price(&mut deps, "BTCUSD", 3 * UNIT, now - 10).unwrap();
+    assert_eq!(
+        query_price(&deps, mock_env()).price,
+        Uint128::from(6 * UNIT)
+    );
+
+    let inverse = Derivation::Inverse {
+        key: "ETHUSD".to_string(),
+    };
+    let mut deps = setup(inverse, Some(100 * UNIT));
+    append_price(&mut deps, "ETHUSD", 4 * UNIT, now - 20).unwrap();
+    assert_eq!(
+        query_price(&deps, mock_env()).price,
+        Uint128::from(25 * UNIT)
+    );
+}
+
+#[test]
+fn test_derived_previous_price() {
+    let now = mock_env().block.time.seconds();
+    let mut deps = setup(ratio(), None);
+
+    append_price(&mut deps, "ETHUSD", 2_000 * UNIT, now - 40).unwrap();
+    append_price(&mut deps, "BTCUSD", 40_000 * UNIT, now - 30).unwrap();
+    append_price(&mut deps, "ETHUSD", 3_000 * UNIT, now - 20).unwrap();
+    append_price(&mut deps, "BTCUSD", 50_000 * UNIT, now - 10).unwrap();
+
+    let previous_price = |deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+                          num_round_back: u128| {
+        let msg = QueryMsg::GetPreviousPrice {
+            key: "DERIVED".to_string(),
+            num_round_back: Uint128::from(num_round_back),
+        };
+        query(deps.as_ref(), mock_env(), msg).map(|res| from_binary::<PriceData>(&res).unwrap())
+    };
+
+    assert_eq!(
+        previous_price(&deps, 0).unwrap(),
+        PriceData {
+            round_id: Uint128::from(4u128),
+            price: Uint128::from(60_000u128),
+            timestamp: Timestamp::from_seconds(now - 10),
+        }
+    );
+
+    // stepping back undoes the latest underlying update
+    assert_eq!(
+        previous_price(&deps, 1).unwrap(),
+        PriceData {
+            round_id: Uint128::from(3u128),
+            price: Uint128::from(75_000u128),
+            timestamp: Timestamp::from_seconds(now - 20),
+        }
+    );
+    assert_eq!(
+        previous_price(&deps, 2).unwrap(),
+        PriceData {
+            round_id: Uint128::from(2u128),
+            price: Uint128::from(50_000u128),
+            timestamp: Timestamp::from_seconds(now - 30),
+        }
+    );
+
+    // the derived price doesn't exist before both underlying keys have a price
+    let err = previous_price(&deps, 3).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Not enough history");
+}
+
+#[test]
+fn test_derived_twap_price() {
+    let now = mock_env().block.time.seconds();
+    let mut deps = setup(ratio(), None);
+
+    append_price(&mut deps, "ETHUSD", 2_000 * UNIT, now - 400).unwrap();
+    append_price(&mut deps, "BTCUSD", 40_000 * UNIT, now - 300).unwrap();
+    append_price(&mut deps, "ETHUSD", 3_000 * UNIT, now - 100).unwrap();
+
+    let msg = QueryMsg::GetTwapPrice {
+        key: "DERIVED".to_string(),
+        interval: 200,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let twap: Uint128 = from_binary(&res).unwrap();
+
+    // 50_000 for the first 100 seconds and 75_000 for the last 100 seconds
+    assert_eq!(twap, Uint128::from(62_500u128));
+}
+
+#[test]
+fn test_derived_twap_price_with_max_gap() {
+    let now = mock_env().block.time.seconds();
+    let mut deps = setup(ratio(), None);
+
+    let msg = ExecuteMsg::SetAveraging {
+        key: "DERIVED".to_string(),
+        max_gap: 50,
+        ema_half_life: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    append_price(&mut deps, "ETHUSD", 2_000 * UNIT, now - 400).unwrap();
+    append_price(&mut deps, "BTCUSD", 40_000 * UNIT, now - 300).unwrap();
+    append_price(&mut deps, "ETHUSD", 3_000 * UNIT, now - 100).unwrap();
+
+    // 50_000 lapses before the window starts and 75_000 counts for 50 seconds
+    let msg = QueryMsg::GetTwapPrice {
+        key: "DERIVED".to_string(),
+        interval: 200,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let twap: Uint128 = from_binary(&res).unwrap();
+    assert_eq!(twap, Uint128::from(75_000u128));
+
+    let msg = QueryMsg::GetTwapPriceAt {
+        key: "DERIVED".to_string(),
+        interval: 10,
+        timestamp: now - 20,
+    };
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: No prices within the max gap during the interval"
+    );
+}
+
+#[test]
+fn test_force_error_derived_twap_price_too_many_rounds() {
+    let now = mock_env().block.time.seconds();
+    let mut deps = setup(ratio(), None);
+
+    append_price(&mut deps, "BTCUSD", 40_000 * UNIT, now - 1_000).unwrap();
+    for index in 0..120u64 {
+        append_price(&mut deps, "ETHUSD", 2_000 * UNIT, now - 300 + 2 * index).unwrap();
+    }
+
+    let msg = QueryMsg::GetTwapPrice {
+        key: "DERIVED".to_string(),
+        interval: 300,
+    };
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Too many derived rounds during the interval"
+    );
+
+    // a shorter interval steps through fewer rounds
+    let msg = QueryMsg::GetTwapPrice {
+        key: "DERIVED".to_string(),
+        interval: 100,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let twap: Uint128 = from_binary(&res).unwrap();
+    assert_eq!(twap, Uint128::from(50_000u128));
+}
+
+#[test]
+fn test_derived_staleness() {
+    let now = mock_env().block.time.seconds();
+    let mut deps = setup(ratio(), None);
+
+    let msg = ExecuteMsg::SetValidation {
+        key: "BTCUSD".to_string(),
+        heartbeat: 60,
+        confirmation_threshold_bps: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    append_price(&mut deps, "BTCUSD", 40_000 * UNIT, now - 50).unwrap();
+    append_price(&mut deps, "ETHUSD", 2_000 * UNIT, now - 10).unwrap();
+    assert!(!query_price(&deps, mock_env()).is_stale);
+
+    // the derived price is stale once any underlying price is
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(20);
+    assert!(query_price(&deps, env).is_stale);
+}
+
+#[test]
+fn test_force_error_invalid_derived_feeds() {
+    let now = mock_env().block.time.seconds();
+    let mut deps = setup(ratio(), None);
+
+    let err = append_price(&mut deps, "DERIVED", UNIT, now).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Prices can't be stored for a derived feed"
+    );
+
+    let inverse = |key: &str| Derivation::Inverse {
+        key: key.to_string(),
+    };
+
+    let err = set_derived_feed(&mut deps, "OTHER", inverse("DERIVED"), None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Derived feeds can't depend on derived feeds"
+    );
+
+    let err = set_derived_feed(&mut deps, "ETHUSD", inverse("SOLUSD"), None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Derived feeds can't depend on derived feeds"
+    );
+
+    let err = set_derived_feed(&mut deps, "OTHER", inverse("OTHER"), None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Derived feed can't depend on itself"
+    );
+
+    let err = set_derived_feed(&mut deps, "OTHER", inverse("ETHUSD"), Some(0)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Unit and scale can't be zero"
+    );
+
+    append_price(&mut deps, "SOLUSD", UNIT, now).unwrap();
+    let err = set_derived_feed(&mut deps, "SOLUSD", inverse("ETHUSD"), None).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Key already has prices");
+
+    let msg = ExecuteMsg::SetDerivedFeed {
+        key: "OTHER".to_string(),
+        feed: DerivedFeed {
+            derivation: inverse("ETHUSD"),
+            unit: Uint128::from(UNIT),
+            scale: None,
+        },
+    };
+    let info = mock_info("addr0001", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Caller is not admin");
+
+    // once removed prices can be stored for the key again
+    let msg = ExecuteMsg::RemoveDerivedFeed {
+        key: "DERIVED".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    append_price(&mut deps, "DERIVED", UNIT, now).unwrap();
+}
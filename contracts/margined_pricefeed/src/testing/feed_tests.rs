@@ -0,0 +1,179 @@
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{from_binary, Addr, MemoryStorage, OwnedDeps, Response, Uint128};
+use margined_perp::margined_pricefeed::{
+    ExecuteMsg, FeedInfoResponse, FeedsResponse, InstantiateMsg, QueryMsg,
+};
+
+use crate::error::ContractError;
+
+fn setup() -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps
+}
+
+fn register_feed(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    sender: &str,
+    key: &str,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::RegisterFeed {
+        key: key.to_string(),
+        decimals: 6,
+        description: format!("{} feed", key),
+        heartbeat: 3_600,
+        reporters: vec!["reporter0".to_string(), "reporter1".to_string()],
+        admin: Some("feed_admin".to_string()),
+    };
+    let info = mock_info(sender, &[]);
+    execute(deps.as_mut(), mock_env(), info, msg)
+}
+
+fn append_price(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    sender: &str,
+    key: &str,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::AppendPrice {
+        key: key.to_string(),
+        price: Uint128::from(500_000_000u128),
+        timestamp: 1_000_000,
+    };
+    let info = mock_info(sender, &[]);
+    execute(deps.as_mut(), mock_env(), info, msg)
+}
+
+fn set_reporters(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    sender: &str,
+    reporters: &[&str],
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::SetReporters {
+        key: "ETHUSD".to_string(),
+        reporters: reporters.iter().map(|r| r.to_string()).collect(),
+        quorum: 1,
+        max_deviation_bps: 0,
+        max_round_age: 300,
+    };
+    let info = mock_info(sender, &[]);
+    execute(deps.as_mut(), mock_env(), info, msg)
+}
+
+fn query_feed_info(deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>) -> FeedInfoResponse {
+    let msg = QueryMsg::FeedInfo {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    from_binary(&res).unwrap()
+}
+
+#[test]
+fn test_register_feed() {
+    let mut deps = setup();
+
+    let err = append_price(&mut deps, "addr0000", "ETHUSD").unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Feed not registered");
+
+    let err = register_feed(&mut deps, "feed_admin", "ETHUSD").unwrap_err();
+    assert_eq!(err.to_string(), "Caller is not admin");
+
+    register_feed(&mut deps, "addr0000", "ETHUSD").unwrap();
+    assert_eq!(
+        query_feed_info(&deps),
+        FeedInfoResponse {
+            key: "ETHUSD".to_string(),
+            decimals: 6,
+            description: "ETHUSD feed".to_string(),
+            heartbeat: 3_600,
+            reporters: vec![Addr::unchecked("reporter0"), Addr::unchecked("reporter1")],
+            admin: Addr::unchecked("feed_admin"),
+        }
+    );
+
+    let err = register_feed(&mut deps, "addr0000", "ETHUSD").unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Feed already registered");
+
+    append_price(&mut deps, "addr0000", "ETHUSD").unwrap();
+}
+
+#[test]
+fn test_feed_admin_manages_feed() {
+    let mut deps = setup();
+    register_feed(&mut deps, "addr0000", "ETHUSD").unwrap();
+    register_feed(&mut deps, "addr0000", "BTCUSD").unwrap();
+
+    // the feed admin can only manage its own feed
+    append_price(&mut deps, "feed_admin", "ETHUSD").unwrap();
+    let err = append_price(&mut deps, "addr0001", "BTCUSD").unwrap_err();
+    assert_eq!(err.to_string(), "Caller is not admin");
+
+    // reporters must be allowed by the feed
+    let err = set_reporters(&mut deps, "feed_admin", &["reporter0", "reporter2"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Reporter not allowed for feed"
+    );
+    set_reporters(&mut deps, "feed_admin", &["reporter0", "reporter1"]).unwrap();
+
+    let msg = ExecuteMsg::UpdateFeed {
+        key: "ETHUSD".to_string(),
+        description: Some("Ether".to_string()),
+        heartbeat: Some(60),
+        reporters: Some(vec!["reporter1".to_string()]),
+        admin: Some("addr0001".to_string()),
+    };
+    let info = mock_info("addr0001", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "Caller is not admin");
+
+    let info = mock_info("feed_admin", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let feed = query_feed_info(&deps);
+    assert_eq!(feed.description, "Ether".to_string());
+    assert_eq!(feed.heartbeat, 60);
+    assert_eq!(feed.reporters, vec![Addr::unchecked("reporter1")]);
+    assert_eq!(feed.admin, Addr::unchecked("addr0001"));
+
+    // the reporters of the key are cleared once reporter0 is no longer allowed
+    let msg = QueryMsg::GetReporters {
+        key: "ETHUSD".to_string(),
+    };
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: No reporters set for key");
+}
+
+#[test]
+fn test_list_feeds() {
+    let mut deps = setup();
+    for key in ["ATOMUSD", "BTCUSD", "ETHUSD", "JUNOUSD"] {
+        register_feed(&mut deps, "addr0000", key).unwrap();
+    }
+
+    let list_feeds = |start_after: Option<&str>, limit: Option<u32>| -> Vec<String> {
+        let msg = QueryMsg::ListFeeds {
+            start_after: start_after.map(|key| key.to_string()),
+            limit,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let feeds: FeedsResponse = from_binary(&res).unwrap();
+        feeds.feeds.into_iter().map(|feed| feed.key).collect()
+    };
+
+    assert_eq!(
+        list_feeds(None, None),
+        vec!["ATOMUSD", "BTCUSD", "ETHUSD", "JUNOUSD"]
+    );
+    assert_eq!(list_feeds(None, Some(2)), vec!["ATOMUSD", "BTCUSD"]);
+    assert_eq!(
+        list_feeds(Some("BTCUSD"), Some(2)),
+        vec!["ETHUSD", "JUNOUSD"]
+    );
+    assert!(list_feeds(Some("JUNOUSD"), None).is_empty());
+}
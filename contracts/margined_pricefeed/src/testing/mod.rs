@@ -1 +1,28 @@
+mod averaging_tests;
+mod derived_feed_tests;
+mod feed_tests;
+mod price_history_tests;
+mod reporter_tests;
+mod signed_price_tests;
+mod subscription_tests;
 mod tests;
+mod validation_tests;
+
+use crate::contract::execute;
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::DepsMut;
+use margined_perp::margined_pricefeed::ExecuteMsg;
+
+// registers a feed owned by the instantiator so that prices are accepted for it
+fn register_feed(deps: DepsMut, key: &str, reporters: &[&str]) {
+    let msg = ExecuteMsg::RegisterFeed {
+        key: key.to_string(),
+        decimals: 6,
+        description: format!("{} feed", key),
+        heartbeat: 0,
+        reporters: reporters.iter().map(|r| r.to_string()).collect(),
+        admin: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps, mock_env(), info, msg).unwrap();
+}
@@ -0,0 +1,338 @@
+use super::register_feed;
+use crate::{
+    contract::{execute, instantiate, migrate, query},
+    state::{PriceData, CUMULATIVES, LEGACY_PRICES},
+};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, Addr, Timestamp, Uint128};
+use margined_perp::margined_pricefeed::{
+    ExecuteMsg, FeedInfoResponse, InstantiateMsg, MigrateMsg, PriceDataResponse,
+    PriceHistoryResponse, QueryMsg,
+};
+
+fn price_data(round_id: u128, price: u128, timestamp: u64) -> PriceDataResponse {
+    PriceDataResponse {
+        round_id: Uint128::from(round_id),
+        price: Uint128::from(price),
+        timestamp: Timestamp::from_seconds(timestamp),
+    }
+}
+
+#[test]
+fn test_price_history_pagination() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
+
+    let msg = ExecuteMsg::AppendMultiplePrice {
+        key: "ETHUSD".to_string(),
+        prices: vec![
+            Uint128::from(500_000_000u128),
+            Uint128::from(600_000_000u128),
+            Uint128::from(700_000_000u128),
+        ],
+        timestamps: vec![1_000_000, 1_000_001, 1_000_002],
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = QueryMsg::PriceHistory {
+        key: "ETHUSD".to_string(),
+        start_after: None,
+        limit: Some(2),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let history: PriceHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        history.prices,
+        vec![
+            price_data(1, 500_000_000, 1_000_000),
+            price_data(2, 600_000_000, 1_000_001),
+        ]
+    );
+
+    let msg = QueryMsg::PriceHistory {
+        key: "ETHUSD".to_string(),
+        start_after: Some(Uint128::from(2u128)),
+        limit: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let history: PriceHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history.prices, vec![price_data(3, 700_000_000, 1_000_002)]);
+
+    // other keys have their own history
+    let msg = QueryMsg::PriceHistory {
+        key: "BTCUSD".to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let history: PriceHistoryResponse = from_binary(&res).unwrap();
+    assert!(history.prices.is_empty());
+}
+
+#[test]
+fn test_force_error_get_price_without_prices() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = QueryMsg::GetPrice {
+        key: "ETHUSD".to_string(),
+    };
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Unable to retrieve price data for key: ETHUSD"
+    );
+
+    let msg = QueryMsg::GetPreviousPrice {
+        key: "ETHUSD".to_string(),
+        num_round_back: Uint128::zero(),
+    };
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Not enough history");
+}
+
+#[test]
+fn test_migrate_legacy_price_history() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // histories were stored with an empty round zero in front
+    let legacy = |round_id: u128, price: u128, timestamp: u64| PriceData {
+        round_id: Uint128::from(round_id),
+        price: Uint128::from(price),
+        timestamp: Timestamp::from_seconds(timestamp),
+    };
+    LEGACY_PRICES
+        .save(
+            deps.as_mut().storage,
+            "ETHUSD".to_string(),
+            &vec![
+                PriceData::default(),
+                legacy(1, 500_000_000, 1_000_000),
+                legacy(2, 600_000_000, 1_000_001),
+            ],
+        )
+        .unwrap();
+    LEGACY_PRICES
+        .save(
+            deps.as_mut().storage,
+            "BTCUSD".to_string(),
+            &vec![PriceData::default(), legacy(1, 900_000_000, 1_000_000)],
+        )
+        .unwrap();
+
+    // the btc feed was registered after its prices were stored
+    register_feed(deps.as_mut(), "BTCUSD", &[]);
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { decimals: 9u8 }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "registered_feeds" && attr.value == "1"));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "migrated_rounds" && attr.value == "3"));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "has_remaining" && attr.value == "false"));
+
+    // the running values are stored as the rounds are moved
+    assert!(
+        (1..=2u128).all(|round_id| CUMULATIVES.has(deps.as_ref().storage, ("ETHUSD", round_id)))
+    );
+
+    let msg = QueryMsg::PriceHistory {
+        key: "ETHUSD".to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let history: PriceHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        history.prices,
+        vec![
+            price_data(1, 500_000_000, 1_000_000),
+            price_data(2, 600_000_000, 1_000_001),
+        ]
+    );
+
+    let msg = QueryMsg::GetPrice {
+        key: "BTCUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let price: PriceData = from_binary(&res).unwrap();
+    assert_eq!(price, legacy(1, 900_000_000, 1_000_000));
+
+    // the key is registered to the owner, so new prices carry on from the migrated rounds
+    let msg = QueryMsg::FeedInfo {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let feed: FeedInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(feed.decimals, 9u8);
+    assert_eq!(feed.admin, Addr::unchecked("addr0000"));
+
+    let msg = ExecuteMsg::AppendPrice {
+        key: "ETHUSD".to_string(),
+        price: Uint128::from(700_000_000u128),
+        timestamp: 1_000_002,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = QueryMsg::GetPrice {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let price: PriceData = from_binary(&res).unwrap();
+    assert_eq!(price, legacy(3, 700_000_000, 1_000_002));
+
+    assert!(LEGACY_PRICES
+        .may_load(deps.as_ref().storage, "ETHUSD".to_string())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_migrate_legacy_price_history_in_batches() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let legacy = |round_id: u128, price: u128, timestamp: u64| PriceData {
+        round_id: Uint128::from(round_id),
+        price: Uint128::from(price),
+        timestamp: Timestamp::from_seconds(timestamp),
+    };
+    LEGACY_PRICES
+        .save(
+            deps.as_mut().storage,
+            "BTCUSD".to_string(),
+            &vec![PriceData::default(), legacy(1, 900_000_000, 1_000_000)],
+        )
+        .unwrap();
+    LEGACY_PRICES
+        .save(
+            deps.as_mut().storage,
+            "ETHUSD".to_string(),
+            &vec![
+                PriceData::default(),
+                legacy(1, 500_000_000, 1_000_000),
+                legacy(2, 600_000_000, 1_000_001),
+                legacy(3, 700_000_000, 1_000_002),
+            ],
+        )
+        .unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
+
+    // anyone can move the next batch, which stops part way through a history
+    let msg = ExecuteMsg::MigratePriceHistory { limit: Some(2u32) };
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "migrated_rounds" && attr.value == "2"));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "has_remaining" && attr.value == "true"));
+
+    let msg_history = QueryMsg::PriceHistory {
+        key: "ETHUSD".to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg_history.clone()).unwrap();
+    let history: PriceHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history.prices, vec![price_data(1, 500_000_000, 1_000_000)]);
+
+    // the key can't be read or written until all of its history has been moved
+    let msg_price = QueryMsg::GetPrice {
+        key: "ETHUSD".to_string(),
+    };
+    let err = query(deps.as_ref(), mock_env(), msg_price.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Price history of key is still being migrated: ETHUSD"
+    );
+
+    let msg_append = ExecuteMsg::AppendPrice {
+        key: "ETHUSD".to_string(),
+        price: Uint128::from(800_000_000u128),
+        timestamp: 1_000_003,
+    };
+    let owner = mock_info("addr0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), msg_append.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Price history of key is still being migrated: ETHUSD"
+    );
+
+    // the next batch carries on from the latest round
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "migrated_rounds" && attr.value == "2"));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "has_remaining" && attr.value == "false"));
+
+    let res = query(deps.as_ref(), mock_env(), msg_history).unwrap();
+    let history: PriceHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        history.prices,
+        vec![
+            price_data(1, 500_000_000, 1_000_000),
+            price_data(2, 600_000_000, 1_000_001),
+            price_data(3, 700_000_000, 1_000_002),
+        ]
+    );
+
+    assert!(LEGACY_PRICES
+        .may_load(deps.as_ref().storage, "ETHUSD".to_string())
+        .unwrap()
+        .is_none());
+
+    // once migrated the key carries on from its last round
+    let res = query(deps.as_ref(), mock_env(), msg_price.clone()).unwrap();
+    let price: PriceData = from_binary(&res).unwrap();
+    assert_eq!(price.round_id, Uint128::from(3u128));
+
+    execute(deps.as_mut(), mock_env(), owner, msg_append).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), msg_price).unwrap();
+    let price: PriceData = from_binary(&res).unwrap();
+    assert_eq!(price.round_id, Uint128::from(4u128));
+
+    // the time weighted price covers the migrated rounds
+    let msg = QueryMsg::GetTwapPriceAt {
+        key: "ETHUSD".to_string(),
+        interval: 2,
+        timestamp: 1_000_002,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let twap: Uint128 = from_binary(&res).unwrap();
+    assert_eq!(twap, Uint128::from(550_000_000u128));
+}
@@ -0,0 +1,314 @@
+use super::register_feed;
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{from_binary, Addr, MemoryStorage, OwnedDeps, Timestamp, Uint128};
+use margined_perp::margined_pricefeed::{
+    ExecuteMsg, InstantiateMsg, QueryMsg, ReportersResponse, RoundDataResponse,
+};
+
+fn setup(quorum: u32, max_deviation_bps: u64) -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    register_feed(
+        deps.as_mut(),
+        "ETHUSD",
+        &["reporter0", "reporter1", "reporter2"],
+    );
+
+    let msg = ExecuteMsg::SetReporters {
+        key: "ETHUSD".to_string(),
+        reporters: vec![
+            "reporter0".to_string(),
+            "reporter1".to_string(),
+            "reporter2".to_string(),
+        ],
+        quorum,
+        max_deviation_bps,
+        max_round_age: 300,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps
+}
+
+fn submit_price(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    reporter: &str,
+    price: u128,
+    timestamp: u64,
+) -> Vec<(String, String)> {
+    let msg = ExecuteMsg::SubmitPrice {
+        key: "ETHUSD".to_string(),
+        price: Uint128::from(price),
+        timestamp,
+    };
+    let info = mock_info(reporter, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    res.attributes
+        .into_iter()
+        .map(|attr| (attr.key, attr.value))
+        .collect()
+}
+
+fn query_latest_round_data(
+    deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+) -> RoundDataResponse {
+    let msg = QueryMsg::GetLatestRoundData {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    from_binary(&res).unwrap()
+}
+
+#[test]
+fn test_set_reporters() {
+    let mut deps = setup(2, 500);
+
+    let msg = QueryMsg::GetReporters {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let reporters: ReportersResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        reporters,
+        ReportersResponse {
+            reporters: vec![
+                Addr::unchecked("reporter0"),
+                Addr::unchecked("reporter1"),
+                Addr::unchecked("reporter2"),
+            ],
+            quorum: 2,
+            max_deviation_bps: 500,
+            max_round_age: 300,
+        }
+    );
+
+    // only the owner can set reporters
+    let msg = ExecuteMsg::SetReporters {
+        key: "ETHUSD".to_string(),
+        reporters: vec!["reporter0".to_string()],
+        quorum: 1,
+        max_deviation_bps: 0,
+        max_round_age: 300,
+    };
+    let info = mock_info("reporter0", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Caller is not admin");
+
+    // the quorum can't be more than the reporters
+    let msg = ExecuteMsg::SetReporters {
+        key: "ETHUSD".to_string(),
+        reporters: vec!["reporter0".to_string()],
+        quorum: 2,
+        max_deviation_bps: 0,
+        max_round_age: 300,
+    };
+    let info = mock_info("addr0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Invalid quorum");
+
+    let msg = ExecuteMsg::SetReporters {
+        key: "ETHUSD".to_string(),
+        reporters: vec!["reporter0".to_string(), "reporter0".to_string()],
+        quorum: 1,
+        max_deviation_bps: 0,
+        max_round_age: 300,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Duplicate reporter");
+
+    // rounds have to expire
+    let msg = ExecuteMsg::SetReporters {
+        key: "ETHUSD".to_string(),
+        reporters: vec!["reporter0".to_string()],
+        quorum: 1,
+        max_deviation_bps: 0,
+        max_round_age: 0,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Invalid max round age");
+}
+
+#[test]
+fn test_round_answered_by_median_once_quorum_reached() {
+    let mut deps = setup(2, 0);
+
+    let attributes = submit_price(&mut deps, "reporter0", 500_000_000, 1_000_000);
+    assert!(!attributes.iter().any(|(key, _)| key == "price"));
+
+    // nothing is answered before quorum
+    let msg = QueryMsg::GetLatestRoundData {
+        key: "ETHUSD".to_string(),
+    };
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: No data for round");
+
+    let attributes = submit_price(&mut deps, "reporter1", 520_000_000, 1_000_000);
+    assert!(attributes.contains(&("price".to_string(), "510000000".to_string())));
+
+    let round_data = query_latest_round_data(&deps);
+    assert_eq!(
+        round_data,
+        RoundDataResponse {
+            round_id: Uint128::from(1u128),
+            price: Uint128::from(510_000_000u128),
+            timestamp: Timestamp::from_seconds(1_000_000),
+            answered_in_round: Uint128::from(1u128),
+            reporters: vec![Addr::unchecked("reporter0"), Addr::unchecked("reporter1")],
+            started_at: mock_env().block.time,
+            updated_at: mock_env().block.time,
+        }
+    );
+
+    // a late submission starts the next round, which is answered by the previous one
+    submit_price(&mut deps, "reporter2", 530_000_000, 1_000_060);
+
+    let round_data = query_latest_round_data(&deps);
+    assert_eq!(round_data.round_id, Uint128::from(2u128));
+    assert_eq!(round_data.answered_in_round, Uint128::from(1u128));
+    assert_eq!(round_data.price, Uint128::from(510_000_000u128));
+
+    submit_price(&mut deps, "reporter0", 540_000_000, 1_000_060);
+
+    let msg = QueryMsg::GetRoundData {
+        key: "ETHUSD".to_string(),
+        round_id: Uint128::from(2u128),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let round_data: RoundDataResponse = from_binary(&res).unwrap();
+    assert_eq!(round_data.price, Uint128::from(535_000_000u128));
+    assert_eq!(round_data.answered_in_round, Uint128::from(2u128));
+    assert_eq!(
+        round_data.reporters,
+        vec![Addr::unchecked("reporter2"), Addr::unchecked("reporter0")]
+    );
+}
+
+#[test]
+fn test_outliers_are_rejected() {
+    // 5% max deviation from the median
+    let mut deps = setup(2, 500);
+
+    submit_price(&mut deps, "reporter0", 500_000_000, 1_000_000);
+
+    // the two prices are too far apart to agree
+    let attributes = submit_price(&mut deps, "reporter1", 750_000_000, 1_000_000);
+    assert!(!attributes.iter().any(|(key, _)| key == "price"));
+
+    let attributes = submit_price(&mut deps, "reporter2", 505_000_000, 1_000_000);
+    assert!(attributes.contains(&("price".to_string(), "502500000".to_string())));
+    assert!(attributes.contains(&("outliers".to_string(), "reporter1".to_string())));
+
+    let round_data = query_latest_round_data(&deps);
+    assert_eq!(
+        round_data.reporters,
+        vec![Addr::unchecked("reporter0"), Addr::unchecked("reporter2")]
+    );
+}
+
+#[test]
+fn test_round_discarded_without_agreement() {
+    let mut deps = setup(3, 500);
+
+    submit_price(&mut deps, "reporter0", 500_000_000, 1_000_000);
+    submit_price(&mut deps, "reporter1", 600_000_000, 1_000_000);
+    let attributes = submit_price(&mut deps, "reporter2", 700_000_000, 1_000_000);
+    assert!(attributes.contains(&("discarded".to_string(), "true".to_string())));
+
+    // every reporter can submit to the next round
+    submit_price(&mut deps, "reporter0", 500_000_000, 1_000_000);
+    submit_price(&mut deps, "reporter1", 500_000_000, 1_000_000);
+    let attributes = submit_price(&mut deps, "reporter2", 500_000_000, 1_000_000);
+    assert!(attributes.contains(&("round_id".to_string(), "1".to_string())));
+    assert!(attributes.contains(&("price".to_string(), "500000000".to_string())));
+}
+
+#[test]
+fn test_force_error_submit_price() {
+    let mut deps = setup(2, 0);
+
+    let msg = ExecuteMsg::SubmitPrice {
+        key: "ETHUSD".to_string(),
+        price: Uint128::from(500_000_000u128),
+        timestamp: 1_000_000,
+    };
+    let info = mock_info("addr0001", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    let info = mock_info("reporter0", &[]);
+    execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Reporter has already submitted for this round"
+    );
+
+    let msg = ExecuteMsg::SubmitPrice {
+        key: "BTCUSD".to_string(),
+        price: Uint128::from(500_000_000u128),
+        timestamp: 1_000_000,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: No reporters set for key");
+}
+
+#[test]
+fn test_round_expires_after_max_age() {
+    let mut deps = setup(2, 0);
+
+    submit_price(&mut deps, "reporter0", 500_000_000, 1_000_000);
+
+    // the round has not expired yet, so reporter0 cannot submit again
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(299);
+    let msg = ExecuteMsg::SubmitPrice {
+        key: "ETHUSD".to_string(),
+        price: Uint128::from(520_000_000u128),
+        timestamp: 1_000_299,
+    };
+    let info = mock_info("reporter0", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Reporter has already submitted for this round"
+    );
+
+    // once expired the old submission is discarded and a new round is started
+    env.block.time = env.block.time.plus_seconds(1);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "expired" && attr.value == "true"));
+    assert!(!res.attributes.iter().any(|attr| attr.key == "price"));
+
+    let msg = ExecuteMsg::SubmitPrice {
+        key: "ETHUSD".to_string(),
+        price: Uint128::from(540_000_000u128),
+        timestamp: 1_000_300,
+    };
+    let info = mock_info("reporter1", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "price" && attr.value == "530000000"));
+
+    let msg = QueryMsg::GetLatestRoundData {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let round_data: RoundDataResponse = from_binary(&res).unwrap();
+    assert_eq!(round_data.started_at, env.block.time);
+    assert_eq!(
+        round_data.reporters,
+        vec![Addr::unchecked("reporter0"), Addr::unchecked("reporter1")]
+    );
+}
@@ -0,0 +1,290 @@
+use super::register_feed;
+use crate::contract::{execute, instantiate, query};
+use crate::utils::price_payload_hash;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{from_binary, to_vec, Addr, Binary, Env, MemoryStorage, OwnedDeps, Uint128};
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use margined_perp::margined_pricefeed::{
+    ExecuteMsg, InstantiateMsg, PricePayload, QueryMsg, RoundDataResponse, SignatureAlgorithm,
+    SignedPrice, SignerResponse,
+};
+
+fn setup() -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &["reporter0", "reporter1"]);
+
+    let msg = ExecuteMsg::SetReporters {
+        key: "ETHUSD".to_string(),
+        reporters: vec!["reporter0".to_string(), "reporter1".to_string()],
+        quorum: 2,
+        max_deviation_bps: 0,
+        max_round_age: 300,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::AddSigner {
+        pubkey: secp256k1_pubkey(),
+        algorithm: SignatureAlgorithm::Secp256k1,
+        reporter: "reporter0".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::AddSigner {
+        pubkey: ed25519_pubkey(),
+        algorithm: SignatureAlgorithm::Ed25519,
+        reporter: "reporter1".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps
+}
+
+fn secp256k1_key() -> SigningKey {
+    SigningKey::from_bytes(&[1u8; 32]).unwrap()
+}
+
+fn secp256k1_pubkey() -> Binary {
+    Binary::from(secp256k1_key().verifying_key().to_bytes().to_vec())
+}
+
+fn ed25519_key() -> ed25519_zebra::SigningKey {
+    ed25519_zebra::SigningKey::from([2u8; 32])
+}
+
+fn ed25519_pubkey() -> Binary {
+    let pubkey = ed25519_zebra::VerificationKey::from(&ed25519_key());
+    Binary::from(<[u8; 32]>::from(pubkey).to_vec())
+}
+
+fn sign_secp256k1(env: &Env, key: &str, price: Uint128, timestamp: u64) -> SignedPrice {
+    // the signer hashes the payload with sha256 before signing
+    let payload = to_vec(&PricePayload {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        key: key.to_string(),
+        price,
+        timestamp,
+    })
+    .unwrap();
+    let signature: Signature = secp256k1_key().sign(&payload);
+
+    SignedPrice {
+        key: key.to_string(),
+        price,
+        timestamp,
+        pubkey: secp256k1_pubkey(),
+        signature: Binary::from(signature.as_ref().to_vec()),
+    }
+}
+
+fn sign_ed25519(env: &Env, key: &str, price: Uint128, timestamp: u64) -> SignedPrice {
+    let message_hash = price_payload_hash(env, key, price, timestamp).unwrap();
+    let signature = ed25519_key().sign(&message_hash);
+
+    SignedPrice {
+        key: key.to_string(),
+        price,
+        timestamp,
+        pubkey: ed25519_pubkey(),
+        signature: Binary::from(<[u8; 64]>::from(signature).to_vec()),
+    }
+}
+
+#[test]
+fn test_add_and_remove_signer() {
+    let mut deps = setup();
+
+    let msg = QueryMsg::GetSigner {
+        pubkey: secp256k1_pubkey(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let signer: SignerResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        signer,
+        SignerResponse {
+            pubkey: secp256k1_pubkey(),
+            algorithm: SignatureAlgorithm::Secp256k1,
+            reporter: Addr::unchecked("reporter0"),
+        }
+    );
+
+    // only the owner can remove signers
+    let msg = ExecuteMsg::RemoveSigner {
+        pubkey: secp256k1_pubkey(),
+    };
+    let info = mock_info("reporter0", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "Caller is not admin");
+
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = QueryMsg::GetSigner {
+        pubkey: secp256k1_pubkey(),
+    };
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Signer not found");
+}
+
+#[test]
+fn test_signed_prices_relayed_by_anyone() {
+    let mut deps = setup();
+
+    let payloads = vec![
+        sign_secp256k1(
+            &mock_env(),
+            "ETHUSD",
+            Uint128::from(500_000_000u128),
+            1_000_000,
+        ),
+        sign_ed25519(
+            &mock_env(),
+            "ETHUSD",
+            Uint128::from(520_000_000u128),
+            1_000_001,
+        ),
+    ];
+    let msg = ExecuteMsg::SubmitSignedPrices { payloads };
+    let info = mock_info("relayer", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = QueryMsg::GetLatestRoundData {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let round_data: RoundDataResponse = from_binary(&res).unwrap();
+    assert_eq!(round_data.price, Uint128::from(510_000_000u128));
+    assert_eq!(
+        round_data.reporters,
+        vec![Addr::unchecked("reporter0"), Addr::unchecked("reporter1")]
+    );
+}
+
+#[test]
+fn test_force_error_signed_price_replayed() {
+    let mut deps = setup();
+
+    let payload = sign_secp256k1(
+        &mock_env(),
+        "ETHUSD",
+        Uint128::from(500_000_000u128),
+        1_000_000,
+    );
+    let msg = ExecuteMsg::SubmitSignedPrices {
+        payloads: vec![payload.clone()],
+    };
+    let info = mock_info("relayer", &[]);
+    execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+
+    // answer the round so the reporter could submit again
+    let msg_other = ExecuteMsg::SubmitSignedPrices {
+        payloads: vec![sign_ed25519(
+            &mock_env(),
+            "ETHUSD",
+            Uint128::from(500_000_000u128),
+            1_000_000,
+        )],
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg_other).unwrap();
+
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Signed price is not newer than the last price of the signer"
+    );
+
+    // neither can an older price be submitted
+    let msg = ExecuteMsg::SubmitSignedPrices {
+        payloads: vec![sign_secp256k1(
+            &mock_env(),
+            "ETHUSD",
+            Uint128::from(500_000_000u128),
+            999_999,
+        )],
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Signed price is not newer than the last price of the signer"
+    );
+}
+
+#[test]
+fn test_force_error_invalid_signature() {
+    let mut deps = setup();
+
+    // the price has been tampered with after signing
+    let mut payload = sign_secp256k1(
+        &mock_env(),
+        "ETHUSD",
+        Uint128::from(500_000_000u128),
+        1_000_000,
+    );
+    payload.price = Uint128::from(900_000_000u128);
+    let msg = ExecuteMsg::SubmitSignedPrices {
+        payloads: vec![payload],
+    };
+    let info = mock_info("relayer", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Invalid signature");
+
+    let mut payload = sign_ed25519(
+        &mock_env(),
+        "ETHUSD",
+        Uint128::from(500_000_000u128),
+        1_000_000,
+    );
+    payload.timestamp = 1_000_001;
+    let msg = ExecuteMsg::SubmitSignedPrices {
+        payloads: vec![payload],
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Invalid signature");
+
+    // signed by a key that isn't registered
+    let mut payload = sign_ed25519(
+        &mock_env(),
+        "ETHUSD",
+        Uint128::from(500_000_000u128),
+        1_000_000,
+    );
+    payload.pubkey = Binary::from(vec![3u8; 32]);
+    let msg = ExecuteMsg::SubmitSignedPrices {
+        payloads: vec![payload],
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Signer not found");
+
+    // signed for another pricefeed or chain
+    let mut env = mock_env();
+    env.contract.address = Addr::unchecked("other_pricefeed");
+    let msg = ExecuteMsg::SubmitSignedPrices {
+        payloads: vec![sign_ed25519(
+            &env,
+            "ETHUSD",
+            Uint128::from(500_000_000u128),
+            1_000_000,
+        )],
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Invalid signature");
+
+    let mut env = mock_env();
+    env.block.chain_id = "other-chain".to_string();
+    let msg = ExecuteMsg::SubmitSignedPrices {
+        payloads: vec![sign_secp256k1(
+            &env,
+            "ETHUSD",
+            Uint128::from(500_000_000u128),
+            1_000_000,
+        )],
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Invalid signature");
+}
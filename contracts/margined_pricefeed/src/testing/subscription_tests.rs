@@ -0,0 +1,257 @@
+use super::register_feed;
+use crate::contract::{execute, instantiate, query, reply, PRICE_UPDATED_REPLY_ID};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{
+    from_binary, Addr, CosmosMsg, MemoryStorage, OwnedDeps, Reply, ReplyOn, SubMsgResult,
+    Timestamp, Uint128, WasmMsg,
+};
+use cw_controllers::HooksResponse;
+use margined_perp::margined_pricefeed::{
+    ExecuteMsg, InstantiateMsg, PriceHookMsg, QueryMsg, SubscriptionResponse, SubscriptionsResponse,
+};
+
+fn setup() -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
+
+    for subscriber in ["vamm0000", "guard0000"] {
+        let msg = ExecuteMsg::AddSubscriber {
+            address: subscriber.to_string(),
+        };
+        let info = mock_info("addr0000", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    deps
+}
+
+fn subscribe(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    subscriber: &str,
+    deviation_bps: u64,
+) {
+    let msg = ExecuteMsg::Subscribe {
+        key: "ETHUSD".to_string(),
+        deviation_bps,
+    };
+    let info = mock_info(subscriber, &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+// returns the subscribers that are called back with the price
+fn append_price(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    price: u128,
+    timestamp: u64,
+) -> Vec<String> {
+    let msg = ExecuteMsg::AppendPrice {
+        key: "ETHUSD".to_string(),
+        price: Uint128::from(price),
+        timestamp,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    res.messages
+        .into_iter()
+        .map(|message| {
+            assert_eq!(message.id, PRICE_UPDATED_REPLY_ID);
+            assert_eq!(message.reply_on, ReplyOn::Error);
+            assert_eq!(message.gas_limit, Some(500_000));
+
+            match message.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr, msg, ..
+                }) => {
+                    let PriceHookMsg::PriceUpdated {
+                        key,
+                        price: hook_price,
+                        timestamp: hook_timestamp,
+                        ..
+                    } = from_binary(&msg).unwrap();
+                    assert_eq!(key, "ETHUSD".to_string());
+                    assert_eq!(hook_price, Uint128::from(price));
+                    assert_eq!(hook_timestamp, Timestamp::from_seconds(timestamp));
+
+                    contract_addr
+                }
+                _ => panic!("unexpected message"),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_subscribers_called_back_with_new_rounds() {
+    let mut deps = setup();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::GetSubscribers {}).unwrap();
+    let subscribers: HooksResponse = from_binary(&res).unwrap();
+    assert_eq!(subscribers.hooks, vec!["vamm0000", "guard0000"]);
+
+    subscribe(&mut deps, "vamm0000", 0);
+    assert_eq!(
+        append_price(&mut deps, 500_000_000, 1_000_000),
+        vec!["vamm0000"]
+    );
+    assert_eq!(
+        append_price(&mut deps, 501_000_000, 1_000_001),
+        vec!["vamm0000"]
+    );
+
+    // the subscription stops once the subscriber is removed
+    let msg = ExecuteMsg::RemoveSubscriber {
+        address: "vamm0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert!(append_price(&mut deps, 502_000_000, 1_000_002).is_empty());
+}
+
+#[test]
+fn test_subscriber_called_back_once_deviation_crossed() {
+    let mut deps = setup();
+    subscribe(&mut deps, "vamm0000", 0);
+    subscribe(&mut deps, "guard0000", 1_000);
+
+    assert_eq!(
+        append_price(&mut deps, 500_000_000, 1_000_000),
+        vec!["guard0000", "vamm0000"]
+    );
+
+    // within 10% of the last price the guard was sent
+    assert_eq!(
+        append_price(&mut deps, 540_000_000, 1_000_001),
+        vec!["vamm0000"]
+    );
+    assert_eq!(
+        append_price(&mut deps, 560_000_000, 1_000_002),
+        vec!["guard0000", "vamm0000"]
+    );
+
+    let msg = QueryMsg::GetSubscriptions {
+        key: "ETHUSD".to_string(),
+        start_after: None,
+        limit: Some(1),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let subscriptions: SubscriptionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        subscriptions.subscriptions,
+        vec![SubscriptionResponse {
+            subscriber: Addr::unchecked("guard0000"),
+            deviation_bps: 1_000,
+            last_price: Some(Uint128::from(560_000_000u128)),
+        }]
+    );
+
+    let msg = ExecuteMsg::Unsubscribe {
+        key: "ETHUSD".to_string(),
+    };
+    let info = mock_info("guard0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        append_price(&mut deps, 700_000_000, 1_000_003),
+        vec!["vamm0000"]
+    );
+}
+
+#[test]
+fn test_failing_subscriber_does_not_block_price_update() {
+    let mut deps = setup();
+
+    let msg = Reply {
+        id: PRICE_UPDATED_REPLY_ID,
+        result: SubMsgResult::Err("subscriber out of gas".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.attributes[0].value, "price_updated_failed");
+    assert_eq!(res.attributes[1].value, "subscriber out of gas");
+    assert!(res.messages.is_empty());
+}
+
+#[test]
+fn test_force_error_subscribe() {
+    let mut deps = setup();
+
+    let msg = ExecuteMsg::Subscribe {
+        key: "ETHUSD".to_string(),
+        deviation_bps: 0,
+    };
+    let info = mock_info("addr0001", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    let msg = ExecuteMsg::Subscribe {
+        key: "BTCUSD".to_string(),
+        deviation_bps: 0,
+    };
+    let info = mock_info("vamm0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Feed not registered");
+
+    let msg = ExecuteMsg::AddSubscriber {
+        address: "addr0001".to_string(),
+    };
+    let info = mock_info("addr0001", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Caller is not admin");
+}
+
+#[test]
+fn test_subscriptions_capped_per_key() {
+    let mut deps = setup();
+
+    // only the owner can change the limits
+    let msg = ExecuteMsg::UpdateConfig {
+        subscriber_gas_limit: Some(200_000),
+        max_subscriptions: Some(1),
+    };
+    let info = mock_info("addr0001", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "Caller is not admin");
+
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        subscriber_gas_limit: Some(0),
+        max_subscriptions: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Invalid subscriber gas limit"
+    );
+
+    // the subscription can be updated but no other subscriber can be added
+    subscribe(&mut deps, "vamm0000", 0);
+    subscribe(&mut deps, "vamm0000", 100);
+
+    let msg = ExecuteMsg::Subscribe {
+        key: "ETHUSD".to_string(),
+        deviation_bps: 0,
+    };
+    let info = mock_info("guard0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Too many subscriptions for key"
+    );
+
+    // callbacks are sent with the new gas limit
+    let msg = ExecuteMsg::AppendPrice {
+        key: "ETHUSD".to_string(),
+        price: Uint128::from(500_000_000u128),
+        timestamp: 1_000_000,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].gas_limit, Some(200_000));
+}
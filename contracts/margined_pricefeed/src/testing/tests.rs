@@ -1,3 +1,4 @@
+use super::register_feed;
 use crate::{
     contract::{execute, instantiate, query},
     state::PriceData,
@@ -22,7 +23,13 @@ fn test_instantiation() {
 
     let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
     let config: ConfigResponse = from_binary(&res).unwrap();
-    assert_eq!(config, ConfigResponse {});
+    assert_eq!(
+        config,
+        ConfigResponse {
+            subscriber_gas_limit: 500_000,
+            max_subscriptions: 10,
+        }
+    );
 }
 
 #[test]
@@ -68,6 +75,7 @@ fn test_set_and_get_price() {
     };
     let info = mock_info("addr0000", &[]);
     instantiate(deps.as_mut(), mock_env(), info, _msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
 
     let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
     let config: OwnerResponse = from_binary(&res).unwrap();
@@ -139,6 +147,7 @@ fn test_set_multiple_price() {
     };
     let info = mock_info("addr0000", &[]);
     instantiate(deps.as_mut(), mock_env(), info, _msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
 
     let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
     let config: OwnerResponse = from_binary(&res).unwrap();
@@ -190,6 +199,7 @@ fn test_get_previous_price() {
     };
     let info = mock_info("addr0000", &[]);
     instantiate(deps.as_mut(), mock_env(), info, _msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
 
     let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
     let config: OwnerResponse = from_binary(&res).unwrap();
@@ -259,6 +269,7 @@ fn test_get_twap_price() {
     };
     let info = mock_info("addr0000", &[]);
     instantiate(deps.as_mut(), env.clone(), info, _msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
 
     let res = query(deps.as_ref(), env.clone(), QueryMsg::GetOwner {}).unwrap();
     let config: OwnerResponse = from_binary(&res).unwrap();
@@ -332,6 +343,96 @@ fn test_get_twap_price() {
     assert_eq!(twap, Uint128::from(405_113_636u128));
 }
 
+#[test]
+fn test_get_twap_price_at() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
+
+    let start = env.block.time.seconds();
+    let msg = ExecuteMsg::AppendMultiplePrice {
+        key: "ETHUSD".to_string(),
+        prices: vec![
+            Uint128::from(400_000_000u128),
+            Uint128::from(405_000_000u128),
+            Uint128::from(410_000_000u128),
+        ],
+        timestamps: vec![start, start + 15, start + 30],
+    };
+
+    env.block.time = env.block.time.plus_seconds(45);
+
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // the window ends at the timestamp so the later round is not counted
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::GetTwapPriceAt {
+            key: "ETHUSD".to_string(),
+            interval: 30,
+            timestamp: start + 30,
+        },
+    )
+    .unwrap();
+
+    let twap: Uint128 = from_binary(&res).unwrap();
+    assert_eq!(twap, Uint128::from(402_500_000u128));
+
+    // the result does not change as time moves on
+    env.block.time = env.block.time.plus_seconds(1_000);
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::GetTwapPriceAt {
+            key: "ETHUSD".to_string(),
+            interval: 30,
+            timestamp: start + 30,
+        },
+    )
+    .unwrap();
+
+    let twap: Uint128 = from_binary(&res).unwrap();
+    assert_eq!(twap, Uint128::from(402_500_000u128));
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::GetTwapPriceAt {
+            key: "ETHUSD".to_string(),
+            interval: 30,
+            timestamp: env.block.time.seconds() + 1,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        res.to_string(),
+        "Generic error: Timestamp is in the future".to_string()
+    );
+
+    // there is no history before the first round
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::GetTwapPriceAt {
+            key: "ETHUSD".to_string(),
+            interval: 30,
+            timestamp: start - 1,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        res.to_string(),
+        "Generic error: Insufficient history".to_string()
+    );
+}
+
 #[test]
 fn test_get_twap_variant_price_period() {
     let mut deps = mock_dependencies();
@@ -341,6 +442,7 @@ fn test_get_twap_variant_price_period() {
     };
     let info = mock_info("addr0000", &[]);
     instantiate(deps.as_mut(), env.clone(), info, _msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
 
     let res = query(deps.as_ref(), env.clone(), QueryMsg::GetOwner {}).unwrap();
     let config: OwnerResponse = from_binary(&res).unwrap();
@@ -396,6 +498,7 @@ fn test_get_twap_latest_price_update_is_earlier_than_request() {
     };
     let info = mock_info("addr0000", &[]);
     instantiate(deps.as_mut(), env.clone(), info, _msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
 
     let res = query(deps.as_ref(), env.clone(), QueryMsg::GetOwner {}).unwrap();
     let config: OwnerResponse = from_binary(&res).unwrap();
@@ -449,6 +552,7 @@ fn test_get_twap_no_rounds() {
     };
     let info = mock_info("addr0000", &[]);
     instantiate(deps.as_mut(), env.clone(), info, _msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
 
     let res = query(deps.as_ref(), env.clone(), QueryMsg::GetOwner {}).unwrap();
     let config: OwnerResponse = from_binary(&res).unwrap();
@@ -476,6 +580,7 @@ fn test_get_twap_error_zero_interval() {
     };
     let info = mock_info("addr0000", &[]);
     instantiate(deps.as_mut(), env.clone(), info, _msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
 
     let res = query(deps.as_ref(), env.clone(), QueryMsg::GetOwner {}).unwrap();
     let config: OwnerResponse = from_binary(&res).unwrap();
@@ -0,0 +1,172 @@
+use super::register_feed;
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{from_binary, Env, MemoryStorage, OwnedDeps, Response, Uint128};
+use margined_perp::margined_pricefeed::{
+    ExecuteMsg, InstantiateMsg, PriceResponse, QueryMsg, ValidationResponse,
+};
+
+use crate::error::ContractError;
+
+fn setup(
+    heartbeat: u64,
+    confirmation_threshold_bps: u64,
+) -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        oracle_hub_contract: "oracle_hub0000".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    register_feed(deps.as_mut(), "ETHUSD", &[]);
+
+    let msg = ExecuteMsg::SetValidation {
+        key: "ETHUSD".to_string(),
+        heartbeat,
+        confirmation_threshold_bps,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps
+}
+
+fn append_price(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    price: u128,
+    timestamp: u64,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::AppendPrice {
+        key: "ETHUSD".to_string(),
+        price: Uint128::from(price),
+        timestamp,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg)
+}
+
+fn query_price(deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>, env: Env) -> PriceResponse {
+    let msg = QueryMsg::GetPrice {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), env, msg).unwrap();
+    from_binary(&res).unwrap()
+}
+
+#[test]
+fn test_force_error_invalid_prices() {
+    let mut deps = setup(0, 0);
+    let now = mock_env().block.time.seconds();
+
+    let err = append_price(&mut deps, 0, now).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Price can't be zero");
+
+    let err = append_price(&mut deps, 500_000_000, now + 1).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Timestamp is in the future");
+
+    append_price(&mut deps, 500_000_000, now - 10).unwrap();
+
+    // timestamps must increase with every round
+    let err = append_price(&mut deps, 500_000_000, now - 10).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Timestamp must be after the latest round"
+    );
+
+    let err = append_price(&mut deps, 500_000_000, now - 20).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Timestamp must be after the latest round"
+    );
+
+    append_price(&mut deps, 500_000_000, now - 5).unwrap();
+}
+
+#[test]
+fn test_price_is_stale_after_heartbeat() {
+    let mut deps = setup(60, 0);
+    let now = mock_env().block.time.seconds();
+
+    append_price(&mut deps, 500_000_000, now - 30).unwrap();
+
+    let price = query_price(&deps, mock_env());
+    assert_eq!(price.price, Uint128::from(500_000_000u128));
+    assert!(!price.is_stale);
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(31);
+    assert!(query_price(&deps, env).is_stale);
+
+    // without a heartbeat prices never go stale
+    let msg = ExecuteMsg::SetValidation {
+        key: "ETHUSD".to_string(),
+        heartbeat: 0,
+        confirmation_threshold_bps: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(86_400);
+    assert!(!query_price(&deps, env).is_stale);
+}
+
+#[test]
+fn test_large_move_needs_confirmation() {
+    // 10% moves need confirming
+    let mut deps = setup(0, 1_000);
+    let now = mock_env().block.time.seconds();
+
+    append_price(&mut deps, 500_000_000, now - 30).unwrap();
+
+    let res = append_price(&mut deps, 600_000_000, now - 20).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "requires_confirmation" && attr.value == "true"));
+
+    // the move is held rather than stored
+    let price = query_price(&deps, mock_env());
+    assert_eq!(price.round_id, Uint128::from(1u128));
+    assert_eq!(price.price, Uint128::from(500_000_000u128));
+
+    let msg = QueryMsg::GetValidation {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let validation: ValidationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        validation,
+        ValidationResponse {
+            heartbeat: 0,
+            confirmation_threshold_bps: 1_000,
+            unconfirmed_price: Some(Uint128::from(600_000_000u128)),
+        }
+    );
+
+    // a second price close to the held one confirms the move
+    let res = append_price(&mut deps, 610_000_000, now - 10).unwrap();
+    assert!(!res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "requires_confirmation"));
+
+    let price = query_price(&deps, mock_env());
+    assert_eq!(price.round_id, Uint128::from(2u128));
+    assert_eq!(price.price, Uint128::from(610_000_000u128));
+
+    // a move that isn't confirmed is dropped once prices come back
+    append_price(&mut deps, 900_000_000, now - 5).unwrap();
+    append_price(&mut deps, 620_000_000, now).unwrap();
+
+    let price = query_price(&deps, mock_env());
+    assert_eq!(price.round_id, Uint128::from(3u128));
+    assert_eq!(price.price, Uint128::from(620_000_000u128));
+
+    let msg = QueryMsg::GetValidation {
+        key: "ETHUSD".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let validation: ValidationResponse = from_binary(&res).unwrap();
+    assert_eq!(validation.unconfirmed_price, None);
+}
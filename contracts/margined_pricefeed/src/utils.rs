@@ -0,0 +1,102 @@
+use cosmwasm_std::{to_vec, Env, StdError, StdResult, Uint128};
+use margined_perp::margined_pricefeed::PricePayload;
+use sha2::{Digest, Sha256};
+
+use crate::state::Submission;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+const EMA_PRECISION: u128 = 1_000_000_000_000_000_000u128;
+
+/// Returns the median of the prices, the mean of the middle two for an even number of prices
+pub fn calc_median(mut prices: Vec<Uint128>) -> StdResult<Uint128> {
+    if prices.is_empty() {
+        return Err(StdError::generic_err("Cannot take the median of no prices"));
+    }
+
+    prices.sort();
+
+    let middle = prices.len() / 2;
+    if prices.len() % 2 == 1 {
+        return Ok(prices[middle]);
+    }
+
+    let sum = prices[middle - 1].checked_add(prices[middle])?;
+
+    Ok(sum.checked_div(Uint128::from(2u128))?)
+}
+
+/// Splits the submissions into those within the max deviation of their median and the outliers,
+/// a zero max deviation accepts every submission
+pub fn filter_outliers(
+    submissions: &[Submission],
+    max_deviation_bps: u64,
+) -> StdResult<(Vec<Submission>, Vec<Submission>)> {
+    if max_deviation_bps == 0 {
+        return Ok((submissions.to_vec(), vec![]));
+    }
+
+    let median = calc_median(submissions.iter().map(|s| s.price).collect())?;
+
+    Ok(submissions
+        .iter()
+        .cloned()
+        .partition(|s| !is_over_threshold(median, s.price, max_deviation_bps)))
+}
+
+/// Returns whether the price deviates from the reference by more than the threshold
+pub fn is_over_threshold(reference: Uint128, price: Uint128, threshold_bps: u64) -> bool {
+    let deviation = if price > reference {
+        price - reference
+    } else {
+        reference - price
+    };
+
+    deviation > reference.multiply_ratio(threshold_bps, BPS_DENOMINATOR)
+}
+
+/// Moves the EMA towards the price over the elapsed time, halving the distance every half-life,
+/// the decay within a half-life is interpolated linearly and a zero half-life tracks the price
+pub fn calc_ema(ema: Uint128, price: Uint128, elapsed: u64, half_life: u64) -> StdResult<Uint128> {
+    if elapsed == 0 {
+        return Ok(ema);
+    }
+
+    if half_life == 0 || elapsed / half_life >= 128 {
+        return Ok(price);
+    }
+
+    let halvings = elapsed / half_life;
+    let remainder = elapsed % half_life;
+
+    // the weight that the EMA keeps, out of EMA_PRECISION
+    let weight = Uint128::from(EMA_PRECISION >> halvings)
+        .multiply_ratio(2 * half_life - remainder, 2 * half_life);
+
+    let precision = Uint128::from(EMA_PRECISION);
+    let kept = ema
+        .checked_multiply_ratio(weight, precision)
+        .map_err(|_| StdError::generic_err("Unable to calculate EMA"))?;
+    let added = price
+        .checked_multiply_ratio(precision - weight, precision)
+        .map_err(|_| StdError::generic_err("Unable to calculate EMA"))?;
+
+    Ok(kept.checked_add(added)?)
+}
+
+/// Returns the sha256 hash of the JSON encoded price payload, which is what signers sign
+pub fn price_payload_hash(
+    env: &Env,
+    key: &str,
+    price: Uint128,
+    timestamp: u64,
+) -> StdResult<Vec<u8>> {
+    let payload = to_vec(&PricePayload {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        key: key.to_string(),
+        price,
+        timestamp,
+    })?;
+
+    Ok(Sha256::digest(&payload).to_vec())
+}
@@ -1,7 +1,8 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Uint128,
 };
 use cw2::set_contract_version;
 use cw_controllers::Admin;
@@ -9,17 +10,24 @@ use margined_common::{
     integer::Integer,
     validate::{validate_assets, validate_decimal_places, validate_non_fraction, validate_ratio},
 };
-use margined_perp::margined_vamm::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use margined_perp::margined_vamm::{
+    CurveType, ExecuteMsg, FundingMode, InstantiateMsg, QueryMsg, TradingSchedule,
+};
 
+use crate::curve::CurveConfig;
 use crate::error::ContractError;
 use crate::querier::{query_underlying_price, query_underlying_twap_price};
 use crate::{
-    handle::{set_open, settle_funding, swap_input, swap_output, update_config, update_owner},
+    handle::{
+        set_open, settle_expiry, settle_funding, swap_input, swap_output, update_config,
+        update_owner,
+    },
     query::{
-        query_calc_fee, query_config, query_input_amount, query_input_price, query_input_twap,
-        query_is_over_fluctuation_limit, query_is_over_spread_limit, query_output_amount,
-        query_output_price, query_output_twap, query_owner, query_spot_price, query_state,
-        query_twap_price,
+        query_accrued_premium_fraction, query_amount_to_price, query_calc_fee, query_config,
+        query_input_amount, query_input_price, query_input_twap, query_is_over_fluctuation_limit,
+        query_is_over_spread_limit, query_mark_price, query_market_status, query_oracle_status,
+        query_output_amount, query_output_price, query_output_twap, query_owner, query_quote,
+        query_spot_price, query_state, query_twap_price,
     },
     state::{store_config, store_reserve_snapshot, store_state, Config, ReserveSnapshot, State},
 };
@@ -55,6 +63,15 @@ pub fn instantiate(
     validate_assets(msg.base_asset.clone())?;
     validate_assets(msg.quote_asset.clone())?;
 
+    // dated futures must expire in the future
+    if let Some(expiry) = msg.expiry {
+        if expiry <= env.block.time.seconds() {
+            return Err(ContractError::Std(StdError::generic_err(
+                "expiry must be in the future",
+            )));
+        }
+    }
+
     let mut config = Config {
         margin_engine: Addr::unchecked("".to_string()), // default to nothing, must be set
         insurance_fund: Addr::unchecked("".to_string()), // default to nothing, must be set like the engine
@@ -64,12 +81,28 @@ pub fn instantiate(
         open_interest_notional_cap: Uint128::zero(),
         toll_ratio: msg.toll_ratio,
         spread_ratio: msg.spread_ratio,
+        skew_fee_ratio: Uint128::zero(), // default to no skew adjustment of the spread fee
         fluctuation_limit_ratio: msg.fluctuation_limit_ratio,
+        max_oracle_spread_ratio: decimals / Uint128::from(10u128), // 0.1 i.e. 10%
+        max_oracle_deviation_ratio: decimals / Uint128::from(10u128), // 0.1 i.e. 10%
+        oracle_heartbeat: ONE_DAY_IN_SECONDS,
         pricefeed: deps.api.addr_validate(&msg.pricefeed).unwrap(),
         decimals,
         spot_price_twap_interval: ONE_HOUR_IN_SECONDS,
         funding_period: msg.funding_period,
         funding_buffer_period: msg.funding_period / 2u64,
+        funding_mode: FundingMode::Periodic,
+        expiry: msg.expiry,
+        expiry_cutoff_period: ONE_HOUR_IN_SECONDS,
+        trading_schedule: TradingSchedule::default(),
+        curve: CurveConfig::new(
+            msg.curve.unwrap_or(CurveType::ConstantProduct),
+            decimals,
+            msg.quote_asset_reserve,
+            msg.base_asset_reserve,
+        )?,
+        circuit_breaker_window: 0u64, // default to no circuit breaker
+        circuit_breaker_ratio: Uint128::zero(),
     };
 
     // set and update margin engine
@@ -97,6 +130,8 @@ pub fn instantiate(
         total_position_size: Integer::zero(),
         funding_rate: Integer::zero(),
         next_funding_time: 0u64,
+        last_funding_time: 0u64,
+        settlement_price: None,
     };
 
     store_state(deps.storage, &state)?;
@@ -128,8 +163,18 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             insurance_fund,
             pricefeed,
             spot_price_twap_interval,
+            funding_mode,
+            max_oracle_spread_ratio,
+            skew_fee_ratio,
+            oracle_heartbeat,
+            max_oracle_deviation_ratio,
+            expiry_cutoff_period,
+            trading_schedule,
+            circuit_breaker_window,
+            circuit_breaker_ratio,
         } => update_config(
             deps,
+            env,
             info,
             base_asset_holding_cap,
             open_interest_notional_cap,
@@ -140,6 +185,15 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             insurance_fund,
             pricefeed,
             spot_price_twap_interval,
+            funding_mode,
+            max_oracle_spread_ratio,
+            skew_fee_ratio,
+            oracle_heartbeat,
+            max_oracle_deviation_ratio,
+            expiry_cutoff_period,
+            trading_schedule,
+            circuit_breaker_window,
+            circuit_breaker_ratio,
         ),
         ExecuteMsg::UpdateOwner { owner } => update_owner(deps, info, owner),
         ExecuteMsg::SwapInput {
@@ -169,6 +223,7 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             quote_asset_limit,
         ),
         ExecuteMsg::SettleFunding {} => settle_funding(deps, env, info),
+        ExecuteMsg::SettleExpiry {} => settle_expiry(deps, env),
         ExecuteMsg::SetOpen { open } => set_open(deps, env, info, open),
     }
 }
@@ -201,12 +256,33 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::UnderlyingTwapPrice { interval } => {
             to_binary(&query_underlying_twap_price(&deps, interval)?)
         }
-        QueryMsg::CalcFee { quote_asset_amount } => {
-            to_binary(&query_calc_fee(deps, quote_asset_amount)?)
-        }
+        QueryMsg::CalcFee {
+            quote_asset_amount,
+            direction,
+        } => to_binary(&query_calc_fee(deps, env, quote_asset_amount, direction)?),
         QueryMsg::SpotPrice {} => to_binary(&query_spot_price(deps)?),
         QueryMsg::TwapPrice { interval } => to_binary(&query_twap_price(deps, env, interval)?),
-        QueryMsg::IsOverSpreadLimit {} => to_binary(&query_is_over_spread_limit(deps)?),
+        QueryMsg::MarkPrice {} => to_binary(&query_mark_price(deps, env)?),
+        QueryMsg::IsOverSpreadLimit {} => to_binary(&query_is_over_spread_limit(deps, env)?),
+        QueryMsg::OracleStatus {} => to_binary(&query_oracle_status(deps, env)?),
+        QueryMsg::MarketStatus {} => to_binary(&query_market_status(deps, env)?),
+        QueryMsg::Quote {
+            direction,
+            quote_amount,
+            base_amount,
+        } => to_binary(&query_quote(
+            deps,
+            env,
+            direction,
+            quote_amount,
+            base_amount,
+        )?),
+        QueryMsg::AmountToPrice { target_price } => {
+            to_binary(&query_amount_to_price(deps, target_price)?)
+        }
+        QueryMsg::AccruedPremiumFraction {} => {
+            to_binary(&query_accrued_premium_fraction(deps, env)?)
+        }
         QueryMsg::IsOverFluctuationLimit {
             direction,
             base_asset_amount,
@@ -0,0 +1,350 @@
+use cosmwasm_std::{Isqrt, StdError, StdResult, Uint128, Uint256};
+use margined_perp::margined_vamm::{CurveType, Direction};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+use crate::{state::Config, utils::modulo};
+
+/// The pricing math of the vAMM, all amounts, reserves and prices use the decimals of the vAMM
+pub trait Curve {
+    /// Returns the base asset amount swapped for a quote asset amount
+    fn input_price(
+        &self,
+        direction: &Direction,
+        quote_asset_amount: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Uint128>;
+
+    /// Returns the quote asset amount swapped for a base asset amount
+    fn output_price(
+        &self,
+        direction: &Direction,
+        base_asset_amount: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Uint128>;
+
+    /// Returns the spot price of the reserves
+    fn spot_price(
+        &self,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Uint128>;
+
+    /// Returns the quote and base asset reserves at which the spot price is the target price
+    fn reserves_at_price(
+        &self,
+        target_price: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<(Uint128, Uint128)>;
+}
+
+/// The curve stored in the config, the concentrated curve keeps the virtual liquidity that is
+/// added to the reserves when pricing
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveConfig {
+    ConstantProduct,
+    Concentrated {
+        concentration: Uint128,
+        quote_offset: Uint128,
+        base_offset: Uint128,
+    },
+}
+
+impl CurveConfig {
+    pub fn new(
+        curve_type: CurveType,
+        decimals: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Self> {
+        match curve_type {
+            CurveType::ConstantProduct => Ok(CurveConfig::ConstantProduct),
+            CurveType::Concentrated { concentration } => {
+                if concentration < decimals {
+                    return Err(StdError::generic_err("concentration must be at least one"));
+                }
+
+                // the virtual liquidity is the part of the deeper reserves that can't be traded
+                let extra = concentration - decimals;
+
+                Ok(CurveConfig::Concentrated {
+                    concentration,
+                    quote_offset: quote_asset_reserve
+                        .checked_mul(extra)?
+                        .checked_div(decimals)?,
+                    base_offset: base_asset_reserve
+                        .checked_mul(extra)?
+                        .checked_div(decimals)?,
+                })
+            }
+        }
+    }
+
+    pub fn curve_type(&self) -> CurveType {
+        match self {
+            CurveConfig::ConstantProduct => CurveType::ConstantProduct,
+            CurveConfig::Concentrated { concentration, .. } => CurveType::Concentrated {
+                concentration: *concentration,
+            },
+        }
+    }
+}
+
+/// Returns the curve of the vAMM
+pub fn get_curve(config: &Config) -> Box<dyn Curve> {
+    match config.curve {
+        CurveConfig::ConstantProduct => Box::new(ConstantProduct {
+            decimals: config.decimals,
+        }),
+        CurveConfig::Concentrated {
+            quote_offset,
+            base_offset,
+            ..
+        } => Box::new(Concentrated {
+            decimals: config.decimals,
+            quote_offset,
+            base_offset,
+        }),
+    }
+}
+
+/// The x * y = k curve
+pub struct ConstantProduct {
+    pub decimals: Uint128,
+}
+
+impl Curve for ConstantProduct {
+    fn input_price(
+        &self,
+        direction: &Direction,
+        quote_asset_amount: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Uint128> {
+        if quote_asset_amount == Uint128::zero() {
+            return Ok(Uint128::zero());
+        }
+
+        // k = x * y (divided by decimal places)
+        let invariant_k = quote_asset_reserve
+            .checked_mul(base_asset_reserve)?
+            .checked_div(self.decimals)?;
+
+        let quote_asset_after: Uint128 = match direction {
+            Direction::AddToAmm => quote_asset_reserve.checked_add(quote_asset_amount)?,
+            Direction::RemoveFromAmm => quote_asset_reserve.checked_sub(quote_asset_amount)?,
+        };
+
+        let base_asset_after: Uint128 = invariant_k
+            .checked_mul(self.decimals)?
+            .checked_div(quote_asset_after)?;
+
+        let mut base_asset_bought = if base_asset_after > base_asset_reserve {
+            base_asset_after - base_asset_reserve
+        } else {
+            base_asset_reserve - base_asset_after
+        };
+
+        let remainder = modulo(invariant_k, quote_asset_after, self.decimals);
+        if remainder != Uint128::zero() {
+            if *direction == Direction::AddToAmm {
+                base_asset_bought = base_asset_bought.checked_sub(Uint128::new(1u128))?;
+            } else {
+                base_asset_bought = base_asset_bought.checked_add(Uint128::from(1u128))?;
+            }
+        }
+
+        Ok(base_asset_bought)
+    }
+
+    fn output_price(
+        &self,
+        direction: &Direction,
+        base_asset_amount: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Uint128> {
+        if base_asset_amount == Uint128::zero() {
+            return Ok(Uint128::zero());
+        }
+
+        let invariant_k = quote_asset_reserve
+            .checked_mul(base_asset_reserve)?
+            .checked_div(self.decimals)?;
+
+        let base_asset_after: Uint128 = match direction {
+            Direction::AddToAmm => base_asset_reserve.checked_add(base_asset_amount)?,
+            Direction::RemoveFromAmm => base_asset_reserve.checked_sub(base_asset_amount)?,
+        };
+
+        let quote_asset_after: Uint128 = invariant_k
+            .checked_mul(self.decimals)?
+            .checked_div(base_asset_after)?;
+
+        let mut quote_asset_sold = if quote_asset_after > quote_asset_reserve {
+            quote_asset_after - quote_asset_reserve
+        } else {
+            quote_asset_reserve - quote_asset_after
+        };
+
+        let remainder = modulo(invariant_k, base_asset_after, self.decimals);
+        if remainder != Uint128::zero() {
+            if *direction == Direction::AddToAmm {
+                quote_asset_sold = quote_asset_sold.checked_sub(Uint128::from(1u128))?;
+            } else {
+                quote_asset_sold = quote_asset_sold.checked_add(Uint128::new(1u128))?;
+            }
+        }
+        Ok(quote_asset_sold)
+    }
+
+    fn spot_price(
+        &self,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Uint128> {
+        Ok(quote_asset_reserve
+            .checked_mul(self.decimals)?
+            .checked_div(base_asset_reserve)?)
+    }
+
+    fn reserves_at_price(
+        &self,
+        target_price: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<(Uint128, Uint128)> {
+        // the reserves keep x * y = k, with y / x moved to the target price
+        let invariant_k =
+            Uint256::from(quote_asset_reserve).checked_mul(base_asset_reserve.into())?;
+        let decimals = Uint256::from(self.decimals);
+        let target = Uint256::from(target_price);
+
+        let quote_asset_target = Uint128::try_from(
+            invariant_k
+                .checked_mul(target)?
+                .checked_div(decimals)?
+                .isqrt(),
+        )?;
+        let base_asset_target = Uint128::try_from(
+            invariant_k
+                .checked_mul(decimals)?
+                .checked_div(target)?
+                .isqrt(),
+        )?;
+
+        Ok((quote_asset_target, base_asset_target))
+    }
+}
+
+/// A constant product over the reserves plus a fixed amount of virtual liquidity, the real
+/// reserves can't be depleted so swaps that leave the price range of the curve are rejected
+pub struct Concentrated {
+    pub decimals: Uint128,
+    pub quote_offset: Uint128,
+    pub base_offset: Uint128,
+}
+
+impl Concentrated {
+    fn virtual_curve(&self) -> ConstantProduct {
+        ConstantProduct {
+            decimals: self.decimals,
+        }
+    }
+}
+
+impl Curve for Concentrated {
+    fn input_price(
+        &self,
+        direction: &Direction,
+        quote_asset_amount: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Uint128> {
+        let base_asset_amount = self.virtual_curve().input_price(
+            direction,
+            quote_asset_amount,
+            quote_asset_reserve.checked_add(self.quote_offset)?,
+            base_asset_reserve.checked_add(self.base_offset)?,
+        )?;
+
+        let is_out_of_range = match direction {
+            Direction::AddToAmm => base_asset_amount >= base_asset_reserve,
+            Direction::RemoveFromAmm => quote_asset_amount >= quote_asset_reserve,
+        };
+        if is_out_of_range {
+            return Err(StdError::generic_err(
+                "swap is outside the range of the curve",
+            ));
+        }
+
+        Ok(base_asset_amount)
+    }
+
+    fn output_price(
+        &self,
+        direction: &Direction,
+        base_asset_amount: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Uint128> {
+        let quote_asset_amount = self.virtual_curve().output_price(
+            direction,
+            base_asset_amount,
+            quote_asset_reserve.checked_add(self.quote_offset)?,
+            base_asset_reserve.checked_add(self.base_offset)?,
+        )?;
+
+        let is_out_of_range = match direction {
+            Direction::AddToAmm => quote_asset_amount >= quote_asset_reserve,
+            Direction::RemoveFromAmm => base_asset_amount >= base_asset_reserve,
+        };
+        if is_out_of_range {
+            return Err(StdError::generic_err(
+                "swap is outside the range of the curve",
+            ));
+        }
+
+        Ok(quote_asset_amount)
+    }
+
+    fn spot_price(
+        &self,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<Uint128> {
+        self.virtual_curve().spot_price(
+            quote_asset_reserve.checked_add(self.quote_offset)?,
+            base_asset_reserve.checked_add(self.base_offset)?,
+        )
+    }
+
+    fn reserves_at_price(
+        &self,
+        target_price: Uint128,
+        quote_asset_reserve: Uint128,
+        base_asset_reserve: Uint128,
+    ) -> StdResult<(Uint128, Uint128)> {
+        let (quote_asset_target, base_asset_target) = self.virtual_curve().reserves_at_price(
+            target_price,
+            quote_asset_reserve.checked_add(self.quote_offset)?,
+            base_asset_reserve.checked_add(self.base_offset)?,
+        )?;
+
+        if quote_asset_target <= self.quote_offset || base_asset_target <= self.base_offset {
+            return Err(StdError::generic_err(
+                "target price is outside the range of the curve",
+            ));
+        }
+
+        Ok((
+            quote_asset_target - self.quote_offset,
+            base_asset_target - self.base_offset,
+        ))
+    }
+}
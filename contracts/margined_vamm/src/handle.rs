@@ -1,26 +1,31 @@
 use cosmwasm_std::{
-    Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128,
+    to_binary, Deps, DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult, Storage,
+    Uint128,
 };
 
 use margined_common::{integer::Integer, validate::validate_ratio};
-use margined_perp::margined_vamm::Direction;
+use margined_perp::margined_vamm::{
+    Direction, FundingMode, FundingResult, SwapResult, SwapType, TradingSchedule,
+};
 
 use crate::{
-    contract::{
-        ONE_DAY_IN_SECONDS, ONE_HOUR_IN_SECONDS, ONE_MINUTE_IN_SECONDS, ONE_WEEK_IN_SECONDS, OWNER,
-    },
-    querier::query_underlying_twap_price,
+    contract::{ONE_HOUR_IN_SECONDS, ONE_MINUTE_IN_SECONDS, ONE_WEEK_IN_SECONDS, OWNER},
+    curve::get_curve,
+    querier::{query_feed_info, query_underlying_twap_price, query_underlying_twap_price_at},
     query::query_twap_price,
     state::{read_config, read_state, store_config, store_state, Config, State},
     utils::{
-        add_reserve_snapshot, check_is_over_block_fluctuation_limit, modulo, require_margin_engine,
-        require_open,
+        add_reserve_snapshot, calc_circuit_breaker_reference_price, calc_premium_fraction,
+        calc_session_time, check_is_over_block_fluctuation_limit, is_circuit_breaker_tripped,
+        is_in_session, is_oracle_tripped, require_in_session, require_margin_engine,
+        require_not_expired, require_open, validate_trading_schedule,
     },
 };
 
 #[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     base_asset_holding_cap: Option<Uint128>,
     open_interest_notional_cap: Option<Uint128>,
@@ -31,6 +36,15 @@ pub fn update_config(
     insurance_fund: Option<String>,
     pricefeed: Option<String>,
     spot_price_twap_interval: Option<u64>,
+    funding_mode: Option<FundingMode>,
+    max_oracle_spread_ratio: Option<Uint128>,
+    skew_fee_ratio: Option<Uint128>,
+    oracle_heartbeat: Option<u64>,
+    max_oracle_deviation_ratio: Option<Uint128>,
+    expiry_cutoff_period: Option<u64>,
+    trading_schedule: Option<TradingSchedule>,
+    circuit_breaker_window: Option<u64>,
+    circuit_breaker_ratio: Option<Uint128>,
 ) -> StdResult<Response> {
     let mut config: Config = read_config(deps.storage)?;
 
@@ -70,15 +84,74 @@ pub fn update_config(
         config.spread_ratio = spread_ratio;
     }
 
+    // change skew fee ratio
+    if let Some(skew_fee_ratio) = skew_fee_ratio {
+        validate_ratio(skew_fee_ratio, config.decimals)?;
+        config.skew_fee_ratio = skew_fee_ratio;
+    }
+
     // change fluctuation limit ratio
     if let Some(fluctuation_limit_ratio) = fluctuation_limit_ratio {
         validate_ratio(fluctuation_limit_ratio, config.decimals)?;
         config.fluctuation_limit_ratio = fluctuation_limit_ratio;
     }
 
-    // change pricefeed
+    // change max oracle spread ratio
+    if let Some(max_oracle_spread_ratio) = max_oracle_spread_ratio {
+        validate_ratio(max_oracle_spread_ratio, config.decimals)?;
+        config.max_oracle_spread_ratio = max_oracle_spread_ratio;
+    }
+
+    // change max oracle deviation ratio
+    if let Some(max_oracle_deviation_ratio) = max_oracle_deviation_ratio {
+        validate_ratio(max_oracle_deviation_ratio, config.decimals)?;
+        config.max_oracle_deviation_ratio = max_oracle_deviation_ratio;
+    }
+
+    // change oracle heartbeat
+    if let Some(oracle_heartbeat) = oracle_heartbeat {
+        config.oracle_heartbeat = oracle_heartbeat;
+    }
+
+    // change the period before expiry in which new positions cannot be opened
+    if let Some(expiry_cutoff_period) = expiry_cutoff_period {
+        config.expiry_cutoff_period = expiry_cutoff_period;
+    }
+
+    // change trading schedule
+    if let Some(trading_schedule) = trading_schedule {
+        validate_trading_schedule(&trading_schedule)?;
+        config.trading_schedule = trading_schedule;
+    }
+
+    // change the rolling window of the circuit breaker, zero disables it
+    if let Some(circuit_breaker_window) = circuit_breaker_window {
+        config.circuit_breaker_window = circuit_breaker_window;
+    }
+
+    // change the max price move within the window before the circuit breaker trips
+    if let Some(circuit_breaker_ratio) = circuit_breaker_ratio {
+        validate_ratio(circuit_breaker_ratio, config.decimals)?;
+        config.circuit_breaker_ratio = circuit_breaker_ratio;
+    }
+
+    // change pricefeed, which must have a feed registered for the base asset with our decimals
+    // and a feed registered for the quote asset
     if let Some(pricefeed) = pricefeed {
-        config.pricefeed = deps.api.addr_validate(&pricefeed).unwrap();
+        config.pricefeed = deps.api.addr_validate(&pricefeed)?;
+
+        let feed = query_feed_info(&deps.as_ref(), &config.pricefeed, config.base_asset.clone())?;
+        if 10u128.checked_pow(feed.decimals as u32) != Some(config.decimals.u128()) {
+            return Err(StdError::generic_err(
+                "Pricefeed decimals do not match the vAMM",
+            ));
+        }
+
+        query_feed_info(
+            &deps.as_ref(),
+            &config.pricefeed,
+            config.quote_asset.clone(),
+        )?;
     }
 
     // change spot price twap interval - check that the twap interval is between 1 min and 1 week
@@ -91,6 +164,29 @@ pub fn update_config(
         config.spot_price_twap_interval = spot_price_twap_interval;
     }
 
+    // change funding mode, continuous funding has to be settled first or what has accrued
+    // would be lost, periodic funding accrues continuously from the last settlement
+    if let Some(funding_mode) = funding_mode {
+        let state: State = read_state(deps.storage)?;
+
+        if config.funding_mode == FundingMode::Continuous
+            && funding_mode != FundingMode::Continuous
+            && config.expiry.is_none()
+            && state.open
+            && calc_session_time(
+                &config.trading_schedule,
+                state.last_funding_time,
+                env.block.time.seconds(),
+            ) > 0
+        {
+            return Err(StdError::generic_err(
+                "funding must be settled before changing the funding mode",
+            ));
+        }
+
+        config.funding_mode = funding_mode;
+    }
+
     store_config(deps.storage, &config)?;
 
     Ok(Response::default().add_attribute("action", "update_config"))
@@ -116,12 +212,19 @@ pub fn set_open(deps: DepsMut, env: Env, info: MessageInfo, open: bool) -> StdRe
         return Err(StdError::generic_err("unauthorized"));
     }
 
+    // a dated vamm cannot be reopened once it has expired
+    if open {
+        require_not_expired(config.expiry, &env)?;
+    }
+
     state.open = open;
 
-    // if state.open is true then we update the next funding time
+    // if state.open is true then we update the next funding time, funding does not
+    // accrue for the period that the vamm was closed
     if state.open {
         state.next_funding_time = env.block.time.seconds()
             + config.funding_period / ONE_HOUR_IN_SECONDS * ONE_HOUR_IN_SECONDS;
+        state.last_funding_time = env.block.time.seconds();
     }
 
     store_state(deps.storage, &state)?;
@@ -143,6 +246,8 @@ pub fn swap_input(
     let config: Config = read_config(deps.storage)?;
 
     require_open(state.open)?;
+    require_not_expired(config.expiry, &env)?;
+    require_in_session(&config.trading_schedule, &env)?;
     require_margin_engine(info.sender, config.margin_engine)?;
 
     let base_asset_amount: Uint128 = if !quote_asset_amount.is_zero() {
@@ -184,7 +289,14 @@ pub fn swap_input(
         can_go_over_fluctuation,
     )?;
 
-    Ok(response.add_attributes(vec![
+    let result = SwapResult {
+        swap_type: SwapType::Input,
+        direction: direction.clone(),
+        quote_asset_amount,
+        base_asset_amount,
+    };
+
+    Ok(response.set_data(to_binary(&result)?).add_attributes(vec![
         ("action", "swap"),
         ("type", "input"),
         ("direction", &direction.to_string()),
@@ -206,6 +318,8 @@ pub fn swap_output(
     let config: Config = read_config(deps.storage)?;
 
     require_open(state.open)?;
+    require_not_expired(config.expiry, &env)?;
+    require_in_session(&config.trading_schedule, &env)?;
     require_margin_engine(info.sender, config.margin_engine)?;
 
     // flip direction when updating reserve
@@ -256,7 +370,14 @@ pub fn swap_output(
         true,
     )?;
 
-    Ok(response.add_attributes(vec![
+    let result = SwapResult {
+        swap_type: SwapType::Output,
+        direction: direction.clone(),
+        quote_asset_amount,
+        base_asset_amount,
+    };
+
+    Ok(response.set_data(to_binary(&result)?).add_attributes(vec![
         ("action", "swap"),
         ("type", "output"),
         ("direction", &direction.to_string()),
@@ -272,29 +393,70 @@ pub fn settle_funding(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<R
     require_open(state.open)?;
     require_margin_engine(info.sender, config.margin_engine)?;
 
-    if env.block.time.seconds() < state.next_funding_time {
-        return Err(StdError::generic_err("settle funding called too early"));
+    // dated futures converge through settlement rather than funding
+    if config.expiry.is_some() {
+        return Err(StdError::generic_err(
+            "funding is disabled for dated futures",
+        ));
     }
 
-    // twap price from oracle
-    let underlying_price: Uint128 =
-        query_underlying_twap_price(&deps.as_ref(), config.spot_price_twap_interval)?;
+    let now = env.block.time.seconds();
 
-    // twap price from here, i.e. the amm
-    let index_price: Uint128 =
-        query_twap_price(deps.as_ref(), env.clone(), config.spot_price_twap_interval)?;
+    // periodic funding settles a full funding period once the next funding time has passed,
+    // continuous funding settles whatever has accrued since it was last settled
+    let funding_period = match config.funding_mode {
+        FundingMode::Periodic => {
+            if now < state.next_funding_time {
+                return Err(StdError::generic_err("settle funding called too early"));
+            }
+            config.funding_period
+        }
+        FundingMode::Continuous => {
+            // funding only accrues for the time the market was in session
+            calc_session_time(&config.trading_schedule, state.last_funding_time, now)
+        }
+    };
 
-    let premium =
-        Integer::new_positive(index_price).checked_sub(Integer::new_positive(underlying_price))?;
+    // funding cannot be settled against an untrusted oracle, the period is left to be
+    // settled once the oracle recovers
+    if is_oracle_tripped(deps.as_ref(), env.clone())? {
+        return Err(StdError::generic_err("oracle is stale or deviated"));
+    }
 
-    let premium_fraction = premium
-        .checked_mul(Integer::new_positive(config.funding_period))?
-        .checked_div(Integer::new_positive(ONE_DAY_IN_SECONDS))?;
+    let mut response = Response::new();
+
+    let in_session = is_in_session(&config.trading_schedule, now);
+    if !in_session {
+        response = response
+            .add_event(Event::new("market_status").add_attribute("is_open", false.to_string()));
+    }
+
+    // no periodic funding is paid if the market is closed, continuous funding is still paid
+    // for the sessions since it was last settled
+    let no_funding = match config.funding_mode {
+        FundingMode::Periodic => !in_session,
+        FundingMode::Continuous => funding_period == 0,
+    };
+
+    let (premium_fraction, underlying_price, index_price) = if no_funding {
+        let underlying_price =
+            query_underlying_twap_price(&deps.as_ref(), config.spot_price_twap_interval)?;
+        let index_price =
+            query_twap_price(deps.as_ref(), env.clone(), config.spot_price_twap_interval)?;
+
+        (Integer::zero(), underlying_price, index_price)
+    } else {
+        calc_premium_fraction(deps.as_ref(), env.clone(), funding_period)?
+    };
 
     // update funding rate = premiumFraction / twapIndexPrice
-    state.funding_rate = premium_fraction
-        .checked_mul(Integer::new_positive(config.decimals))?
-        .checked_div(Integer::new_positive(underlying_price))?;
+    state.funding_rate = if underlying_price.is_zero() {
+        Integer::zero()
+    } else {
+        premium_fraction
+            .checked_mul(Integer::new_positive(config.decimals))?
+            .checked_div(Integer::new_positive(underlying_price))?
+    };
 
     // in order to prevent multiple funding settlement during very short time after network congestion
     let min_next_funding_time = env.block.time.plus_seconds(config.funding_buffer_period);
@@ -310,10 +472,18 @@ pub fn settle_funding(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<R
     } else {
         min_next_funding_time.seconds()
     };
+    state.last_funding_time = now;
 
     store_state(deps.storage, &state)?;
 
-    Ok(Response::new().add_attributes(vec![
+    let result = FundingResult {
+        vamm: env.contract.address,
+        premium_fraction,
+        underlying_price,
+        index_price,
+    };
+
+    Ok(response.set_data(to_binary(&result)?).add_attributes(vec![
         ("action", "settle_funding"),
         ("premium_fraction", &premium_fraction.to_string()),
         ("underlying_price", &underlying_price.to_string()),
@@ -321,49 +491,59 @@ pub fn settle_funding(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<R
     ]))
 }
 
-pub fn get_input_price_with_reserves(
-    deps: Deps,
-    direction: &Direction,
-    quote_asset_amount: Uint128,
-    quote_asset_reserve: Uint128,
-    base_asset_reserve: Uint128,
-) -> StdResult<Uint128> {
+// Can be called by anyone once a dated vamm has expired
+pub fn settle_expiry(deps: DepsMut, env: Env) -> StdResult<Response> {
     let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
 
-    if quote_asset_amount == Uint128::zero() {
-        return Ok(Uint128::zero());
+    let expiry = match config.expiry {
+        Some(expiry) => expiry,
+        None => return Err(StdError::generic_err("amm is not a dated future")),
+    };
+
+    if env.block.time.seconds() < expiry {
+        return Err(StdError::generic_err("amm has not expired"));
     }
 
-    // k = x * y (divided by decimal places)
-    let invariant_k = quote_asset_reserve
-        .checked_mul(base_asset_reserve)?
-        .checked_div(config.decimals)?;
+    if state.settlement_price.is_some() {
+        return Err(StdError::generic_err("amm has already been settled"));
+    }
 
-    let quote_asset_after: Uint128 = match direction {
-        Direction::AddToAmm => quote_asset_reserve.checked_add(quote_asset_amount)?,
-        Direction::RemoveFromAmm => quote_asset_reserve.checked_sub(quote_asset_amount)?,
-    };
+    // positions are settled against the twap of the underlying leading up to expiry, the
+    // window ends at expiry so it doesn't matter when settlement is called
+    let settlement_price =
+        query_underlying_twap_price_at(&deps.as_ref(), config.spot_price_twap_interval, expiry)?;
+    if settlement_price.is_zero() {
+        return Err(StdError::generic_err("settlement price is zero"));
+    }
 
-    let base_asset_after: Uint128 = invariant_k
-        .checked_mul(config.decimals)?
-        .checked_div(quote_asset_after)?;
+    state.settlement_price = Some(settlement_price);
+    state.open = false;
 
-    let mut base_asset_bought = if base_asset_after > base_asset_reserve {
-        base_asset_after - base_asset_reserve
-    } else {
-        base_asset_reserve - base_asset_after
-    };
+    store_state(deps.storage, &state)?;
 
-    let remainder = modulo(invariant_k, quote_asset_after, config.decimals);
-    if remainder != Uint128::zero() {
-        if *direction == Direction::AddToAmm {
-            base_asset_bought = base_asset_bought.checked_sub(Uint128::new(1u128))?;
-        } else {
-            base_asset_bought = base_asset_bought.checked_add(Uint128::from(1u128))?;
-        }
-    }
+    Ok(Response::new().add_attributes(vec![
+        ("action", "settle_expiry"),
+        ("settlement_price", &settlement_price.to_string()),
+        ("expiry", &expiry.to_string()),
+    ]))
+}
 
-    Ok(base_asset_bought)
+pub fn get_input_price_with_reserves(
+    deps: Deps,
+    direction: &Direction,
+    quote_asset_amount: Uint128,
+    quote_asset_reserve: Uint128,
+    base_asset_reserve: Uint128,
+) -> StdResult<Uint128> {
+    let config: Config = read_config(deps.storage)?;
+
+    get_curve(&config).input_price(
+        direction,
+        quote_asset_amount,
+        quote_asset_reserve,
+        base_asset_reserve,
+    )
 }
 
 pub fn get_output_price_with_reserves(
@@ -375,38 +555,12 @@ pub fn get_output_price_with_reserves(
 ) -> StdResult<Uint128> {
     let config: Config = read_config(deps.storage)?;
 
-    if base_asset_amount == Uint128::zero() {
-        return Ok(Uint128::zero());
-    }
-
-    let invariant_k = quote_asset_reserve
-        .checked_mul(base_asset_reserve)?
-        .checked_div(config.decimals)?;
-
-    let base_asset_after: Uint128 = match direction {
-        Direction::AddToAmm => base_asset_reserve.checked_add(base_asset_amount)?,
-        Direction::RemoveFromAmm => base_asset_reserve.checked_sub(base_asset_amount)?,
-    };
-
-    let quote_asset_after: Uint128 = invariant_k
-        .checked_mul(config.decimals)?
-        .checked_div(base_asset_after)?;
-
-    let mut quote_asset_sold = if quote_asset_after > quote_asset_reserve {
-        quote_asset_after - quote_asset_reserve
-    } else {
-        quote_asset_reserve - quote_asset_after
-    };
-
-    let remainder = modulo(invariant_k, base_asset_after, config.decimals);
-    if remainder != Uint128::zero() {
-        if *direction == Direction::AddToAmm {
-            quote_asset_sold = quote_asset_sold.checked_sub(Uint128::from(1u128))?;
-        } else {
-            quote_asset_sold = quote_asset_sold.checked_add(Uint128::new(1u128))?;
-        }
-    }
-    Ok(quote_asset_sold)
+    get_curve(&config).output_price(
+        direction,
+        base_asset_amount,
+        quote_asset_reserve,
+        base_asset_reserve,
+    )
 }
 
 pub fn update_reserve(
@@ -419,6 +573,8 @@ pub fn update_reserve(
 ) -> StdResult<Response> {
     let mut state: State = read_state(storage)?;
 
+    let was_tripped = is_circuit_breaker_tripped(storage, &env)?;
+
     check_is_over_block_fluctuation_limit(
         storage,
         env.clone(),
@@ -454,7 +610,25 @@ pub fn update_reserve(
         state.base_asset_reserve,
     )?;
 
-    Ok(Response::new().add_attributes(vec![
+    let mut response = Response::new();
+
+    // only opening trades are blocked once tripped, so the swap that trips it goes through
+    if !was_tripped && is_circuit_breaker_tripped(storage, &env)? {
+        let config: Config = read_config(storage)?;
+        let reference_price = calc_circuit_breaker_reference_price(storage, &env)?;
+        let spot_price =
+            get_curve(&config).spot_price(state.quote_asset_reserve, state.base_asset_reserve)?;
+
+        response = response.add_event(
+            Event::new("circuit_breaker")
+                .add_attribute("is_tripped", true.to_string())
+                .add_attribute("reference_price", reference_price.to_string())
+                .add_attribute("spot_price", spot_price.to_string())
+                .add_attribute("window", config.circuit_breaker_window.to_string()),
+        );
+    }
+
+    Ok(response.add_attributes(vec![
         (
             "quote_asset_reserve",
             &state.quote_asset_reserve.to_string(),
@@ -1,4 +1,5 @@
 pub mod contract;
+mod curve;
 mod error;
 mod handle;
 mod querier;
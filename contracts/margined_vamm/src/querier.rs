@@ -1,7 +1,7 @@
 // Contains queries for external contracts,
-use cosmwasm_std::{to_binary, Deps, QueryRequest, StdResult, Uint128, WasmQuery};
+use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, Uint128, WasmQuery};
 
-use margined_perp::margined_pricefeed::QueryMsg;
+use margined_perp::margined_pricefeed::{FeedInfoResponse, PriceDataResponse, QueryMsg};
 
 use crate::state::{read_config, Config};
 
@@ -26,3 +26,47 @@ pub fn query_underlying_twap_price(deps: &Deps, interval: u64) -> StdResult<Uint
         msg: to_binary(&QueryMsg::GetTwapPrice { key, interval })?,
     }))
 }
+
+// returns the twap price of the underlying over the interval ending at the timestamp
+pub fn query_underlying_twap_price_at(
+    deps: &Deps,
+    interval: u64,
+    timestamp: u64,
+) -> StdResult<Uint128> {
+    let config: Config = read_config(deps.storage)?;
+    let key: String = config.base_asset;
+
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: config.pricefeed.to_string(),
+        msg: to_binary(&QueryMsg::GetTwapPriceAt {
+            key,
+            interval,
+            timestamp,
+        })?,
+    }))
+}
+
+// returns the price data, i.e. including round and timestamp, provided by an oracle
+pub fn query_underlying_price_data(
+    deps: &Deps,
+    num_round_back: Uint128,
+) -> StdResult<PriceDataResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let key: String = config.base_asset;
+
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: config.pricefeed.to_string(),
+        msg: to_binary(&QueryMsg::GetPreviousPriceData {
+            key,
+            num_round_back,
+        })?,
+    }))
+}
+
+// returns the metadata of a feed registered with a pricefeed
+pub fn query_feed_info(deps: &Deps, pricefeed: &Addr, key: String) -> StdResult<FeedInfoResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pricefeed.to_string(),
+        msg: to_binary(&QueryMsg::FeedInfo { key })?,
+    }))
+}
@@ -1,21 +1,26 @@
 use cosmwasm_std::{Deps, Env, StdError, StdResult, Uint128};
 use margined_common::integer::Integer;
 use margined_perp::margined_vamm::{
-    CalcFeeResponse, ConfigResponse, Direction, OwnerResponse, StateResponse,
+    AmountToPriceResponse, CalcFeeResponse, ConfigResponse, Direction, FundingMode,
+    MarketStatusResponse, OracleStatusResponse, OwnerResponse, QuoteResponse, StateResponse,
 };
 
 use crate::{
     contract::OWNER,
+    curve::get_curve,
     handle::{get_input_price_with_reserves, get_output_price_with_reserves},
-    querier::query_underlying_price,
+    querier::{query_underlying_price, query_underlying_twap_price},
     state::{read_config, read_reserve_snapshot_counter, read_state, Config, State},
     utils::{
-        calc_twap, price_boundaries_of_last_block, TwapCalcOption, TwapInputAsset,
+        calc_next_open_and_close, calc_oracle_status, calc_premium_fraction, calc_session_time,
+        calc_skew_adjusted_spread_ratio, calc_twap, is_circuit_breaker_tripped, is_in_session,
+        is_oracle_tripped, price_boundaries_of_last_block, TwapCalcOption, TwapInputAsset,
         TwapPriceCalcParams,
     },
 };
 
 const FIFTEEN_MINUTES: u64 = 15 * 60;
+const BPS_DENOMINATOR: u128 = 10_000;
 
 /// Queries contract Config
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
@@ -28,12 +33,23 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         base_asset: config.base_asset,
         toll_ratio: config.toll_ratio,
         spread_ratio: config.spread_ratio,
+        skew_fee_ratio: config.skew_fee_ratio,
         fluctuation_limit_ratio: config.fluctuation_limit_ratio,
+        max_oracle_spread_ratio: config.max_oracle_spread_ratio,
+        max_oracle_deviation_ratio: config.max_oracle_deviation_ratio,
+        oracle_heartbeat: config.oracle_heartbeat,
+        expiry: config.expiry,
+        expiry_cutoff_period: config.expiry_cutoff_period,
+        trading_schedule: config.trading_schedule,
+        curve: config.curve.curve_type(),
+        circuit_breaker_window: config.circuit_breaker_window,
+        circuit_breaker_ratio: config.circuit_breaker_ratio,
         decimals: config.decimals,
         margin_engine: config.margin_engine,
         insurance_fund: config.insurance_fund,
         pricefeed: config.pricefeed,
         funding_period: config.funding_period,
+        funding_mode: config.funding_mode,
         spot_price_twap_interval: config.spot_price_twap_interval,
     })
 }
@@ -49,6 +65,8 @@ pub fn query_state(deps: Deps) -> StdResult<StateResponse> {
         total_position_size: state.total_position_size,
         funding_rate: state.funding_rate,
         next_funding_time: state.next_funding_time,
+        last_funding_time: state.last_funding_time,
+        settlement_price: state.settlement_price,
     })
 }
 
@@ -144,12 +162,7 @@ pub fn query_spot_price(deps: Deps) -> StdResult<Uint128> {
     let config: Config = read_config(deps.storage)?;
     let state: State = read_state(deps.storage)?;
 
-    let res = state
-        .quote_asset_reserve
-        .checked_mul(config.decimals)?
-        .checked_div(state.base_asset_reserve)?;
-
-    Ok(res)
+    get_curve(&config).spot_price(state.quote_asset_reserve, state.base_asset_reserve)
 }
 
 /// Queries twap price of the vAMM, using the reserve snapshots
@@ -211,8 +224,48 @@ pub fn query_output_twap(
     calc_twap(deps, env, params, FIFTEEN_MINUTES)
 }
 
-/// Returns the total (i.e. toll + spread) fees for an amount
-pub fn query_calc_fee(deps: Deps, quote_asset_amount: Uint128) -> StdResult<CalcFeeResponse> {
+/// Queries the mark price of the vAMM, this is the median of the spot price, the twap price
+/// and the oracle price adjusted by the basis between the vAMM and oracle twaps
+pub fn query_mark_price(deps: Deps, env: Env) -> StdResult<Uint128> {
+    let config: Config = read_config(deps.storage)?;
+
+    let spot_price = query_spot_price(deps)?;
+    let twap_price = query_twap_price(deps, env.clone(), config.spot_price_twap_interval)?;
+
+    // an untrusted oracle is replaced by the twap price
+    let oracle_plus_basis = if is_oracle_tripped(deps, env)? {
+        twap_price
+    } else {
+        let oracle_price = query_underlying_price(&deps)?;
+        let oracle_twap_price =
+            query_underlying_twap_price(&deps, config.spot_price_twap_interval)?;
+
+        // basis = vAMM twap - oracle twap, the oracle price is shifted by this
+        let oracle_plus_basis = Integer::new_positive(oracle_price)
+            .checked_add(Integer::new_positive(twap_price))?
+            .checked_sub(Integer::new_positive(oracle_twap_price))?;
+
+        if oracle_plus_basis.is_negative() {
+            Uint128::zero()
+        } else {
+            oracle_plus_basis.value
+        }
+    };
+
+    let mut prices = [spot_price, twap_price, oracle_plus_basis];
+    prices.sort();
+
+    Ok(prices[1])
+}
+
+/// Returns the total (i.e. toll + spread) fees for an amount, the spread fee is
+/// increased for trades in the direction of the skew and reduced for trades against it
+pub fn query_calc_fee(
+    deps: Deps,
+    env: Env,
+    quote_asset_amount: Uint128,
+    direction: Direction,
+) -> StdResult<CalcFeeResponse> {
     let mut res = CalcFeeResponse {
         toll_fee: Uint128::zero(),
         spread_fee: Uint128::zero(),
@@ -224,22 +277,29 @@ pub fn query_calc_fee(deps: Deps, quote_asset_amount: Uint128) -> StdResult<Calc
         res.toll_fee = quote_asset_amount
             .checked_mul(config.toll_ratio)?
             .checked_div(config.decimals)?;
+        let spread_ratio = calc_skew_adjusted_spread_ratio(deps.storage, env, direction)?;
+
         res.spread_fee = quote_asset_amount
-            .checked_mul(config.spread_ratio)?
+            .checked_mul(spread_ratio)?
             .checked_div(config.decimals)?;
     }
 
     Ok(res)
 }
 
+/// Returns the status of the oracle, i.e. whether it is stale or deviated
+pub fn query_oracle_status(deps: Deps, env: Env) -> StdResult<OracleStatusResponse> {
+    calc_oracle_status(deps, env)
+}
+
 /// Returns bool to show is spread limit has been exceeded
-pub fn query_is_over_spread_limit(deps: Deps) -> StdResult<bool> {
+pub fn query_is_over_spread_limit(deps: Deps, env: Env) -> StdResult<bool> {
     let config: Config = read_config(deps.storage)?;
 
-    // get price from the oracle
+    // an untrusted or zero oracle price fails closed, the spread is treated as over the limit
     let oracle_price = query_underlying_price(&deps)?;
-    if oracle_price.is_zero() {
-        return Err(StdError::generic_err("underlying price is 0"));
+    if oracle_price.is_zero() || is_oracle_tripped(deps, env)? {
+        return Ok(true);
     }
 
     // get the local market price of the vamm
@@ -250,10 +310,191 @@ pub fn query_is_over_spread_limit(deps: Deps) -> StdResult<bool> {
         * Integer::new_positive(config.decimals)
         / Integer::new_positive(oracle_price);
 
-    let max_oracle_spread_ratio: Integer =
-        Integer::new_positive(config.decimals).checked_div(Integer::from(10u128))?; // 0.1 i.e. 10%
+    Ok(current_spread_ratio.abs() >= Integer::new_positive(config.max_oracle_spread_ratio))
+}
+
+/// Returns the premium fraction accrued since the last funding settlement, this is
+/// only non-zero for vAMMs using continuous funding
+pub fn query_accrued_premium_fraction(deps: Deps, env: Env) -> StdResult<Integer> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+
+    if config.funding_mode != FundingMode::Continuous
+        || config.expiry.is_some()
+        || !state.open
+        || env.block.time.seconds() <= state.last_funding_time
+    {
+        return Ok(Integer::zero());
+    }
+
+    // funding is not accrued against an untrusted oracle
+    if is_oracle_tripped(deps, env.clone())? {
+        return Ok(Integer::zero());
+    }
+
+    // funding only accrues for the time the market was in session
+    let now = env.block.time.seconds();
+    let elapsed = calc_session_time(&config.trading_schedule, state.last_funding_time, now);
+    if elapsed == 0 {
+        return Ok(Integer::zero());
+    }
+
+    let (premium_fraction, _, _) = calc_premium_fraction(deps, env, elapsed)?;
+
+    Ok(premium_fraction)
+}
+
+/// Returns whether the market is open under its trading schedule along with the
+/// next times that it opens and closes
+pub fn query_market_status(deps: Deps, env: Env) -> StdResult<MarketStatusResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+
+    let now = env.block.time.seconds();
+    let in_session = is_in_session(&config.trading_schedule, now);
+
+    let (next_open, next_close) = calc_next_open_and_close(&config.trading_schedule, now);
+
+    let is_circuit_breaker_tripped = is_circuit_breaker_tripped(deps.storage, &env)?;
+
+    // the circuit breaker only allows reducing trades while the market is in session
+    Ok(MarketStatusResponse {
+        is_open: state.open && in_session && !is_circuit_breaker_tripped,
+        is_reduce_only: state.open
+            && ((!in_session && config.trading_schedule.reduce_only_when_closed)
+                || (in_session && is_circuit_breaker_tripped)),
+        is_circuit_breaker_tripped,
+        next_open,
+        next_close,
+    })
+}
+
+/// Quotes a swap of either a quote or base asset amount, the direction is that of the
+/// quote asset, i.e. adding quote to the vAMM buys base asset
+pub fn query_quote(
+    deps: Deps,
+    env: Env,
+    direction: Direction,
+    quote_amount: Option<Uint128>,
+    base_amount: Option<Uint128>,
+) -> StdResult<QuoteResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+
+    let (quote_asset_amount, base_asset_amount) = match (quote_amount, base_amount) {
+        (Some(quote_asset_amount), None) => {
+            let base_asset_amount = get_input_price_with_reserves(
+                deps,
+                &direction,
+                quote_asset_amount,
+                state.quote_asset_reserve,
+                state.base_asset_reserve,
+            )?;
+            (quote_asset_amount, base_asset_amount)
+        }
+        (None, Some(base_asset_amount)) => {
+            // the base asset moves in the opposite direction to the quote asset
+            let base_direction = match direction {
+                Direction::AddToAmm => Direction::RemoveFromAmm,
+                Direction::RemoveFromAmm => Direction::AddToAmm,
+            };
+            let quote_asset_amount = get_output_price_with_reserves(
+                deps,
+                &base_direction,
+                base_asset_amount,
+                state.quote_asset_reserve,
+                state.base_asset_reserve,
+            )?;
+            (quote_asset_amount, base_asset_amount)
+        }
+        _ => {
+            return Err(StdError::generic_err(
+                "either quote amount or base amount must be given",
+            ))
+        }
+    };
+
+    let (quote_asset_reserve, base_asset_reserve) = match direction {
+        Direction::AddToAmm => (
+            state.quote_asset_reserve.checked_add(quote_asset_amount)?,
+            state.base_asset_reserve.checked_sub(base_asset_amount)?,
+        ),
+        Direction::RemoveFromAmm => (
+            state.quote_asset_reserve.checked_sub(quote_asset_amount)?,
+            state.base_asset_reserve.checked_add(base_asset_amount)?,
+        ),
+    };
+
+    let spot_price = query_spot_price(deps)?;
+    let spot_price_after =
+        get_curve(&config).spot_price(quote_asset_reserve, base_asset_reserve)?;
+
+    let average_price = if base_asset_amount.is_zero() {
+        spot_price
+    } else {
+        quote_asset_amount
+            .checked_mul(config.decimals)?
+            .checked_div(base_asset_amount)?
+    };
+
+    let price_impact_bps = if spot_price_after > spot_price {
+        spot_price_after - spot_price
+    } else {
+        spot_price - spot_price_after
+    }
+    .checked_mul(Uint128::from(BPS_DENOMINATOR))?
+    .checked_div(spot_price)?;
 
-    Ok(current_spread_ratio.abs() >= max_oracle_spread_ratio)
+    let is_over_fluctuation_limit = if config.fluctuation_limit_ratio.is_zero() {
+        false
+    } else {
+        let (upper_limit, lower_limit) = price_boundaries_of_last_block(deps.storage, env)?;
+        spot_price_after > upper_limit || spot_price_after < lower_limit
+    };
+
+    Ok(QuoteResponse {
+        quote_asset_amount,
+        base_asset_amount,
+        average_price,
+        spot_price_after,
+        price_impact_bps,
+        is_over_fluctuation_limit,
+    })
+}
+
+/// Returns the swap that moves the spot price of the vAMM to the target price
+pub fn query_amount_to_price(
+    deps: Deps,
+    target_price: Uint128,
+) -> StdResult<AmountToPriceResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+
+    if target_price.is_zero() {
+        return Err(StdError::generic_err("target price must be non-zero"));
+    }
+
+    let (quote_asset_target, base_asset_target) = get_curve(&config).reserves_at_price(
+        target_price,
+        state.quote_asset_reserve,
+        state.base_asset_reserve,
+    )?;
+
+    let response = if quote_asset_target >= state.quote_asset_reserve {
+        AmountToPriceResponse {
+            direction: Direction::AddToAmm,
+            quote_asset_amount: quote_asset_target - state.quote_asset_reserve,
+            base_asset_amount: state.base_asset_reserve.saturating_sub(base_asset_target),
+        }
+    } else {
+        AmountToPriceResponse {
+            direction: Direction::RemoveFromAmm,
+            quote_asset_amount: state.quote_asset_reserve - quote_asset_target,
+            base_asset_amount: base_asset_target.saturating_sub(state.base_asset_reserve),
+        }
+    };
+
+    Ok(response)
 }
 
 /// Returns bool to show is fluctuation limit has been exceeded
@@ -275,17 +516,15 @@ pub fn query_is_over_fluctuation_limit(
     let quote_asset_amount = query_output_amount(deps, direction.clone(), base_asset_amount)?;
 
     let price = if direction == Direction::RemoveFromAmm {
-        state
-            .quote_asset_reserve
-            .checked_add(quote_asset_amount)?
-            .checked_mul(config.decimals)?
-            .checked_div(state.base_asset_reserve.checked_sub(base_asset_amount)?)
+        get_curve(&config).spot_price(
+            state.quote_asset_reserve.checked_add(quote_asset_amount)?,
+            state.base_asset_reserve.checked_sub(base_asset_amount)?,
+        )
     } else {
-        state
-            .quote_asset_reserve
-            .checked_sub(quote_asset_amount)?
-            .checked_mul(config.decimals)?
-            .checked_div(state.base_asset_reserve.checked_add(base_asset_amount)?)
+        get_curve(&config).spot_price(
+            state.quote_asset_reserve.checked_sub(quote_asset_amount)?,
+            state.base_asset_reserve.checked_add(base_asset_amount)?,
+        )
     }?;
 
     if price <= upper_limit && price >= lower_limit {
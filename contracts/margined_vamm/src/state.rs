@@ -5,6 +5,9 @@ use cosmwasm_std::{Addr, StdResult, Storage, Timestamp, Uint128};
 use cosmwasm_storage::{bucket, bucket_read, singleton, singleton_read};
 
 use margined_common::integer::Integer;
+use margined_perp::margined_vamm::{FundingMode, TradingSchedule};
+
+use crate::curve::CurveConfig;
 
 pub static KEY_CONFIG: &[u8] = b"config";
 pub static KEY_STATE: &[u8] = b"state";
@@ -23,10 +26,21 @@ pub struct Config {
     pub decimals: Uint128,
     pub toll_ratio: Uint128,
     pub spread_ratio: Uint128,
+    pub skew_fee_ratio: Uint128,
     pub fluctuation_limit_ratio: Uint128,
+    pub max_oracle_spread_ratio: Uint128,
+    pub max_oracle_deviation_ratio: Uint128,
+    pub oracle_heartbeat: u64,
     pub spot_price_twap_interval: u64,
     pub funding_period: u64,
     pub funding_buffer_period: u64,
+    pub funding_mode: FundingMode,
+    pub expiry: Option<u64>,
+    pub expiry_cutoff_period: u64,
+    pub trading_schedule: TradingSchedule,
+    pub curve: CurveConfig,
+    pub circuit_breaker_window: u64,
+    pub circuit_breaker_ratio: Uint128,
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -45,6 +59,8 @@ pub struct State {
     pub total_position_size: Integer,
     pub funding_rate: Integer,
     pub next_funding_time: u64,
+    pub last_funding_time: u64,
+    pub settlement_price: Option<Uint128>,
 }
 
 pub fn store_state(storage: &mut dyn Storage, state: &State) -> StdResult<()> {
@@ -0,0 +1,207 @@
+use cosmwasm_std::{StdError, Uint128};
+use cw_multi_test::Executor;
+use margined_perp::margined_vamm::Direction;
+use margined_utils::scenarios::{to_decimals, VammScenario};
+
+// halts opening trades after a 5% move within 10 minutes
+const WINDOW: u64 = 600u64;
+const RATIO: Uint128 = Uint128::new(50_000_000u128);
+
+#[test]
+fn test_circuit_breaker_trips_on_rolling_window_move() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let msg = vamm.set_circuit_breaker(WINDOW, RATIO).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let config = vamm.config(&router).unwrap();
+    assert_eq!(config.circuit_breaker_window, WINDOW);
+    assert_eq!(config.circuit_breaker_ratio, RATIO);
+
+    // each block moves the price less than the limit, 10 -> 10.201
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+    let msg = vamm
+        .swap_input(Direction::AddToAmm, to_decimals(10), Uint128::zero(), true)
+        .unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+    assert!(!res
+        .events
+        .iter()
+        .any(|event| event.ty == "wasm-circuit_breaker"));
+    assert!(
+        !vamm
+            .market_status(&router)
+            .unwrap()
+            .is_circuit_breaker_tripped
+    );
+
+    // but the next block moves it more than 5% within the window, 10.201 -> 10.609
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+    let msg = vamm
+        .swap_input(Direction::AddToAmm, to_decimals(20), Uint128::zero(), true)
+        .unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+
+    let event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-circuit_breaker")
+        .unwrap();
+    let reference_price = event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "reference_price")
+        .unwrap();
+    assert_eq!(reference_price.value, to_decimals(10).to_string());
+
+    let status = vamm.market_status(&router).unwrap();
+    assert!(status.is_circuit_breaker_tripped);
+    assert!(!status.is_open);
+    assert!(status.is_reduce_only);
+
+    // further swaps do not emit the event again
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+    let msg = vamm
+        .swap_input(
+            Direction::RemoveFromAmm,
+            to_decimals(5),
+            Uint128::zero(),
+            true,
+        )
+        .unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+    assert!(!res
+        .events
+        .iter()
+        .any(|event| event.ty == "wasm-circuit_breaker"));
+
+    // the circuit breaker clears once the window has rolled past the move
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(WINDOW);
+        block.height += 1;
+    });
+
+    let status = vamm.market_status(&router).unwrap();
+    assert!(!status.is_circuit_breaker_tripped);
+    assert!(status.is_open);
+    assert!(!status.is_reduce_only);
+}
+
+#[test]
+fn test_circuit_breaker_reference_price_with_many_snapshots() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let msg = vamm.set_circuit_breaker(WINDOW, RATIO).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // a snapshot a minute, drifting the price up by less than the limit each time
+    let mut prices: Vec<(u64, Uint128)> = vec![];
+    for _ in 0..30 {
+        router.update_block(|block| {
+            block.time = block.time.plus_seconds(60);
+            block.height += 1;
+        });
+        let msg = vamm
+            .swap_input(
+                Direction::AddToAmm,
+                Uint128::from(200_000_000u128),
+                Uint128::zero(),
+                true,
+            )
+            .unwrap();
+        router.execute(owner.clone(), msg).unwrap();
+
+        let time = router.block_info().time.seconds();
+        prices.push((time, vamm.spot_price(&router).unwrap()));
+    }
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(30);
+        block.height += 1;
+    });
+    let msg = vamm
+        .swap_input(Direction::AddToAmm, to_decimals(50), Uint128::zero(), true)
+        .unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+
+    // the reference is the latest snapshot at or before the start of the window
+    let window_start = router.block_info().time.seconds() - WINDOW;
+    let expected = prices
+        .iter()
+        .rfind(|(time, _)| *time <= window_start)
+        .unwrap()
+        .1;
+
+    let event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-circuit_breaker")
+        .unwrap();
+    let reference_price = event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "reference_price")
+        .unwrap();
+    assert_eq!(reference_price.value, expected.to_string());
+}
+
+#[test]
+fn test_circuit_breaker_disabled_by_default() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let msg = vamm
+        .swap_input(Direction::AddToAmm, to_decimals(100), Uint128::zero(), true)
+        .unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+    assert!(!res
+        .events
+        .iter()
+        .any(|event| event.ty == "wasm-circuit_breaker"));
+
+    let status = vamm.market_status(&router).unwrap();
+    assert!(!status.is_circuit_breaker_tripped);
+    assert!(status.is_open);
+}
+
+#[test]
+fn test_force_error_invalid_circuit_breaker_ratio() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let msg = vamm.set_circuit_breaker(WINDOW, to_decimals(2)).unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Invalid ratio".to_string()
+        },
+        err.downcast().unwrap()
+    );
+}
@@ -0,0 +1,242 @@
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{from_binary, OwnedDeps, StdError, Uint128};
+use margined_perp::margined_vamm::{
+    AmountToPriceResponse, ConfigResponse, CurveType, Direction, ExecuteMsg, InstantiateMsg,
+    QueryMsg,
+};
+use margined_utils::scenarios::to_decimals;
+
+fn setup(curve: Option<CurveType>) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        decimals: 9u8,
+        quote_asset: "ETH".to_string(),
+        base_asset: "USD".to_string(),
+        quote_asset_reserve: to_decimals(1_000),
+        base_asset_reserve: to_decimals(100),
+        funding_period: 3_600_u64,
+        expiry: None,
+        curve,
+        toll_ratio: Uint128::zero(),
+        spread_ratio: Uint128::zero(),
+        fluctuation_limit_ratio: Uint128::zero(),
+        pricefeed: "oracle".to_string(),
+        margin_engine: Some("addr0000".to_string()),
+        insurance_fund: Some("insurance_fund".to_string()),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("addr0000", &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::SetOpen { open: true },
+    )
+    .unwrap();
+
+    deps
+}
+
+// four times the initial reserves, i.e. virtual reserves of 4000 : 400
+fn concentrated() -> Option<CurveType> {
+    Some(CurveType::Concentrated {
+        concentration: to_decimals(4),
+    })
+}
+
+fn query_spot_price(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>) -> Uint128 {
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::SpotPrice {}).unwrap();
+    from_binary(&res).unwrap()
+}
+
+#[test]
+fn test_constant_product_is_default_curve() {
+    let deps = setup(None);
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.curve, CurveType::ConstantProduct);
+}
+
+#[test]
+fn test_concentrated_curve_has_less_slippage() {
+    let constant_product = setup(None);
+    let deps = setup(concentrated());
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.curve, concentrated().unwrap());
+
+    // both curves start at the same spot price
+    assert_eq!(query_spot_price(&constant_product), to_decimals(10));
+    assert_eq!(query_spot_price(&deps), to_decimals(10));
+
+    let msg = QueryMsg::InputPrice {
+        direction: Direction::AddToAmm,
+        amount: to_decimals(250),
+    };
+    let res = query(constant_product.as_ref(), mock_env(), msg.clone()).unwrap();
+    let constant_product_price: Uint128 = from_binary(&res).unwrap();
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let concentrated_price: Uint128 = from_binary(&res).unwrap();
+
+    // 250 / 20 vs 250 / 23.529411764
+    assert_eq!(constant_product_price, Uint128::from(12_500_000_000u128));
+    assert_eq!(concentrated_price, Uint128::from(10_625_000_000u128));
+}
+
+#[test]
+fn test_concentrated_curve_swap_moves_virtual_reserves() {
+    let mut deps = setup(concentrated());
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::SwapInput {
+            direction: Direction::AddToAmm,
+            quote_asset_amount: to_decimals(250),
+            base_asset_limit: Uint128::zero(),
+            can_go_over_fluctuation: false,
+        },
+    )
+    .unwrap();
+
+    let base_asset_amount = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "base_asset_amount")
+        .unwrap();
+    assert_eq!(base_asset_amount.value, "23529411764");
+
+    // 4250 / 376.470588236
+    assert_eq!(query_spot_price(&deps), Uint128::from(11_289_062_499u128));
+}
+
+#[test]
+fn test_concentrated_amount_to_price() {
+    let mut deps = setup(concentrated());
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::AmountToPrice {
+            target_price: Uint128::from(12_100_000_000u128),
+        },
+    )
+    .unwrap();
+    let result: AmountToPriceResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        result,
+        AmountToPriceResponse {
+            direction: Direction::AddToAmm,
+            quote_asset_amount: to_decimals(400),
+            base_asset_amount: Uint128::from(36_363_636_364u128),
+        }
+    );
+
+    let info = mock_info("addr0000", &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::SwapInput {
+            direction: result.direction,
+            quote_asset_amount: result.quote_asset_amount,
+            base_asset_limit: Uint128::zero(),
+            can_go_over_fluctuation: false,
+        },
+    )
+    .unwrap();
+    assert_eq!(query_spot_price(&deps), Uint128::from(12_099_999_999u128));
+
+    // the price can't go past the point where the real base reserve is used up
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::AmountToPrice {
+            target_price: to_decimals(40),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("target price is outside the range of the curve")
+    );
+}
+
+#[test]
+fn test_force_error_swap_outside_curve_range() {
+    let mut deps = setup(concentrated());
+
+    // the whole quote reserve can't be removed
+    let info = mock_info("addr0000", &[]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::SwapInput {
+            direction: Direction::RemoveFromAmm,
+            quote_asset_amount: to_decimals(1_000),
+            base_asset_limit: Uint128::zero(),
+            can_go_over_fluctuation: false,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("swap is outside the range of the curve")
+    );
+
+    // nor can the whole base reserve
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::SwapOutput {
+            direction: Direction::RemoveFromAmm,
+            base_asset_amount: to_decimals(100),
+            quote_asset_limit: Uint128::zero(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("swap is outside the range of the curve")
+    );
+}
+
+#[test]
+fn test_force_error_concentration_below_one() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        decimals: 9u8,
+        quote_asset: "ETH".to_string(),
+        base_asset: "USD".to_string(),
+        quote_asset_reserve: to_decimals(1_000),
+        base_asset_reserve: to_decimals(100),
+        funding_period: 3_600_u64,
+        expiry: None,
+        curve: Some(CurveType::Concentrated {
+            concentration: Uint128::from(500_000_000u128),
+        }),
+        toll_ratio: Uint128::zero(),
+        spread_ratio: Uint128::zero(),
+        fluctuation_limit_ratio: Uint128::zero(),
+        pricefeed: "oracle".to_string(),
+        margin_engine: Some("addr0000".to_string()),
+        insurance_fund: Some("insurance_fund".to_string()),
+    };
+    let info = mock_info("addr0000", &[]);
+    let err = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: concentration must be at least one"
+    );
+}
@@ -0,0 +1,158 @@
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, Uint128};
+use margined_perp::margined_vamm::{
+    ConfigResponse, Direction, ExecuteMsg, InstantiateMsg, QueryMsg,
+};
+use margined_utils::scenarios::to_decimals;
+
+fn dated_instantiate_msg(expiry: u64) -> InstantiateMsg {
+    InstantiateMsg {
+        decimals: 9u8,
+        quote_asset: "ETH".to_string(),
+        base_asset: "USD".to_string(),
+        quote_asset_reserve: to_decimals(100),
+        base_asset_reserve: to_decimals(10_000),
+        funding_period: 3_600_u64,
+        expiry: Some(expiry),
+        curve: None,
+        toll_ratio: Uint128::zero(),
+        spread_ratio: Uint128::zero(),
+        fluctuation_limit_ratio: Uint128::zero(),
+        margin_engine: Some("addr0000".to_string()),
+        insurance_fund: Some("insurance_fund".to_string()),
+        pricefeed: "oracle".to_string(),
+    }
+}
+
+#[test]
+fn test_instantiate_dated_vamm() {
+    let mut deps = mock_dependencies();
+    let expiry = mock_env().block.time.seconds() + 86_400u64;
+
+    let info = mock_info("addr0000", &[]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        dated_instantiate_msg(expiry),
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.expiry, Some(expiry));
+    assert_eq!(config.expiry_cutoff_period, 3_600u64);
+
+    // expiry in the past is rejected
+    let mut deps = mock_dependencies();
+    let expiry = mock_env().block.time.seconds();
+
+    let info = mock_info("addr0000", &[]);
+    let result = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        dated_instantiate_msg(expiry),
+    )
+    .unwrap_err();
+    assert_eq!(
+        result.to_string(),
+        "Generic error: expiry must be in the future".to_string()
+    );
+}
+
+#[test]
+fn test_dated_vamm_has_no_funding() {
+    let mut deps = mock_dependencies();
+    let expiry = mock_env().block.time.seconds() + 86_400u64;
+
+    let info = mock_info("addr0000", &[]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        dated_instantiate_msg(expiry),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::SetOpen { open: true };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::SettleFunding {};
+    let info = mock_info("addr0000", &[]);
+    let result = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        result.to_string(),
+        "Generic error: funding is disabled for dated futures".to_string()
+    );
+}
+
+#[test]
+fn test_cant_swap_or_reopen_after_expiry() {
+    let mut deps = mock_dependencies();
+    let expiry = mock_env().block.time.seconds() + 86_400u64;
+
+    let info = mock_info("addr0000", &[]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        dated_instantiate_msg(expiry),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::SetOpen { open: true };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(86_400u64);
+
+    // settlement cannot happen early
+    let msg = ExecuteMsg::SettleExpiry {};
+    let info = mock_info("addr0001", &[]);
+    let result = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        result.to_string(),
+        "Generic error: amm has not expired".to_string()
+    );
+
+    let msg = ExecuteMsg::SwapInput {
+        direction: Direction::AddToAmm,
+        quote_asset_amount: to_decimals(600),
+        base_asset_limit: Uint128::zero(),
+        can_go_over_fluctuation: false,
+    };
+    let info = mock_info("addr0000", &[]);
+    let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        result.to_string(),
+        "Generic error: amm has expired".to_string()
+    );
+
+    let msg = ExecuteMsg::SwapOutput {
+        direction: Direction::AddToAmm,
+        base_asset_amount: to_decimals(10),
+        quote_asset_limit: Uint128::zero(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        result.to_string(),
+        "Generic error: amm has expired".to_string()
+    );
+
+    let msg = ExecuteMsg::SetOpen { open: false };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::SetOpen { open: true };
+    let info = mock_info("addr0000", &[]);
+    let result = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(
+        result.to_string(),
+        "Generic error: amm has expired".to_string()
+    );
+}
@@ -1,7 +1,7 @@
 use cosmwasm_std::{StdError, Uint128};
 use cw_multi_test::Executor;
-use margined_perp::margined_vamm::CalcFeeResponse;
-use margined_utils::scenarios::{to_decimals, SimpleScenario};
+use margined_perp::margined_vamm::{CalcFeeResponse, Direction};
+use margined_utils::scenarios::{to_decimals, SimpleScenario, VammScenario};
 
 #[test]
 fn test_calc_fee() {
@@ -20,7 +20,9 @@ fn test_calc_fee() {
         .unwrap();
     router.execute(owner.clone(), msg).unwrap();
 
-    let result = vamm.calc_fee(&router, to_decimals(10)).unwrap();
+    let result = vamm
+        .calc_fee(&router, to_decimals(10), Direction::AddToAmm)
+        .unwrap();
 
     assert_eq!(
         result,
@@ -54,7 +56,9 @@ fn test_set_diff_fee_ratio() {
         )
         .unwrap();
     router.execute(owner.clone(), msg).unwrap();
-    let result = vamm.calc_fee(&router, to_decimals(100)).unwrap();
+    let result = vamm
+        .calc_fee(&router, to_decimals(100), Direction::AddToAmm)
+        .unwrap();
 
     assert_eq!(
         result,
@@ -89,7 +93,9 @@ fn test_set_fee_ratio_zero() {
         .unwrap();
     router.execute(owner.clone(), msg).unwrap();
 
-    let result = vamm.calc_fee(&router, to_decimals(100)).unwrap();
+    let result = vamm
+        .calc_fee(&router, to_decimals(100), Direction::AddToAmm)
+        .unwrap();
     assert_eq!(
         result,
         CalcFeeResponse {
@@ -103,7 +109,9 @@ fn test_set_fee_ratio_zero() {
 fn test_calc_fee_input_zero() {
     let SimpleScenario { router, vamm, .. } = SimpleScenario::new();
 
-    let result = vamm.calc_fee(&router, to_decimals(0)).unwrap();
+    let result = vamm
+        .calc_fee(&router, to_decimals(0), Direction::AddToAmm)
+        .unwrap();
     assert_eq!(
         result,
         CalcFeeResponse {
@@ -143,3 +151,93 @@ fn test_update_not_owner() {
         err.downcast().unwrap()
     );
 }
+
+#[test]
+fn test_calc_fee_with_skew_fee_ratio() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let msg = vamm
+        .set_spread_ratio(Uint128::from(10_000_000u128))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm
+        .set_skew_fee_ratio(Uint128::from(500_000_000u128))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+
+    // (1000 + 250) * (100 - 20) = 100k, i.e. longs hold 20 base
+    let msg = vamm
+        .swap_input(Direction::AddToAmm, to_decimals(250), Uint128::zero(), true)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // the skew of the previous block is used so the fee is unchanged in the same block
+    let result = vamm
+        .calc_fee(&router, to_decimals(10), Direction::AddToAmm)
+        .unwrap();
+    assert_eq!(result.spread_fee, Uint128::from(100_000_000u128));
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(15);
+        block.height += 1;
+    });
+
+    // spread ratio: 0.01 + 0.5 * 20 / 80 = 0.135
+    let result = vamm
+        .calc_fee(&router, to_decimals(10), Direction::AddToAmm)
+        .unwrap();
+    assert_eq!(
+        result,
+        CalcFeeResponse {
+            toll_fee: Uint128::from(100_000_000u128),
+            spread_fee: Uint128::from(1_350_000_000u128),
+        }
+    );
+
+    // spread ratio: 0.01 - 0.125 floors at zero
+    let result = vamm
+        .calc_fee(&router, to_decimals(10), Direction::RemoveFromAmm)
+        .unwrap();
+    assert_eq!(
+        result,
+        CalcFeeResponse {
+            toll_fee: Uint128::from(100_000_000u128),
+            spread_fee: Uint128::zero(),
+        }
+    );
+
+    let config = vamm.config(&router).unwrap();
+    assert_eq!(config.skew_fee_ratio, Uint128::from(500_000_000u128));
+}
+
+#[test]
+fn test_set_skew_fee_ratio_invalid_ratio() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let msg = vamm
+        .set_skew_fee_ratio(Uint128::from(1_500_000_000u128))
+        .unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "Invalid ratio".to_string(),
+        },
+        err.downcast().unwrap()
+    );
+}
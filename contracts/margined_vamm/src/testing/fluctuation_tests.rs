@@ -22,6 +22,8 @@ fn setup() -> TestingEnv {
         quote_asset_reserve: to_decimals(1_000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::from(10_000_000u128),   // 0.01
         spread_ratio: Uint128::from(10_000_000u128), // 0.01
         fluctuation_limit_ratio: Uint128::from(50_000_000u128), // 0.05
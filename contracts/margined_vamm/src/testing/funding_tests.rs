@@ -1,5 +1,7 @@
 use cosmwasm_std::{StdError, Uint128};
 use cw_multi_test::Executor;
+use margined_common::integer::Integer;
+use margined_perp::margined_vamm::FundingMode;
 use margined_utils::scenarios::VammScenario;
 
 #[test]
@@ -137,3 +139,129 @@ fn test_cant_settle_funding_multiple_times_at_once_even_settle_funding_delay() {
         err.downcast().unwrap()
     );
 }
+
+#[test]
+fn test_accrued_premium_fraction_is_zero_for_periodic_funding() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let price: Uint128 = Uint128::from(500_000_000u128);
+    let timestamp: u64 = 1_000_000_000;
+
+    let msg = pricefeed
+        .append_price("ETH".to_string(), price, timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(3_600u64);
+        block.height += 1;
+    });
+
+    let accrued = vamm.accrued_premium_fraction(&router).unwrap();
+    assert_eq!(accrued, Integer::zero());
+}
+
+#[test]
+fn test_continuous_funding_accrues_with_elapsed_time() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let price: Uint128 = Uint128::from(500_000_000u128);
+    let timestamp: u64 = 1_000_000_000;
+
+    let msg = pricefeed
+        .append_price("ETH".to_string(), price, timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm.set_funding_mode(FundingMode::Continuous).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let config = vamm.config(&router).unwrap();
+    assert_eq!(config.funding_mode, FundingMode::Continuous);
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(1_800u64);
+        block.height += 1;
+    });
+
+    // premium = 10 - 0.5 = 9.5, accrued over half an hour = 9.5 * 1_800 / 86_400
+    let accrued = vamm.accrued_premium_fraction(&router).unwrap();
+    assert_eq!(accrued, Integer::new_positive(197_916_666u128));
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(1_800u64);
+        block.height += 1;
+    });
+
+    let accrued = vamm.accrued_premium_fraction(&router).unwrap();
+    assert_eq!(accrued, Integer::new_positive(395_833_333u128));
+}
+
+#[test]
+fn test_continuous_funding_can_settle_before_next_funding_time() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let price: Uint128 = Uint128::from(500_000_000u128);
+    let timestamp: u64 = 1_000_000_000;
+
+    let msg = pricefeed
+        .append_price("ETH".to_string(), price, timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm.set_funding_mode(FundingMode::Continuous).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(1_800u64);
+        block.height += 1;
+    });
+
+    let msg = vamm.settle_funding().unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+    let premium_fraction = res.events[1]
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "premium_fraction")
+        .unwrap()
+        .value
+        .clone();
+    assert_eq!(premium_fraction, "197916666");
+
+    let state = vamm.state(&router).unwrap();
+    assert_eq!(state.last_funding_time, router.block_info().time.seconds());
+
+    // nothing has accrued since the settlement
+    let accrued = vamm.accrued_premium_fraction(&router).unwrap();
+    assert_eq!(accrued, Integer::zero());
+
+    // settling again in the same block settles nothing
+    let msg = vamm.settle_funding().unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+    let premium_fraction = res.events[1]
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "premium_fraction")
+        .unwrap()
+        .value
+        .clone();
+    assert_eq!(premium_fraction, "0");
+}
@@ -16,6 +16,8 @@ fn test_get_input_add_to_amm() {
         quote_asset_reserve: to_decimals(1_000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -131,6 +133,8 @@ fn test_get_output_amount() {
         quote_asset_reserve: to_decimals(1_000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -201,6 +205,8 @@ fn test_get_input_and_output_price_with_reserves() {
         quote_asset_reserve: to_decimals(1_000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -0,0 +1,365 @@
+use cosmwasm_std::{StdError, Timestamp, Uint128};
+use cw_multi_test::Executor;
+use margined_common::integer::Integer;
+use margined_perp::margined_vamm::{
+    Direction, FundingMode, TradingHoliday, TradingSchedule, TradingSession,
+};
+use margined_utils::scenarios::{to_decimals, VammScenario};
+
+const ONE_DAY: u64 = 86_400u64;
+const ONE_HOUR: u64 = 3_600u64;
+
+// the scenario starts at Wednesday 02:23:39 UTC, the week started on Monday 1_571_616_000
+const WEEK_START: u64 = 1_571_616_000u64;
+
+// weekdays from 09:00 until 17:00 UTC
+fn weekday_schedule() -> TradingSchedule {
+    TradingSchedule {
+        sessions: (0..5)
+            .map(|day| TradingSession {
+                open: day * ONE_DAY + 9 * ONE_HOUR,
+                close: day * ONE_DAY + 17 * ONE_HOUR,
+            })
+            .collect(),
+        holidays: vec![],
+        reduce_only_when_closed: false,
+    }
+}
+
+#[test]
+fn test_market_status_always_open_by_default() {
+    let VammScenario { router, vamm, .. } = VammScenario::new();
+
+    let status = vamm.market_status(&router).unwrap();
+    assert!(status.is_open);
+    assert!(!status.is_reduce_only);
+    assert_eq!(status.next_open, None);
+    assert_eq!(status.next_close, None);
+}
+
+#[test]
+fn test_market_status_follows_weekly_sessions() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let msg = vamm.set_trading_schedule(weekday_schedule()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // wednesday before the open
+    let status = vamm.market_status(&router).unwrap();
+    assert!(!status.is_open);
+    assert_eq!(
+        status.next_open,
+        Some(WEEK_START + 2 * ONE_DAY + 9 * ONE_HOUR)
+    );
+    assert_eq!(
+        status.next_close,
+        Some(WEEK_START + 2 * ONE_DAY + 17 * ONE_HOUR)
+    );
+
+    // wednesday 10:00
+    router.update_block(|block| {
+        block.time = Timestamp::from_seconds(WEEK_START + 2 * ONE_DAY + 10 * ONE_HOUR);
+        block.height += 1;
+    });
+
+    let status = vamm.market_status(&router).unwrap();
+    assert!(status.is_open);
+    assert_eq!(
+        status.next_open,
+        Some(WEEK_START + 3 * ONE_DAY + 9 * ONE_HOUR)
+    );
+    assert_eq!(
+        status.next_close,
+        Some(WEEK_START + 2 * ONE_DAY + 17 * ONE_HOUR)
+    );
+
+    // saturday reopens on monday
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(3 * ONE_DAY);
+        block.height += 1;
+    });
+
+    let status = vamm.market_status(&router).unwrap();
+    assert!(!status.is_open);
+    assert_eq!(
+        status.next_open,
+        Some(WEEK_START + 7 * ONE_DAY + 9 * ONE_HOUR)
+    );
+}
+
+#[test]
+fn test_market_status_closed_on_holiday() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let mut schedule = weekday_schedule();
+    schedule.holidays = vec![TradingHoliday {
+        start: WEEK_START + 2 * ONE_DAY,
+        end: WEEK_START + 3 * ONE_DAY,
+    }];
+
+    let msg = vamm.set_trading_schedule(schedule).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let status = vamm.market_status(&router).unwrap();
+    assert!(!status.is_open);
+    assert_eq!(
+        status.next_open,
+        Some(WEEK_START + 3 * ONE_DAY + 9 * ONE_HOUR)
+    );
+    assert_eq!(
+        status.next_close,
+        Some(WEEK_START + 3 * ONE_DAY + 17 * ONE_HOUR)
+    );
+}
+
+#[test]
+fn test_force_error_invalid_trading_schedule() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let schedule = TradingSchedule {
+        sessions: vec![TradingSession {
+            open: 17 * ONE_HOUR,
+            close: 9 * ONE_HOUR,
+        }],
+        holidays: vec![],
+        reduce_only_when_closed: false,
+    };
+
+    let msg = vamm.set_trading_schedule(schedule).unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "invalid trading session".to_string()
+        },
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn test_force_error_too_many_trading_holidays() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let mut schedule = weekday_schedule();
+    schedule.holidays = (0..65)
+        .map(|week| TradingHoliday {
+            start: WEEK_START + week * 7 * ONE_DAY,
+            end: WEEK_START + week * 7 * ONE_DAY + ONE_DAY,
+        })
+        .collect();
+
+    let msg = vamm.set_trading_schedule(schedule.clone()).unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "too many trading holidays".to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    schedule.holidays.pop();
+    let msg = vamm.set_trading_schedule(schedule).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+}
+
+#[test]
+fn test_market_status_next_open_beyond_a_week() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    // closed for the next three weeks
+    let mut schedule = weekday_schedule();
+    schedule.holidays = vec![TradingHoliday {
+        start: WEEK_START,
+        end: WEEK_START + 21 * ONE_DAY,
+    }];
+
+    let msg = vamm.set_trading_schedule(schedule).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let status = vamm.market_status(&router).unwrap();
+    assert!(!status.is_open);
+    assert_eq!(
+        status.next_open,
+        Some(WEEK_START + 21 * ONE_DAY + 9 * ONE_HOUR)
+    );
+    assert_eq!(
+        status.next_close,
+        Some(WEEK_START + 21 * ONE_DAY + 17 * ONE_HOUR)
+    );
+}
+
+#[test]
+fn test_swaps_rejected_while_market_is_closed() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let msg = vamm.set_trading_schedule(weekday_schedule()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm
+        .swap_input(Direction::AddToAmm, to_decimals(10), Uint128::zero(), false)
+        .unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        StdError::GenericErr {
+            msg: "market is closed".to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    // the margin engine enforces that only reducing trades are made
+    let mut schedule = weekday_schedule();
+    schedule.reduce_only_when_closed = true;
+
+    let msg = vamm.set_trading_schedule(schedule).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let status = vamm.market_status(&router).unwrap();
+    assert!(!status.is_open);
+    assert!(status.is_reduce_only);
+
+    let msg = vamm
+        .swap_input(Direction::AddToAmm, to_decimals(10), Uint128::zero(), true)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+}
+
+#[test]
+fn test_settle_funding_pays_nothing_while_market_is_closed() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let msg = pricefeed
+        .append_price("ETH".to_string(), to_decimals(5), 1_000_000_000u64)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm.set_trading_schedule(weekday_schedule()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(ONE_HOUR);
+        block.height += 1;
+    });
+
+    let msg = vamm.settle_funding().unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+
+    assert!(res
+        .events
+        .iter()
+        .any(|event| event.ty == "wasm-market_status"));
+
+    let premium_fraction = res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "premium_fraction")
+        .unwrap();
+    assert_eq!(premium_fraction.value, "0");
+}
+
+#[test]
+fn test_continuous_funding_accrues_over_every_session_since_settlement() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let msg = pricefeed
+        .append_price("ETH".to_string(), to_decimals(5), 1_000_000_000u64)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm.set_trading_schedule(weekday_schedule()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm.set_funding_mode(FundingMode::Continuous).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // settle on wednesday 16:00
+    router.update_block(|block| {
+        block.time = Timestamp::from_seconds(WEEK_START + 2 * ONE_DAY + 16 * ONE_HOUR);
+        block.height += 1;
+    });
+
+    let msg = vamm.settle_funding().unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // an hour accrues until the close
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(2 * ONE_HOUR);
+        block.height += 1;
+    });
+
+    let one_hour = vamm.accrued_premium_fraction(&router).unwrap();
+    assert_eq!(one_hour, Integer::new_positive(208_333_333u64));
+
+    // thursday 10:00 adds another hour from the open
+    router.update_block(|block| {
+        block.time = Timestamp::from_seconds(WEEK_START + 3 * ONE_DAY + 10 * ONE_HOUR);
+        block.height += 1;
+    });
+
+    let accrued = vamm.accrued_premium_fraction(&router).unwrap();
+    assert_eq!(accrued, Integer::new_positive(416_666_666u64));
+
+    // saturday the market is closed, yet the sessions since wednesday are still paid
+    router.update_block(|block| {
+        block.time = Timestamp::from_seconds(WEEK_START + 5 * ONE_DAY + 12 * ONE_HOUR);
+        block.height += 1;
+    });
+
+    let accrued = vamm.accrued_premium_fraction(&router).unwrap();
+    assert_eq!(accrued, Integer::new_positive(3_541_666_666u64));
+
+    let msg = vamm.settle_funding().unwrap();
+    let res = router.execute(owner.clone(), msg).unwrap();
+
+    let premium_fraction = res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "premium_fraction")
+        .unwrap();
+    assert_eq!(premium_fraction.value, accrued.to_string());
+
+    let state = vamm.state(&router).unwrap();
+    assert_eq!(state.last_funding_time, router.block_info().time.seconds());
+}
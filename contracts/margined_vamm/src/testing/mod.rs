@@ -1,8 +1,16 @@
 #[cfg(test)]
+mod circuit_breaker_tests;
+mod curve_tests;
+mod expiry_tests;
 mod fee_tests;
 mod fluctuation_tests;
 mod funding_tests;
 mod get_price_tests;
+mod market_status_tests;
+mod oracle_status_tests;
+mod price_path_tests;
+mod pricefeed_tests;
+mod quote_tests;
 mod set_open_tests;
 mod spread_limit_tests;
 mod swap_input_output_tests;
@@ -0,0 +1,134 @@
+use cosmwasm_std::Uint128;
+use cw_multi_test::Executor;
+use margined_utils::scenarios::{to_decimals, VammScenario};
+
+#[test]
+fn test_oracle_status_is_stale_after_heartbeat() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let msg = vamm.set_oracle_heartbeat(3_600u64).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // nothing has been published so the default price data is stale
+    let status = vamm.oracle_status(&router).unwrap();
+    assert_eq!(status.price, Uint128::zero());
+    assert_eq!(status.timestamp, 0u64);
+    assert!(status.is_stale);
+
+    let timestamp = router.block_info().time.seconds();
+    let msg = pricefeed
+        .append_price("ETH".to_string(), to_decimals(10u64), timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let status = vamm.oracle_status(&router).unwrap();
+    assert_eq!(status.price, to_decimals(10u64));
+    assert_eq!(status.timestamp, timestamp);
+    assert!(!status.is_stale);
+    assert!(!status.is_deviated);
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(3_601u64);
+        block.height += 1;
+    });
+
+    let status = vamm.oracle_status(&router).unwrap();
+    assert!(status.is_stale);
+}
+
+#[test]
+fn test_oracle_status_is_deviated_if_greater_than_max_ratio() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let timestamp = router.block_info().time.seconds();
+    let msg = pricefeed
+        .append_price("ETH".to_string(), to_decimals(10u64), timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = pricefeed
+        .append_price("ETH".to_string(), to_decimals(12u64), timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // the guard is disabled by default
+    let status = vamm.oracle_status(&router).unwrap();
+    assert_eq!(status.deviation_ratio, Uint128::from(200_000_000u128));
+    assert!(!status.is_deviated);
+
+    let msg = vamm
+        .set_max_oracle_deviation_ratio(Uint128::from(100_000_000u128))
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let status = vamm.oracle_status(&router).unwrap();
+    assert!(status.is_deviated);
+
+    // a tripped oracle fails closed
+    let result = vamm.is_over_spread_limit(&router).unwrap();
+    assert!(result);
+}
+
+#[test]
+fn test_settle_funding_rejected_if_oracle_is_stale() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let price: Uint128 = Uint128::from(500_000_000u128);
+    let timestamp: u64 = 1_000_000_000;
+
+    let msg = pricefeed
+        .append_price("ETH".to_string(), price, timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm.set_oracle_heartbeat(3_600u64).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let state = vamm.state(&router).unwrap();
+    let original_next_funding_time = state.next_funding_time;
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(3_600u64);
+        block.height += 1;
+    });
+
+    let msg = vamm.settle_funding().unwrap();
+    let err = router.execute(owner.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: oracle is stale or deviated"
+    );
+
+    // the funding period is left to be settled once the oracle recovers
+    let state = vamm.state(&router).unwrap();
+    assert_eq!(state.next_funding_time, original_next_funding_time);
+
+    let timestamp = router.block_info().time.seconds();
+    let msg_price = pricefeed
+        .append_price("ETH".to_string(), price, timestamp)
+        .unwrap();
+    router.execute(owner.clone(), msg_price).unwrap();
+
+    router.execute(owner.clone(), msg).unwrap();
+
+    let state = vamm.state(&router).unwrap();
+    assert!(state.next_funding_time > original_next_funding_time);
+}
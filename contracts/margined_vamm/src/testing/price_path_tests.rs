@@ -0,0 +1,185 @@
+use cosmwasm_std::Uint128;
+use cw_multi_test::Executor;
+use margined_utils::scenarios::{to_decimals, PricePath, PricePoint, VammScenario};
+
+#[test]
+fn test_linear_price_path_follows_block_time() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let start = router.block_info().time.seconds();
+    let msg = pricefeed
+        .set_price_path(PricePath::Linear {
+            points: vec![
+                PricePoint {
+                    timestamp: start,
+                    price: to_decimals(10u64),
+                },
+                PricePoint {
+                    timestamp: start + 100,
+                    price: to_decimals(20u64),
+                },
+            ],
+        })
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let price = pricefeed.get_price(&router, "ETH".to_string()).unwrap();
+    assert_eq!(price, to_decimals(10u64));
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(50u64);
+        block.height += 1;
+    });
+
+    let price = pricefeed.get_price(&router, "ETH".to_string()).unwrap();
+    assert_eq!(price, to_decimals(15u64));
+
+    let twap = pricefeed
+        .twap_price(&router, "ETH".to_string(), 50u64)
+        .unwrap();
+    assert_eq!(twap, Uint128::from(12_500_000_000u128));
+
+    // the latest round is interpolated at the block time so it is never stale
+    let msg = vamm.set_oracle_heartbeat(10u64).unwrap();
+    router.execute(owner, msg).unwrap();
+
+    let status = vamm.oracle_status(&router).unwrap();
+    assert_eq!(status.price, to_decimals(15u64));
+    assert_eq!(status.timestamp, start + 50);
+    assert!(!status.is_stale);
+
+    // the price holds at the end of the path
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(1_000u64);
+        block.height += 1;
+    });
+
+    let price = pricefeed.get_price(&router, "ETH".to_string()).unwrap();
+    assert_eq!(price, to_decimals(20u64));
+}
+
+#[test]
+fn test_points_price_path_steps_between_rounds() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let start = router.block_info().time.seconds();
+    let csv = format!(
+        "# timestamp,price\n{},10000000000\n{},12000000000\n",
+        start,
+        start + 60
+    );
+    let msg = pricefeed.set_price_path(PricePath::Points { csv }).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(30u64);
+        block.height += 1;
+    });
+
+    let status = vamm.oracle_status(&router).unwrap();
+    assert_eq!(status.price, to_decimals(10u64));
+    assert_eq!(status.timestamp, start);
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(30u64);
+        block.height += 1;
+    });
+
+    let status = vamm.oracle_status(&router).unwrap();
+    assert_eq!(status.price, to_decimals(12u64));
+    assert_eq!(status.timestamp, start + 60);
+    assert_eq!(status.deviation_ratio, Uint128::from(200_000_000u128));
+
+    router.update_block(|block| {
+        block.time = block.time.plus_seconds(60u64);
+        block.height += 1;
+    });
+
+    let twap = pricefeed
+        .twap_price(&router, "ETH".to_string(), 120u64)
+        .unwrap();
+    assert_eq!(twap, Uint128::from(11_000_000_000u128));
+
+    let msg = pricefeed
+        .set_price_path(PricePath::Points {
+            csv: "100,ten".to_string(),
+        })
+        .unwrap();
+    let err = router.execute(owner, msg).unwrap_err();
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "Generic error: invalid price point: 100,ten"
+    );
+}
+
+#[test]
+fn test_random_walk_price_path_is_seeded() {
+    let VammScenario {
+        mut router,
+        owner,
+        pricefeed,
+        ..
+    } = VammScenario::new();
+
+    let start = router.block_info().time.seconds();
+    let msg = pricefeed
+        .append_price("ETH".to_string(), to_decimals(10u64), start)
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let path = PricePath::RandomWalk {
+        start_time: start,
+        start_price: to_decimals(10u64),
+        interval: 60u64,
+        steps: 100u32,
+        volatility_bps: 100u64,
+        seed: 42u64,
+    };
+    let msg = pricefeed.set_price_path(path.clone()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let mut previous = pricefeed.get_price(&router, "ETH".to_string()).unwrap();
+    assert_eq!(previous, to_decimals(10u64));
+
+    let mut prices = vec![];
+    for _ in 0..100 {
+        router.update_block(|block| {
+            block.time = block.time.plus_seconds(60u64);
+            block.height += 1;
+        });
+
+        // each step moves by at most the volatility
+        let price = pricefeed.get_price(&router, "ETH".to_string()).unwrap();
+        assert!(price <= previous.multiply_ratio(10_100u128, 10_000u128));
+        assert!(price >= previous.multiply_ratio(9_900u128, 10_000u128));
+        prices.push(price);
+        previous = price;
+    }
+    assert_ne!(prices.first(), prices.last());
+
+    // reloading the same seed gives the same path
+    let msg = pricefeed.set_price_path(path).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let price = pricefeed.get_price(&router, "ETH".to_string()).unwrap();
+    assert_eq!(price, *prices.last().unwrap());
+
+    // clearing the path falls back to the appended price
+    let msg = pricefeed.clear_price_path().unwrap();
+    router.execute(owner, msg).unwrap();
+
+    let price = pricefeed.get_price(&router, "ETH".to_string()).unwrap();
+    assert_eq!(price, to_decimals(10u64));
+}
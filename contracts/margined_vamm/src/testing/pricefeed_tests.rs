@@ -0,0 +1,83 @@
+use cosmwasm_std::{Addr, Empty};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use margined_perp::margined_pricefeed::InstantiateMsg as PricefeedInstantiateMsg;
+use margined_utils::{contracts::helpers::PricefeedController, scenarios::VammScenario};
+
+fn contract_pricefeed() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new_with_empty(
+        margined_pricefeed::contract::execute,
+        margined_pricefeed::contract::instantiate,
+        margined_pricefeed::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn instantiate_pricefeed(router: &mut App, owner: &Addr) -> PricefeedController {
+    let pricefeed_id = router.store_code(contract_pricefeed());
+    let pricefeed_addr = router
+        .instantiate_contract(
+            pricefeed_id,
+            owner.clone(),
+            &PricefeedInstantiateMsg {
+                oracle_hub_contract: "oracle_hub0000".to_string(),
+            },
+            &[],
+            "pricefeed",
+            None,
+        )
+        .unwrap();
+
+    PricefeedController(pricefeed_addr)
+}
+
+#[test]
+fn test_update_pricefeed_requires_registered_feed() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let pricefeed = instantiate_pricefeed(&mut router, &owner);
+
+    // the base asset of the vamm is not registered
+    let msg = vamm.set_pricefeed(pricefeed.addr().to_string()).unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert!(err.root_cause().to_string().contains("Feed not registered"));
+
+    // the feed has fewer decimals than the vamm
+    let msg = pricefeed
+        .register_feed("USD".to_string(), 6, "USD".to_string(), 0, vec![])
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm.set_pricefeed(pricefeed.addr().to_string()).unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Pricefeed decimals do not match the vAMM"
+    );
+
+    let pricefeed = instantiate_pricefeed(&mut router, &owner);
+    let msg = pricefeed
+        .register_feed("USD".to_string(), 9, "USD".to_string(), 0, vec![])
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    // the quote asset of the vamm is not registered
+    let msg = vamm.set_pricefeed(pricefeed.addr().to_string()).unwrap();
+    let err = router.execute(owner.clone(), msg).unwrap_err();
+    assert!(err.root_cause().to_string().contains("Feed not registered"));
+
+    let msg = pricefeed
+        .register_feed("ETH".to_string(), 9, "ETH".to_string(), 0, vec![])
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let msg = vamm.set_pricefeed(pricefeed.addr().to_string()).unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let config = vamm.config(&router).unwrap();
+    assert_eq!(config.pricefeed, pricefeed.addr());
+}
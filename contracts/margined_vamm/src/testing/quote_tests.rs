@@ -0,0 +1,147 @@
+use cosmwasm_std::{StdError, Uint128};
+use cw_multi_test::Executor;
+use margined_perp::margined_vamm::{AmountToPriceResponse, Direction, QuoteResponse};
+use margined_utils::scenarios::{to_decimals, VammScenario};
+
+#[test]
+fn test_quote_quote_amount() {
+    let VammScenario { router, vamm, .. } = VammScenario::new();
+
+    // buying with 250 moves the reserves to 1250 : 80
+    let quote = vamm
+        .quote(&router, Direction::AddToAmm, Some(to_decimals(250)), None)
+        .unwrap();
+    assert_eq!(
+        quote,
+        QuoteResponse {
+            quote_asset_amount: to_decimals(250),
+            base_asset_amount: to_decimals(20),
+            average_price: Uint128::from(12_500_000_000u128),
+            spot_price_after: Uint128::from(15_625_000_000u128),
+            price_impact_bps: Uint128::from(5_625u128),
+            is_over_fluctuation_limit: true,
+        }
+    );
+
+    // selling 200 moves the reserves to 800 : 125
+    let quote = vamm
+        .quote(
+            &router,
+            Direction::RemoveFromAmm,
+            Some(to_decimals(200)),
+            None,
+        )
+        .unwrap();
+    assert_eq!(
+        quote,
+        QuoteResponse {
+            quote_asset_amount: to_decimals(200),
+            base_asset_amount: to_decimals(25),
+            average_price: to_decimals(8),
+            spot_price_after: Uint128::from(6_400_000_000u128),
+            price_impact_bps: Uint128::from(3_600u128),
+            is_over_fluctuation_limit: true,
+        }
+    );
+}
+
+#[test]
+fn test_quote_base_amount() {
+    let VammScenario { router, vamm, .. } = VammScenario::new();
+
+    let quote = vamm
+        .quote(&router, Direction::AddToAmm, None, Some(to_decimals(20)))
+        .unwrap();
+    assert_eq!(quote.quote_asset_amount, to_decimals(250));
+    assert_eq!(quote.base_asset_amount, to_decimals(20));
+    assert_eq!(quote.spot_price_after, Uint128::from(15_625_000_000u128));
+
+    // a small trade stays within the fluctuation limit
+    let quote = vamm
+        .quote(
+            &router,
+            Direction::AddToAmm,
+            None,
+            Some(Uint128::from(10_000_000u128)),
+        )
+        .unwrap();
+    assert_eq!(quote.price_impact_bps, Uint128::from(2u128));
+    assert!(!quote.is_over_fluctuation_limit);
+}
+
+#[test]
+fn test_force_error_quote_needs_one_amount() {
+    let VammScenario { router, vamm, .. } = VammScenario::new();
+
+    let err = vamm
+        .quote(&router, Direction::AddToAmm, None, None)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            "Querier contract error: Generic error: either quote amount or base amount must be given"
+        )
+    );
+
+    let err = vamm
+        .quote(
+            &router,
+            Direction::AddToAmm,
+            Some(to_decimals(10)),
+            Some(to_decimals(1)),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            "Querier contract error: Generic error: either quote amount or base amount must be given"
+        )
+    );
+}
+
+#[test]
+fn test_amount_to_price() {
+    let VammScenario {
+        mut router,
+        owner,
+        vamm,
+        ..
+    } = VammScenario::new();
+
+    let result = vamm.amount_to_price(&router, to_decimals(40)).unwrap();
+    assert_eq!(
+        result,
+        AmountToPriceResponse {
+            direction: Direction::AddToAmm,
+            quote_asset_amount: to_decimals(1_000),
+            base_asset_amount: to_decimals(50),
+        }
+    );
+
+    let result = vamm
+        .amount_to_price(&router, Uint128::from(2_500_000_000u128))
+        .unwrap();
+    assert_eq!(
+        result,
+        AmountToPriceResponse {
+            direction: Direction::RemoveFromAmm,
+            quote_asset_amount: to_decimals(500),
+            base_asset_amount: to_decimals(100),
+        }
+    );
+
+    // executing the swap moves the spot price to the target
+    let result = vamm.amount_to_price(&router, to_decimals(40)).unwrap();
+    let msg = vamm
+        .swap_input(
+            result.direction,
+            result.quote_asset_amount,
+            Uint128::zero(),
+            true,
+        )
+        .unwrap();
+    router.execute(owner.clone(), msg).unwrap();
+
+    let price = vamm.spot_price(&router).unwrap();
+    assert_eq!(price, to_decimals(40));
+}
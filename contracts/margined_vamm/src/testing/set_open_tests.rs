@@ -16,6 +16,8 @@ fn test_set_open_admin_open_amm() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -46,6 +48,8 @@ fn test_set_open_init_next_funding_time_zero() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -71,6 +75,8 @@ fn test_set_open_admin_open_updates_next_funding_time() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -104,6 +110,8 @@ fn test_set_open_admin_closes_amm() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -138,6 +146,8 @@ fn test_set_open_cant_do_anything_when_its_beginning() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -192,6 +202,8 @@ fn test_set_open_cant_do_anything_when_closed() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
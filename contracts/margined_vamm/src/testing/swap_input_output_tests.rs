@@ -3,7 +3,7 @@ use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 use cosmwasm_std::{from_binary, Uint128};
 use margined_common::integer::Integer;
 use margined_perp::margined_vamm::{
-    Direction, ExecuteMsg, InstantiateMsg, QueryMsg, StateResponse,
+    Direction, ExecuteMsg, InstantiateMsg, QueryMsg, StateResponse, SwapResult, SwapType,
 };
 use margined_utils::scenarios::to_decimals;
 
@@ -17,6 +17,8 @@ fn test_use_getoutputprice_use_to_swapinput_long() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::from(500_000_000u128), // 0.5
@@ -97,6 +99,8 @@ fn test_use_getoutputprice_use_to_swapinput_short() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::from(500_000_000u128), // 0.5
@@ -177,6 +181,8 @@ fn test_use_getinputprice_long_use_to_swapoutput() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::from(500_000_000u128), // 0.5
@@ -256,6 +262,8 @@ fn test_use_getinputprice_short_use_to_swapoutput() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::from(500_000_000u128), // 0.5
@@ -335,6 +343,8 @@ fn test_swap_input_twice_short_long() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -387,6 +397,8 @@ fn test_swap_input_twice_short_long() {
             total_position_size: Integer::new_negative(1u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -401,6 +413,8 @@ fn test_swap_input_twice_long_short() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -453,6 +467,8 @@ fn test_swap_input_twice_long_short() {
             total_position_size: Integer::new_negative(1u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -467,6 +483,8 @@ fn test_swap_output_twice_short_long() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -517,6 +535,8 @@ fn test_swap_output_twice_short_long() {
             total_position_size: Integer::default(),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -531,6 +551,8 @@ fn test_swap_output_twice_long_short() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -581,6 +603,77 @@ fn test_swap_output_twice_long_short() {
             total_position_size: Integer::default(),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
+        }
+    );
+}
+
+#[test]
+fn test_swap_returns_swap_result_data() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        decimals: 9u8,
+        quote_asset: "ETH".to_string(),
+        base_asset: "USD".to_string(),
+        quote_asset_reserve: to_decimals(1000),
+        base_asset_reserve: to_decimals(100),
+        funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
+        toll_ratio: Uint128::zero(),
+        spread_ratio: Uint128::zero(),
+        fluctuation_limit_ratio: Uint128::zero(),
+        pricefeed: "oracle".to_string(),
+        margin_engine: Some("addr0000".to_string()),
+        insurance_fund: Some("insurance_fund".to_string()),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("addr0000", &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::SetOpen { open: true },
+    )
+    .unwrap();
+
+    let swap_msg = ExecuteMsg::SwapInput {
+        direction: Direction::AddToAmm,
+        quote_asset_amount: to_decimals(250),
+        base_asset_limit: Uint128::zero(),
+        can_go_over_fluctuation: false,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, swap_msg).unwrap();
+    let result: SwapResult = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(
+        result,
+        SwapResult {
+            swap_type: SwapType::Input,
+            direction: Direction::AddToAmm,
+            quote_asset_amount: to_decimals(250),
+            base_asset_amount: to_decimals(20),
+        }
+    );
+
+    let swap_msg = ExecuteMsg::SwapOutput {
+        direction: Direction::AddToAmm,
+        base_asset_amount: to_decimals(20),
+        quote_asset_limit: Uint128::zero(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, swap_msg).unwrap();
+    let result: SwapResult = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(
+        result,
+        SwapResult {
+            swap_type: SwapType::Output,
+            direction: Direction::AddToAmm,
+            quote_asset_amount: to_decimals(250),
+            base_asset_amount: to_decimals(20),
         }
     );
 }
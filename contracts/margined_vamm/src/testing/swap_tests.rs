@@ -5,7 +5,8 @@ use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 use cosmwasm_std::{from_binary, Addr, Uint128};
 use margined_common::integer::Integer;
 use margined_perp::margined_vamm::{
-    ConfigResponse, Direction, ExecuteMsg, InstantiateMsg, OwnerResponse, QueryMsg, StateResponse,
+    ConfigResponse, CurveType, Direction, ExecuteMsg, FundingMode, InstantiateMsg, OwnerResponse,
+    QueryMsg, StateResponse, TradingSchedule,
 };
 use margined_utils::scenarios::{parse_event, to_decimals, DECIMAL_MULTIPLIER};
 
@@ -19,6 +20,8 @@ fn test_instantiation() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -40,13 +43,24 @@ fn test_instantiation() {
             base_asset: "USD".to_string(),
             toll_ratio: Uint128::zero(),
             spread_ratio: Uint128::zero(),
+            skew_fee_ratio: Uint128::zero(),
             fluctuation_limit_ratio: Uint128::zero(),
+            max_oracle_spread_ratio: Uint128::from(100_000_000u128),
+            max_oracle_deviation_ratio: Uint128::from(100_000_000u128),
+            oracle_heartbeat: 86_400u64,
             decimals: DECIMAL_MULTIPLIER,
             margin_engine: Addr::unchecked("addr0000".to_string()),
             insurance_fund: Addr::unchecked("insurance_fund".to_string()),
             pricefeed: Addr::unchecked("oracle".to_string()),
             funding_period: 3_600u64,
+            funding_mode: FundingMode::Periodic,
             spot_price_twap_interval: ONE_HOUR_IN_SECONDS,
+            expiry: None,
+            expiry_cutoff_period: ONE_HOUR_IN_SECONDS,
+            trading_schedule: TradingSchedule::default(),
+            curve: CurveType::ConstantProduct,
+            circuit_breaker_window: 0u64,
+            circuit_breaker_ratio: Uint128::zero(),
         }
     );
 
@@ -61,6 +75,8 @@ fn test_instantiation() {
             total_position_size: Integer::default(),
             funding_rate: Integer::zero(),
             next_funding_time: 0u64,
+            last_funding_time: 0u64,
+            settlement_price: None,
         }
     );
 }
@@ -75,6 +91,8 @@ fn test_update_owner() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -111,6 +129,8 @@ fn test_bad_decimals() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -138,6 +158,8 @@ fn test_bad_reserves() {
         quote_asset_reserve: Uint128::from(100u128),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -161,6 +183,8 @@ fn test_bad_reserves() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: Uint128::from(10_000u128),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -190,6 +214,8 @@ fn test_bad_asset_strings() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -211,6 +237,8 @@ fn test_bad_asset_strings() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -235,6 +263,8 @@ fn test_bad_twap_interval() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -256,6 +286,15 @@ fn test_bad_twap_interval() {
         insurance_fund: None,
         pricefeed: None,
         spot_price_twap_interval: Some(59u64),
+        funding_mode: None,
+        max_oracle_spread_ratio: None,
+        skew_fee_ratio: None,
+        oracle_heartbeat: None,
+        max_oracle_deviation_ratio: None,
+        expiry_cutoff_period: None,
+        trading_schedule: None,
+        circuit_breaker_window: None,
+        circuit_breaker_ratio: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -277,6 +316,15 @@ fn test_bad_twap_interval() {
         insurance_fund: None,
         pricefeed: None,
         spot_price_twap_interval: Some(ONE_WEEK_IN_SECONDS + 1),
+        funding_mode: None,
+        max_oracle_spread_ratio: None,
+        skew_fee_ratio: None,
+        oracle_heartbeat: None,
+        max_oracle_deviation_ratio: None,
+        expiry_cutoff_period: None,
+        trading_schedule: None,
+        circuit_breaker_window: None,
+        circuit_breaker_ratio: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -298,6 +346,8 @@ fn test_update_config() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -319,6 +369,15 @@ fn test_update_config() {
         insurance_fund: Some("new_insurance_fund".to_string()),
         pricefeed: None,
         spot_price_twap_interval: Some(ONE_MINUTE_IN_SECONDS),
+        funding_mode: None,
+        max_oracle_spread_ratio: None,
+        skew_fee_ratio: None,
+        oracle_heartbeat: None,
+        max_oracle_deviation_ratio: None,
+        expiry_cutoff_period: None,
+        trading_schedule: None,
+        circuit_breaker_window: None,
+        circuit_breaker_ratio: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -335,13 +394,24 @@ fn test_update_config() {
             base_asset: "USD".to_string(),
             toll_ratio: Uint128::zero(),
             spread_ratio: Uint128::zero(),
+            skew_fee_ratio: Uint128::zero(),
             fluctuation_limit_ratio: Uint128::zero(),
+            max_oracle_spread_ratio: Uint128::from(100_000_000u128),
+            max_oracle_deviation_ratio: Uint128::from(100_000_000u128),
+            oracle_heartbeat: 86_400u64,
             decimals: DECIMAL_MULTIPLIER,
             margin_engine: Addr::unchecked("addr0001".to_string()),
             insurance_fund: Addr::unchecked("new_insurance_fund".to_string()),
             pricefeed: Addr::unchecked("oracle".to_string()),
             funding_period: 3_600u64,
+            funding_mode: FundingMode::Periodic,
             spot_price_twap_interval: ONE_MINUTE_IN_SECONDS,
+            expiry: None,
+            expiry_cutoff_period: ONE_HOUR_IN_SECONDS,
+            trading_schedule: TradingSchedule::default(),
+            curve: CurveType::ConstantProduct,
+            circuit_breaker_window: 0u64,
+            circuit_breaker_ratio: Uint128::zero(),
         }
     );
 }
@@ -356,6 +426,8 @@ fn test_update_config_fail() {
         quote_asset_reserve: to_decimals(100),
         base_asset_reserve: to_decimals(10_000),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -377,6 +449,15 @@ fn test_update_config_fail() {
         insurance_fund: None,
         pricefeed: None,
         spot_price_twap_interval: None,
+        funding_mode: None,
+        max_oracle_spread_ratio: None,
+        skew_fee_ratio: None,
+        oracle_heartbeat: None,
+        max_oracle_deviation_ratio: None,
+        expiry_cutoff_period: None,
+        trading_schedule: None,
+        circuit_breaker_window: None,
+        circuit_breaker_ratio: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -397,6 +478,8 @@ fn test_swap_input_zero_amount() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -438,6 +521,8 @@ fn test_swap_input_zero_amount() {
             total_position_size: Integer::zero(),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -452,6 +537,8 @@ fn test_swap_output_zero_amount() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -492,6 +579,8 @@ fn test_swap_output_zero_amount() {
             total_position_size: Integer::zero(),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -506,6 +595,8 @@ fn test_swap_input_long() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -547,6 +638,8 @@ fn test_swap_input_long() {
             total_position_size: Integer::new_positive(37_500_000_000u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -561,6 +654,8 @@ fn test_swap_input_short() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -602,6 +697,8 @@ fn test_swap_input_short() {
             total_position_size: Integer::new_negative(to_decimals(150)),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -616,6 +713,8 @@ fn test_swap_output_short() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -656,6 +755,8 @@ fn test_swap_output_short() {
             total_position_size: Integer::new_negative(to_decimals(150)),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -670,6 +771,8 @@ fn test_swap_output_long() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -710,6 +813,8 @@ fn test_swap_output_long() {
             total_position_size: Integer::new_positive(to_decimals(50)),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -724,6 +829,8 @@ fn test_swap_input_short_long() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -766,6 +873,8 @@ fn test_swap_input_short_long() {
             total_position_size: Integer::new_negative(92_307_692_308u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 
@@ -790,6 +899,8 @@ fn test_swap_input_short_long() {
             total_position_size: Integer::new_positive(32_432_432_432u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -804,6 +915,8 @@ fn test_swap_input_short_long_long() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -845,6 +958,8 @@ fn test_swap_input_short_long_long() {
             total_position_size: Integer::new_negative(25_000_000_000u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 
@@ -869,6 +984,8 @@ fn test_swap_input_short_long_long() {
             total_position_size: Integer::new_negative(11_111_111_112u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 
@@ -893,6 +1010,8 @@ fn test_swap_input_short_long_long() {
             total_position_size: Integer::new_positive(90_909_090_90u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -907,6 +1026,8 @@ fn test_swap_input_short_long_short() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -948,6 +1069,8 @@ fn test_swap_input_short_long_short() {
             total_position_size: Integer::new_negative(25_000_000_000u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 
@@ -972,6 +1095,8 @@ fn test_swap_input_short_long_short() {
             total_position_size: Integer::new_positive(20_000_000_000u128),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 
@@ -996,6 +1121,8 @@ fn test_swap_input_short_long_short() {
             total_position_size: Integer::default(),
             funding_rate: Integer::zero(),
             next_funding_time: 1_571_801_019u64,
+            last_funding_time: 1_571_797_419u64,
+            settlement_price: None,
         }
     );
 }
@@ -1010,6 +1137,8 @@ fn test_swap_output_short_and_indivisable() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1070,6 +1199,8 @@ fn test_swap_output_long_and_indivisable() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1130,6 +1261,8 @@ fn test_swap_output_long_short_same_size_should_get_diff_base_asset_amount() {
         quote_asset_reserve: to_decimals(1000),
         base_asset_reserve: to_decimals(100),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1203,6 +1336,8 @@ fn test_force_error_swapinput_long_but_less_than_min_base_amount() {
         quote_asset_reserve: to_decimals(1250),
         base_asset_reserve: to_decimals(80),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1249,6 +1384,8 @@ fn test_force_error_swapinput_short_but_more_than_min_base_amount() {
         quote_asset_reserve: to_decimals(800),
         base_asset_reserve: to_decimals(125),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1295,6 +1432,8 @@ fn test_swapoutput_short_slippage_limit() {
         quote_asset_reserve: to_decimals(1250),
         base_asset_reserve: to_decimals(80),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1339,6 +1478,8 @@ fn test_swapoutput_short_at_slippage_limit() {
         quote_asset_reserve: to_decimals(1250),
         base_asset_reserve: to_decimals(80),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1383,6 +1524,8 @@ fn test_swapoutput_short_force_error_min_quote_251() {
         quote_asset_reserve: to_decimals(1250),
         base_asset_reserve: to_decimals(80),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1426,6 +1569,8 @@ fn test_swapoutput_short_force_error_min_quote_400() {
         quote_asset_reserve: to_decimals(1250),
         base_asset_reserve: to_decimals(80),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1469,6 +1614,8 @@ fn test_swapoutput_long_slippage_limit() {
         quote_asset_reserve: to_decimals(800),
         base_asset_reserve: to_decimals(125),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1513,6 +1660,8 @@ fn test_swapoutput_long_at_slippage_limit() {
         quote_asset_reserve: to_decimals(800),
         base_asset_reserve: to_decimals(125),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1557,6 +1706,8 @@ fn test_swapoutput_long_force_error_min_quote_199() {
         quote_asset_reserve: to_decimals(800),
         base_asset_reserve: to_decimals(125),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
@@ -1600,6 +1751,8 @@ fn test_swapoutput_long_force_error_min_quote_100() {
         quote_asset_reserve: to_decimals(800),
         base_asset_reserve: to_decimals(125),
         funding_period: 3_600_u64,
+        expiry: None,
+        curve: None,
         toll_ratio: Uint128::zero(),
         spread_ratio: Uint128::zero(),
         fluctuation_limit_ratio: Uint128::zero(),
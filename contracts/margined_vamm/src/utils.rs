@@ -1,8 +1,13 @@
 use cosmwasm_std::{Addr, Deps, Env, Response, StdError, StdResult, Storage, Uint128};
-use margined_perp::margined_vamm::Direction;
+use margined_common::integer::Integer;
+use margined_perp::margined_vamm::{Direction, OracleStatusResponse, TradingSchedule};
 
 use crate::{
+    contract::{ONE_DAY_IN_SECONDS, ONE_WEEK_IN_SECONDS},
+    curve::get_curve,
     handle::{get_input_price_with_reserves, get_output_price_with_reserves},
+    querier::{query_underlying_price_data, query_underlying_twap_price},
+    query::query_twap_price,
     state::{
         read_config, read_reserve_snapshot, read_reserve_snapshot_counter, read_state,
         store_reserve_snapshot, update_current_reserve_snapshot, Config, ReserveSnapshot,
@@ -47,6 +52,179 @@ pub fn require_open(open: bool) -> StdResult<Response> {
     Ok(Response::new())
 }
 
+pub fn require_not_expired(expiry: Option<u64>, env: &Env) -> StdResult<Response> {
+    // check that a dated vamm has not passed its expiry
+    if let Some(expiry) = expiry {
+        if env.block.time.seconds() >= expiry {
+            return Err(StdError::generic_err("amm has expired"));
+        }
+    }
+
+    Ok(Response::new())
+}
+
+// the unix epoch is a Thursday so weeks are offset by three days to start on a Monday
+const WEEK_START_OFFSET: u64 = 3 * ONE_DAY_IN_SECONDS;
+
+// number of weeks ahead that the schedule is searched for the next open or close
+const SCHEDULE_LOOKAHEAD_WEEKS: u64 = 53;
+
+// most holidays a schedule can have, old holidays can be removed when new ones are added
+const MAX_TRADING_HOLIDAYS: usize = 64;
+
+pub fn validate_trading_schedule(schedule: &TradingSchedule) -> StdResult<Response> {
+    for session in schedule.sessions.iter() {
+        if session.open >= session.close || session.close > ONE_WEEK_IN_SECONDS {
+            return Err(StdError::generic_err("invalid trading session"));
+        }
+    }
+
+    if schedule.holidays.len() > MAX_TRADING_HOLIDAYS {
+        return Err(StdError::generic_err("too many trading holidays"));
+    }
+
+    for holiday in schedule.holidays.iter() {
+        if holiday.start >= holiday.end {
+            return Err(StdError::generic_err("invalid trading holiday"));
+        }
+    }
+
+    Ok(Response::new())
+}
+
+/// Returns true if the schedule has the market open at the given time
+pub fn is_in_session(schedule: &TradingSchedule, time: u64) -> bool {
+    if schedule
+        .holidays
+        .iter()
+        .any(|holiday| holiday.start <= time && time < holiday.end)
+    {
+        return false;
+    }
+
+    if schedule.sessions.is_empty() {
+        return true;
+    }
+
+    let seconds_into_week = (time + WEEK_START_OFFSET) % ONE_WEEK_IN_SECONDS;
+
+    schedule
+        .sessions
+        .iter()
+        .any(|session| session.open <= seconds_into_week && seconds_into_week < session.close)
+}
+
+/// Returns the sessions of the schedule that overlap the given range as sorted and merged
+/// unix timestamp intervals with the holidays removed, intervals are not cut at the range
+pub fn calc_session_intervals(schedule: &TradingSchedule, from: u64, to: u64) -> Vec<(u64, u64)> {
+    let mut intervals: Vec<(u64, u64)> = if schedule.sessions.is_empty() {
+        vec![(from, to)]
+    } else {
+        let mut intervals = vec![];
+        let mut start = from.saturating_sub((from + WEEK_START_OFFSET) % ONE_WEEK_IN_SECONDS);
+        while start < to {
+            for session in schedule.sessions.iter() {
+                let (open, close) = (start + session.open, start + session.close);
+                if open < to && from < close {
+                    intervals.push((open, close));
+                }
+            }
+            start += ONE_WEEK_IN_SECONDS;
+        }
+        intervals
+    };
+
+    // merge sessions that overlap or follow on from one another
+    intervals.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = vec![];
+    for (open, close) in intervals {
+        match merged.last_mut() {
+            Some(last) if open <= last.1 => last.1 = last.1.max(close),
+            _ => merged.push((open, close)),
+        }
+    }
+
+    // remove any holidays
+    for holiday in schedule.holidays.iter() {
+        if holiday.end <= from || to <= holiday.start {
+            continue;
+        }
+
+        merged = merged
+            .into_iter()
+            .flat_map(|(open, close)| {
+                if holiday.end <= open || close <= holiday.start {
+                    return vec![(open, close)];
+                }
+
+                let mut remaining = vec![];
+                if open < holiday.start {
+                    remaining.push((open, holiday.start));
+                }
+                if holiday.end < close {
+                    remaining.push((holiday.end, close));
+                }
+                remaining
+            })
+            .collect();
+    }
+
+    merged
+}
+
+/// Returns the time the market was in session between the two times, funding only
+/// accrues for this time
+pub fn calc_session_time(schedule: &TradingSchedule, from: u64, to: u64) -> u64 {
+    if to <= from {
+        return 0;
+    }
+
+    calc_session_intervals(schedule, from, to)
+        .into_iter()
+        .map(|(open, close)| close.min(to).saturating_sub(open.max(from)))
+        .sum()
+}
+
+/// Returns the next times the market opens and closes, searching ahead a week at first and
+/// widening up to the lookahead, a close beyond the lookahead is returned as none
+pub fn calc_next_open_and_close(
+    schedule: &TradingSchedule,
+    time: u64,
+) -> (Option<u64>, Option<u64>) {
+    let mut weeks = 1u64;
+    loop {
+        let to = time + weeks * ONE_WEEK_IN_SECONDS;
+        let intervals = calc_session_intervals(schedule, time, to);
+
+        let next_open = intervals
+            .iter()
+            .find(|(open, _)| *open > time)
+            .map(|(open, _)| *open);
+
+        // a close at the end of the range may carry on into the next one
+        let next_close = intervals
+            .iter()
+            .find(|(_, close)| *close > time)
+            .map(|(_, close)| *close)
+            .filter(|close| *close < to);
+
+        if (next_open.is_some() && next_close.is_some()) || weeks >= SCHEDULE_LOOKAHEAD_WEEKS {
+            return (next_open, next_close);
+        }
+
+        weeks = (weeks * 2).min(SCHEDULE_LOOKAHEAD_WEEKS);
+    }
+}
+
+pub fn require_in_session(schedule: &TradingSchedule, env: &Env) -> StdResult<Response> {
+    // reducing trades are left to the margin engine to enforce
+    if !schedule.reduce_only_when_closed && !is_in_session(schedule, env.block.time.seconds()) {
+        return Err(StdError::generic_err("market is closed"));
+    }
+
+    Ok(Response::new())
+}
+
 pub fn check_is_over_block_fluctuation_limit(
     storage: &mut dyn Storage,
     env: Env,
@@ -64,10 +242,8 @@ pub fn check_is_over_block_fluctuation_limit(
 
     let (upper_limit, lower_limit) = price_boundaries_of_last_block(storage, env)?;
 
-    let current_price = state
-        .quote_asset_reserve
-        .checked_mul(config.decimals)?
-        .checked_div(state.base_asset_reserve)?;
+    let curve = get_curve(&config);
+    let current_price = curve.spot_price(state.quote_asset_reserve, state.base_asset_reserve)?;
 
     // ensure that the latest price isn't over the limit which would restrict any further
     // swaps from occurring in this block
@@ -79,17 +255,15 @@ pub fn check_is_over_block_fluctuation_limit(
 
     if !can_go_over_limit {
         let price = if direction == Direction::AddToAmm {
-            state
-                .quote_asset_reserve
-                .checked_add(quote_asset_amount)?
-                .checked_mul(config.decimals)?
-                .checked_div(state.base_asset_reserve.checked_sub(base_asset_amount)?)
+            curve.spot_price(
+                state.quote_asset_reserve.checked_add(quote_asset_amount)?,
+                state.base_asset_reserve.checked_sub(base_asset_amount)?,
+            )
         } else {
-            state
-                .quote_asset_reserve
-                .checked_sub(quote_asset_amount)?
-                .checked_mul(config.decimals)?
-                .checked_div(state.base_asset_reserve.checked_add(base_asset_amount)?)
+            curve.spot_price(
+                state.quote_asset_reserve.checked_sub(quote_asset_amount)?,
+                state.base_asset_reserve.checked_add(base_asset_amount)?,
+            )
         }?;
         if price > upper_limit || price < lower_limit {
             return Err(StdError::generic_err("price is over fluctuation limit"));
@@ -113,10 +287,10 @@ pub fn price_boundaries_of_last_block(
         latest_snapshot = read_reserve_snapshot(storage, height - 1u64)?;
     }
 
-    let last_price = latest_snapshot
-        .quote_asset_reserve
-        .checked_mul(config.decimals)?
-        .checked_div(latest_snapshot.base_asset_reserve)?;
+    let last_price = get_curve(&config).spot_price(
+        latest_snapshot.quote_asset_reserve,
+        latest_snapshot.base_asset_reserve,
+    )?;
 
     let upper_limit = last_price
         .checked_mul(config.decimals + config.fluctuation_limit_ratio)?
@@ -128,6 +302,181 @@ pub fn price_boundaries_of_last_block(
     Ok((upper_limit, lower_limit))
 }
 
+/// Returns the spot price at the start of the circuit breaker window, i.e. that of the latest
+/// snapshot taken at or before it, or of the first snapshot if the vamm is younger
+pub fn calc_circuit_breaker_reference_price(
+    storage: &dyn Storage,
+    env: &Env,
+) -> StdResult<Uint128> {
+    let config = read_config(storage)?;
+
+    let window_start = env
+        .block
+        .time
+        .seconds()
+        .saturating_sub(config.circuit_breaker_window);
+
+    // snapshots are in time order so the latest one at or before the window start is found
+    // with a binary search, keeping the cost logarithmic in the number of snapshots
+    let mut low = 1u64;
+    let mut high = read_reserve_snapshot_counter(storage)?;
+
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+
+        if read_reserve_snapshot(storage, mid)?.timestamp.seconds() <= window_start {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    let snapshot = read_reserve_snapshot(storage, low)?;
+
+    get_curve(&config).spot_price(snapshot.quote_asset_reserve, snapshot.base_asset_reserve)
+}
+
+/// Returns true if the spot price has moved by more than the circuit breaker ratio from the
+/// reference price, it clears once the window has rolled past the move
+pub fn is_circuit_breaker_tripped(storage: &dyn Storage, env: &Env) -> StdResult<bool> {
+    let config = read_config(storage)?;
+
+    // a zero window or ratio disables the circuit breaker
+    if config.circuit_breaker_window == 0 || config.circuit_breaker_ratio.is_zero() {
+        return Ok(false);
+    }
+
+    let state = read_state(storage)?;
+
+    let spot_price =
+        get_curve(&config).spot_price(state.quote_asset_reserve, state.base_asset_reserve)?;
+    let reference_price = calc_circuit_breaker_reference_price(storage, env)?;
+
+    let delta = if spot_price > reference_price {
+        spot_price - reference_price
+    } else {
+        reference_price - spot_price
+    };
+
+    Ok(delta.checked_mul(config.decimals)?
+        > reference_price.checked_mul(config.circuit_breaker_ratio)?)
+}
+
+/// Returns the spread ratio adjusted by the long/short skew of the previous block, trades
+/// that increase the skew pay more and trades that rebalance it pay less
+pub fn calc_skew_adjusted_spread_ratio(
+    storage: &dyn Storage,
+    env: Env,
+    direction: Direction,
+) -> StdResult<Uint128> {
+    let config = read_config(storage)?;
+
+    if config.skew_fee_ratio.is_zero() {
+        return Ok(config.spread_ratio);
+    }
+
+    let state = read_state(storage)?;
+
+    // the skew is taken from the previous block so that the fee is the same before and after
+    // a swap in this block, i.e. it can be calculated ahead of time for native token transfers
+    let height = read_reserve_snapshot_counter(storage)?;
+    let mut latest_snapshot = read_reserve_snapshot(storage, height)?;
+
+    if latest_snapshot.block_height == env.block.height {
+        // still in the block the vamm was created in, so there was no skew
+        if height == 1 {
+            return Ok(config.spread_ratio);
+        }
+
+        latest_snapshot = read_reserve_snapshot(storage, height - 1u64)?;
+    }
+
+    // total position size plus the base reserve is constant across swaps so the skew of the
+    // previous block can be recovered from its base reserve
+    let skew = state.total_position_size + Integer::new_positive(state.base_asset_reserve)
+        - Integer::new_positive(latest_snapshot.base_asset_reserve);
+
+    if skew.is_zero() {
+        return Ok(config.spread_ratio);
+    }
+
+    let adjustment = skew
+        .value
+        .checked_mul(config.skew_fee_ratio)?
+        .checked_div(latest_snapshot.base_asset_reserve)?;
+
+    let is_increasing_skew = (skew.is_positive() && direction == Direction::AddToAmm)
+        || (skew.is_negative() && direction == Direction::RemoveFromAmm);
+
+    let spread_ratio = if is_increasing_skew {
+        config
+            .spread_ratio
+            .checked_add(adjustment)?
+            .min(config.decimals)
+    } else {
+        config.spread_ratio.saturating_sub(adjustment)
+    };
+
+    Ok(spread_ratio)
+}
+
+/// Returns the status of the oracle, it is stale if the latest price is older than the
+/// heartbeat and deviated if it moved from the previous price by more than the max ratio
+pub fn calc_oracle_status(deps: Deps, env: Env) -> StdResult<OracleStatusResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    let latest = query_underlying_price_data(&deps, Uint128::zero())?;
+    let timestamp = latest.timestamp.seconds();
+
+    // a zero heartbeat disables the staleness check
+    let is_stale = config.oracle_heartbeat != 0
+        && env.block.time.seconds().saturating_sub(timestamp) > config.oracle_heartbeat;
+
+    // compare against the previous round, if there is one
+    let mut deviation_ratio = Uint128::zero();
+    if latest.round_id > Uint128::from(1u128) {
+        let previous = query_underlying_price_data(&deps, Uint128::from(1u128))?;
+
+        if !previous.price.is_zero() {
+            let delta = if latest.price > previous.price {
+                latest.price.checked_sub(previous.price)?
+            } else {
+                previous.price.checked_sub(latest.price)?
+            };
+
+            deviation_ratio = delta
+                .checked_mul(config.decimals)?
+                .checked_div(previous.price)?;
+        }
+    }
+
+    // a zero max deviation ratio disables the deviation check
+    let is_deviated = !config.max_oracle_deviation_ratio.is_zero()
+        && deviation_ratio > config.max_oracle_deviation_ratio;
+
+    Ok(OracleStatusResponse {
+        price: latest.price,
+        timestamp,
+        deviation_ratio,
+        is_stale,
+        is_deviated,
+    })
+}
+
+/// Returns true if the oracle is stale or deviated, the oracle is only queried if either guard
+/// is enabled
+pub fn is_oracle_tripped(deps: Deps, env: Env) -> StdResult<bool> {
+    let config: Config = read_config(deps.storage)?;
+
+    if config.oracle_heartbeat == 0 && config.max_oracle_deviation_ratio.is_zero() {
+        return Ok(false);
+    }
+
+    let status = calc_oracle_status(deps, env)?;
+
+    Ok(status.is_stale || status.is_deviated)
+}
+
 pub fn add_reserve_snapshot(
     storage: &mut dyn Storage,
     env: Env,
@@ -165,10 +514,8 @@ pub fn get_price_with_specific_snapshot(
     // RESERVE_ASSET means price comes from quoteAssetReserve/baseAssetReserve
     // INPUT_ASSET means getInput/Output price with snapshot's reserve
     if params.opt == TwapCalcOption::Reserve {
-        let current_price = snapshot
-            .quote_asset_reserve
-            .checked_mul(config.decimals)?
-            .checked_div(snapshot.base_asset_reserve)?;
+        let current_price = get_curve(&config)
+            .spot_price(snapshot.quote_asset_reserve, snapshot.base_asset_reserve)?;
 
         return Ok(current_price);
     } else if params.opt == TwapCalcOption::Input {
@@ -271,6 +618,32 @@ pub fn calc_twap(
     Ok(weighted_price.checked_div(Uint128::from(interval))?)
 }
 
+/// Calculates the premium fraction owed over a funding period given in seconds,
+/// returns the premium fraction along with the oracle and vAMM twap prices used
+pub fn calc_premium_fraction(
+    deps: Deps,
+    env: Env,
+    funding_period: u64,
+) -> StdResult<(Integer, Uint128, Uint128)> {
+    let config: Config = read_config(deps.storage)?;
+
+    // twap price from oracle
+    let underlying_price: Uint128 =
+        query_underlying_twap_price(&deps, config.spot_price_twap_interval)?;
+
+    // twap price from here, i.e. the amm
+    let index_price: Uint128 = query_twap_price(deps, env, config.spot_price_twap_interval)?;
+
+    let premium =
+        Integer::new_positive(index_price).checked_sub(Integer::new_positive(underlying_price))?;
+
+    let premium_fraction = premium
+        .checked_mul(Integer::new_positive(funding_period))?
+        .checked_div(Integer::new_positive(ONE_DAY_IN_SECONDS))?;
+
+    Ok((premium_fraction, underlying_price, index_price))
+}
+
 /// Does the modulus (%) operator on Uint128.
 /// However it follows the design of the perpetual protocol decimals
 /// https://github.com/perpetual-protocol/perpetual-protocol/blob/release/v2.1.x/src/utils/Decimal.sol
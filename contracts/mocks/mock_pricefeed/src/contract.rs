@@ -11,6 +11,10 @@ use cosmwasm_storage::{singleton, singleton_read};
 
 pub static KEY_CONFIG: &[u8] = b"config";
 pub static KEY_PRICES: &[u8] = b"prices";
+pub static KEY_PRICE_DATA: &[u8] = b"price_data";
+pub static KEY_PRICE_PATH: &[u8] = b"price_path";
+
+const BPS_DENOMINATOR: u64 = 10_000;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -33,6 +37,37 @@ pub enum ExecuteMsg {
     UpdateConfig {
         owner: Option<String>,
     },
+    /// loads a price path that the price queries follow by block time
+    SetPricePath {
+        path: PricePath,
+    },
+    /// removes the price path so the queries use the appended prices again
+    ClearPricePath {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PricePoint {
+    pub timestamp: u64,
+    pub price: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PricePath {
+    /// interpolates linearly between the points
+    Linear { points: Vec<PricePoint> },
+    /// moves by a random return of up to `volatility_bps` every `interval`
+    /// seconds, the same seed always gives the same path
+    RandomWalk {
+        start_time: u64,
+        start_price: Uint128,
+        interval: u64,
+        steps: u32,
+        volatility_bps: u64,
+        seed: u64,
+    },
+    /// explicit `timestamp,price` lines, each price holds until the next
+    Points { csv: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -50,6 +85,18 @@ pub enum QueryMsg {
         key: String,
         interval: u64,
     },
+    GetTwapPriceAt {
+        key: String,
+        interval: u64,
+        timestamp: u64,
+    },
+    GetPriceData {
+        key: String,
+    },
+    GetPreviousPriceData {
+        key: String,
+        num_round_back: Uint128,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -92,6 +139,8 @@ pub fn execute(
             timestamps,
         } => append_multiple_price(deps, info, key, prices, timestamps),
         ExecuteMsg::UpdateConfig { owner } => update_config(deps, info, owner),
+        ExecuteMsg::SetPricePath { path } => set_price_path(deps, path),
+        ExecuteMsg::ClearPricePath {} => clear_price_path(deps),
     }
 }
 
@@ -150,19 +199,152 @@ pub fn append_multiple_price(
     Ok(Response::default())
 }
 
+/// this is a mock function that replaces the stored prices with a path
+/// evaluated at the block time, so tests only need to advance the block
+#[cfg(not(tarpaulin_include))]
+pub fn set_price_path(deps: DepsMut, path: PricePath) -> StdResult<Response> {
+    let path = match path {
+        PricePath::Linear { points } => Path {
+            interpolate: true,
+            points,
+        },
+        PricePath::RandomWalk {
+            start_time,
+            start_price,
+            interval,
+            steps,
+            volatility_bps,
+            seed,
+        } => Path {
+            interpolate: false,
+            points: random_walk(
+                start_time,
+                start_price,
+                interval,
+                steps,
+                volatility_bps,
+                seed,
+            )?,
+        },
+        PricePath::Points { csv } => Path {
+            interpolate: false,
+            points: parse_points(&csv)?,
+        },
+    };
+
+    if path.points.is_empty() {
+        return Err(StdError::generic_err("price path is empty"));
+    }
+    if path
+        .points
+        .windows(2)
+        .any(|pair| pair[0].timestamp >= pair[1].timestamp)
+    {
+        return Err(StdError::generic_err(
+            "price path timestamps must be increasing",
+        ));
+    }
+
+    singleton(deps.storage, KEY_PRICE_PATH).save(&path)?;
+
+    Ok(Response::new().add_attribute("action", "set_price_path"))
+}
+
+#[cfg(not(tarpaulin_include))]
+pub fn clear_price_path(deps: DepsMut) -> StdResult<Response> {
+    singleton::<Path>(deps.storage, KEY_PRICE_PATH).remove();
+
+    Ok(Response::new().add_attribute("action", "clear_price_path"))
+}
+
+#[cfg(not(tarpaulin_include))]
+fn random_walk(
+    start_time: u64,
+    start_price: Uint128,
+    interval: u64,
+    steps: u32,
+    volatility_bps: u64,
+    seed: u64,
+) -> StdResult<Vec<PricePoint>> {
+    if interval == 0 {
+        return Err(StdError::generic_err("interval must be greater than zero"));
+    }
+    if volatility_bps >= BPS_DENOMINATOR {
+        return Err(StdError::generic_err("volatility must be below 10000 bps"));
+    }
+
+    let mut state = seed;
+    let mut price = start_price;
+    let mut points = vec![PricePoint {
+        timestamp: start_time,
+        price,
+    }];
+    for step in 1..=u64::from(steps) {
+        // uniform return in [-volatility_bps, volatility_bps]
+        let shock = splitmix64(&mut state) % (2 * volatility_bps + 1);
+        price = price.multiply_ratio(BPS_DENOMINATOR - volatility_bps + shock, BPS_DENOMINATOR);
+        points.push(PricePoint {
+            timestamp: start_time + step * interval,
+            price,
+        });
+    }
+
+    Ok(points)
+}
+
+#[cfg(not(tarpaulin_include))]
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(not(tarpaulin_include))]
+fn parse_points(csv: &str) -> StdResult<Vec<PricePoint>> {
+    csv.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let invalid = || StdError::generic_err(format!("invalid price point: {}", line));
+            let (timestamp, price) = line.split_once(',').ok_or_else(invalid)?;
+            Ok(PricePoint {
+                timestamp: timestamp.trim().parse().map_err(|_| invalid())?,
+                price: Uint128::new(price.trim().parse().map_err(|_| invalid())?),
+            })
+        })
+        .collect()
+}
+
 #[cfg(not(tarpaulin_include))]
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::GetPrice { key } => to_binary(&query_get_price(deps, key)?),
+        QueryMsg::GetPrice { key } => to_binary(&query_get_price(deps, env, key)?),
         QueryMsg::GetPreviousPrice {
             key,
             num_round_back,
-        } => to_binary(&query_get_previous_price(deps, key, num_round_back)?),
+        } => to_binary(&query_get_previous_price(deps, env, key, num_round_back)?),
         QueryMsg::GetTwapPrice { key, interval } => {
             to_binary(&query_get_twap_price(deps, env, key, interval)?)
         }
+        QueryMsg::GetTwapPriceAt {
+            key,
+            interval,
+            timestamp,
+        } => to_binary(&query_get_twap_price_at(deps, key, interval, timestamp)?),
+        QueryMsg::GetPriceData { key } => to_binary(&query_get_price_data(deps, env, key)?),
+        QueryMsg::GetPreviousPriceData {
+            key,
+            num_round_back,
+        } => to_binary(&query_get_previous_price_data(
+            deps,
+            env,
+            key,
+            num_round_back,
+        )?),
     }
 }
 
@@ -178,7 +360,11 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 
 /// Queries latest price for pair stored with key
 #[cfg(not(tarpaulin_include))]
-pub fn query_get_price(deps: Deps, _key: String) -> StdResult<Uint128> {
+pub fn query_get_price(deps: Deps, env: Env, _key: String) -> StdResult<Uint128> {
+    if let Some(path) = read_price_path(deps.storage)? {
+        return Ok(path.price_at(env.block.time.seconds()));
+    }
+
     singleton_read(deps.storage, KEY_PRICES).load()
 }
 
@@ -186,20 +372,69 @@ pub fn query_get_price(deps: Deps, _key: String) -> StdResult<Uint128> {
 #[cfg(not(tarpaulin_include))]
 pub fn query_get_previous_price(
     deps: Deps,
-    _key: String,
-    _num_round_back: Uint128,
+    env: Env,
+    key: String,
+    num_round_back: Uint128,
 ) -> StdResult<Uint128> {
+    if read_price_path(deps.storage)?.is_some() {
+        return Ok(query_get_previous_price_data(deps, env, key, num_round_back)?.price);
+    }
+
     singleton_read(deps.storage, KEY_PRICES).load()
 }
 
+/// Queries latest price data, including the round and timestamp, for pair stored with key
+#[cfg(not(tarpaulin_include))]
+pub fn query_get_price_data(deps: Deps, env: Env, key: String) -> StdResult<PriceData> {
+    query_get_previous_price_data(deps, env, key, Uint128::zero())
+}
+
+/// Queries previous price data for pair stored with key
+#[cfg(not(tarpaulin_include))]
+pub fn query_get_previous_price_data(
+    deps: Deps,
+    env: Env,
+    _key: String,
+    num_round_back: Uint128,
+) -> StdResult<PriceData> {
+    let rounds = match read_price_path(deps.storage)? {
+        Some(path) => path.rounds_at(env.block.time.seconds()),
+        None => read_price_rounds(deps.storage)?,
+    };
+
+    if num_round_back.u128() as usize >= rounds.len() {
+        return Ok(PriceData::default());
+    }
+
+    Ok(rounds[rounds.len() - 1 - num_round_back.u128() as usize].clone())
+}
+
 /// Queries contract Config
 #[cfg(not(tarpaulin_include))]
 pub fn query_get_twap_price(
     deps: Deps,
-    _env: Env,
+    env: Env,
     _key: String,
-    _interval: u64,
+    interval: u64,
 ) -> StdResult<Uint128> {
+    if let Some(path) = read_price_path(deps.storage)? {
+        return Ok(path.twap_at(env.block.time.seconds(), interval));
+    }
+
+    singleton_read(deps.storage, KEY_PRICES).load()
+}
+
+#[cfg(not(tarpaulin_include))]
+pub fn query_get_twap_price_at(
+    deps: Deps,
+    _key: String,
+    interval: u64,
+    timestamp: u64,
+) -> StdResult<Uint128> {
+    if let Some(path) = read_price_path(deps.storage)? {
+        return Ok(path.twap_at(timestamp, interval));
+    }
+
     singleton_read(deps.storage, KEY_PRICES).load()
 }
 
@@ -230,12 +465,123 @@ pub fn store_price_data(
     storage: &mut dyn Storage,
     _key: String,
     price: Uint128,
-    _timestamp: u64,
+    timestamp: u64,
 ) -> StdResult<()> {
+    let mut rounds = read_price_rounds(storage)?;
+    rounds.push(PriceData {
+        round_id: Uint128::from(rounds.len() as u64 + 1u64),
+        price,
+        timestamp: Timestamp::from_seconds(timestamp),
+    });
+    singleton(storage, KEY_PRICE_DATA).save(&rounds)?;
+
     singleton(storage, KEY_PRICES).save(&price)
 }
 
+#[cfg(not(tarpaulin_include))]
+pub fn read_price_rounds(storage: &dyn Storage) -> StdResult<Vec<PriceData>> {
+    Ok(singleton_read(storage, KEY_PRICE_DATA)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 #[cfg(not(tarpaulin_include))]
 pub fn read_price_data(storage: &dyn Storage, _key: String) -> StdResult<Uint128> {
     singleton_read(storage, KEY_PRICES).load()
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Path {
+    pub interpolate: bool,
+    pub points: Vec<PricePoint>,
+}
+
+impl Path {
+    /// price of the path at the time, holding the first and last
+    /// prices before and after the path
+    #[cfg(not(tarpaulin_include))]
+    pub fn price_at(&self, time: u64) -> Uint128 {
+        let next = self.points.partition_point(|point| point.timestamp <= time);
+        if next == 0 {
+            return self.points[0].price;
+        }
+
+        let prev = &self.points[next - 1];
+        if !self.interpolate || next == self.points.len() {
+            return prev.price;
+        }
+
+        let next = &self.points[next];
+        let elapsed = time - prev.timestamp;
+        let duration = next.timestamp - prev.timestamp;
+        if next.price >= prev.price {
+            prev.price + (next.price - prev.price).multiply_ratio(elapsed, duration)
+        } else {
+            prev.price - (prev.price - next.price).multiply_ratio(elapsed, duration)
+        }
+    }
+
+    /// time weighted average of the path over the interval ending at the time
+    #[cfg(not(tarpaulin_include))]
+    pub fn twap_at(&self, time: u64, interval: u64) -> Uint128 {
+        let start = time.saturating_sub(interval);
+        if start == time {
+            return self.price_at(time);
+        }
+
+        let mut times = vec![start];
+        times.extend(
+            self.points
+                .iter()
+                .map(|point| point.timestamp)
+                .filter(|timestamp| *timestamp > start && *timestamp < time),
+        );
+        times.push(time);
+
+        let mut weighted = Uint128::zero();
+        for pair in times.windows(2) {
+            let duration = Uint128::from(pair[1] - pair[0]);
+            let price = if self.interpolate {
+                (self.price_at(pair[0]) + self.price_at(pair[1])) / Uint128::from(2u64)
+            } else {
+                self.price_at(pair[0])
+            };
+            weighted += price * duration;
+        }
+
+        weighted / Uint128::from(time - start)
+    }
+
+    /// rounds of the path up to the time, an interpolated path has a
+    /// round at the time itself
+    #[cfg(not(tarpaulin_include))]
+    pub fn rounds_at(&self, time: u64) -> Vec<PriceData> {
+        let mut rounds: Vec<PriceData> = self
+            .points
+            .iter()
+            .take_while(|point| point.timestamp <= time)
+            .enumerate()
+            .map(|(index, point)| PriceData {
+                round_id: Uint128::from(index as u64 + 1u64),
+                price: point.price,
+                timestamp: Timestamp::from_seconds(point.timestamp),
+            })
+            .collect();
+
+        let at_point = matches!(rounds.last(), Some(round) if round.timestamp.seconds() == time);
+        if self.interpolate && !at_point {
+            rounds.push(PriceData {
+                round_id: Uint128::from(rounds.len() as u64 + 1u64),
+                price: self.price_at(time),
+                timestamp: Timestamp::from_seconds(time),
+            });
+        }
+
+        rounds
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+pub fn read_price_path(storage: &dyn Storage) -> StdResult<Option<Path>> {
+    singleton_read(storage, KEY_PRICE_PATH).may_load()
+}
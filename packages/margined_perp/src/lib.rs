@@ -1,4 +1,5 @@
 pub mod margined_engine;
+pub mod margined_factory;
 pub mod margined_fee_pool;
 pub mod margined_insurance_fund;
 pub mod margined_pricefeed;
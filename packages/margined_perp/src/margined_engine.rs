@@ -17,6 +17,7 @@ pub enum PnlCalcOption {
     SpotPrice,
     Twap,
     Oracle,
+    MarkPrice,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -41,6 +42,7 @@ pub enum ExecuteMsg {
         maintenance_margin_ratio: Option<Uint128>,
         partial_liquidation_ratio: Option<Uint128>,
         liquidation_fee: Option<Uint128>,
+        use_mark_price: Option<bool>,
     },
     UpdatePauser {
         pauser: String,
@@ -70,6 +72,10 @@ pub enum ExecuteMsg {
     PayFunding {
         vamm: String,
     },
+    SettleExpiredPosition {
+        vamm: String,
+        trader: String,
+    },
     DepositMargin {
         vamm: String,
         amount: Uint128,
@@ -108,6 +114,9 @@ pub enum QueryMsg {
     CumulativePremiumFraction {
         vamm: String,
     },
+    OpenInterestNotional {
+        vamm: String,
+    },
     MarginRatio {
         vamm: String,
         trader: String,
@@ -136,6 +145,7 @@ pub struct ConfigResponse {
     pub maintenance_margin_ratio: Uint128,
     pub partial_liquidation_ratio: Uint128,
     pub liquidation_fee: Uint128,
+    pub use_mark_price: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
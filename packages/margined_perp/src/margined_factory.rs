@@ -0,0 +1,88 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::margined_vamm::CurveType;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub vamm_code_id: u64,
+    pub engine: String,
+    pub insurance_fund: String,
+    pub pricefeed: String,
+}
+
+/// The parameters of a new vAMM, the base asset is also the key used to query the pricefeed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MarketParams {
+    pub decimals: u8,
+    pub quote_asset: String,
+    pub base_asset: String,
+    pub quote_asset_reserve: Uint128,
+    pub base_asset_reserve: Uint128,
+    pub funding_period: u64,
+    pub toll_ratio: Uint128,
+    pub spread_ratio: Uint128,
+    pub fluctuation_limit_ratio: Uint128,
+    pub expiry: Option<u64>,
+    pub curve: Option<CurveType>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    UpdateConfig {
+        vamm_code_id: Option<u64>,
+        engine: Option<String>,
+        insurance_fund: Option<String>,
+        pricefeed: Option<String>,
+    },
+    UpdateOwner {
+        owner: String,
+    },
+    CreateMarket {
+        symbol: String,
+        params: MarketParams,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    GetOwner {},
+    Market {
+        symbol: String,
+    },
+    Markets {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ConfigResponse {
+    pub vamm_code_id: u64,
+    pub engine: Addr,
+    pub insurance_fund: Addr,
+    pub pricefeed: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OwnerResponse {
+    pub owner: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MarketResponse {
+    pub symbol: String,
+    pub vamm: Addr,
+    pub oracle_key: String,
+    pub launch_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MarketsResponse {
+    pub markets: Vec<MarketResponse>,
+}
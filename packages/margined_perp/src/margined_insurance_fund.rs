@@ -11,11 +11,87 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    UpdateOwner { owner: String },
-    AddVamm { vamm: String },
-    RemoveVamm { vamm: String },
-    Withdraw { token: AssetInfo, amount: Uint128 },
-    ShutdownVamms {},
+    UpdateOwner {
+        owner: String,
+    },
+    UpdateConfig {
+        min_coverage_ratio: Option<Uint128>,
+        staking_fee_share: Option<Uint128>,
+        unstake_cooldown: Option<u64>,
+        unstake_window: Option<u64>,
+        withdrawal_window: Option<u64>,
+        max_withdrawal_amount: Option<Uint128>,
+        max_withdrawal_ratio: Option<Uint128>,
+        factory: Option<String>,
+    },
+    AddVamm {
+        vamm: String,
+    },
+    RemoveVamm {
+        vamm: String,
+    },
+    Withdraw {
+        token: AssetInfo,
+        amount: Uint128,
+        vamm: String,
+        kind: DrawKind,
+        #[serde(default)]
+        defer: bool,
+    },
+    ApproveWithdrawal {
+        id: u64,
+    },
+    RejectWithdrawal {
+        id: u64,
+    },
+    RecordContribution {
+        vamm: String,
+        kind: ContributionKind,
+        amount: Uint128,
+    },
+    SetBudget {
+        vamm: String,
+        budget: Option<Uint128>,
+    },
+    ShutdownVamms {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    ShutdownVamm {
+        vamm: String,
+    },
+    ReopenVamm {
+        vamm: String,
+    },
+    CheckSolvency {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    Stake {
+        amount: Uint128,
+    },
+    RequestUnstake {
+        shares: Uint128,
+    },
+    Unstake {},
+}
+
+/// What the margin engine paid into the insurance fund for a market
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContributionKind {
+    Liquidation,
+    Fee,
+    Funding,
+}
+
+/// What the margin engine withdrew from the insurance fund for a market
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawKind {
+    BadDebt,
+    Funding,
+    Repeg,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -23,15 +99,48 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     Config {},
     GetOwner {},
-    IsVamm { vamm: String },
-    GetAllVamm { limit: Option<u32> },
-    GetAllVammStatus { limit: Option<u32> },
-    GetVammStatus { vamm: String },
+    IsVamm {
+        vamm: String,
+    },
+    GetAllVamm {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetAllVammStatus {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetVammStatus {
+        vamm: String,
+    },
+    GetLedger {
+        vamm: String,
+    },
+    GetCoverage {
+        vamm: String,
+    },
+    GetStakingPool {},
+    GetStaker {
+        staker: String,
+    },
+    GetWithdrawalAllowance {},
+    GetPendingWithdrawals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct ConfigResponse {
     pub engine: Addr,
+    pub min_coverage_ratio: Uint128,
+    pub staking_fee_share: Uint128,
+    pub unstake_cooldown: u64,
+    pub unstake_window: u64,
+    pub withdrawal_window: u64,
+    pub max_withdrawal_amount: Uint128,
+    pub max_withdrawal_ratio: Uint128,
+    pub factory: Option<Addr>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -58,3 +167,67 @@ pub struct AllVammResponse {
 pub struct AllVammStatusResponse {
     pub vamm_list_status: Vec<(Addr, bool)>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LedgerResponse {
+    pub vamm: Addr,
+    pub liquidation_contributions: Uint128,
+    pub fee_contributions: Uint128,
+    pub funding_contributions: Uint128,
+    pub bad_debt_draws: Uint128,
+    pub funding_draws: Uint128,
+    pub repeg_draws: Uint128,
+    pub total_contributions: Uint128,
+    pub total_draws: Uint128,
+    pub budget: Option<Uint128>,
+    pub remaining_budget: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CoverageResponse {
+    pub vamm: Addr,
+    pub available: Uint128,
+    pub open_interest_notional: Uint128,
+    pub coverage_ratio: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StakingPoolResponse {
+    pub total_shares: Uint128,
+    pub total_staked: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StakerResponse {
+    pub staker: Addr,
+    pub shares: Uint128,
+    pub value: Uint128,
+    pub unstaking_shares: Uint128,
+    pub unstake_release_time: Option<u64>,
+    pub unstake_expiry_time: Option<u64>,
+}
+
+/// A withdrawal by the margin engine that was over the allowance of the window and
+/// is waiting for the owner to approve it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingWithdrawal {
+    pub id: u64,
+    pub token: AssetInfo,
+    pub amount: Uint128,
+    pub vamm: Addr,
+    pub kind: DrawKind,
+    pub requested_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WithdrawalAllowanceResponse {
+    pub window_start: u64,
+    pub window_end: u64,
+    pub withdrawn: Uint128,
+    pub remaining: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingWithdrawalsResponse {
+    pub withdrawals: Vec<PendingWithdrawal>,
+}
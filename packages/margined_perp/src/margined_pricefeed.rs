@@ -1,7 +1,7 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Timestamp, Uint128};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -10,6 +10,58 @@ pub enum Direction {
     RemoveFromAmm,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureAlgorithm {
+    Secp256k1,
+    Ed25519,
+}
+
+/// The price that signers sign, the signature is over the sha256 hash of its JSON encoding,
+/// which includes the chain and pricefeed it is signed for so it can't be replayed elsewhere
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PricePayload {
+    pub chain_id: String,
+    pub contract: String,
+    pub key: String,
+    pub price: Uint128,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SignedPrice {
+    pub key: String,
+    pub price: Uint128,
+    pub timestamp: u64,
+    pub pubkey: Binary,
+    pub signature: Binary,
+}
+
+/// How a derived price is computed from the prices of other keys, all in units of `unit`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Derivation {
+    Ratio {
+        numerator: String,
+        denominator: String,
+    },
+    Product {
+        first: String,
+        second: String,
+    },
+    Inverse {
+        key: String,
+    },
+}
+
+/// A price computed on the fly from the rounds of other keys, optionally multiplied by `scale`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DerivedFeed {
+    pub derivation: Derivation,
+    pub unit: Uint128,
+    pub scale: Option<Uint128>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
     pub oracle_hub_contract: String, // address of the oracle hub we are using
@@ -31,6 +83,81 @@ pub enum ExecuteMsg {
     UpdateOwner {
         owner: String,
     },
+    UpdateConfig {
+        subscriber_gas_limit: Option<u64>,
+        max_subscriptions: Option<u32>,
+    },
+    RegisterFeed {
+        key: String,
+        decimals: u8,
+        description: String,
+        heartbeat: u64,
+        reporters: Vec<String>,
+        admin: Option<String>,
+    },
+    UpdateFeed {
+        key: String,
+        description: Option<String>,
+        heartbeat: Option<u64>,
+        reporters: Option<Vec<String>>,
+        admin: Option<String>,
+    },
+    SetReporters {
+        key: String,
+        reporters: Vec<String>,
+        quorum: u32,
+        max_deviation_bps: u64,
+        max_round_age: u64,
+    },
+    SubmitPrice {
+        key: String,
+        price: Uint128,
+        timestamp: u64,
+    },
+    AddSigner {
+        pubkey: Binary,
+        algorithm: SignatureAlgorithm,
+        reporter: String,
+    },
+    RemoveSigner {
+        pubkey: Binary,
+    },
+    SubmitSignedPrices {
+        payloads: Vec<SignedPrice>,
+    },
+    SetValidation {
+        key: String,
+        heartbeat: u64,
+        confirmation_threshold_bps: u64,
+    },
+    SetAveraging {
+        key: String,
+        max_gap: u64,
+        ema_half_life: u64,
+    },
+    SetDerivedFeed {
+        key: String,
+        feed: DerivedFeed,
+    },
+    AddSubscriber {
+        address: String,
+    },
+    RemoveSubscriber {
+        address: String,
+    },
+    Subscribe {
+        key: String,
+        deviation_bps: u64,
+    },
+    Unsubscribe {
+        key: String,
+    },
+    RemoveDerivedFeed {
+        key: String,
+    },
+    MigratePriceHistory {
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -49,12 +176,182 @@ pub enum QueryMsg {
         key: String,
         interval: u64,
     },
+    GetTwapPriceAt {
+        key: String,
+        interval: u64,
+        timestamp: u64,
+    },
+    GetEmaPrice {
+        key: String,
+    },
+    GetMedianPrice {
+        key: String,
+        interval: u64,
+    },
+    GetPriceData {
+        key: String,
+    },
+    GetPreviousPriceData {
+        key: String,
+        num_round_back: Uint128,
+    },
+    GetReporters {
+        key: String,
+    },
+    GetRoundData {
+        key: String,
+        round_id: Uint128,
+    },
+    GetLatestRoundData {
+        key: String,
+    },
+    GetSigner {
+        pubkey: Binary,
+    },
+    PriceHistory {
+        key: String,
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+    },
+    GetValidation {
+        key: String,
+    },
+    GetAveraging {
+        key: String,
+    },
+    GetDerivedFeed {
+        key: String,
+    },
+    FeedInfo {
+        key: String,
+    },
+    GetSubscribers {},
+    GetSubscriptions {
+        key: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    ListFeeds {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+/// The callback that subscribers receive when a key they subscribed to is updated, which
+/// subscribers include in their own `ExecuteMsg`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceHookMsg {
+    PriceUpdated {
+        key: String,
+        round_id: Uint128,
+        price: Uint128,
+        timestamp: Timestamp,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MigrateMsg {
+    pub decimals: u8,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct ConfigResponse {}
+pub struct ConfigResponse {
+    pub subscriber_gas_limit: u64,
+    pub max_subscriptions: u32,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct OwnerResponse {
     pub owner: Addr,
 }
+
+#[derive(Serialize, Default, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PriceDataResponse {
+    pub round_id: Uint128,
+    pub price: Uint128,
+    pub timestamp: Timestamp,
+}
+
+/// The latest price of a key, which is stale once it is older than the heartbeat of the key
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PriceResponse {
+    pub round_id: Uint128,
+    pub price: Uint128,
+    pub timestamp: Timestamp,
+    pub is_stale: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ValidationResponse {
+    pub heartbeat: u64,
+    pub confirmation_threshold_bps: u64,
+    pub unconfirmed_price: Option<Uint128>,
+}
+
+/// A registered feed, prices are only accepted for registered keys
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeedInfoResponse {
+    pub key: String,
+    pub decimals: u8,
+    pub description: String,
+    pub heartbeat: u64,
+    pub reporters: Vec<Addr>,
+    pub admin: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeedsResponse {
+    pub feeds: Vec<FeedInfoResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AveragingResponse {
+    pub max_gap: u64,
+    pub ema_half_life: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SubscriptionResponse {
+    pub subscriber: Addr,
+    pub deviation_bps: u64,
+    pub last_price: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SubscriptionsResponse {
+    pub subscriptions: Vec<SubscriptionResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReportersResponse {
+    pub reporters: Vec<Addr>,
+    pub quorum: u32,
+    pub max_deviation_bps: u64,
+    pub max_round_age: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SignerResponse {
+    pub pubkey: Binary,
+    pub algorithm: SignatureAlgorithm,
+    pub reporter: Addr,
+}
+
+/// The answer of a round along with how it was reached, while a new round is collecting
+/// submissions the latest round data is the pending round answered by the previous one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RoundDataResponse {
+    pub round_id: Uint128,
+    pub price: Uint128,
+    pub timestamp: Timestamp,
+    pub answered_in_round: Uint128,
+    pub reporters: Vec<Addr>,
+    pub started_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PriceHistoryResponse {
+    pub prices: Vec<PriceDataResponse>,
+}
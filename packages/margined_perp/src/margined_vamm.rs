@@ -13,6 +13,46 @@ pub enum Direction {
     RemoveFromAmm,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingMode {
+    Periodic,
+    Continuous,
+}
+
+/// The pricing curve of a vAMM, the concentrated curve trades as a constant product over
+/// virtual reserves that are `concentration` times the initial reserves, so the same
+/// liquidity gives less slippage but the price can only move within a bounded range
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveType {
+    ConstantProduct,
+    Concentrated { concentration: Uint128 },
+}
+
+/// A weekly trading session, open and close are given in seconds since Monday 00:00 UTC
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TradingSession {
+    pub open: u64,
+    pub close: u64,
+}
+
+/// A period where the market is closed, start and end are unix timestamps in seconds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TradingHoliday {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// The weekly calendar of a vAMM, a schedule without sessions trades continuously
+/// apart from any holidays
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct TradingSchedule {
+    pub sessions: Vec<TradingSession>,
+    pub holidays: Vec<TradingHoliday>,
+    pub reduce_only_when_closed: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
     pub decimals: u8,
@@ -27,6 +67,8 @@ pub struct InstantiateMsg {
     pub toll_ratio: Uint128,
     pub spread_ratio: Uint128,
     pub fluctuation_limit_ratio: Uint128,
+    pub expiry: Option<u64>,
+    pub curve: Option<CurveType>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -43,6 +85,15 @@ pub enum ExecuteMsg {
         insurance_fund: Option<String>,
         pricefeed: Option<String>,
         spot_price_twap_interval: Option<u64>,
+        funding_mode: Option<FundingMode>,
+        max_oracle_spread_ratio: Option<Uint128>,
+        skew_fee_ratio: Option<Uint128>,
+        oracle_heartbeat: Option<u64>,
+        max_oracle_deviation_ratio: Option<Uint128>,
+        expiry_cutoff_period: Option<u64>,
+        trading_schedule: Option<TradingSchedule>,
+        circuit_breaker_window: Option<u64>,
+        circuit_breaker_ratio: Option<Uint128>,
     },
     UpdateOwner {
         owner: String,
@@ -59,6 +110,7 @@ pub enum ExecuteMsg {
         quote_asset_limit: Uint128,
     },
     SettleFunding {},
+    SettleExpiry {},
     SetOpen {
         open: bool,
     },
@@ -104,8 +156,21 @@ pub enum QueryMsg {
     },
     CalcFee {
         quote_asset_amount: Uint128,
+        direction: Direction,
     },
+    MarkPrice {},
     IsOverSpreadLimit {},
+    OracleStatus {},
+    MarketStatus {},
+    Quote {
+        direction: Direction,
+        quote_amount: Option<Uint128>,
+        base_amount: Option<Uint128>,
+    },
+    AmountToPrice {
+        target_price: Uint128,
+    },
+    AccruedPremiumFraction {},
     IsOverFluctuationLimit {
         direction: Direction,
         base_asset_amount: Uint128,
@@ -123,10 +188,21 @@ pub struct ConfigResponse {
     pub base_asset: String,
     pub toll_ratio: Uint128,
     pub spread_ratio: Uint128,
+    pub skew_fee_ratio: Uint128,
     pub fluctuation_limit_ratio: Uint128,
+    pub max_oracle_spread_ratio: Uint128,
+    pub max_oracle_deviation_ratio: Uint128,
+    pub oracle_heartbeat: u64,
     pub decimals: Uint128,
     pub funding_period: u64,
+    pub funding_mode: FundingMode,
     pub spot_price_twap_interval: u64,
+    pub expiry: Option<u64>,
+    pub expiry_cutoff_period: u64,
+    pub trading_schedule: TradingSchedule,
+    pub curve: CurveType,
+    pub circuit_breaker_window: u64,
+    pub circuit_breaker_ratio: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -142,6 +218,43 @@ pub struct StateResponse {
     pub total_position_size: Integer,
     pub funding_rate: Integer,
     pub next_funding_time: u64,
+    pub last_funding_time: u64,
+    pub settlement_price: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OracleStatusResponse {
+    pub price: Uint128,
+    pub timestamp: u64,
+    pub deviation_ratio: Uint128,
+    pub is_stale: bool,
+    pub is_deviated: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MarketStatusResponse {
+    pub is_open: bool,
+    pub is_reduce_only: bool,
+    pub is_circuit_breaker_tripped: bool,
+    pub next_open: Option<u64>,
+    pub next_close: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct QuoteResponse {
+    pub quote_asset_amount: Uint128,
+    pub base_asset_amount: Uint128,
+    pub average_price: Uint128,
+    pub spot_price_after: Uint128,
+    pub price_impact_bps: Uint128,
+    pub is_over_fluctuation_limit: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AmountToPriceResponse {
+    pub direction: Direction,
+    pub quote_asset_amount: Uint128,
+    pub base_asset_amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -149,3 +262,29 @@ pub struct CalcFeeResponse {
     pub toll_fee: Uint128,
     pub spread_fee: Uint128,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapType {
+    Input,
+    Output,
+}
+
+/// Returned in the data of a swap, input swaps exchange the quote asset amount for the base
+/// asset amount and output swaps the base asset amount for the quote asset amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SwapResult {
+    pub swap_type: SwapType,
+    pub direction: Direction,
+    pub quote_asset_amount: Uint128,
+    pub base_asset_amount: Uint128,
+}
+
+/// Returned in the data of a funding settlement
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FundingResult {
+    pub vamm: Addr,
+    pub premium_fraction: Integer,
+    pub underlying_price: Uint128,
+    pub index_price: Uint128,
+}
@@ -1,10 +1,12 @@
 pub use crate::contracts::helpers::margined_engine::EngineController;
+pub use crate::contracts::helpers::margined_factory::FactoryController;
 pub use crate::contracts::helpers::margined_fee_pool::FeePoolController;
 pub use crate::contracts::helpers::margined_insurance_fund::InsuranceFundController;
 pub use crate::contracts::helpers::margined_pricefeed::PricefeedController;
 pub use crate::contracts::helpers::margined_vamm::VammController;
 
 pub mod margined_engine;
+pub mod margined_factory;
 pub mod margined_fee_pool;
 pub mod margined_insurance_fund;
 pub mod margined_pricefeed;
@@ -51,6 +51,21 @@ impl EngineController {
             maintenance_margin_ratio,
             partial_liquidation_ratio,
             liquidation_fee,
+            use_mark_price: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_use_mark_price(&self, use_mark_price: bool) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            insurance_fund: None,
+            fee_pool: None,
+            initial_margin_ratio: None,
+            maintenance_margin_ratio: None,
+            partial_liquidation_ratio: None,
+            liquidation_fee: None,
+            use_mark_price: Some(use_mark_price),
         };
         self.call(msg, vec![])
     }
@@ -64,6 +79,7 @@ impl EngineController {
             maintenance_margin_ratio: None,
             partial_liquidation_ratio: None,
             liquidation_fee: None,
+            use_mark_price: None,
         };
         self.call(msg, vec![])
     }
@@ -80,6 +96,7 @@ impl EngineController {
             maintenance_margin_ratio: Some(maintenance_margin_ratio),
             partial_liquidation_ratio: None,
             liquidation_fee: None,
+            use_mark_price: None,
         };
         self.call(msg, vec![])
     }
@@ -93,6 +110,7 @@ impl EngineController {
             maintenance_margin_ratio: Some(margin_ratio),
             partial_liquidation_ratio: None,
             liquidation_fee: None,
+            use_mark_price: None,
         };
         self.call(msg, vec![])
     }
@@ -109,6 +127,7 @@ impl EngineController {
             maintenance_margin_ratio: None,
             partial_liquidation_ratio: Some(partial_liquidation_ratio),
             liquidation_fee: None,
+            use_mark_price: None,
         };
         self.call(msg, vec![])
     }
@@ -122,6 +141,7 @@ impl EngineController {
             maintenance_margin_ratio: None,
             partial_liquidation_ratio: None,
             liquidation_fee: Some(liquidation_fee),
+            use_mark_price: None,
         };
         self.call(msg, vec![])
     }
@@ -177,6 +197,11 @@ impl EngineController {
         self.call(msg, vec![])
     }
 
+    pub fn settle_expired_position(&self, vamm: String, trader: String) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SettleExpiredPosition { vamm, trader };
+        self.call(msg, vec![])
+    }
+
     pub fn deposit_margin(
         &self,
         vamm: String,
@@ -333,6 +358,23 @@ impl EngineController {
         Ok(res)
     }
 
+    /// get the open interest notional of a vamm
+    pub fn open_interest_notional<Q: Querier>(
+        &self,
+        querier: &Q,
+        vamm: String,
+    ) -> StdResult<Uint128> {
+        let msg = QueryMsg::OpenInterestNotional { vamm };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: Uint128 = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
     /// get margin ratio
     pub fn get_margin_ratio<Q: Querier>(
         &self,
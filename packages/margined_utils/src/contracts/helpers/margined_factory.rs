@@ -0,0 +1,100 @@
+use margined_perp::margined_factory::{
+    ConfigResponse, ExecuteMsg, MarketParams, MarketResponse, MarketsResponse, QueryMsg,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    to_binary, Addr, Coin, CosmosMsg, Empty, Querier, QuerierWrapper, StdResult, WasmMsg, WasmQuery,
+};
+
+/// FactoryController is a wrapper around Addr that provides a lot of helpers
+/// for working with this.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FactoryController(pub Addr);
+
+impl FactoryController {
+    pub fn addr(&self) -> Addr {
+        self.0.clone()
+    }
+
+    pub fn call<T: Into<ExecuteMsg>>(&self, msg: T, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        let msg = to_binary(&msg.into())?;
+        Ok(WasmMsg::Execute {
+            contract_addr: self.addr().into(),
+            msg,
+            funds,
+        }
+        .into())
+    }
+
+    /////////////////////////
+    ///  Execute Messages ///
+    /////////////////////////
+    pub fn update_owner(&self, owner: String) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateOwner { owner };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_vamm_code_id(&self, vamm_code_id: u64) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            vamm_code_id: Some(vamm_code_id),
+            engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn create_market(&self, symbol: String, params: MarketParams) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::CreateMarket { symbol, params };
+        self.call(msg, vec![])
+    }
+
+    //////////////////////
+    /// Query Messages ///
+    //////////////////////
+    /// get factory configuration
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        let msg = QueryMsg::Config {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: ConfigResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get the market with the given symbol
+    pub fn market<Q: Querier>(&self, querier: &Q, symbol: String) -> StdResult<MarketResponse> {
+        let msg = QueryMsg::Market { symbol };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: MarketResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get the markets ordered by symbol
+    pub fn markets<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<MarketsResponse> {
+        let msg = QueryMsg::Markets { start_after, limit };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: MarketsResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+}
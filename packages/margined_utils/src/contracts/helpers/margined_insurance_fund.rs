@@ -1,12 +1,14 @@
 use margined_perp::margined_insurance_fund::{
-    AllVammResponse, AllVammStatusResponse, ConfigResponse, ExecuteMsg, QueryMsg, VammResponse,
-    VammStatusResponse,
+    AllVammResponse, AllVammStatusResponse, ConfigResponse, CoverageResponse, ExecuteMsg,
+    LedgerResponse, PendingWithdrawalsResponse, QueryMsg, StakerResponse, StakingPoolResponse,
+    VammResponse, VammStatusResponse, WithdrawalAllowanceResponse,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{
-    to_binary, Addr, Coin, CosmosMsg, Empty, Querier, QuerierWrapper, StdResult, WasmMsg, WasmQuery,
+    to_binary, Addr, Coin, CosmosMsg, Empty, Querier, QuerierWrapper, StdResult, Uint128, WasmMsg,
+    WasmQuery,
 };
 
 /// InsuranceFundController is a wrapper around Addr that provides a lot of helpers
@@ -49,8 +51,86 @@ impl InsuranceFundController {
         self.call(msg, vec![])
     }
 
-    pub fn shutdown_vamms(&self) -> StdResult<CosmosMsg> {
-        let msg = ExecuteMsg::ShutdownVamms {};
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_config(
+        &self,
+        min_coverage_ratio: Option<Uint128>,
+        staking_fee_share: Option<Uint128>,
+        unstake_cooldown: Option<u64>,
+        unstake_window: Option<u64>,
+        withdrawal_window: Option<u64>,
+        max_withdrawal_amount: Option<Uint128>,
+        max_withdrawal_ratio: Option<Uint128>,
+        factory: Option<String>,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            min_coverage_ratio,
+            staking_fee_share,
+            unstake_cooldown,
+            unstake_window,
+            withdrawal_window,
+            max_withdrawal_amount,
+            max_withdrawal_ratio,
+            factory,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn shutdown_vamms(
+        &self,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::ShutdownVamms { start_after, limit };
+        self.call(msg, vec![])
+    }
+
+    pub fn shutdown_vamm(&self, vamm: String) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::ShutdownVamm { vamm };
+        self.call(msg, vec![])
+    }
+
+    pub fn reopen_vamm(&self, vamm: String) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::ReopenVamm { vamm };
+        self.call(msg, vec![])
+    }
+
+    pub fn check_solvency(
+        &self,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::CheckSolvency { start_after, limit };
+        self.call(msg, vec![])
+    }
+
+    pub fn stake(&self, amount: Uint128, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::Stake { amount };
+        self.call(msg, funds)
+    }
+
+    pub fn request_unstake(&self, shares: Uint128) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::RequestUnstake { shares };
+        self.call(msg, vec![])
+    }
+
+    pub fn unstake(&self) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::Unstake {};
+        self.call(msg, vec![])
+    }
+
+    pub fn approve_withdrawal(&self, id: u64) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::ApproveWithdrawal { id };
+        self.call(msg, vec![])
+    }
+
+    pub fn reject_withdrawal(&self, id: u64) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::RejectWithdrawal { id };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_budget(&self, vamm: String, budget: Option<Uint128>) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SetBudget { vamm, budget };
         self.call(msg, vec![])
     }
 
@@ -91,10 +171,11 @@ impl InsuranceFundController {
     /// get all the vamms status'
     pub fn all_vamm_status<Q: Querier>(
         &self,
+        start_after: Option<String>,
         limit: Option<u32>,
         querier: &Q,
     ) -> StdResult<AllVammStatusResponse> {
-        let msg = QueryMsg::GetAllVammStatus { limit };
+        let msg = QueryMsg::GetAllVammStatus { start_after, limit };
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
             msg: to_binary(&msg)?,
@@ -108,10 +189,11 @@ impl InsuranceFundController {
     /// get a list of all the vamms
     pub fn all_vamms<Q: Querier>(
         &self,
+        start_after: Option<String>,
         limit: Option<u32>,
         querier: &Q,
     ) -> StdResult<AllVammResponse> {
-        let msg = QueryMsg::GetAllVamm { limit };
+        let msg = QueryMsg::GetAllVamm { start_after, limit };
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
             msg: to_binary(&msg)?,
@@ -134,4 +216,92 @@ impl InsuranceFundController {
         let res: VammResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
         Ok(res)
     }
+
+    /// get the insurance contributed and drawn by the vamm
+    pub fn ledger<Q: Querier>(&self, vamm: String, querier: &Q) -> StdResult<LedgerResponse> {
+        let msg = QueryMsg::GetLedger { vamm };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: LedgerResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get the insurance coverage of the open interest of the vamm
+    pub fn coverage<Q: Querier>(&self, vamm: String, querier: &Q) -> StdResult<CoverageResponse> {
+        let msg = QueryMsg::GetCoverage { vamm };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: CoverageResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get the staking pool
+    pub fn staking_pool<Q: Querier>(&self, querier: &Q) -> StdResult<StakingPoolResponse> {
+        let msg = QueryMsg::GetStakingPool {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: StakingPoolResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get the shares of a staker
+    pub fn staker<Q: Querier>(&self, staker: String, querier: &Q) -> StdResult<StakerResponse> {
+        let msg = QueryMsg::GetStaker { staker };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: StakerResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get what the engine can still withdraw in the current window
+    pub fn withdrawal_allowance<Q: Querier>(
+        &self,
+        querier: &Q,
+    ) -> StdResult<WithdrawalAllowanceResponse> {
+        let msg = QueryMsg::GetWithdrawalAllowance {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: WithdrawalAllowanceResponse =
+            QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get the withdrawals waiting for approval
+    pub fn pending_withdrawals<Q: Querier>(
+        &self,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        querier: &Q,
+    ) -> StdResult<PendingWithdrawalsResponse> {
+        let msg = QueryMsg::GetPendingWithdrawals { start_after, limit };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: PendingWithdrawalsResponse =
+            QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
 }
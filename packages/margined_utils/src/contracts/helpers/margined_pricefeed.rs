@@ -1,10 +1,13 @@
-use margined_perp::margined_pricefeed::{ConfigResponse, ExecuteMsg, QueryMsg};
+use margined_perp::margined_pricefeed::{
+    ConfigResponse, DerivedFeed, ExecuteMsg, QueryMsg, SignatureAlgorithm, SignedPrice,
+};
+use mock_pricefeed::contract::{ExecuteMsg as MockExecuteMsg, PricePath};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{
-    to_binary, Addr, Coin, CosmosMsg, Empty, Querier, QuerierWrapper, StdResult, Uint128, WasmMsg,
-    WasmQuery,
+    to_binary, Addr, Binary, Coin, CosmosMsg, Empty, Querier, QuerierWrapper, StdResult, Uint128,
+    WasmMsg, WasmQuery,
 };
 
 /// PricefeedController is a wrapper around Addr that provides a lot of helpers
@@ -55,6 +58,141 @@ impl PricefeedController {
         self.call(msg, vec![])
     }
 
+    pub fn register_feed(
+        &self,
+        key: String,
+        decimals: u8,
+        description: String,
+        heartbeat: u64,
+        reporters: Vec<String>,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::RegisterFeed {
+            key,
+            decimals,
+            description,
+            heartbeat,
+            reporters,
+            admin: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_reporters(
+        &self,
+        key: String,
+        reporters: Vec<String>,
+        quorum: u32,
+        max_deviation_bps: u64,
+        max_round_age: u64,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SetReporters {
+            key,
+            reporters,
+            quorum,
+            max_deviation_bps,
+            max_round_age,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn submit_price(
+        &self,
+        key: String,
+        price: Uint128,
+        timestamp: u64,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SubmitPrice {
+            key,
+            price,
+            timestamp,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_validation(
+        &self,
+        key: String,
+        heartbeat: u64,
+        confirmation_threshold_bps: u64,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SetValidation {
+            key,
+            heartbeat,
+            confirmation_threshold_bps,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_averaging(
+        &self,
+        key: String,
+        max_gap: u64,
+        ema_half_life: u64,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SetAveraging {
+            key,
+            max_gap,
+            ema_half_life,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_derived_feed(&self, key: String, feed: DerivedFeed) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SetDerivedFeed { key, feed };
+        self.call(msg, vec![])
+    }
+
+    pub fn add_subscriber(&self, address: String) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::AddSubscriber { address };
+        self.call(msg, vec![])
+    }
+
+    pub fn subscribe(&self, key: String, deviation_bps: u64) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::Subscribe { key, deviation_bps };
+        self.call(msg, vec![])
+    }
+
+    pub fn add_signer(
+        &self,
+        pubkey: Binary,
+        algorithm: SignatureAlgorithm,
+        reporter: String,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::AddSigner {
+            pubkey,
+            algorithm,
+            reporter,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn submit_signed_prices(&self, payloads: Vec<SignedPrice>) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SubmitSignedPrices { payloads };
+        self.call(msg, vec![])
+    }
+
+    /// loads a price path into the mock pricefeed, only the mock supports this
+    pub fn set_price_path(&self, path: PricePath) -> StdResult<CosmosMsg> {
+        let msg = MockExecuteMsg::SetPricePath { path };
+        Ok(WasmMsg::Execute {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+            funds: vec![],
+        }
+        .into())
+    }
+
+    /// removes the price path from the mock pricefeed
+    pub fn clear_price_path(&self) -> StdResult<CosmosMsg> {
+        let msg = MockExecuteMsg::ClearPricePath {};
+        Ok(WasmMsg::Execute {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+            funds: vec![],
+        }
+        .into())
+    }
+
     /// get margined pricefeed configuration
     pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
         let msg = QueryMsg::Config {};
@@ -119,4 +257,27 @@ impl PricefeedController {
         let res: Uint128 = QuerierWrapper::<Empty>::new(querier).query(&query)?;
         Ok(res)
     }
+
+    /// get twap price over the interval ending at the timestamp
+    pub fn twap_price_at<Q: Querier>(
+        &self,
+        querier: &Q,
+        key: String,
+        interval: u64,
+        timestamp: u64,
+    ) -> StdResult<Uint128> {
+        let msg = QueryMsg::GetTwapPriceAt {
+            key,
+            interval,
+            timestamp,
+        };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: Uint128 = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
 }
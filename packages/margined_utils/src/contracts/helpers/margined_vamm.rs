@@ -1,5 +1,8 @@
+use margined_common::integer::Integer;
 use margined_perp::margined_vamm::{
-    CalcFeeResponse, ConfigResponse, Direction, ExecuteMsg, QueryMsg, StateResponse,
+    AmountToPriceResponse, CalcFeeResponse, ConfigResponse, Direction, ExecuteMsg, FundingMode,
+    MarketStatusResponse, OracleStatusResponse, QueryMsg, QuoteResponse, StateResponse,
+    TradingSchedule,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -52,6 +55,15 @@ impl VammController {
             insurance_fund,
             pricefeed,
             spot_price_twap_interval,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
         };
         self.call(msg, vec![])
     }
@@ -61,6 +73,30 @@ impl VammController {
         self.call(msg, vec![])
     }
 
+    pub fn set_pricefeed(&self, pricefeed: String) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            base_asset_holding_cap: None,
+            open_interest_notional_cap: None,
+            toll_ratio: None,
+            spread_ratio: None,
+            fluctuation_limit_ratio: None,
+            margin_engine: None,
+            insurance_fund: None,
+            pricefeed: Some(pricefeed),
+            spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
+        };
+        self.call(msg, vec![])
+    }
+
     pub fn set_toll_ratio(&self, toll_ratio: Uint128) -> StdResult<CosmosMsg> {
         let msg = ExecuteMsg::UpdateConfig {
             base_asset_holding_cap: None,
@@ -72,6 +108,15 @@ impl VammController {
             insurance_fund: None,
             pricefeed: None,
             spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
         };
         self.call(msg, vec![])
     }
@@ -87,6 +132,15 @@ impl VammController {
             insurance_fund: None,
             pricefeed: None,
             spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
         };
         self.call(msg, vec![])
     }
@@ -105,6 +159,15 @@ impl VammController {
             insurance_fund: None,
             pricefeed: None,
             spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
         };
         self.call(msg, vec![])
     }
@@ -123,6 +186,15 @@ impl VammController {
             insurance_fund: None,
             pricefeed: None,
             spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
         };
         self.call(msg, vec![])
     }
@@ -141,6 +213,213 @@ impl VammController {
             insurance_fund: None,
             pricefeed: None,
             spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_funding_mode(&self, funding_mode: FundingMode) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            base_asset_holding_cap: None,
+            open_interest_notional_cap: None,
+            toll_ratio: None,
+            spread_ratio: None,
+            fluctuation_limit_ratio: None,
+            margin_engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+            spot_price_twap_interval: None,
+            funding_mode: Some(funding_mode),
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_max_oracle_spread_ratio(
+        &self,
+        max_oracle_spread_ratio: Uint128,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            base_asset_holding_cap: None,
+            open_interest_notional_cap: None,
+            toll_ratio: None,
+            spread_ratio: None,
+            fluctuation_limit_ratio: None,
+            margin_engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+            spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: Some(max_oracle_spread_ratio),
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_skew_fee_ratio(&self, skew_fee_ratio: Uint128) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            base_asset_holding_cap: None,
+            open_interest_notional_cap: None,
+            toll_ratio: None,
+            spread_ratio: None,
+            fluctuation_limit_ratio: None,
+            margin_engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+            spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: Some(skew_fee_ratio),
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_oracle_heartbeat(&self, oracle_heartbeat: u64) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            base_asset_holding_cap: None,
+            open_interest_notional_cap: None,
+            toll_ratio: None,
+            spread_ratio: None,
+            fluctuation_limit_ratio: None,
+            margin_engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+            spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: Some(oracle_heartbeat),
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_max_oracle_deviation_ratio(
+        &self,
+        max_oracle_deviation_ratio: Uint128,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            base_asset_holding_cap: None,
+            open_interest_notional_cap: None,
+            toll_ratio: None,
+            spread_ratio: None,
+            fluctuation_limit_ratio: None,
+            margin_engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+            spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: Some(max_oracle_deviation_ratio),
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_expiry_cutoff_period(&self, expiry_cutoff_period: u64) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            base_asset_holding_cap: None,
+            open_interest_notional_cap: None,
+            toll_ratio: None,
+            spread_ratio: None,
+            fluctuation_limit_ratio: None,
+            margin_engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+            spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: Some(expiry_cutoff_period),
+            trading_schedule: None,
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_trading_schedule(&self, trading_schedule: TradingSchedule) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            base_asset_holding_cap: None,
+            open_interest_notional_cap: None,
+            toll_ratio: None,
+            spread_ratio: None,
+            fluctuation_limit_ratio: None,
+            margin_engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+            spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: Some(trading_schedule),
+            circuit_breaker_window: None,
+            circuit_breaker_ratio: None,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn set_circuit_breaker(&self, window: u64, ratio: Uint128) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateConfig {
+            base_asset_holding_cap: None,
+            open_interest_notional_cap: None,
+            toll_ratio: None,
+            spread_ratio: None,
+            fluctuation_limit_ratio: None,
+            margin_engine: None,
+            insurance_fund: None,
+            pricefeed: None,
+            spot_price_twap_interval: None,
+            funding_mode: None,
+            max_oracle_spread_ratio: None,
+            skew_fee_ratio: None,
+            oracle_heartbeat: None,
+            max_oracle_deviation_ratio: None,
+            expiry_cutoff_period: None,
+            trading_schedule: None,
+            circuit_breaker_window: Some(window),
+            circuit_breaker_ratio: Some(ratio),
         };
         self.call(msg, vec![])
     }
@@ -185,6 +464,11 @@ impl VammController {
         self.call(msg, vec![])
     }
 
+    pub fn settle_expiry(&self) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SettleExpiry {};
+        self.call(msg, vec![])
+    }
+
     /// get margin vamm configuration
     pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
         let msg = QueryMsg::Config {};
@@ -255,13 +539,30 @@ impl VammController {
         Ok(res)
     }
 
+    /// get mark price
+    pub fn mark_price<Q: Querier>(&self, querier: &Q) -> StdResult<Uint128> {
+        let msg = QueryMsg::MarkPrice {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: Uint128 = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
     /// get swap fees
     pub fn calc_fee<Q: Querier>(
         &self,
         querier: &Q,
         quote_asset_amount: Uint128,
+        direction: Direction,
     ) -> StdResult<CalcFeeResponse> {
-        let msg = QueryMsg::CalcFee { quote_asset_amount };
+        let msg = QueryMsg::CalcFee {
+            quote_asset_amount,
+            direction,
+        };
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
             msg: to_binary(&msg)?,
@@ -272,6 +573,85 @@ impl VammController {
         Ok(res)
     }
 
+    /// get the staleness and deviation status of the oracle
+    pub fn oracle_status<Q: Querier>(&self, querier: &Q) -> StdResult<OracleStatusResponse> {
+        let msg = QueryMsg::OracleStatus {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: OracleStatusResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get whether the market is open under its trading schedule
+    pub fn market_status<Q: Querier>(&self, querier: &Q) -> StdResult<MarketStatusResponse> {
+        let msg = QueryMsg::MarketStatus {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: MarketStatusResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get a quote for swapping either a quote or base asset amount
+    pub fn quote<Q: Querier>(
+        &self,
+        querier: &Q,
+        direction: Direction,
+        quote_amount: Option<Uint128>,
+        base_amount: Option<Uint128>,
+    ) -> StdResult<QuoteResponse> {
+        let msg = QueryMsg::Quote {
+            direction,
+            quote_amount,
+            base_amount,
+        };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: QuoteResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get the swap required to move the spot price to a target price
+    pub fn amount_to_price<Q: Querier>(
+        &self,
+        querier: &Q,
+        target_price: Uint128,
+    ) -> StdResult<AmountToPriceResponse> {
+        let msg = QueryMsg::AmountToPrice { target_price };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: AmountToPriceResponse = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    /// get premium fraction accrued since the last funding settlement
+    pub fn accrued_premium_fraction<Q: Querier>(&self, querier: &Q) -> StdResult<Integer> {
+        let msg = QueryMsg::AccruedPremiumFraction {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+        }
+        .into();
+
+        let res: Integer = QuerierWrapper::<Empty>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
     /// is over spread limit
     pub fn is_over_spread_limit<Q: Querier>(&self, querier: &Q) -> StdResult<bool> {
         let msg = QueryMsg::IsOverSpreadLimit {};
@@ -1,11 +1,12 @@
 use crate::contracts::helpers::{
-    margined_engine::EngineController, margined_fee_pool::FeePoolController,
-    margined_insurance_fund::InsuranceFundController, margined_pricefeed::PricefeedController,
-    margined_vamm::VammController,
+    margined_engine::EngineController, margined_factory::FactoryController,
+    margined_fee_pool::FeePoolController, margined_insurance_fund::InsuranceFundController,
+    margined_pricefeed::PricefeedController, margined_vamm::VammController,
 };
 use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Empty, Response, Uint128};
 use cw20::{Cw20Coin, Cw20Contract, Cw20ExecuteMsg, MinterResponse};
 use margined_perp::margined_engine::{ExecuteMsg, InstantiateMsg, Side};
+use margined_perp::margined_factory::InstantiateMsg as FactoryInstantiateMsg;
 use margined_perp::margined_fee_pool::InstantiateMsg as FeePoolInstantiateMsg;
 use margined_perp::margined_insurance_fund::InstantiateMsg as InsuranceFundInstantiateMsg;
 use margined_perp::margined_pricefeed::{
@@ -14,6 +15,7 @@ use margined_perp::margined_pricefeed::{
 use margined_perp::margined_vamm::{
     ExecuteMsg as VammExecuteMsg, InstantiateMsg as VammInstantiateMsg,
 };
+pub use mock_pricefeed::contract::{PricePath, PricePoint};
 // use terra_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
 use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
 
@@ -145,6 +147,7 @@ impl NativeTokenScenario {
                     maintenance_margin_ratio: None,
                     partial_liquidation_ratio: None,
                     liquidation_fee: None,
+                    use_mark_price: None,
                 },
                 &[],
             )
@@ -175,6 +178,8 @@ impl NativeTokenScenario {
                     quote_asset_reserve: Uint128::from(1_000_000_000u128),
                     base_asset_reserve: Uint128::from(100_000_000u128),
                     funding_period: 86_400_u64, // funding period is 1 day to make calcs easier
+                    expiry: None,
+                    curve: None,
                     toll_ratio: Uint128::zero(),
                     spread_ratio: Uint128::zero(),
                     fluctuation_limit_ratio: Uint128::zero(),
@@ -189,7 +194,8 @@ impl NativeTokenScenario {
             .unwrap();
         let vamm = VammController(vamm_addr.clone());
 
-        // set margin engine in vamm
+        // set margin engine in vamm, the test prices are historical so the oracle guards
+        // are disabled
         router
             .execute_contract(
                 owner.clone(),
@@ -204,6 +210,15 @@ impl NativeTokenScenario {
                     insurance_fund: None,
                     pricefeed: None,
                     spot_price_twap_interval: None,
+                    funding_mode: None,
+                    max_oracle_spread_ratio: None,
+                    skew_fee_ratio: None,
+                    oracle_heartbeat: Some(0u64),
+                    max_oracle_deviation_ratio: Some(Uint128::zero()),
+                    expiry_cutoff_period: None,
+                    trading_schedule: None,
+                    circuit_breaker_window: None,
+                    circuit_breaker_ratio: None,
                 },
                 &[],
             )
@@ -416,6 +431,7 @@ impl SimpleScenario {
                     maintenance_margin_ratio: None,
                     partial_liquidation_ratio: None,
                     liquidation_fee: None,
+                    use_mark_price: None,
                 },
                 &[],
             )
@@ -458,6 +474,8 @@ impl SimpleScenario {
                     quote_asset_reserve: to_decimals(1_000),
                     base_asset_reserve: to_decimals(100),
                     funding_period: 86_400_u64, // funding period is 1 day to make calcs easier
+                    expiry: None,
+                    curve: None,
                     toll_ratio: Uint128::zero(),
                     spread_ratio: Uint128::zero(),
                     fluctuation_limit_ratio: Uint128::zero(),
@@ -472,7 +490,8 @@ impl SimpleScenario {
             .unwrap();
         let vamm = VammController(vamm_addr.clone());
 
-        // set margin engine in vamm
+        // set margin engine in vamm, the test prices are historical so the oracle guards
+        // are disabled
         router
             .execute_contract(
                 owner.clone(),
@@ -487,6 +506,15 @@ impl SimpleScenario {
                     insurance_fund: None,
                     pricefeed: None,
                     spot_price_twap_interval: None,
+                    funding_mode: None,
+                    max_oracle_spread_ratio: None,
+                    skew_fee_ratio: None,
+                    oracle_heartbeat: Some(0u64),
+                    max_oracle_deviation_ratio: Some(Uint128::zero()),
+                    expiry_cutoff_period: None,
+                    trading_schedule: None,
+                    circuit_breaker_window: None,
+                    circuit_breaker_ratio: None,
                 },
                 &[],
             )
@@ -599,6 +627,92 @@ impl SimpleScenario {
             });
         }
     }
+
+    // instantiates a second vamm that expires at the given time and registers it with the engine
+    pub fn add_dated_vamm(&mut self, expiry: u64) -> VammController {
+        let vamm_id = self.router.store_code(contract_vamm());
+
+        let vamm_addr = self
+            .router
+            .instantiate_contract(
+                vamm_id,
+                self.owner.clone(),
+                &VammInstantiateMsg {
+                    decimals: 9u8,
+                    quote_asset: "ETH".to_string(),
+                    base_asset: "USD".to_string(),
+                    quote_asset_reserve: to_decimals(1_000),
+                    base_asset_reserve: to_decimals(100),
+                    funding_period: 86_400_u64,
+                    expiry: Some(expiry),
+                    curve: None,
+                    toll_ratio: Uint128::zero(),
+                    spread_ratio: Uint128::zero(),
+                    fluctuation_limit_ratio: Uint128::zero(),
+                    pricefeed: self.pricefeed.addr().to_string(),
+                    margin_engine: Some(self.engine.addr().to_string()),
+                    insurance_fund: Some(self.insurance_fund.addr().to_string()),
+                },
+                &[],
+                "dated_vamm",
+                None,
+            )
+            .unwrap();
+        let vamm = VammController(vamm_addr);
+
+        // set open and register
+        let msg = vamm.set_open(true).unwrap();
+        self.router.execute(self.owner.clone(), msg).unwrap();
+
+        let msg = self
+            .insurance_fund
+            .add_vamm(vamm.addr().to_string())
+            .unwrap();
+        self.router.execute(self.owner.clone(), msg).unwrap();
+
+        vamm
+    }
+
+    // instantiates a factory for new markets and allows it to add vamms to the insurance fund
+    pub fn add_factory(&mut self) -> FactoryController {
+        let vamm_id = self.router.store_code(contract_vamm());
+        let factory_id = self.router.store_code(contract_factory());
+
+        let factory_addr = self
+            .router
+            .instantiate_contract(
+                factory_id,
+                self.owner.clone(),
+                &FactoryInstantiateMsg {
+                    vamm_code_id: vamm_id,
+                    engine: self.engine.addr().to_string(),
+                    insurance_fund: self.insurance_fund.addr().to_string(),
+                    pricefeed: self.pricefeed.addr().to_string(),
+                },
+                &[],
+                "factory",
+                None,
+            )
+            .unwrap();
+        let factory = FactoryController(factory_addr);
+
+        let msg = self
+            .insurance_fund
+            .update_config(
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(factory.addr().to_string()),
+            )
+            .unwrap();
+        self.router.execute(self.owner.clone(), msg).unwrap();
+
+        factory
+    }
 }
 
 impl Default for SimpleScenario {
@@ -689,6 +803,8 @@ impl VammScenario {
                     quote_asset_reserve: to_decimals(1_000),
                     base_asset_reserve: to_decimals(100),
                     funding_period: 3_600_u64, // funding period is 1 day to make calcs easier
+                    expiry: None,
+                    curve: None,
                     toll_ratio: Uint128::from(10_000_000u128), // 0.01
                     spread_ratio: Uint128::from(10_000_000u128), // 0.01
                     fluctuation_limit_ratio: Uint128::from(10_000_000u128), // 0.01
@@ -703,6 +819,14 @@ impl VammScenario {
             .unwrap();
         let vamm = VammController(vamm_addr);
 
+        // the test prices are historical so the oracle guards are disabled
+        let msg = vamm.set_oracle_heartbeat(0u64).unwrap();
+        router.execute(owner.clone(), msg).unwrap();
+        let msg = vamm
+            .set_max_oracle_deviation_ratio(Uint128::zero())
+            .unwrap();
+        router.execute(owner.clone(), msg).unwrap();
+
         let msg = vamm.set_open(true).unwrap();
         router.execute(owner.clone(), msg).unwrap();
 
@@ -808,6 +932,8 @@ impl ShutdownScenario {
                     quote_asset_reserve: Uint128::from(1_000_000_000u128),
                     base_asset_reserve: Uint128::from(100_000_000u128),
                     funding_period: 3_600_u64, // funding period is 1 day to make calcs easier
+                    expiry: None,
+                    curve: None,
                     toll_ratio: Uint128::from(10_000u128), // 0.01
                     spread_ratio: Uint128::from(10_000u128), // 0.01
                     fluctuation_limit_ratio: Uint128::from(10_000u128), // 0.01
@@ -836,6 +962,8 @@ impl ShutdownScenario {
                     quote_asset_reserve: Uint128::from(1_000_000_000u128),
                     base_asset_reserve: Uint128::from(100_000_000u128),
                     funding_period: 3_600_u64, // funding period is 1 day to make calcs easier
+                    expiry: None,
+                    curve: None,
                     toll_ratio: Uint128::from(10_000u128), // 0.01
                     spread_ratio: Uint128::from(10_000u128), // 0.01
                     fluctuation_limit_ratio: Uint128::from(10_000u128), // 0.01
@@ -864,6 +992,8 @@ impl ShutdownScenario {
                     quote_asset_reserve: Uint128::from(1_000_000_000u128),
                     base_asset_reserve: Uint128::from(100_000_000u128),
                     funding_period: 3_600_u64, // funding period is 1 day to make calcs easier
+                    expiry: None,
+                    curve: None,
                     toll_ratio: Uint128::from(10_000u128), // 0.01
                     spread_ratio: Uint128::from(10_000u128), // 0.01
                     fluctuation_limit_ratio: Uint128::from(10_000u128), // 0.01
@@ -892,6 +1022,8 @@ impl ShutdownScenario {
                     quote_asset_reserve: Uint128::from(1_000_000_000u128),
                     base_asset_reserve: Uint128::from(100_000_000u128),
                     funding_period: 3_600_u64, // funding period is 1 day to make calcs easier
+                    expiry: None,
+                    curve: None,
                     toll_ratio: Uint128::from(10_000u128), // 0.01
                     spread_ratio: Uint128::from(10_000u128), // 0.01
                     fluctuation_limit_ratio: Uint128::from(10_000u128), // 0.01
@@ -920,6 +1052,8 @@ impl ShutdownScenario {
                     quote_asset_reserve: Uint128::from(1_000_000_000u128),
                     base_asset_reserve: Uint128::from(100_000_000u128),
                     funding_period: 3_600_u64, // funding period is 1 day to make calcs easier
+                    expiry: None,
+                    curve: None,
                     toll_ratio: Uint128::from(10_000u128), // 0.01
                     spread_ratio: Uint128::from(10_000u128), // 0.01
                     fluctuation_limit_ratio: Uint128::from(10_000u128), // 0.01
@@ -974,6 +1108,16 @@ fn contract_vamm() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+fn contract_factory() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new_with_empty(
+        margined_factory::contract::execute,
+        margined_factory::contract::instantiate,
+        margined_factory::contract::query,
+    )
+    .with_reply(margined_factory::contract::reply);
+    Box::new(contract)
+}
+
 fn contract_insurance_fund() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new_with_empty(
         margined_insurance_fund::contract::execute,
@@ -34,8 +34,14 @@ pub fn calculate_funds_needed<Q: Querier>(
         .checked_mul(leverage)?
         .checked_div(SIX_D_P)?;
 
+    // the direction of the trade on the vamm, this is needed as the spread fee depends on the skew
+    let direction = match side {
+        Side::Buy => Direction::AddToAmm,
+        Side::Sell => Direction::RemoveFromAmm,
+    };
+
     // pull the fees for the vamm that the position will be taken on; note that this will be shifted however many digits
-    let fee_amount: Uint128 = query_vamm_fees(querier, vamm.to_string(), new_notional)?;
+    let fee_amount: Uint128 = query_vamm_fees(querier, vamm.to_string(), new_notional, direction)?;
 
     // check if they have an existing position so we can calculate if someone owes margin
     let position: Position = query_existing_position(
@@ -86,18 +92,27 @@ pub fn calculate_funds_needed<Q: Querier>(
     }
 }
 
-// to query the given vamm's fees for use in the fund calculator
+// to query the given vamm's fees (toll + spread) for use in the fund calculator
 pub fn query_vamm_fees<Q: Querier>(
     querier: &Q,
     vamm_addr: String,
     quote_asset_amount: Uint128,
+    direction: Direction,
 ) -> StdResult<Uint128> {
-    Ok(QuerierWrapper::<Empty>::new(querier)
-        .query::<CalcFeeResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
+    let CalcFeeResponse {
+        toll_fee,
+        spread_fee,
+    } = QuerierWrapper::<Empty>::new(querier).query::<CalcFeeResponse>(&QueryRequest::Wasm(
+        WasmQuery::Smart {
             contract_addr: vamm_addr,
-            msg: to_binary(&VammQueryMsg::CalcFee { quote_asset_amount })?,
-        }))?
-        .toll_fee)
+            msg: to_binary(&VammQueryMsg::CalcFee {
+                quote_asset_amount,
+                direction,
+            })?,
+        },
+    ))?;
+
+    Ok(toll_fee.checked_add(spread_fee)?)
 }
 
 // to query the position of the given trader, on the given vamm for use in the fund calculator